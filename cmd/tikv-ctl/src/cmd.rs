@@ -446,6 +446,13 @@ pub enum Cmd {
         /// Output meta file path
         file: String,
     },
+    /// Check a binary-encoded JSON value for structural corruption
+    ValidateJson {
+        #[clap(short = 'v', long)]
+        /// Hex-encoded JSON value, as stored in a row (type byte followed by
+        /// the binary JSON body)
+        value: String,
+    },
     /// Compact the whole cluster in a specified range in one or more column
     /// families
     CompactCluster {
@@ -580,6 +587,15 @@ pub enum Cmd {
         #[clap(long)]
         data_dir: String,
     },
+    /// Remove a stale MIGRATING-RAFT marker left over from an aborted or
+    /// manually performed raft engine migration.
+    ///
+    /// Use this only after confirming by hand that `source` and `target` (the
+    /// raftdb and raft-engine directories derived from --config) already
+    /// hold the data you want, e.g. after TiKV refused to start because the
+    /// marker points at a path that doesn't match either directory on this
+    /// machine.
+    ResetRaftDataMigrationMarker {},
     /// Usage: tikv-ctl fork-readonly-tikv
     ///
     /// fork-readonly-tikv is for creating a tikv-server agent based on a
@@ -48,6 +48,7 @@ use raftstore::store::util::build_key_range;
 use regex::Regex;
 use security::{SecurityConfig, SecurityManager};
 use tempfile::TempDir;
+use tidb_query_datatype::codec::mysql::json::{JsonRef, JsonType};
 use tikv::{
     config::TikvConfig,
     server::{KvEngineFactoryBuilder, debug::BottommostLevelCompaction},
@@ -154,6 +155,15 @@ fn main() {
             let path = file.as_ref();
             dump_snap_meta_file(path);
         }
+        Cmd::ValidateJson { value } => {
+            let bytes = from_hex(&value).unwrap();
+            let (&type_byte, body) = bytes.split_first().expect("empty JSON value");
+            let json_type = JsonType::try_from(type_byte).expect("unknown JSON type tag");
+            match JsonRef::new(json_type, body).validate() {
+                Ok(()) => println!("JSON value is structurally valid"),
+                Err(e) => println!("JSON value is corrupted: {}", e),
+            }
+        }
         Cmd::DecryptFile { file, out_file } => {
             if !validate_storage_data_dir(&mut cfg, opt.data_dir) {
                 return;
@@ -306,6 +316,41 @@ fn main() {
                 }
             }
         }
+        Cmd::ResetRaftDataMigrationMarker {} => {
+            if opt.config.is_none() {
+                exit_with_clap_error(
+                    ErrorKind::MissingRequiredArgument,
+                    "(--config) must be specified",
+                );
+            }
+            if !validate_storage_data_dir(&mut cfg, opt.data_dir) {
+                return;
+            }
+            let (source, target) = if cfg.raft_engine.enable {
+                (cfg.raft_store.raftdb_path.clone(), cfg.raft_engine.config().dir)
+            } else {
+                (cfg.raft_engine.config().dir, cfg.raft_store.raftdb_path.clone())
+            };
+            let state = tikv_util::config::RaftDataStateMachine::new(
+                &cfg.storage.data_dir,
+                &source,
+                &target,
+            );
+            match state.force_reset_marker() {
+                Ok(true) => {
+                    println!("removed stale MIGRATING-RAFT marker");
+                    process::exit(0);
+                }
+                Ok(false) => {
+                    println!("no MIGRATING-RAFT marker found, nothing to do");
+                    process::exit(0);
+                }
+                Err(e) => {
+                    eprintln!("failed to remove MIGRATING-RAFT marker: {}", e);
+                    process::exit(-1);
+                }
+            }
+        }
         Cmd::ReuseReadonlyRemains {
             data_dir,
             agent_dir,
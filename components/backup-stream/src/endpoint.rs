@@ -36,7 +36,7 @@ use tikv_util::{
     config::ReadableDuration,
     debug, defer, error, info,
     memory::MemoryQuota,
-    sys::thread::ThreadBuildWrapper,
+    sys::{SysQuota, thread::ThreadBuildWrapper},
     thread_name_prefix::BACKUP_STREAM_THREAD,
     time::{Instant, Limiter},
     warn,
@@ -189,7 +189,10 @@ where
         }));
 
         let initial_scan_memory_quota = Arc::new(MemoryQuota::new(
-            config.initial_scan_pending_memory_quota.0 as _,
+            config
+                .initial_scan_pending_memory_quota
+                .resolve(SysQuota::memory_limit_in_bytes())
+                .0 as _,
         ));
         let limit = if config.initial_scan_rate_limit.0 > 0 {
             config.initial_scan_rate_limit.0 as f64
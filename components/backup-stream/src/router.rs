@@ -529,6 +529,7 @@ impl RouterInner {
             s3_multi_part_size: self.s3_multi_part_size.load(Ordering::Relaxed),
             gcp_v2_enable,
             hdfs_config: HdfsConfig::default(),
+            ..Default::default()
         };
         let stream_task = StreamTaskHandler::new(
             task,
@@ -35,7 +35,7 @@ mod all {
     use tempfile::TempDir;
     use tikv_util::{
         HandyRwLock, box_err,
-        config::{ReadableDuration, ReadableSize},
+        config::{ReadableDuration, ReadableSize, ReadableSizeOrRatio},
         defer,
     };
     use txn_types::Key;
@@ -334,7 +334,10 @@ mod all {
     #[test]
     fn memory_quota() {
         let mut suite = SuiteBuilder::new_named("memory_quota")
-            .cfg(|cfg| cfg.initial_scan_pending_memory_quota = ReadableSize::kb(2))
+            .cfg(|cfg| {
+                cfg.initial_scan_pending_memory_quota =
+                    ReadableSizeOrRatio::Size(ReadableSize::kb(2))
+            })
             .build();
         let keys = run_async_test(suite.write_records(0, 128, 1));
         let failed = Arc::new(AtomicBool::new(false));
@@ -69,9 +69,30 @@ pub fn storage_backend_config(config: &BackupConfig) -> BackendConfig {
             hadoop_home: config.hadoop.home.clone(),
             linux_user: config.hadoop.linux_user.clone(),
         },
+        gcs_client_config: gcp::ClientConfig {
+            request_timeout: none_if_zero(config.gcs_request_timeout.0),
+            connect_timeout: none_if_zero(config.gcs_connect_timeout.0),
+            pool_max_idle_per_host: match config.gcs_pool_max_idle_per_host {
+                0 => None,
+                n => Some(n),
+            },
+            oauth_endpoint: none_if_empty(config.gcs_oauth_endpoint.clone()),
+        },
     }
 }
 
+/// `0` is the "leave the default in effect" sentinel for the `gcs_*`
+/// duration settings above.
+fn none_if_zero(d: Duration) -> Option<Duration> {
+    if d == Duration::ZERO { None } else { Some(d) }
+}
+
+/// `""` is the "leave the default in effect" sentinel for `gcs_oauth_endpoint`,
+/// the same way `none_if_zero` treats `0` for the `gcs_*` durations.
+fn none_if_empty(s: String) -> Option<String> {
+    if s.is_empty() { None } else { Some(s) }
+}
+
 #[derive(Clone)]
 struct Request {
     start_key: Vec<u8>,
@@ -797,6 +797,11 @@ impl BlobStorage for S3Storage {
         // inclusive, bytes=0-499 -> [0, 499]
         self.get_range(name, Some(format!("bytes={}-{}", off, off + len - 1)))
     }
+
+    fn get_suffix(&self, name: &str, len: u64) -> cloud::blob::BlobStream<'_> {
+        // suffix range, bytes=-500 -> last 500 bytes
+        self.get_range(name, Some(format!("bytes=-{}", len)))
+    }
 }
 
 impl DeletableStorage for S3Storage {
@@ -850,6 +855,7 @@ impl IterableStorage for S3Storage {
                                         )
                                     },
                                 )?,
+                                ..Default::default()
                             })
                         }))
                         .left_stream()
@@ -789,6 +789,16 @@ impl BlobStorage for AzureStorage {
     fn get_part(&self, name: &str, off: u64, len: u64) -> cloud::blob::BlobStream<'_> {
         self.get_range(name, Some(off..off + len))
     }
+
+    fn get_suffix(&self, _name: &str, _len: u64) -> cloud::blob::BlobStream<'_> {
+        // Azure's range header requires a known start offset, and this backend has no
+        // cheap way to learn the object size up front; callers that need a suffix range
+        // should stat the object and call `get_part` instead.
+        let stream = stream::once(futures::future::err::<Vec<u8>, _>(unimplemented()))
+            .boxed()
+            .into_async_read();
+        Box::new(stream)
+    }
 }
 
 impl IterableStorage for AzureStorage {
@@ -6,6 +6,7 @@ use std::{
     io,
     result::Result as StdResult,
     sync::Arc,
+    time::Duration,
 };
 
 use hyper::{Body, Client, Request, Response, StatusCode, client::HttpConnector};
@@ -18,16 +19,107 @@ use tame_oauth::gcp::{
 };
 use tikv_util::stream::RetryError;
 
+/// Client-side tuning that isn't part of the GCS `InputConfig` protobuf
+/// (that type is generated from `kvproto`, a git dependency, so it can't
+/// grow new fields here). Callers that want non-default behavior thread
+/// this in separately, the same way `external_storage::BackendConfig`
+/// already carries `s3_multi_part_size` alongside the S3 `InputConfig`.
+///
+/// Every field defaults to `None`, which preserves hyper's own defaults and
+/// therefore today's behavior (no request timeout, no connect timeout, and
+/// hyper's built-in idle pool size).
+#[derive(Clone, Debug, Default)]
+pub struct ClientConfig {
+    /// Caps how long a single GCS request may take, measured separately for
+    /// the header phase (connecting, sending the request, and receiving the
+    /// response headers) and for responses that are buffered in full before
+    /// being used. A stalled streaming download is not covered.
+    pub request_timeout: Option<Duration>,
+    /// Forwarded to the underlying `HttpConnector`.
+    pub connect_timeout: Option<Duration>,
+    /// Forwarded to hyper's `Client::builder().pool_max_idle_per_host`.
+    pub pool_max_idle_per_host: Option<usize>,
+    /// Overrides the OAuth token endpoint that credential requests are made
+    /// against, e.g. `https://proxy.internal/gcs` for an air-gapped
+    /// deployment that routes GCS through an internal proxy. `gcs::Config`'s
+    /// own `bucket.endpoint` already redirects the storage API the same way;
+    /// this covers the OAuth token exchange, which `bucket.endpoint` never
+    /// touched, so backups using a storage-only proxy override used to fail
+    /// at token acquisition even though the storage endpoint was reachable.
+    pub oauth_endpoint: Option<String>,
+}
+
+/// Default OAuth token endpoint that `tame_oauth`'s credential flows request
+/// against, playing the same role for token acquisition that
+/// `gcs::GOOGLE_APIS` plays for the storage API. An air-gapped deployment
+/// that proxies GCS through an internal host usually needs to proxy this
+/// too, since neither is reachable from outside the proxy.
+const GOOGLE_OAUTH_ENDPOINT: &str = "https://oauth2.googleapis.com";
+/// Well-known path of the token endpoint, handled the same way
+/// `gcs::HARDCODED_ENDPOINTS_SUFFIX` handles the storage API's, in case a
+/// caller's custom endpoint already has it baked in.
+const GOOGLE_OAUTH_HARDCODED_SUFFIXES: &[&str] = &["token"];
+
+/// Rewrites a request URL that starts with a well-known default address into
+/// the equivalent path under a caller-supplied `endpoint`, so a proxy or
+/// emulator can be substituted transparently. Shared by the GCS storage
+/// endpoint override (see `gcs::GcsStorage::rewrite_endpoint`) and the OAuth
+/// token endpoint override below, which used to each hand-roll their own
+/// copy of this string surgery.
+///
+/// If `endpoint` itself already ends with one of `hardcoded_suffixes` --
+/// e.g. because it was pointed at `https://proxy.internal/gcs/storage/v1/`
+/// rather than at the API root -- that suffix is stripped first so it isn't
+/// duplicated when the real request's own suffix is appended back on.
+#[derive(Clone, Debug)]
+pub(crate) struct EndpointRewriter {
+    default: &'static str,
+    endpoint: String,
+    hardcoded_suffixes: &'static [&'static str],
+}
+
+impl EndpointRewriter {
+    pub(crate) fn new(
+        default: &'static str,
+        endpoint: String,
+        hardcoded_suffixes: &'static [&'static str],
+    ) -> Self {
+        Self {
+            default,
+            endpoint,
+            hardcoded_suffixes,
+        }
+    }
+
+    /// Returns `url` with the well-known default address replaced by this
+    /// rewriter's endpoint, or `None` if `url` doesn't start with that
+    /// address (nothing to rewrite).
+    pub(crate) fn rewrite(&self, url: &str) -> Option<String> {
+        let new_host = self
+            .hardcoded_suffixes
+            .iter()
+            .find_map(|suffix| self.endpoint.strip_suffix(suffix))
+            .unwrap_or(&self.endpoint);
+        let rest = url.strip_prefix(self.default)?;
+        Some([new_host.trim_end_matches('/'), rest].concat())
+    }
+}
+
 // GCS compatible storage
 #[derive(Clone)]
 pub(crate) struct GcpClient {
     token_provider: Option<Arc<TokenProviderWrapper>>,
     client: Client<HttpsConnector<HttpConnector>, Body>,
+    request_timeout: Option<Duration>,
+    oauth_rewriter: Option<EndpointRewriter>,
 }
 
 impl GcpClient {
     /// Create a new gcp cleint for the given config.
-    pub fn with_svc_info(svc_info: Option<ServiceAccountInfo>) -> io::Result<GcpClient> {
+    pub fn with_svc_info(
+        svc_info: Option<ServiceAccountInfo>,
+        config: ClientConfig,
+    ) -> io::Result<GcpClient> {
         let token_provider = if let Some(info) = svc_info {
             let svc_info_provider = ServiceAccountProviderInner::new(info)
                 .or_invalid_input("invalid credentials_blob")?;
@@ -35,43 +127,71 @@ impl GcpClient {
         } else {
             None
         };
-        Ok(Self::with_token_provider(token_provider))
+        Self::with_token_provider(token_provider, config)
     }
 
-    fn with_token_provider(token_provider: Option<TokenProviderWrapperInner>) -> Self {
-        let client = Client::builder().build(HttpsConnector::new());
-        Self {
-            token_provider: token_provider.map(|t| Arc::new(TokenProviderWrapper::wrap(t))),
-            client,
+    fn with_token_provider(
+        token_provider: Option<TokenProviderWrapperInner>,
+        config: ClientConfig,
+    ) -> io::Result<Self> {
+        let mut http = HttpConnector::new();
+        http.enforce_http(false);
+        http.set_connect_timeout(config.connect_timeout);
+        let tls = native_tls::TlsConnector::new().or_io_error("build gcs tls connector")?;
+        let https = HttpsConnector::from((http, tls.into()));
+        let mut builder = Client::builder();
+        if let Some(n) = config.pool_max_idle_per_host {
+            builder.pool_max_idle_per_host(n);
         }
+        let oauth_rewriter = config.oauth_endpoint.map(|endpoint| {
+            EndpointRewriter::new(GOOGLE_OAUTH_ENDPOINT, endpoint, GOOGLE_OAUTH_HARDCODED_SUFFIXES)
+        });
+        Ok(Self {
+            token_provider: token_provider.map(|t| Arc::new(TokenProviderWrapper::wrap(t))),
+            client: builder.build(https),
+            request_timeout: config.request_timeout,
+            oauth_rewriter,
+        })
     }
 
-    pub fn with_default_provider() -> io::Result<GcpClient> {
+    pub fn with_default_provider(config: ClientConfig) -> io::Result<GcpClient> {
         let provider = TokenProviderWrapperInner::get_default_provider()
             .map_err(|e| RequestError::OAuth(e, "default_provider".into()))?;
-        Ok(Self::with_token_provider(provider))
+        Self::with_token_provider(provider, config)
     }
 
-    pub fn load_from(credentail_path: Option<&str>) -> io::Result<GcpClient> {
+    /// Loads credentials from a service account or authorized-user JSON file
+    /// on disk (or falls back to the default provider chain when `None`).
+    ///
+    /// This only understands the two credential types above; it does not
+    /// support `external_account` (workload identity federation) or
+    /// refresh-on-expiry semantics for file-based credentials. The `gcp_v2`
+    /// client (gated by `gcp_v2_enable`) covers those cases via
+    /// `google_cloud_auth` instead of reimplementing them here.
+    pub fn load_from(
+        credentail_path: Option<&str>,
+        config: ClientConfig,
+    ) -> io::Result<GcpClient> {
         if let Some(path) = credentail_path {
             let json_data = std::fs::read(path)?;
             let cred_type = CredentialType::parse_from_json(&json_data)?;
             match cred_type {
                 CredentialType::ServiceAccount => {
                     let svc_info = serde_json::from_slice(&json_data)?;
-                    return Self::with_svc_info(Some(svc_info));
+                    return Self::with_svc_info(Some(svc_info), config);
                 }
                 CredentialType::AuthorizedUser => {
                     let user_credential: EndUserCredentialsInfo =
                         serde_json::from_slice(&json_data)?;
                     let provider = EndUserCredentialsInner::new(user_credential);
-                    return Ok(Self::with_token_provider(Some(
-                        TokenProviderWrapperInner::EndUser(provider),
-                    )));
+                    return Self::with_token_provider(
+                        Some(TokenProviderWrapperInner::EndUser(provider)),
+                        config,
+                    );
                 }
             }
         };
-        Self::with_default_provider()
+        Self::with_default_provider(config)
     }
 
     pub(crate) async fn set_auth(
@@ -90,6 +210,7 @@ impl GcpClient {
                 scope_hash,
                 ..
             } => {
+                let request = self.rewrite_oauth_endpoint(request)?;
                 let res = self
                     .client
                     .request(request.map(From::from))
@@ -120,25 +241,76 @@ impl GcpClient {
         Ok(())
     }
 
+    /// Replaces `tame_oauth`'s hard-coded OAuth token endpoint in `req`'s
+    /// URI with the custom one configured for this client, if any.
+    fn rewrite_oauth_endpoint<B>(
+        &self,
+        mut req: Request<B>,
+    ) -> StdResult<Request<B>, RequestError> {
+        if let Some(rewriter) = &self.oauth_rewriter {
+            let uri = req.uri().to_string();
+            if let Some(new_url) = rewriter.rewrite(&uri) {
+                *req.uri_mut() = new_url.parse()?;
+            }
+        }
+        Ok(req)
+    }
+
     pub async fn make_request(
+        &self,
+        req: Request<Body>,
+        scope: tame_gcs::Scopes,
+    ) -> StdResult<Response<Body>, RequestError> {
+        self.make_request_allowing(req, scope, &[]).await
+    }
+
+    /// Like [`GcpClient::make_request`], but also treats `extra_ok_status`
+    /// as a success.
+    ///
+    /// GCS resumable uploads respond to an intermediate chunk with HTTP 308
+    /// ("Resume Incomplete") to mean "keep sending", which is not a 2xx but
+    /// is not an error either; callers sending chunked `PUT`s pass that
+    /// status here instead of the mutating every call site's error handling.
+    pub async fn make_request_allowing(
         &self,
         mut req: Request<Body>,
         scope: tame_gcs::Scopes,
+        extra_ok_status: &[StatusCode],
     ) -> StdResult<Response<Body>, RequestError> {
         if let Some(svc_access) = &self.token_provider {
             self.set_auth(&mut req, scope, svc_access.clone()).await?;
         }
         let uri = req.uri().to_string();
+        // `ReadWrite` also covers deletes and rewrites, but those share the
+        // "mutating" budget `put` is named after; `ReadOnly` is the only get
+        // path, so the two-way split still matches every caller.
+        let op = match scope {
+            tame_gcs::Scopes::ReadOnly => "get",
+            _ => "put",
+        };
         let res = self
-            .client
-            .request(req)
-            .await
+            .with_timeout(op, self.client.request(req))
+            .await?
             .map_err(|e| RequestError::Hyper(e, uri.clone()))?;
-        if !res.status().is_success() {
+        if !res.status().is_success() && !extra_ok_status.contains(&res.status()) {
             return Err(status_code_error(res.status(), uri));
         }
         Ok(res)
     }
+
+    /// Races `fut` against `op`'s configured request timeout, if any.
+    async fn with_timeout<T>(
+        &self,
+        op: &'static str,
+        fut: impl std::future::Future<Output = hyper::Result<T>>,
+    ) -> StdResult<hyper::Result<T>, RequestError> {
+        match self.request_timeout {
+            Some(d) => tokio::time::timeout(d, fut)
+                .await
+                .map_err(|_| RequestError::Timeout(op)),
+            None => Ok(fut.await),
+        }
+    }
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -166,7 +338,6 @@ trait ResultExt {
 
     // Maps the error of this result as an `std::io::Error` with `Other` error
     // kind.
-    #[allow(dead_code)]
     fn or_io_error<D: Display>(self, msg: D) -> io::Result<Self::Ok>;
 
     // Maps the error of this result as an `std::io::Error` with `InvalidInput`
@@ -190,6 +361,8 @@ pub enum RequestError {
     OAuth(tame_oauth::Error, String),
     Gcs(tame_gcs::Error),
     InvalidEndpoint(http::uri::InvalidUri),
+    /// `op`'s configured request timeout elapsed before it finished.
+    Timeout(&'static str),
 }
 
 impl Display for RequestError {
@@ -242,6 +415,10 @@ impl From<RequestError> for io::Error {
                 io::ErrorKind::InvalidInput,
                 format!("invalid GCS endpoint URI: {}", e),
             ),
+            RequestError::Timeout(op) => Self::new(
+                io::ErrorKind::TimedOut,
+                format!("GCS {} request timed out", op),
+            ),
         }
     }
 }
@@ -260,8 +437,96 @@ impl RetryError for RequestError {
             Self::OAuth(tame_oauth::Error::HttpStatus(StatusCode::TOO_MANY_REQUESTS), _) => true,
             Self::OAuth(tame_oauth::Error::HttpStatus(StatusCode::REQUEST_TIMEOUT), _) => true,
             Self::OAuth(tame_oauth::Error::HttpStatus(status), _) => status.is_server_error(),
+            // A stalled connection is exactly the sort of transient failure
+            // retries are for.
+            Self::Timeout(_) => true,
             // Consider everything else not retryable.
             _ => false,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    /// Accepts one connection and then hangs forever without writing a
+    /// response, the same way a backup over a dead interconnect does.
+    async fn accept_and_hang(listener: TcpListener) {
+        let _socket = listener.accept().await.unwrap();
+        std::future::pending::<()>().await
+    }
+
+    async fn request_timed_out_against_hung_server(scope: tame_gcs::Scopes) -> io::Error {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(accept_and_hang(listener));
+
+        let client = GcpClient::with_svc_info(
+            None,
+            ClientConfig {
+                request_timeout: Some(Duration::from_millis(50)),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let req = Request::builder()
+            .uri(format!("http://{}/", addr))
+            .body(Body::empty())
+            .unwrap();
+        client.make_request(req, scope).await.unwrap_err().into()
+    }
+
+    #[tokio::test]
+    async fn test_make_request_times_out_on_get_with_op_label() {
+        let err = request_timed_out_against_hung_server(tame_gcs::Scopes::ReadOnly).await;
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+        assert!(err.to_string().contains("get"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_make_request_times_out_on_put_with_op_label() {
+        let err = request_timed_out_against_hung_server(tame_gcs::Scopes::ReadWrite).await;
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+        assert!(err.to_string().contains("put"), "{}", err);
+    }
+
+    #[test]
+    fn test_endpoint_rewriter() {
+        let rewriter = EndpointRewriter::new(
+            "https://oauth2.googleapis.com",
+            "http://proxy.internal".to_owned(),
+            GOOGLE_OAUTH_HARDCODED_SUFFIXES,
+        );
+        assert_eq!(
+            rewriter.rewrite("https://oauth2.googleapis.com/token").unwrap(),
+            "http://proxy.internal/token"
+        );
+        assert!(rewriter.rewrite("https://accounts.google.com/o/oauth2/token").is_none());
+
+        // A custom endpoint that already has the hard-coded suffix baked in
+        // doesn't end up with it twice.
+        let rewriter = EndpointRewriter::new(
+            "https://oauth2.googleapis.com",
+            "http://proxy.internal/token".to_owned(),
+            GOOGLE_OAUTH_HARDCODED_SUFFIXES,
+        );
+        assert_eq!(
+            rewriter.rewrite("https://oauth2.googleapis.com/token").unwrap(),
+            "http://proxy.internal/token"
+        );
+
+        // A custom endpoint with its own path prefix keeps that prefix.
+        let rewriter = EndpointRewriter::new(
+            "https://oauth2.googleapis.com",
+            "https://proxy.internal/gcs/".to_owned(),
+            GOOGLE_OAUTH_HARDCODED_SUFFIXES,
+        );
+        assert_eq!(
+            rewriter.rewrite("https://oauth2.googleapis.com/token").unwrap(),
+            "https://proxy.internal/gcs/token"
+        );
+    }
+}
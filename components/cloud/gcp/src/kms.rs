@@ -84,6 +84,7 @@ impl GcpKms {
                 .gcp
                 .as_ref()
                 .and_then(|c| c.credential_file_path.as_deref()),
+            crate::client::ClientConfig::default(),
         )?;
         Ok(Self {
             config,
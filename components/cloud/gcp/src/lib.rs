@@ -7,36 +7,84 @@ mod gcs;
 pub use gcs::{Config, GcsStorage};
 
 mod client;
+pub use client::ClientConfig;
 mod kms;
 pub use kms::GcpKms;
 
 pub const STORAGE_VENDOR_NAME_GCP: &str = "gcp";
 
 pub mod utils {
-    use std::{future::Future, io};
+    use std::{future::Future, io, time::Duration};
 
     use cloud::metrics;
     use hyper::{Body, body::Bytes};
     use tame_gcs::ApiResponse;
     use tikv_util::stream::{RetryError, RetryExt, retry_ext};
+
+    /// Bounds a single [`retry`] call site: it stops once either the retry
+    /// count or the wall-clock budget is exhausted, whichever comes first.
+    /// `None` leaves the corresponding cap at [`RetryExt`]'s default (an
+    /// unbounded elapsed time, and a generous fixed retry count).
+    #[derive(Clone, Copy, Debug, Default)]
+    pub struct RetryBudget {
+        pub max_retry_times: Option<usize>,
+        pub max_elapsed: Option<Duration>,
+    }
+
     pub async fn retry<G, T, F, E>(action: G, name: &'static str) -> Result<T, E>
     where
         G: FnMut() -> F,
         F: Future<Output = Result<T, E>>,
         E: RetryError + std::fmt::Debug,
     {
-        retry_ext(action, RetryExt::default().with_fail_hook(move |err: &E| {
+        retry_with_budget(action, name, RetryBudget::default()).await
+    }
+
+    /// Like [`retry`], but lets the call site tighten the retry budget
+    /// instead of always inheriting [`RetryExt`]'s defaults. Errors that
+    /// `RequestError::is_retryable` classifies as permanent (e.g. a 403 or
+    /// 404 on `put`) are never retried regardless of the budget.
+    pub async fn retry_with_budget<G, T, F, E>(
+        action: G,
+        name: &'static str,
+        budget: RetryBudget,
+    ) -> Result<T, E>
+    where
+        G: FnMut() -> F,
+        F: Future<Output = Result<T, E>>,
+        E: RetryError + std::fmt::Debug,
+    {
+        let mut ext = RetryExt::default().with_fail_hook(move |err: &E| {
             warn!("gcp request meet error."; "err" => ?err, "retry?" => %err.is_retryable(), "context" => %name);
             metrics::CLOUD_ERROR_VEC.with_label_values(&["gcp", name]).inc();
-        })).await
+        });
+        if let Some(max_retry_times) = budget.max_retry_times {
+            ext = ext.with_max_retry_times(max_retry_times);
+        }
+        if let Some(max_elapsed) = budget.max_elapsed {
+            ext = ext.with_max_elapsed(max_elapsed);
+        }
+        retry_ext(action, ext).await
     }
 
     pub async fn read_from_http_body<M: ApiResponse<Bytes>>(
         b: http::Response<Body>,
+        op: &'static str,
+        timeout: Option<Duration>,
     ) -> io::Result<M> {
         use crate::gcs::ResultExt;
         let (headers, body) = b.into_parts();
-        let bytes = hyper::body::to_bytes(body).await.or_io_error(format_args!(
+        let read = hyper::body::to_bytes(body);
+        let bytes = match timeout {
+            Some(d) => tokio::time::timeout(d, read).await.map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    format!("GCS {} response body timed out", op),
+                )
+            })?,
+            None => read.await,
+        }
+        .or_io_error(format_args!(
             "cannot read bytes from http response {:?}",
             headers
         ))?;
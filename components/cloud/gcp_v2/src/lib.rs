@@ -542,12 +542,25 @@ impl BlobStorage for GcsStorage {
     }
 
     fn get_part(&self, name: &str, off: u64, len: u64) -> BlobStream<'_> {
-        self.get_range(name, Some((off, len)))
+        self.get_range(name, Some(GcsReadRange::Segment(off, len)))
     }
+
+    fn get_suffix(&self, name: &str, len: u64) -> BlobStream<'_> {
+        self.get_range(name, Some(GcsReadRange::Suffix(len)))
+    }
+}
+
+/// The subset of range shapes this backend needs; kept separate from the
+/// underlying client's `ReadRange` so the two constructors (`segment` for
+/// `off..off+len`, `tail` for the last `len` bytes) stay in one place.
+#[derive(Clone, Copy)]
+enum GcsReadRange {
+    Segment(u64, u64),
+    Suffix(u64),
 }
 
 impl GcsStorage {
-    fn get_range(&self, name: &str, range: Option<(u64, u64)>) -> BlobStream<'_> {
+    fn get_range(&self, name: &str, range: Option<GcsReadRange>) -> BlobStream<'_> {
         let bucket = self.bucket_resource_name();
         let object = self.full_path(name);
         let storage = self.inner.clone();
@@ -561,8 +574,14 @@ impl GcsStorage {
                     let object = object.clone();
                     async move {
                         let mut builder = client.read_object(&bucket, &object);
-                        if let Some((off, len)) = range {
-                            builder = builder.set_read_range(ReadRange::segment(off, len));
+                        match range {
+                            Some(GcsReadRange::Segment(off, len)) => {
+                                builder = builder.set_read_range(ReadRange::segment(off, len));
+                            }
+                            Some(GcsReadRange::Suffix(len)) => {
+                                builder = builder.set_read_range(ReadRange::tail(len));
+                            }
+                            None => {}
                         }
                         builder.send().await.map_err(|e| GcsApiError::new("read_object", e))
                     }
@@ -610,6 +629,7 @@ impl IterableStorage for GcsStorage {
                 for object in resp.objects {
                     yield BlobObject {
                         key: self.strip_prefix_if_needed(object.name),
+                        ..Default::default()
                     };
                 }
 
@@ -44,12 +44,19 @@ fn response_for_target(target: &str, addr: &str) -> Vec<u8> {
 }
 
 async fn start_server() -> io::Result<(String, oneshot::Sender<()>, Arc<Mutex<Vec<Vec<u8>>>>)> {
+    start_server_with(response_for_target).await
+}
+
+async fn start_server_with(
+    responder: impl Fn(&str, &str) -> Vec<u8> + Send + Sync + 'static,
+) -> io::Result<(String, oneshot::Sender<()>, Arc<Mutex<Vec<Vec<u8>>>>)> {
     let listener = tokio::net::TcpListener::bind(("127.0.0.1", 0)).await?;
     let addr = listener.local_addr()?;
     let (shutdown_tx, mut shutdown_rx) = oneshot::channel::<()>();
     let captured = Arc::new(Mutex::new(Vec::<Vec<u8>>::new()));
     let captured_in_server = captured.clone();
     let addr_string = addr.to_string();
+    let responder = Arc::new(responder);
 
     tokio::spawn(async move {
         loop {
@@ -59,6 +66,7 @@ async fn start_server() -> io::Result<(String, oneshot::Sender<()>, Arc<Mutex<Ve
                     let Ok((mut socket, _)) = res else { break; };
                     let captured = captured_in_server.clone();
                     let addr_string = addr_string.clone();
+                    let responder = responder.clone();
                     tokio::spawn(async move {
                         let mut buf = Vec::with_capacity(4096);
                         let mut tmp = [0u8; 1024];
@@ -97,7 +105,7 @@ async fn start_server() -> io::Result<(String, oneshot::Sender<()>, Arc<Mutex<Ve
                         let target = parts.next().unwrap_or("/");
 
                         captured.lock().unwrap().push(buf);
-                        let response = response_for_target(target, &addr_string);
+                        let response = responder(target, &addr_string);
                         let _ = socket.write_all(&response).await;
                         let _ = socket.shutdown().await;
                     });
@@ -223,3 +231,99 @@ async fn gcp_v2_zero_length_put_uses_resumable_upload() -> Result<(), Box<dyn st
     let _ = shutdown.send(());
     Ok(())
 }
+
+#[tokio::test]
+async fn gcp_v2_external_account_refreshes_token_on_expiry() -> Result<(), Box<dyn std::error::Error>>
+{
+    let token_requests = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let counted = token_requests.clone();
+    let (endpoint, shutdown, _captured) = start_server_with(move |target, addr| {
+        if target.contains("/token") {
+            counted.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            return build_http_response(
+                r#"{"access_token":"test-token","issued_token_type":"urn:ietf:params:oauth:token-type:access_token","token_type":"Bearer","expires_in":1}"#,
+                &[],
+            );
+        }
+        response_for_target(target, addr)
+    })
+    .await?;
+
+    let mut cfg = make_cfg(&endpoint, "test-bucket", "pfx", "", "");
+    let token_file = tempfile::NamedTempFile::new()?;
+    std::fs::write(token_file.path(), "header.payload.signature")?;
+    cfg.credentials_blob =
+        external_account_credentials_blob(&format!("{endpoint}/token"), token_file.path());
+    let s = gcp_v2::GcsStorage::from_input(cfg)?;
+
+    s.put(
+        "a",
+        PutResource(Box::new(futures::io::Cursor::new(b"alpha".to_vec()))),
+        5,
+    )
+    .await?;
+
+    // Wait past the 1s-lived token so the second put must fetch a new one.
+    tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+
+    s.put(
+        "b",
+        PutResource(Box::new(futures::io::Cursor::new(b"beta".to_vec()))),
+        4,
+    )
+    .await?;
+
+    assert!(
+        token_requests.load(std::sync::atomic::Ordering::SeqCst) >= 2,
+        "expired token should have been refreshed for the second put"
+    );
+
+    let _ = shutdown.send(());
+    Ok(())
+}
+
+#[tokio::test]
+async fn gcp_v2_external_account_picks_up_rotated_subject_token_file()
+-> Result<(), Box<dyn std::error::Error>> {
+    let (endpoint, shutdown, _captured) = start_server_with(move |target, addr| {
+        if target.contains("/token") {
+            return build_http_response(
+                r#"{"access_token":"test-token","issued_token_type":"urn:ietf:params:oauth:token-type:access_token","token_type":"Bearer","expires_in":1}"#,
+                &[],
+            );
+        }
+        response_for_target(target, addr)
+    })
+    .await?;
+
+    let mut cfg = make_cfg(&endpoint, "test-bucket", "pfx", "", "");
+    let token_file = tempfile::NamedTempFile::new()?;
+    std::fs::write(token_file.path(), "header.payload.signature-v1")?;
+    cfg.credentials_blob =
+        external_account_credentials_blob(&format!("{endpoint}/token"), token_file.path());
+    let s = gcp_v2::GcsStorage::from_input(cfg)?;
+
+    s.put(
+        "a",
+        PutResource(Box::new(futures::io::Cursor::new(b"alpha".to_vec()))),
+        5,
+    )
+    .await?;
+
+    // Simulate a workload-identity sidecar rotating the mounted subject token
+    // file, and let the cached (1s-lived) token expire.
+    std::fs::write(token_file.path(), "header.payload.signature-v2")?;
+    tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+
+    // The put must succeed by re-reading the rotated file rather than reusing
+    // a stale subject token.
+    s.put(
+        "b",
+        PutResource(Box::new(futures::io::Cursor::new(b"beta".to_vec()))),
+        4,
+    )
+    .await?;
+
+    let _ = shutdown.send(());
+    Ok(())
+}
@@ -1,11 +1,32 @@
 // Copyright 2021 TiKV Project Authors. Licensed under Apache-2.0.
 
-use std::{fmt::Display, io, marker::Unpin, panic::Location, pin::Pin, task::Poll};
+use std::{
+    cell::{Cell, RefCell},
+    fmt::Display,
+    io,
+    marker::Unpin,
+    panic::Location,
+    pin::Pin,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    task::Poll,
+};
 
 use async_trait::async_trait;
-use futures::{future::LocalBoxFuture, io as async_io, io::Cursor, stream::Stream};
+use futures::{
+    future::{FutureExt, LocalBoxFuture},
+    io as async_io,
+    io::Cursor,
+    stream::{Stream, StreamExt},
+};
 use futures_io::AsyncRead;
 
+/// The default number of concurrent [`DeletableStorage::delete_prefix`]
+/// deletes in flight at once.
+const DEFAULT_DELETE_PREFIX_CONCURRENCY: usize = 8;
+
 pub trait BlobConfig: 'static + Send + Sync {
     fn name(&self) -> &'static str;
     fn url(&self) -> io::Result<url::Url>;
@@ -17,6 +38,10 @@ pub trait BlobConfig: 'static + Send + Sync {
 ///
 /// See the documentation of [external_storage::UnpinReader] for why those
 /// wrappers exists.
+///
+/// Use [`Self::with_cancellation`] to tie the reader to a [`CancellationToken`]
+/// so a caller that aborts mid-transfer (e.g. a cancelled BR task) stops the
+/// upload promptly instead of it running to completion.
 pub struct PutResource<'a>(pub Box<dyn AsyncRead + Send + Unpin + 'a>);
 
 pub type BlobStream<'a> = Box<dyn AsyncRead + Unpin + Send + 'a>;
@@ -37,6 +62,65 @@ impl<'a> From<Box<dyn AsyncRead + Send + Unpin + 'a>> for PutResource<'a> {
     }
 }
 
+impl<'a> PutResource<'a> {
+    /// Wraps this resource's reader so that once `token` is cancelled, the
+    /// next read (and every one after it) fails promptly with
+    /// [`io::ErrorKind::Interrupted`] instead of continuing to pull bytes
+    /// from the underlying reader.
+    ///
+    /// This is checked on every poll, so it takes effect between chunks of
+    /// a chunked upload (e.g. GCS's resumable upload loop) as well as inside
+    /// a single [`read_to_end`] call, without the caller having to poll the
+    /// token itself.
+    pub fn with_cancellation(self, token: CancellationToken) -> PutResource<'a> {
+        PutResource(Box::new(CancellableRead {
+            inner: self.0,
+            token,
+        }))
+    }
+}
+
+/// A flag a caller can flip to signal that an in-flight blob operation
+/// should stop as soon as possible, e.g. when a BR task is aborted mid-
+/// upload. Cheaply `Clone`-able; every clone observes the same cancellation.
+#[derive(Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Marks this token (and every clone of it) cancelled.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Release);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+}
+
+/// See [`PutResource::with_cancellation`].
+struct CancellableRead<R> {
+    inner: R,
+    token: CancellationToken,
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for CancellableRead<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<std::result::Result<usize, futures_io::Error>> {
+        if self.token.is_cancelled() {
+            return Poll::Ready(Err(io::Error::new(io::ErrorKind::Interrupted, "cancelled")));
+        }
+        let this = self.get_mut();
+        Pin::new(&mut this.inner).poll_read(cx, buf)
+    }
+}
+
 /// An abstraction for blob storage.
 /// Currently the same as ExternalStorage
 #[async_trait]
@@ -47,15 +131,218 @@ pub trait BlobStorage: 'static + Send + Sync {
     async fn put(&self, name: &str, reader: PutResource<'_>, content_length: u64)
     -> io::Result<()>;
 
+    /// Write all contents of the read to the given path without knowing its
+    /// length up front, returning the number of bytes written.
+    ///
+    /// This exists for producers like the log-backup (PITR) writer, which
+    /// generate data incrementally and would otherwise have to buffer a
+    /// whole file just to learn its size before calling [`Self::put`].
+    ///
+    /// The default implementation buffers the reader into memory to learn
+    /// its length and then delegates to [`Self::put`]; backends that can
+    /// stream without knowing the length ahead of time (e.g. via a chunked
+    /// upload protocol) should override this. Callers passing very large or
+    /// unbounded readers to a backend that has not overridden this method
+    /// will hold the whole payload in memory at once.
+    async fn put_streaming(&self, name: &str, reader: PutResource<'_>) -> io::Result<u64> {
+        let mut data = Vec::new();
+        let len = read_to_end(reader, &mut data).await?;
+        self.put(name, PutResource(Box::new(Cursor::new(data))), len)
+            .await?;
+        Ok(len)
+    }
+
+    /// Write to `name` only if it doesn't already exist, e.g. for a lock
+    /// object that must never be silently clobbered by a racing writer.
+    ///
+    /// Returns `Ok(true)` if this call created the object, `Ok(false)` if an
+    /// object already existed at `name` (in which case nothing was written),
+    /// and `Err` for any other failure. Implementors must use a real
+    /// create-only precondition on the backing store rather than a
+    /// check-then-write, since the latter is racy against concurrent
+    /// callers.
+    ///
+    /// The default implementation is for backends that don't yet support a
+    /// create-only precondition; it always fails rather than silently
+    /// falling back to a racy check-then-[`Self::put`].
+    async fn put_if_not_exists(
+        &self,
+        _name: &str,
+        _reader: PutResource<'_>,
+        _content_length: u64,
+    ) -> io::Result<bool> {
+        Err(unimplemented())
+    }
+
     /// Read all contents of the given path.
     fn get(&self, name: &str) -> BlobStream<'_>;
 
     /// Read part of contents of the given path.
     fn get_part(&self, name: &str, off: u64, len: u64) -> BlobStream<'_>;
+
+    /// Read the last `len` bytes of the given path.
+    fn get_suffix(&self, name: &str, len: u64) -> BlobStream<'_>;
 }
 
-pub trait DeletableStorage {
+pub trait DeletableStorage: IterableStorage {
     fn delete(&self, name: &str) -> LocalBoxFuture<'_, io::Result<()>>;
+
+    /// Deletes the specific `version` of `name`, e.g. a GCS object
+    /// generation, leaving any other version of `name` untouched.
+    ///
+    /// This is what a caller must use to clean up a stale version left
+    /// behind by a retried [`VersionedStorage::put_versioned`] (e.g. after a
+    /// partial upload failure that a later retry didn't overwrite), since a
+    /// plain [`Self::delete`] would remove whichever version is current
+    /// rather than the one the caller has in hand.
+    ///
+    /// The default implementation is for backends that don't support
+    /// addressing a specific version; it always fails rather than silently
+    /// falling back to [`Self::delete`] and possibly removing the wrong
+    /// version.
+    fn delete_version(&self, _name: &str, _version: &str) -> LocalBoxFuture<'_, io::Result<()>> {
+        async move { Err(unimplemented()) }.boxed_local()
+    }
+
+    /// Deletes every object under `prefix`, with up to
+    /// [`DEFAULT_DELETE_PREFIX_CONCURRENCY`] deletes in flight at once.
+    ///
+    /// See [`Self::delete_prefix_with_concurrency`] for the full semantics
+    /// and for how to tune the concurrency.
+    fn delete_prefix(&self, prefix: &str) -> LocalBoxFuture<'_, io::Result<u64>> {
+        self.delete_prefix_with_concurrency(prefix, DEFAULT_DELETE_PREFIX_CONCURRENCY)
+    }
+
+    /// Like [`Self::delete_prefix`], but lets the caller tune how many
+    /// deletes run concurrently.
+    ///
+    /// Lists objects under `prefix` via [`IterableStorage::iter_prefix`] and
+    /// deletes up to `concurrency` of them at a time, reusing whatever
+    /// retry and metrics behavior the implementor's [`Self::delete`] already
+    /// has. Returns the number of objects successfully deleted.
+    ///
+    /// A failing delete doesn't stop the rest: every other listed object is
+    /// still attempted. If any delete fails, the returned error names the
+    /// first key that failed.
+    fn delete_prefix_with_concurrency(
+        &self,
+        prefix: &str,
+        concurrency: usize,
+    ) -> LocalBoxFuture<'_, io::Result<u64>> {
+        let prefix = prefix.to_owned();
+        async move {
+            let deleted = Cell::new(0u64);
+            let first_err: RefCell<Option<(String, io::Error)>> = RefCell::new(None);
+
+            self.iter_prefix(&prefix)
+                .for_each_concurrent(Some(concurrency), |item| {
+                    let deleted = &deleted;
+                    let first_err = &first_err;
+                    async move {
+                        let key = match item {
+                            Ok(obj) => obj.key,
+                            Err(e) => {
+                                let mut guard = first_err.borrow_mut();
+                                if guard.is_none() {
+                                    *guard = Some((String::new(), e));
+                                }
+                                return;
+                            }
+                        };
+                        match self.delete(&key).await {
+                            Ok(()) => deleted.set(deleted.get() + 1),
+                            Err(e) => {
+                                let mut guard = first_err.borrow_mut();
+                                if guard.is_none() {
+                                    *guard = Some((key, e));
+                                }
+                            }
+                        }
+                    }
+                })
+                .await;
+
+            match first_err.into_inner() {
+                Some((key, err)) => Err(io::Error::new(
+                    err.kind(),
+                    format!("delete_prefix: failed to delete {:?}: {}", key, err),
+                )),
+                None => Ok(deleted.get()),
+            }
+        }
+        .boxed_local()
+    }
+}
+
+/// Metadata about a blob object, obtained without downloading its content.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BlobMeta {
+    pub size: u64,
+    /// A textual representation of the object's last-modified time, in
+    /// whatever format the backing storage reports it.
+    pub last_modified: Option<String>,
+    /// An opaque, storage-specific version identifier (e.g. GCS's object
+    /// generation, S3's ETag). Only meaningful for comparing against another
+    /// value obtained from the same storage.
+    pub version: Option<String>,
+}
+
+/// A storage that can report an object's metadata without downloading it.
+pub trait StatableStorage {
+    /// Returns the metadata of `name`, or an error of kind
+    /// `io::ErrorKind::NotFound` if it doesn't exist.
+    fn stat(&self, name: &str) -> LocalBoxFuture<'_, io::Result<BlobMeta>>;
+}
+
+/// A storage that can copy an object to a new name server-side, without the
+/// caller reading and re-uploading its content.
+pub trait CopyableStorage {
+    /// Copies `from` to `to`, overwriting `to` if it already exists.
+    ///
+    /// Returns an error of kind `io::ErrorKind::NotFound` if `from` doesn't
+    /// exist.
+    fn copy(&self, from: &str, to: &str) -> LocalBoxFuture<'_, io::Result<()>>;
+}
+
+/// A storage that can overwrite an object while gating the write on the
+/// version already stored there, and report back the version it wrote.
+///
+/// This is the opt-in a caller needs to safely retry an upload of the same
+/// name: pass the generation it last observed as `expected_version` and the
+/// backing store rejects the write (rather than silently clobbering a newer
+/// generation written by a racing retry) if the object has since moved on.
+#[async_trait]
+pub trait VersionedStorage {
+    /// Write all contents of `reader` to `name`, returning the new object's
+    /// version on success (see [`BlobMeta::version`]).
+    ///
+    /// If `expected_version` is `Some`, the write only takes effect when
+    /// `name`'s current version matches it; a mismatch fails with an error
+    /// of kind `io::ErrorKind::AlreadyExists` rather than overwriting. If
+    /// `expected_version` is `None`, the write always takes effect,
+    /// overwriting whatever version (if any) is currently stored.
+    async fn put_versioned(
+        &self,
+        name: &str,
+        reader: PutResource<'_>,
+        content_length: u64,
+        expected_version: Option<&str>,
+    ) -> io::Result<Option<String>>;
+}
+
+/// Moves `from` to `to` on a storage that supports both copying and
+/// deleting, by copying and then removing the source.
+///
+/// This isn't atomic: a failure after the copy but before the delete leaves
+/// both `from` and `to` present, and callers that care must handle that
+/// themselves.
+pub async fn rename<S: CopyableStorage + DeletableStorage + ?Sized>(
+    storage: &S,
+    from: &str,
+    to: &str,
+) -> io::Result<()> {
+    storage.copy(from, to).await?;
+    storage.delete(from).await
 }
 
 #[track_caller]
@@ -69,9 +356,22 @@ pub fn unimplemented() -> io::Error {
     )
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct BlobObject {
     pub key: String,
+    /// The object's size in bytes, if the listing populated it. Only set
+    /// when the caller asked for metadata via [`ListOptions::need_meta`] and
+    /// the storage's listing honours it (GCS does; the S3 listing never sets
+    /// it); otherwise `None` rather than costing callers an extra `stat`
+    /// call they didn't ask for.
+    pub size: Option<u64>,
+    /// A textual representation of the object's last-modified time, in
+    /// whatever format the backing storage reports it. See
+    /// [`BlobMeta::last_modified`].
+    pub last_modified: Option<String>,
+    /// See [`BlobMeta::version`]. Set under the same condition as
+    /// [`Self::size`]; `None` otherwise.
+    pub version: Option<String>,
 }
 
 impl Display for BlobObject {
@@ -80,6 +380,42 @@ impl Display for BlobObject {
     }
 }
 
+/// Tuning knobs for [`IterableStorage::iter_prefix_opt`].
+///
+/// Builder-style: start from [`Default::default()`] and chain setters.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ListOptions {
+    /// The maximum number of objects the backing storage should return per
+    /// page, if it supports the concept. `None` leaves it up to the
+    /// storage's own default.
+    page_size: Option<u64>,
+    /// Whether `iter_prefix_opt` should populate `BlobObject::size` and
+    /// `BlobObject::last_modified`. Left off by default, since not every
+    /// caller needs metadata and some storages would need extra requests to
+    /// provide it.
+    need_meta: bool,
+}
+
+impl ListOptions {
+    pub fn page_size(mut self, page_size: u64) -> Self {
+        self.page_size = Some(page_size);
+        self
+    }
+
+    pub fn need_meta(mut self, need_meta: bool) -> Self {
+        self.need_meta = need_meta;
+        self
+    }
+
+    pub fn get_page_size(&self) -> Option<u64> {
+        self.page_size
+    }
+
+    pub fn get_need_meta(&self) -> bool {
+        self.need_meta
+    }
+}
+
 /// An storage that its content can be enumerated by prefix.
 pub trait IterableStorage {
     /// Walk the prefix of the blob storage.
@@ -88,6 +424,20 @@ pub trait IterableStorage {
         &self,
         prefix: &str,
     ) -> Pin<Box<dyn Stream<Item = std::result::Result<BlobObject, io::Error>> + '_>>;
+
+    /// Like [`Self::iter_prefix`], but lets the caller tune paging and
+    /// whether to populate object metadata via [`ListOptions`].
+    ///
+    /// The default implementation ignores `opt` and defers to
+    /// `iter_prefix`, so implementors that don't need per-page or metadata
+    /// control can leave this unimplemented.
+    fn iter_prefix_opt(
+        &self,
+        prefix: &str,
+        _opt: ListOptions,
+    ) -> Pin<Box<dyn Stream<Item = std::result::Result<BlobObject, io::Error>> + '_>> {
+        self.iter_prefix(prefix)
+    }
 }
 
 impl BlobConfig for dyn BlobStorage {
@@ -116,6 +466,21 @@ impl BlobStorage for Box<dyn BlobStorage> {
         fut.await
     }
 
+    async fn put_streaming(&self, name: &str, reader: PutResource<'_>) -> io::Result<u64> {
+        (**self).put_streaming(name, reader).await
+    }
+
+    async fn put_if_not_exists(
+        &self,
+        name: &str,
+        reader: PutResource<'_>,
+        content_length: u64,
+    ) -> io::Result<bool> {
+        (**self)
+            .put_if_not_exists(name, reader, content_length)
+            .await
+    }
+
     fn get(&self, name: &str) -> BlobStream<'_> {
         (**self).get(name)
     }
@@ -123,6 +488,10 @@ impl BlobStorage for Box<dyn BlobStorage> {
     fn get_part(&self, name: &str, off: u64, len: u64) -> BlobStream<'_> {
         (**self).get_part(name, off, len)
     }
+
+    fn get_suffix(&self, name: &str, len: u64) -> BlobStream<'_> {
+        (**self).get_suffix(name, len)
+    }
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -240,10 +609,185 @@ pub async fn read_to_end<R: AsyncRead>(r: R, v: &mut Vec<u8>) -> std::io::Result
 #[cfg(test)]
 mod tests {
     extern crate test;
-    use futures::AsyncReadExt;
+    use futures::{AsyncReadExt, stream::StreamExt};
 
     use super::*;
 
+    /// Delivers one byte immediately, then stalls forever (like a slow
+    /// upload whose next chunk hasn't arrived yet).
+    struct StallAfterFirstByte {
+        delivered: bool,
+    }
+
+    impl AsyncRead for StallAfterFirstByte {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            _cx: &mut std::task::Context<'_>,
+            buf: &mut [u8],
+        ) -> Poll<io::Result<usize>> {
+            let this = self.get_mut();
+            if !this.delivered {
+                this.delivered = true;
+                buf[0] = b'a';
+                Poll::Ready(Ok(1))
+            } else {
+                Poll::Pending
+            }
+        }
+    }
+
+    #[test]
+    fn test_cancellation_token_stops_read_promptly() {
+        use std::{future::Future, task::Context};
+
+        use futures::task::noop_waker_ref;
+
+        let token = CancellationToken::new();
+        let resource = PutResource(Box::new(StallAfterFirstByte { delivered: false }))
+            .with_cancellation(token.clone());
+        let mut data = Vec::new();
+        let mut fut = Box::pin(read_to_end(resource, &mut data));
+        let mut cx = Context::from_waker(noop_waker_ref());
+
+        // The mock delivers its one chunk, then the transfer stalls exactly
+        // like a slow, still-in-flight upload.
+        assert!(fut.as_mut().poll(&mut cx).is_pending());
+        assert_eq!(data, b"a");
+
+        // Cancelling mid-transfer must make the very next poll fail
+        // promptly, within that same chunk boundary, instead of waiting for
+        // the stalled reader to ever produce more data.
+        token.cancel();
+        match fut.as_mut().poll(&mut cx) {
+            Poll::Ready(Err(e)) => assert_eq!(e.kind(), io::ErrorKind::Interrupted),
+            other => panic!(
+                "expected a prompt cancellation error, got pending={}",
+                other.is_pending()
+            ),
+        }
+    }
+
+    #[test]
+    fn test_cancellation_token_is_shared_across_clones() {
+        let token = CancellationToken::new();
+        let clone = token.clone();
+        assert!(!clone.is_cancelled());
+        token.cancel();
+        assert!(clone.is_cancelled());
+    }
+
+    #[test]
+    fn test_list_options_builder() {
+        let opt = ListOptions::default();
+        assert_eq!(opt.get_page_size(), None);
+        assert!(!opt.get_need_meta());
+
+        let opt = ListOptions::default().page_size(16).need_meta(true);
+        assert_eq!(opt.get_page_size(), Some(16));
+        assert!(opt.get_need_meta());
+    }
+
+    struct KeysOnlyStorage(&'static [&'static str]);
+
+    impl IterableStorage for KeysOnlyStorage {
+        fn iter_prefix(
+            &self,
+            _prefix: &str,
+        ) -> Pin<Box<dyn Stream<Item = std::result::Result<BlobObject, io::Error>> + '_>> {
+            Box::pin(futures::stream::iter(self.0.iter().map(|k| {
+                Ok(BlobObject {
+                    key: (*k).to_owned(),
+                    ..Default::default()
+                })
+            })))
+        }
+    }
+
+    #[test]
+    fn test_iter_prefix_opt_default_delegates_to_iter_prefix() {
+        use futures::executor::block_on;
+
+        let storage = KeysOnlyStorage(&["a", "b", "c"]);
+        let keys: Vec<_> = block_on(
+            storage
+                .iter_prefix_opt("", ListOptions::default().page_size(1).need_meta(true))
+                .map(|r| r.unwrap().key)
+                .collect::<Vec<_>>(),
+        );
+        assert_eq!(keys, vec!["a", "b", "c"]);
+    }
+
+    /// A stub storage whose listing is split across several pages (emulated
+    /// by chunking `keys`) and whose `delete` fails for whatever keys are
+    /// named in `failing`.
+    struct StubStorage {
+        pages: Vec<Vec<&'static str>>,
+        failing: &'static [&'static str],
+        delete_attempts: Cell<u64>,
+    }
+
+    impl IterableStorage for StubStorage {
+        fn iter_prefix(
+            &self,
+            _prefix: &str,
+        ) -> Pin<Box<dyn Stream<Item = std::result::Result<BlobObject, io::Error>> + '_>> {
+            let keys: Vec<&'static str> = self.pages.iter().flatten().copied().collect();
+            Box::pin(futures::stream::iter(keys.into_iter().map(|k| {
+                Ok(BlobObject {
+                    key: k.to_owned(),
+                    ..Default::default()
+                })
+            })))
+        }
+    }
+
+    impl DeletableStorage for StubStorage {
+        fn delete(&self, name: &str) -> LocalBoxFuture<'_, io::Result<()>> {
+            self.delete_attempts.set(self.delete_attempts.get() + 1);
+            let failed = self.failing.contains(&name);
+            let name = name.to_owned();
+            async move {
+                if failed {
+                    Err(io::Error::new(
+                        io::ErrorKind::Other,
+                        format!("stub delete failure for {}", name),
+                    ))
+                } else {
+                    Ok(())
+                }
+            }
+            .boxed_local()
+        }
+    }
+
+    #[test]
+    fn test_delete_prefix_deletes_everything_listed() {
+        use futures::executor::block_on;
+
+        let storage = StubStorage {
+            pages: vec![vec!["a", "b"], vec!["c", "d"], vec!["e"]],
+            failing: &[],
+            delete_attempts: Cell::new(0),
+        };
+        let deleted = block_on(storage.delete_prefix("")).unwrap();
+        assert_eq!(deleted, 5);
+        assert_eq!(storage.delete_attempts.get(), 5);
+    }
+
+    #[test]
+    fn test_delete_prefix_reports_first_failure_but_attempts_the_rest() {
+        use futures::executor::block_on;
+
+        let storage = StubStorage {
+            pages: vec![vec!["a", "b"], vec!["c", "d"], vec!["e"]],
+            failing: &["c"],
+            delete_attempts: Cell::new(0),
+        };
+        let err = block_on(storage.delete_prefix_with_concurrency("", 1)).unwrap_err();
+        assert!(err.to_string().contains('c'));
+        assert_eq!(storage.delete_attempts.get(), 5);
+    }
+
     #[test]
     fn test_url_of_bucket() {
         let bucket_name = StringNonEmpty::required("bucket".to_owned()).unwrap();
@@ -18,4 +18,7 @@ pub use kms::{Config, DataKeyPair, EncryptedKey, KeyId, KmsProvider, PlainKey, S
 pub mod blob;
 pub use blob::{BucketConf, StringNonEmpty, none_to_empty};
 
+pub mod retry_reader;
+pub use retry_reader::{RetryableBlobReader, is_retryable};
+
 pub mod metrics;
@@ -0,0 +1,182 @@
+// Copyright 2026 TiKV Project Authors. Licensed under Apache-2.0.
+
+//! An [`AsyncRead`] adapter that transparently resumes a blob download from
+//! the last delivered byte when the underlying stream is interrupted
+//! mid-body, instead of forcing callers to buffer the whole object in
+//! memory to work around awkward retry semantics.
+
+use std::{
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_io::AsyncRead;
+
+use crate::blob::BlobStream;
+
+/// Returns whether `err` represents a transient, mid-body interruption that
+/// is worth retrying by re-issuing the request for the remaining bytes,
+/// rather than a permanent failure (e.g. the object no longer existing).
+pub fn is_retryable(err: &io::Error) -> bool {
+    matches!(
+        err.kind(),
+        io::ErrorKind::Interrupted
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::UnexpectedEof
+    )
+}
+
+/// Wraps a [`BlobStream`] of known length and, on a retryable error (see
+/// [`is_retryable`]), re-issues a request for the bytes not yet delivered
+/// rather than surfacing the error to the caller.
+///
+/// `fetch` is called with `(delivered, remaining)`, where `delivered` is the
+/// number of bytes this reader has already yielded and `remaining` is the
+/// number of bytes still owed. This shape covers both ranged reads (the
+/// closure adds `delivered` to a captured starting offset) and suffix reads
+/// (the closure ignores `delivered` and just asks for the last `remaining`
+/// bytes, which is exactly the tail still owed).
+///
+/// Re-issuing a request counts against `max_retries`; once exhausted, the
+/// next retryable error is returned to the caller instead of being retried
+/// again.
+pub struct RetryableBlobReader<'a> {
+    fetch: Box<dyn FnMut(u64, u64) -> BlobStream<'a> + Send + 'a>,
+    current: BlobStream<'a>,
+    delivered: u64,
+    remaining: u64,
+    retries_left: usize,
+}
+
+impl<'a> RetryableBlobReader<'a> {
+    /// Creates a reader expected to deliver exactly `len` bytes, pulling the
+    /// initial stream from `fetch(0, len)` and allowing up to `max_retries`
+    /// resumptions over its lifetime.
+    pub fn new(
+        mut fetch: Box<dyn FnMut(u64, u64) -> BlobStream<'a> + Send + 'a>,
+        len: u64,
+        max_retries: usize,
+    ) -> Self {
+        let current = fetch(0, len);
+        Self {
+            fetch,
+            current,
+            delivered: 0,
+            remaining: len,
+            retries_left: max_retries,
+        }
+    }
+}
+
+impl AsyncRead for RetryableBlobReader<'_> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        loop {
+            match Pin::new(&mut self.current).poll_read(cx, buf) {
+                Poll::Ready(Ok(n)) => {
+                    self.delivered += n as u64;
+                    self.remaining -= n as u64;
+                    return Poll::Ready(Ok(n));
+                }
+                Poll::Ready(Err(e)) if self.remaining > 0 && is_retryable(&e) => {
+                    if self.retries_left == 0 {
+                        return Poll::Ready(Err(e));
+                    }
+                    self.retries_left -= 1;
+                    self.current = (self.fetch)(self.delivered, self.remaining);
+                }
+                other => return other,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use futures::{AsyncReadExt, executor::block_on};
+
+    use super::*;
+
+    /// A `BlobStream` that yields `chunk` once and then fails with `err`,
+    /// used to simulate a connection that drops partway through a body.
+    fn failing_chunk<'a>(chunk: &'a [u8], err: io::ErrorKind) -> BlobStream<'a> {
+        Box::new(futures::io::Cursor::new(chunk).chain(AlwaysErr(err)))
+    }
+
+    struct AlwaysErr(io::ErrorKind);
+
+    impl AsyncRead for AlwaysErr {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            _buf: &mut [u8],
+        ) -> Poll<io::Result<usize>> {
+            Poll::Ready(Err(io::Error::new(self.0, "simulated connection drop")))
+        }
+    }
+
+    #[test]
+    fn test_resumes_after_interruption_and_delivers_full_object_once() {
+        let object = b"the quick brown fox jumps over the lazy dog";
+        let fetch_calls = AtomicUsize::new(0);
+
+        let reader = RetryableBlobReader::new(
+            Box::new(move |off, remaining| -> BlobStream<'static> {
+                let call = fetch_calls.fetch_add(1, Ordering::SeqCst);
+                let off = off as usize;
+                let remaining = remaining as usize;
+                let tail = &object[off..off + remaining];
+                if call == 0 {
+                    // Drop the connection after delivering only half.
+                    failing_chunk(&tail[..remaining / 2], io::ErrorKind::ConnectionReset)
+                } else {
+                    Box::new(futures::io::Cursor::new(tail))
+                }
+            }),
+            object.len() as u64,
+            /* max_retries */ 2,
+        );
+
+        let mut buf = Vec::new();
+        block_on(async {
+            let mut reader = reader;
+            reader.read_to_end(&mut buf).await.unwrap();
+        });
+        assert_eq!(buf, object);
+    }
+
+    #[test]
+    fn test_surfaces_hard_error_once_retry_budget_is_exhausted() {
+        let object = b"0123456789";
+
+        let reader = RetryableBlobReader::new(
+            Box::new(move |off, remaining| -> BlobStream<'static> {
+                let off = off as usize;
+                let remaining = remaining as usize;
+                let tail = &object[off..off + remaining];
+                // Every attempt delivers one byte and then drops again, so
+                // the budget is exhausted well before the object completes.
+                failing_chunk(&tail[..1.min(tail.len())], io::ErrorKind::Interrupted)
+            }),
+            object.len() as u64,
+            /* max_retries */ 3,
+        );
+
+        let mut buf = Vec::new();
+        let result = block_on(async {
+            let mut reader = reader;
+            reader.read_to_end(&mut buf).await
+        });
+        let err = result.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Interrupted);
+        // One byte per attempt: the initial fetch plus all 3 retries.
+        assert_eq!(buf.len(), 4);
+    }
+}
@@ -0,0 +1,279 @@
+// Copyright 2026 TiKV Project Authors. Licensed under Apache-2.0.
+
+//! A stable C ABI over [`tidb_query_datatype`]'s collation sort keys, for
+//! external tooling (e.g. a standalone key-inspection CLI, or bindings from
+//! another language) that needs byte-for-byte identical sort-key and
+//! comparison semantics to a running TiKV, without linking the rest of the
+//! query engine.
+//!
+//! Every exported function is `catch_unwind`-wrapped: a panic anywhere in
+//! the collation code is turned into [`TikvCollateStatus::Panic`] instead of
+//! unwinding across the FFI boundary, which is undefined behavior.
+
+use std::{cmp::Ordering, panic::catch_unwind, slice};
+
+use tidb_query_datatype::{
+    Collation,
+    codec::collation::Collator,
+    match_template_collator,
+};
+
+/// Bumped whenever a change to a collation's sort-key encoding would make
+/// sort keys produced by a different version incomparable with this one.
+/// Callers that persist sort keys across upgrades should record this
+/// alongside them.
+pub const TIKV_COLLATE_ABI_VERSION: u32 = 1;
+
+/// Result code shared by every function in this crate.
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TikvCollateStatus {
+    Ok = 0,
+    /// `collation_id` doesn't match a collation TiKV knows about, or the
+    /// collation rejected the input (e.g. bytes invalid in its charset).
+    InvalidCollation = -1,
+    /// `out_cap` is too small to hold the sort key; no partial data is
+    /// written.
+    BufferTooSmall = -2,
+    /// The collation code panicked; `out`/`out_ordering` are left untouched.
+    Panic = -3,
+}
+
+fn sort_key_for(collation_id: i32, input: &[u8]) -> Result<Vec<u8>, TikvCollateStatus> {
+    let collation =
+        Collation::from_i32(collation_id).map_err(|_| TikvCollateStatus::InvalidCollation)?;
+    match_template_collator! {
+        TT, match collation {
+            Collation::TT => TT::sort_key(input).map_err(|_| TikvCollateStatus::InvalidCollation),
+        }
+    }
+}
+
+fn compare_for(collation_id: i32, a: &[u8], b: &[u8]) -> Result<Ordering, TikvCollateStatus> {
+    let collation =
+        Collation::from_i32(collation_id).map_err(|_| TikvCollateStatus::InvalidCollation)?;
+    match_template_collator! {
+        TT, match collation {
+            Collation::TT => {
+                TT::sort_compare(a, b, false).map_err(|_| TikvCollateStatus::InvalidCollation)
+            }
+        }
+    }
+}
+
+/// Returns the ABI version implemented by this build. See
+/// [`TIKV_COLLATE_ABI_VERSION`].
+#[no_mangle]
+pub extern "C" fn tikv_collate_abi_version() -> u32 {
+    TIKV_COLLATE_ABI_VERSION
+}
+
+/// Writes the sort key of `input[..input_len]` under `collation_id` into
+/// `out[..out_cap]`, and stores the number of bytes written into
+/// `*written_len`.
+///
+/// # Safety
+///
+/// `input` must be valid for reads of `input_len` bytes (or null if
+/// `input_len` is 0). `out` must be valid for writes of `out_cap` bytes (or
+/// null if `out_cap` is 0). `written_len` must be a valid pointer to a
+/// `usize`. `out` and `input` must not overlap.
+#[no_mangle]
+pub unsafe extern "C" fn tikv_collate_sort_key(
+    collation_id: i32,
+    input: *const u8,
+    input_len: usize,
+    out: *mut u8,
+    out_cap: usize,
+    written_len: *mut usize,
+) -> TikvCollateStatus {
+    let result = catch_unwind(|| {
+        let input = unsafe { bytes_from_raw(input, input_len) };
+        let sort_key = sort_key_for(collation_id, input)?;
+        if sort_key.len() > out_cap {
+            return Err(TikvCollateStatus::BufferTooSmall);
+        }
+        unsafe {
+            if !sort_key.is_empty() {
+                std::ptr::copy_nonoverlapping(sort_key.as_ptr(), out, sort_key.len());
+            }
+            *written_len = sort_key.len();
+        }
+        Ok(())
+    });
+    match result {
+        Ok(Ok(())) => TikvCollateStatus::Ok,
+        Ok(Err(status)) => status,
+        Err(_) => TikvCollateStatus::Panic,
+    }
+}
+
+/// Compares `a[..a_len]` and `b[..b_len]` under `collation_id`, storing -1, 0
+/// or 1 into `*out_ordering`.
+///
+/// # Safety
+///
+/// `a` must be valid for reads of `a_len` bytes (or null if `a_len` is 0),
+/// likewise for `b`/`b_len`. `out_ordering` must be a valid pointer to an
+/// `i32`.
+#[no_mangle]
+pub unsafe extern "C" fn tikv_collate_compare(
+    collation_id: i32,
+    a: *const u8,
+    a_len: usize,
+    b: *const u8,
+    b_len: usize,
+    out_ordering: *mut i32,
+) -> TikvCollateStatus {
+    let result = catch_unwind(|| {
+        let a = unsafe { bytes_from_raw(a, a_len) };
+        let b = unsafe { bytes_from_raw(b, b_len) };
+        let ordering = compare_for(collation_id, a, b)?;
+        unsafe {
+            *out_ordering = match ordering {
+                Ordering::Less => -1,
+                Ordering::Equal => 0,
+                Ordering::Greater => 1,
+            };
+        }
+        Ok(())
+    });
+    match result {
+        Ok(Ok(())) => TikvCollateStatus::Ok,
+        Ok(Err(status)) => status,
+        Err(_) => TikvCollateStatus::Panic,
+    }
+}
+
+/// # Safety
+/// See callers: `ptr` must be valid for reads of `len` bytes, or null when
+/// `len` is 0.
+unsafe fn bytes_from_raw<'a>(ptr: *const u8, len: usize) -> &'a [u8] {
+    if len == 0 {
+        &[]
+    } else {
+        unsafe { slice::from_raw_parts(ptr, len) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Collation ids from `tidb_query_datatype::def::field_type::Collation`.
+    const UTF8MB4_BIN: i32 = -46;
+    const UTF8MB4_GENERAL_CI: i32 = -45;
+
+    #[test]
+    fn test_round_trip_sort_key() {
+        let input = b"Hello";
+        let mut out = [0u8; 64];
+        let mut written = 0usize;
+        let status = unsafe {
+            tikv_collate_sort_key(
+                UTF8MB4_BIN,
+                input.as_ptr(),
+                input.len(),
+                out.as_mut_ptr(),
+                out.len(),
+                &mut written,
+            )
+        };
+        assert_eq!(status, TikvCollateStatus::Ok);
+        let expected = sort_key_for(UTF8MB4_BIN, input).unwrap();
+        assert_eq!(&out[..written], expected.as_slice());
+    }
+
+    #[test]
+    fn test_sort_key_buffer_too_small() {
+        let input = b"a long enough input to need a real buffer";
+        let mut out = [0u8; 1];
+        let mut written = usize::MAX;
+        let status = unsafe {
+            tikv_collate_sort_key(
+                UTF8MB4_BIN,
+                input.as_ptr(),
+                input.len(),
+                out.as_mut_ptr(),
+                out.len(),
+                &mut written,
+            )
+        };
+        assert_eq!(status, TikvCollateStatus::BufferTooSmall);
+        // Nothing should have been written on failure.
+        assert_eq!(written, usize::MAX);
+    }
+
+    #[test]
+    fn test_sort_key_invalid_collation() {
+        let input = b"x";
+        let mut out = [0u8; 64];
+        let mut written = 0usize;
+        let status = unsafe {
+            tikv_collate_sort_key(
+                i32::MIN,
+                input.as_ptr(),
+                input.len(),
+                out.as_mut_ptr(),
+                out.len(),
+                &mut written,
+            )
+        };
+        assert_eq!(status, TikvCollateStatus::InvalidCollation);
+    }
+
+    #[test]
+    fn test_compare_case_insensitive() {
+        let a = b"HELLO";
+        let b = b"hello";
+        let mut ordering = 42;
+        let status = unsafe {
+            tikv_collate_compare(
+                UTF8MB4_GENERAL_CI,
+                a.as_ptr(),
+                a.len(),
+                b.as_ptr(),
+                b.len(),
+                &mut ordering,
+            )
+        };
+        assert_eq!(status, TikvCollateStatus::Ok);
+        assert_eq!(ordering, 0);
+
+        let status = unsafe {
+            tikv_collate_compare(
+                UTF8MB4_BIN,
+                a.as_ptr(),
+                a.len(),
+                b.as_ptr(),
+                b.len(),
+                &mut ordering,
+            )
+        };
+        assert_eq!(status, TikvCollateStatus::Ok);
+        assert_ne!(ordering, 0);
+    }
+
+    #[test]
+    fn test_empty_input_uses_null_pointer() {
+        let mut out = [0u8; 8];
+        let mut written = usize::MAX;
+        let status = unsafe {
+            tikv_collate_sort_key(
+                UTF8MB4_BIN,
+                std::ptr::null(),
+                0,
+                out.as_mut_ptr(),
+                out.len(),
+                &mut written,
+            )
+        };
+        assert_eq!(status, TikvCollateStatus::Ok);
+        assert_eq!(written, 0);
+    }
+
+    #[test]
+    fn test_abi_version_is_stable() {
+        assert_eq!(tikv_collate_abi_version(), TIKV_COLLATE_ABI_VERSION);
+    }
+}
@@ -445,10 +445,10 @@ numeric_enum_serializing_mod! {compaction_style_serde DBCompactionStyle {
 }}
 
 numeric_enum_serializing_mod! {recovery_mode_serde DBRecoveryMode {
-    TolerateCorruptedTailRecords = 0,
+    TolerateCorruptedTailRecords = 0, aliases: ["tolerate-corrupted-tail-record"],
     AbsoluteConsistency = 1,
-    PointInTime = 2,
-    SkipAnyCorruptedRecords = 3,
+    PointInTime = 2, aliases: ["point-in-time-recovery"],
+    SkipAnyCorruptedRecords = 3, aliases: ["skip-any-corrupted-record"],
 }}
 
 #[cfg(test)]
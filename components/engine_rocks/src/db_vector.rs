@@ -10,6 +10,13 @@ use rocksdb::DBVector as RawDBVector;
 
 pub struct RocksDbVector(RawDBVector);
 
+// `RawDBVector` pins a slice owned by RocksDB (either in the block cache or a
+// backing `PinnableSlice`); like `RocksSnapshot`, that pinned memory is safe
+// to hand to another thread as long as it isn't mutated concurrently, which
+// this read-only wrapper never does.
+unsafe impl Send for RocksDbVector {}
+unsafe impl Sync for RocksDbVector {}
+
 impl RocksDbVector {
     pub fn from_raw(raw: RawDBVector) -> RocksDbVector {
         RocksDbVector(raw)
@@ -243,6 +243,12 @@ impl Peekable for RocksEngine {
             .map_err(r2e)?;
         Ok(v.map(RocksDbVector::from_raw))
     }
+
+    fn key_may_exist_cf_opt(&self, opts: &ReadOptions, cf: &str, key: &[u8]) -> Result<bool> {
+        let opt: RocksReadOptions = opts.into();
+        let handle = get_cf_handle(&self.db, cf)?;
+        Ok(self.db.key_may_exist_cf_opt(handle, key, &opt.into_raw()))
+    }
 }
 
 impl SyncMutable for RocksEngine {
@@ -97,6 +97,16 @@ impl Peekable for RocksSnapshot {
         let v = self.db.get_cf_opt(handle, key, &opt).map_err(r2e)?;
         Ok(v.map(RocksDbVector::from_raw))
     }
+
+    fn key_may_exist_cf_opt(&self, opts: &ReadOptions, cf: &str, key: &[u8]) -> Result<bool> {
+        let opt: RocksReadOptions = opts.into();
+        let mut opt = opt.into_raw();
+        unsafe {
+            opt.set_snapshot(&self.snap);
+        }
+        let handle = get_cf_handle(self.db.as_ref(), cf)?;
+        Ok(self.db.key_may_exist_cf_opt(handle, key, &opt))
+    }
 }
 
 impl CfNamesExt for RocksSnapshot {
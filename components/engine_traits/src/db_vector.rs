@@ -6,4 +6,7 @@ use std::{fmt::Debug, ops::Deref};
 ///
 /// The database may optimize this type to be a view into
 /// its own cache.
-pub trait DbVector: Debug + Deref<Target = [u8]> + for<'a> PartialEq<&'a [u8]> {}
+///
+/// `Send + Sync` so callers can pin a value across an await point (e.g. while
+/// building a response) instead of copying it out eagerly.
+pub trait DbVector: Debug + Deref<Target = [u8]> + for<'a> PartialEq<&'a [u8]> + Send + Sync {}
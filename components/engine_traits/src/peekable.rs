@@ -48,6 +48,19 @@ pub trait Peekable {
         self.get_value_cf_opt(&ReadOptions::default(), cf, key)
     }
 
+    /// Cheaply checks, using the column family's bloom filter (and memtables),
+    /// whether `key` *might* be present in `cf`.
+    ///
+    /// A `false` result is authoritative: the key is definitely absent and a
+    /// full read can be skipped. A `true` result is not: the key may or may
+    /// not actually exist and callers must still fall back to a real read to
+    /// confirm.
+    ///
+    /// The default implementation is conservative and never skips a read.
+    fn key_may_exist_cf_opt(&self, _opts: &ReadOptions, _cf: &str, _key: &[u8]) -> Result<bool> {
+        Ok(true)
+    }
+
     /// Read a value and return it as a protobuf message.
     fn get_msg<M: protobuf::Message + Default>(&self, key: &[u8]) -> Result<Option<M>> {
         let value = self.get_value(key)?;
@@ -8,6 +8,7 @@ fn main() {
     let err_codes = vec![
         cloud::ALL_ERROR_CODES.iter(),
         codec::ALL_ERROR_CODES.iter(),
+        config::ALL_ERROR_CODES.iter(),
         coprocessor::ALL_ERROR_CODES.iter(),
         encryption::ALL_ERROR_CODES.iter(),
         engine::ALL_ERROR_CODES.iter(),
@@ -0,0 +1,11 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+define_error_codes!(
+    "KV:Config:",
+
+    LIMIT => ("Limit", "", ""),
+    ADDRESS => ("Address", "", ""),
+    STORE_LABELS => ("StoreLabels", "", ""),
+    VALUE => ("Value", "", ""),
+    FILE_SYSTEM => ("FileSystem", "", "")
+);
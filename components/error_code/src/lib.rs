@@ -32,6 +32,7 @@ pub mod backup_stream;
 pub mod causal_ts;
 pub mod cloud;
 pub mod codec;
+pub mod config;
 pub mod coprocessor;
 pub mod encryption;
 pub mod engine;
@@ -77,7 +77,9 @@ fn create_backend(
                 info!("external storage selected: gcp_v2");
                 blob_store(GcsStorageV2::from_input(config.clone())?)
             } else {
-                blob_store(GcsStorage::from_input(config.clone())?)
+                let mut s = GcsStorage::from_input(config.clone())?;
+                s.set_client_config(backend_config.gcs_client_config)?;
+                blob_store(s)
             }
         }
         Backend::AzureBlobStorage(config) => blob_store(AzureStorage::from_input(config.clone())?),
@@ -73,6 +73,11 @@ pub struct BackendConfig {
     pub s3_multi_part_size: usize,
     pub gcp_v2_enable: bool,
     pub hdfs_config: HdfsConfig,
+    /// Request timeout / connection pool tuning for the GCS backend. Not
+    /// part of the `Gcs` protobuf (generated from the `kvproto` git
+    /// dependency, which this crate can't extend), so it's threaded in here
+    /// the same way `s3_multi_part_size` is for S3.
+    pub gcs_client_config: gcp::ClientConfig,
 }
 
 #[derive(Debug, Default)]
@@ -224,6 +224,7 @@ impl ExternalStorage for LocalStorage {
                         )),
                         Ok(item) => futures::future::ok(BlobObject{
                             key: item.to_string_lossy().into_owned(),
+                            ..Default::default()
                         })
                     }
             })
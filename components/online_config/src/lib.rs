@@ -26,6 +26,8 @@ pub enum ConfigValue {
     // We cannot use Schedule(ReadableSchedule) directly as the module defining `ReadableSchedule`
     // imports the current module
     Schedule(Vec<String>),
+    // Same reasoning as `Schedule`: each entry is the `Display` form of a `ReadableWindow`.
+    Windows(Vec<String>),
     Skip,
     None,
 }
@@ -44,6 +46,7 @@ impl Display for ConfigValue {
             ConfigValue::String(v) => write!(f, "{}", v),
             ConfigValue::Module(v) => write!(f, "{:?}", v),
             ConfigValue::Schedule(v) => write!(f, "{:?}", v),
+            ConfigValue::Windows(v) => write!(f, "{:?}", v),
             ConfigValue::Skip => write!(f, "ConfigValue::Skip"),
             ConfigValue::None => write!(f, ""),
         }
@@ -67,6 +67,12 @@ pub enum ConsistencyCheckMethod {
 
     /// Does consistency check for regions based on MVCC.
     Mvcc = 1,
+
+    /// Compares the persisted value of each region's incremental
+    /// `raftstore::store::hash_chain::HashChain`, instead of scanning the
+    /// region's data. See `hash_chain`'s module docs for the caveats around
+    /// enabling it after a region already has data.
+    HashChain = 2,
 }
 
 /// Default region split size. In version < 8.3.0, the default split size is
@@ -331,6 +331,19 @@ pub struct Config {
     // we still allow big raft batch for better throughput.
     pub apply_yield_write_size: ReadableSize,
 
+    /// Delay flushing an apply write batch to the engine as long as it stays
+    /// under `apply_write_coalesce_max_bytes`, so that several small raft
+    /// apply rounds can be merged into a single RocksDB write.
+    ///
+    /// Set to 0 (the default) to disable coalescing and flush every round as
+    /// before. A write batch is never delayed once it requires a synced WAL
+    /// write, so `sync-log` latency is unaffected.
+    pub apply_write_coalesce_max_delay: ReadableDuration,
+    /// The byte budget used together with `apply_write_coalesce_max_delay`:
+    /// once a pending apply write batch reaches this size it is flushed
+    /// immediately, regardless of how long it has been pending.
+    pub apply_write_coalesce_max_bytes: ReadableSize,
+
     #[serde(with = "perf_level_serde")]
     #[online_config(skip)]
     pub perf_level: PerfLevel,
@@ -487,6 +500,14 @@ pub struct Config {
     #[serde(alias = "enable-partitioned-raft-kv-compatible-learner")]
     pub enable_v2_compatible_learner: bool,
 
+    /// Fold every applied write into a per-region incremental hash chain
+    /// (see `raftstore::store::hash_chain`) and persist it next to the apply
+    /// state, so consistency checking can compare it far more often than a
+    /// full-scan hash. Off by default because it adds a hash update to every
+    /// applied write.
+    #[online_config(hidden)]
+    pub enable_hash_chain_consistency_check: bool,
+
     /// The minimal count of region pending on applying raft logs.
     /// Only when the count of regions which not pending on applying logs is
     /// less than the threshold, can the raftstore supply service.
@@ -618,6 +639,8 @@ impl Default for Config {
             dev_assert: false,
             apply_yield_duration: ReadableDuration::millis(500),
             apply_yield_write_size: ReadableSize::kb(32),
+            apply_write_coalesce_max_delay: ReadableDuration::millis(0),
+            apply_write_coalesce_max_bytes: ReadableSize::kb(64),
             perf_level: PerfLevel::Uninitialized,
             evict_cache_on_memory_ratio: 0.1,
             cmd_batch: true,
@@ -672,6 +695,7 @@ impl Default for Config {
             // TODO: make its value reasonable
             check_request_snapshot_interval: ReadableDuration::minutes(1),
             enable_v2_compatible_learner: false,
+            enable_hash_chain_consistency_check: false,
             unsafe_disable_check_quorum: false,
             min_pending_apply_region_count: 10,
             check_then_compact_force_bottommost_level: true,
@@ -1097,6 +1121,18 @@ impl Config {
             ));
         }
 
+        self.periodic_full_compact_start_times
+            .validate()
+            .map_err(|e| box_err!("invalid periodic-full-compact-start-times: {}", e))?;
+
+        if !self.apply_write_coalesce_max_delay.is_zero() && self.apply_write_coalesce_max_bytes.0 == 0
+        {
+            return Err(box_err!(
+                "apply-write-coalesce-max-bytes must be greater than 0 when \
+                 apply-write-coalesce-max-delay is set"
+            ));
+        }
+
         Ok(())
     }
 
@@ -1324,6 +1360,12 @@ impl Config {
         CONFIG_RAFTSTORE_GAUGE
             .with_label_values(&["apply_yield_write_size"])
             .set(self.apply_yield_write_size.0 as f64);
+        CONFIG_RAFTSTORE_GAUGE
+            .with_label_values(&["apply_write_coalesce_max_delay"])
+            .set(self.apply_write_coalesce_max_delay.as_secs_f64());
+        CONFIG_RAFTSTORE_GAUGE
+            .with_label_values(&["apply_write_coalesce_max_bytes"])
+            .set(self.apply_write_coalesce_max_bytes.0 as f64);
         CONFIG_RAFTSTORE_GAUGE
             .with_label_values(&["perf_level"])
             .set(self.perf_level as u64 as f64);
@@ -1826,5 +1868,16 @@ mod tests {
         cfg.inspect_kvdb_interval = ReadableDuration::millis(1);
         cfg.tune_inspector_configs(true, ReadableDuration::millis(100));
         assert_eq!(cfg.inspect_kvdb_interval, ReadableDuration::millis(1));
+
+        cfg = Config::new();
+        cfg.apply_write_coalesce_max_delay = ReadableDuration::millis(100);
+        cfg.apply_write_coalesce_max_bytes = ReadableSize(0);
+        cfg.optimize_for(false);
+        cfg.validate(split_size, false, ReadableSize(0), false)
+            .unwrap_err();
+        cfg.apply_write_coalesce_max_bytes = ReadableSize::kb(64);
+        cfg.optimize_for(false);
+        cfg.validate(split_size, false, ReadableSize(0), false)
+            .unwrap();
     }
 }
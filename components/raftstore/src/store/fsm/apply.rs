@@ -80,7 +80,7 @@ use crate::{
         RegionState, WriteBatchWrapper,
     },
     store::{
-        Config, RegionSnapshot, SnapGenTask, WriteCallback, cmd_resp,
+        Config, HashChain, RegionSnapshot, SnapGenTask, WriteCallback, cmd_resp,
         entry_storage::{self, CachedEntries},
         fsm::RaftPollerBuilder,
         local_metrics::RaftMetrics,
@@ -413,11 +413,23 @@ where
     // Whether to use the delete range API instead of deleting one by one.
     use_delete_range: bool,
 
+    // See `Config::enable_hash_chain_consistency_check`.
+    enable_hash_chain: bool,
+
     perf_context: EK::PerfContext,
 
     yield_duration: Duration,
     yield_msg_size: u64,
 
+    /// See [`crate::store::Config::apply_write_coalesce_max_delay`]. Zero
+    /// disables coalescing.
+    write_coalesce_max_delay: Duration,
+    /// See [`crate::store::Config::apply_write_coalesce_max_bytes`].
+    write_coalesce_max_bytes: u64,
+    /// Set the first time a `flush` defers writing a non-empty `kv_wb`
+    /// because of coalescing, cleared once that batch is actually written.
+    pending_flush_since: Option<Instant>,
+
     store_id: u64,
     /// region_id -> (peer_id, is_splitting)
     /// Used for handling race between splitting and creating new peer.
@@ -515,9 +527,13 @@ where
             committed_count: 0,
             sync_log_hint: false,
             use_delete_range: cfg.use_delete_range,
+            enable_hash_chain: cfg.enable_hash_chain_consistency_check,
             perf_context: EK::get_perf_context(cfg.perf_level, PerfContextKind::RaftstoreApply),
             yield_duration: cfg.apply_yield_duration.0,
             yield_msg_size: cfg.apply_yield_write_size.0,
+            write_coalesce_max_delay: cfg.apply_write_coalesce_max_delay.0,
+            write_coalesce_max_bytes: cfg.apply_write_coalesce_max_bytes.0,
+            pending_flush_since: None,
             delete_ssts: vec![],
             pending_delete_ssts: vec![],
             store_id,
@@ -790,14 +806,45 @@ where
         &mut self.kv_wb
     }
 
+    /// Whether this round's flush should be held back so that more small
+    /// apply write batches can be coalesced into a single RocksDB write, per
+    /// `apply_write_coalesce_max_delay`/`apply_write_coalesce_max_bytes`.
+    ///
+    /// A write batch that would need a synced WAL write is never delayed:
+    /// coalescing must not add latency to `sync-log` writes.
+    fn should_defer_flush(&mut self) -> bool {
+        if self.write_coalesce_max_delay == Duration::ZERO || self.kv_wb().is_empty() {
+            return false;
+        }
+        if self.sync_log_hint && !self.disable_wal {
+            return false;
+        }
+        if self.kv_wb().data_size() as u64 >= self.write_coalesce_max_bytes {
+            return false;
+        }
+        let pending_since = *self.pending_flush_since.get_or_insert_with(Instant::now_coarse);
+        pending_since.saturating_elapsed() < self.write_coalesce_max_delay
+    }
+
     /// Flush all pending writes to engines.
     /// If it returns true, all pending writes are persisted in engines.
+    ///
+    /// The write can be held back instead, per `should_defer_flush`; in that
+    /// case this returns `false` and the pending writes are picked up by a
+    /// later call once they can no longer be delayed.
     pub fn flush(&mut self) -> bool {
         // TODO: this check is too hacky, need to be more verbose and less buggy.
-        let t = match self.timer.take() {
-            Some(t) => t,
-            None => return false,
-        };
+        if self.timer.is_none() {
+            return false;
+        }
+        if self.should_defer_flush() {
+            // Keep the timer, `committed_count` and pending callbacks so the
+            // deferred data is accounted for once a later round actually
+            // flushes it.
+            return false;
+        }
+        let t = self.timer.take().unwrap();
+        self.pending_flush_since = None;
 
         // Write to engine
         // raftstore.sync-log = true means we need prevent data loss when power failure.
@@ -1112,6 +1159,11 @@ where
     /// apply_index may synced to file, but KV data may not synced to file,
     /// so we will lose data.
     apply_state: RaftApplyState,
+    /// The running value of the region's incremental consistency-check hash
+    /// chain, folded on every applied write when
+    /// `Config::enable_hash_chain_consistency_check` is set and persisted
+    /// next to `apply_state`. See `hash_chain` for details.
+    hash_chain: HashChain,
     /// The term of the raft log at applied index.
     applied_term: u64,
     /// The latest flushed applied index.
@@ -1146,14 +1198,19 @@ where
     EK: KvEngine,
 {
     fn from_registration(reg: Registration) -> ApplyDelegate<EK> {
+        let region_id = reg.region.get_id();
         ApplyDelegate {
-            tag: format!("[region {}] {}", reg.region.get_id(), reg.id),
+            tag: format!("[region {}] {}", region_id, reg.id),
             peer: find_peer_by_id(&reg.region, reg.id).unwrap().clone(),
             region: reg.region,
             pending_remove: false,
             wait_data: false,
             last_flush_applied_index: reg.apply_state.get_applied_index(),
             apply_state: reg.apply_state,
+            hash_chain: match reg.hash_chain_state {
+                Some(value) => HashChain::resume(value),
+                None => HashChain::new(region_id),
+            },
             applied_term: reg.applied_term,
             term: reg.term,
             stopped: false,
@@ -1271,24 +1328,41 @@ where
         self.metrics.written_keys += apply_ctx.delta_keys();
     }
 
-    fn write_apply_state(&self, wb: &mut WriteBatchWrapper<EK::WriteBatch>) {
-        wb.put_msg_cf(
-            CF_RAFT,
-            &keys::apply_state_key(self.region.get_id()),
-            &self.apply_state,
-        )
-        .unwrap_or_else(|e| {
-            panic!(
-                "{} failed to save apply state to write batch, error: {:?}",
-                self.tag, e
-            );
-        });
+    fn write_apply_state(&self, apply_ctx: &mut ApplyContext<EK>) {
+        if apply_ctx.enable_hash_chain {
+            apply_ctx
+                .kv_wb_mut()
+                .put_cf(
+                    CF_RAFT,
+                    &keys::hash_chain_state_key(self.region.get_id()),
+                    &self.hash_chain.value().to_le_bytes(),
+                )
+                .unwrap_or_else(|e| {
+                    panic!(
+                        "{} failed to save hash chain state to write batch, error: {:?}",
+                        self.tag, e
+                    );
+                });
+        }
+        apply_ctx
+            .kv_wb_mut()
+            .put_msg_cf(
+                CF_RAFT,
+                &keys::apply_state_key(self.region.get_id()),
+                &self.apply_state,
+            )
+            .unwrap_or_else(|e| {
+                panic!(
+                    "{} failed to save apply state to write batch, error: {:?}",
+                    self.tag, e
+                );
+            });
     }
 
     fn maybe_write_apply_state(&self, apply_ctx: &mut ApplyContext<EK>) {
         let can_write = apply_ctx.host.pre_write_apply_state(&self.region);
         if can_write {
-            self.write_apply_state(apply_ctx.kv_wb_mut());
+            self.write_apply_state(apply_ctx);
         }
     }
 
@@ -1495,7 +1569,7 @@ where
         if should_write {
             // An observer shall prevent a write_apply_state here by not return true
             // when `post_exec`.
-            self.write_apply_state(apply_ctx.kv_wb_mut());
+            self.write_apply_state(apply_ctx);
             apply_ctx.commit(self);
         }
         exec_result
@@ -1852,9 +1926,13 @@ where
             match cmd_type {
                 CmdType::Put => self.handle_put(ctx, req),
                 CmdType::Delete => self.handle_delete(ctx, req),
-                CmdType::DeleteRange => {
-                    self.handle_delete_range(&ctx.engine, req, &mut ranges, ctx.use_delete_range)
-                }
+                CmdType::DeleteRange => self.handle_delete_range(
+                    &ctx.engine,
+                    req,
+                    &mut ranges,
+                    ctx.use_delete_range,
+                    ctx.enable_hash_chain,
+                ),
                 CmdType::IngestSst => self.handle_ingest_sst(ctx, req, &mut ssts),
                 // Readonly commands are handled in raftstore directly.
                 // Don't panic here in case there are old entries need to be applied.
@@ -1925,8 +2003,8 @@ where
         self.metrics.size_diff_hint += value.len() as i64;
         ctx.key_size.observe(key.len() as f64);
         ctx.value_size.observe(value.len() as f64);
-        if !req.get_put().get_cf().is_empty() {
-            let cf = req.get_put().get_cf();
+        let cf = req.get_put().get_cf();
+        if !cf.is_empty() {
             // TODO: don't allow write preseved cfs.
             if cf == CF_LOCK {
                 self.metrics.lock_cf_written_bytes += key.len() as u64;
@@ -1954,6 +2032,10 @@ where
                 );
             });
         }
+        if ctx.enable_hash_chain {
+            let cf = if cf.is_empty() { CF_DEFAULT } else { cf };
+            self.hash_chain.fold_put(cf, key, value);
+        }
         Ok(())
     }
 
@@ -1972,8 +2054,8 @@ where
         // since size_diff_hint is not accurate, so we just skip calculate the value
         // size.
         self.metrics.size_diff_hint -= key.len() as i64;
-        if !req.get_delete().get_cf().is_empty() {
-            let cf = req.get_delete().get_cf();
+        let cf = req.get_delete().get_cf();
+        if !cf.is_empty() {
             // TODO: check whether cf exists or not.
             ctx.kv_wb.delete_cf(cf, key).unwrap_or_else(|e| {
                 panic!(
@@ -2001,6 +2083,10 @@ where
             });
             self.metrics.delete_keys_hint += 1;
         }
+        if ctx.enable_hash_chain {
+            let cf = if cf.is_empty() { CF_DEFAULT } else { cf };
+            self.hash_chain.fold_delete(cf, key);
+        }
 
         Ok(())
     }
@@ -2011,6 +2097,7 @@ where
         req: &Request,
         ranges: &mut Vec<Range>,
         use_delete_range: bool,
+        enable_hash_chain: bool,
     ) -> Result<()> {
         PEER_WRITE_CMD_COUNTER.delete_range.inc();
         let s_key = req.get_delete_range().get_start_key();
@@ -2073,6 +2160,10 @@ where
                 .unwrap_or_else(move |e| fail_f(e, DeleteStrategy::DeleteBlobs));
         }
 
+        if enable_hash_chain {
+            self.hash_chain.fold_delete_range(cf, &start_key, &end_key);
+        }
+
         // TODO: Should this be executed when `notify_only` is set?
         ranges.push(Range::new(cf.to_owned(), start_key, end_key));
 
@@ -3667,19 +3758,33 @@ pub struct Registration {
     pub region: Region,
     pub pending_request_snapshot_count: Arc<AtomicUsize>,
     pub is_merging: bool,
+    /// The persisted hash chain value for this region, if one was ever
+    /// written. `None` for a region that has never had
+    /// `Config::enable_hash_chain_consistency_check` on, in which case a
+    /// fresh chain is seeded instead of resumed.
+    pub hash_chain_state: Option<u64>,
     raft_engine: Box<dyn RaftEngineReadOnly>,
 }
 
 impl Registration {
     pub fn new<EK: KvEngine, ER: RaftEngine>(peer: &Peer<EK, ER>) -> Registration {
+        let region = peer.region().clone();
+        let hash_chain_state = peer
+            .get_store()
+            .engines
+            .kv
+            .get_value_cf(CF_RAFT, &keys::hash_chain_state_key(region.get_id()))
+            .unwrap_or(None)
+            .and_then(|v| <[u8; 8]>::try_from(&*v).ok().map(u64::from_le_bytes));
         Registration {
             id: peer.peer_id(),
             term: peer.term(),
             apply_state: peer.get_store().apply_state().clone(),
             applied_term: peer.get_store().applied_term(),
-            region: peer.region().clone(),
+            region,
             pending_request_snapshot_count: peer.pending_request_snapshot_count.clone(),
             is_merging: peer.pending_merge_state.is_some(),
+            hash_chain_state,
             raft_engine: Box::new(peer.get_store().engines.raft.clone()),
         }
     }
@@ -4478,7 +4583,7 @@ where
                     // If modified `truncated_state` in `try_compact_log`, the apply state should be
                     // persisted.
                     if should_write {
-                        self.delegate.write_apply_state(ctx.kv_wb_mut());
+                        self.delegate.write_apply_state(ctx);
                         ctx.commit_opt(&mut self.delegate, true);
                     }
                     result.push_back(res);
@@ -4770,6 +4875,8 @@ where
                 _ => {}
             }
             self.apply_ctx.yield_msg_size = incoming.apply_yield_write_size.0;
+            self.apply_ctx.write_coalesce_max_delay = incoming.apply_write_coalesce_max_delay.0;
+            self.apply_ctx.write_coalesce_max_bytes = incoming.apply_write_coalesce_max_bytes.0;
             update_cfg(&incoming.apply_batch_system);
         }
     }
@@ -5246,7 +5353,7 @@ mod tests {
     use tempfile::{Builder, TempDir};
     use test_sst_importer::*;
     use tikv_util::{
-        config::{ReadableSize, VersionTrack},
+        config::{ReadableDuration, ReadableSize, VersionTrack},
         store::{new_learner_peer, new_peer},
         worker::dummy_scheduler,
     };
@@ -5346,6 +5453,7 @@ mod tests {
                 region: Default::default(),
                 pending_request_snapshot_count: Default::default(),
                 is_merging: Default::default(),
+                hash_chain_state: Default::default(),
                 raft_engine: Box::new(PanicEngine),
             }
         }
@@ -5361,6 +5469,7 @@ mod tests {
                 region: self.region.clone(),
                 pending_request_snapshot_count: self.pending_request_snapshot_count.clone(),
                 is_merging: self.is_merging,
+                hash_chain_state: self.hash_chain_state,
                 raft_engine: Box::new(PanicEngine),
             }
         }
@@ -6935,6 +7044,66 @@ mod tests {
         approximate_eq(apply_res.metrics.written_keys, 512, 20);
     }
 
+    #[test]
+    fn test_apply_write_coalesce_should_defer_flush() {
+        let (_path, engine) = create_tmp_engine("test-apply-write-coalesce");
+        let (_import_dir, importer) = create_tmp_importer("test-apply-write-coalesce");
+        let host = CoprocessorHost::<KvTestEngine>::default();
+        let (tx, _rx) = mpsc::channel();
+        let (snap_gen_scheduler, _) = dummy_scheduler();
+        let notifier = Box::new(TestNotifier { tx });
+        let mut cfg = Config::default();
+        cfg.apply_write_coalesce_max_delay = ReadableDuration::millis(50);
+        cfg.apply_write_coalesce_max_bytes = ReadableSize::kb(1);
+        let (router, _system) = create_apply_batch_system(&cfg, None);
+        let pending_create_peers = Arc::new(Mutex::new(HashMap::default()));
+
+        let mut ctx = ApplyContext::new(
+            "test-apply-write-coalesce".to_owned(),
+            host,
+            importer,
+            snap_gen_scheduler,
+            engine,
+            router,
+            notifier,
+            &cfg,
+            1,
+            pending_create_peers,
+            Priority::Normal,
+            Arc::new(AtomicU64::new(0)),
+        );
+
+        // Nothing pending, no reason to hold the (no-op) flush back.
+        assert!(!ctx.should_defer_flush());
+
+        // A small pending write batch is held back until the delay elapses.
+        ctx.kv_wb_mut().put(b"k", b"v").unwrap();
+        assert!(ctx.should_defer_flush());
+        thread::sleep(Duration::from_millis(80));
+        assert!(!ctx.should_defer_flush());
+
+        // Reset the window, then check a write requiring a synced WAL write is
+        // never delayed, however small.
+        ctx.pending_flush_since = None;
+        ctx.sync_log_hint = true;
+        assert!(!ctx.should_defer_flush());
+        ctx.sync_log_hint = false;
+
+        // Exceeding the byte budget also forces an immediate flush.
+        ctx.pending_flush_since = None;
+        for i in 0..2000u32 {
+            ctx.kv_wb_mut()
+                .put(format!("k{}", i).as_bytes(), b"v")
+                .unwrap();
+        }
+        assert!(!ctx.should_defer_flush());
+
+        // Disabled by default (max_delay == 0): never defer.
+        ctx.write_coalesce_max_delay = Duration::ZERO;
+        ctx.pending_flush_since = None;
+        assert!(!ctx.should_defer_flush());
+    }
+
     #[test]
     fn test_handle_ingest_sst() {
         let (_path, engine) = create_tmp_engine("test-ingest");
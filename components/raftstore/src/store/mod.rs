@@ -21,6 +21,7 @@ mod bootstrap;
 mod compaction_guard;
 mod disk_probe;
 mod fail_fast;
+mod hash_chain;
 mod hibernate_state;
 mod peer_storage;
 mod region_snapshot;
@@ -51,6 +52,7 @@ pub use self::{
     entry_storage::{EntryStorage, MAX_INIT_ENTRY_COUNT, RaftlogFetchResult},
     fail_fast::FailFastMonitor,
     fsm::{DestroyPeerJob, RaftRouter, check_sst_for_ingestion},
+    hash_chain::{HashChain, HashChainConsistencyCheckObserver},
     hibernate_state::{GroupState, HibernateState},
     memory::*,
     metrics::RAFT_ENTRY_FETCHES_VEC,
@@ -71,7 +73,7 @@ pub use self::{
         write_peer_state,
     },
     read_queue::{ReadIndexContext, ReadIndexQueue, ReadIndexRequest},
-    region_snapshot::{RegionIterator, RegionSnapshot},
+    region_snapshot::{IterMetrics, RegionIterator, RegionSnapshot},
     replication_mode::{GlobalReplicationState, StoreGroup},
     snap::{
         ApplyOptions, CfFile, Error as SnapError, SnapEntry, SnapKey, SnapManager,
@@ -29,6 +29,32 @@ use crate::{
     store::{PeerStorage, TxnExt, util},
 };
 
+/// Cumulative low-level iterator access counts for the iterators created
+/// from a [`RegionSnapshot`], surfaced through
+/// `tikv_kv::SnapshotExt::iter_metrics` so a slow coprocessor request can
+/// report raw RocksDB seek/next churn (including skips caused by region
+/// boundary clamping) alongside its MVCC-level scan detail.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct IterMetrics {
+    pub seek: u64,
+    pub seek_for_prev: u64,
+    pub next: u64,
+    pub prev: u64,
+    /// Number of `seek`/`seek_for_prev`/`next`/`prev` calls that landed
+    /// outside the iterator's bounds, i.e. left it invalid.
+    pub out_of_bound: u64,
+}
+
+impl IterMetrics {
+    fn merge(&mut self, other: &IterMetrics) {
+        self.seek += other.seek;
+        self.seek_for_prev += other.seek_for_prev;
+        self.next += other.next;
+        self.prev += other.prev;
+        self.out_of_bound += other.out_of_bound;
+    }
+}
+
 /// Snapshot of a region.
 ///
 /// Only data within a region can be accessed.
@@ -44,6 +70,8 @@ pub struct RegionSnapshot<S: Snapshot> {
     pub bucket_meta: Option<Arc<BucketMeta>>,
 
     observed_snap: Option<Arc<Mutex<Option<Box<dyn ObservedSnapshot>>>>>,
+
+    iter_metrics: Arc<Mutex<IterMetrics>>,
 }
 
 impl<S: Snapshot> fmt::Debug for RegionSnapshot<S> {
@@ -92,6 +120,7 @@ where
             txn_ext: None,
             bucket_meta: None,
             observed_snap: None,
+            iter_metrics: Arc::new(Mutex::new(IterMetrics::default())),
         }
     }
 
@@ -128,6 +157,7 @@ where
             txn_ext: self.txn_ext,
             bucket_meta: self.bucket_meta,
             observed_snap: None,
+            iter_metrics: self.iter_metrics,
         }
     }
 
@@ -192,6 +222,7 @@ where
             Arc::clone(&self.region),
             iter_opt,
             cf,
+            Arc::clone(&self.iter_metrics),
         ))
     }
 
@@ -229,6 +260,13 @@ where
     pub fn get_end_key(&self) -> &[u8] {
         self.region.get_end_key()
     }
+
+    /// Cumulative seek/next churn of every iterator created from this
+    /// snapshot so far, including ones that have already been dropped.
+    #[inline]
+    pub fn iter_metrics(&self) -> IterMetrics {
+        *self.iter_metrics.lock().unwrap()
+    }
 }
 
 impl<S> Clone for RegionSnapshot<S>
@@ -246,6 +284,7 @@ where
             txn_ext: self.txn_ext.clone(),
             bucket_meta: self.bucket_meta.clone(),
             observed_snap: self.observed_snap.clone(),
+            iter_metrics: Arc::clone(&self.iter_metrics),
         }
     }
 }
@@ -328,6 +367,11 @@ where
 pub struct RegionIterator<S: Snapshot> {
     iter: <S as Iterable>::Iterator,
     region: Arc<Region>,
+    // Plain, non-atomic counters: every method that touches them takes
+    // `&mut self`, so there is never concurrent access to a single
+    // iterator. They are merged into `shared_metrics` once, on drop.
+    local_metrics: IterMetrics,
+    shared_metrics: Arc<Mutex<IterMetrics>>,
 }
 
 impl<S: Snapshot> MetricsExt for RegionIterator<S> {
@@ -371,21 +415,37 @@ where
         region: Arc<Region>,
         mut iter_opt: IterOptions,
         cf: &str,
+        shared_metrics: Arc<Mutex<IterMetrics>>,
     ) -> RegionIterator<S> {
         update_lower_bound(&mut iter_opt, &region);
         update_upper_bound(&mut iter_opt, &region);
         let iter = snap
             .iterator_opt(cf, iter_opt)
             .expect("creating snapshot iterator"); // FIXME error handling
-        RegionIterator { iter, region }
+        RegionIterator {
+            iter,
+            region,
+            local_metrics: IterMetrics::default(),
+            shared_metrics,
+        }
     }
 
     pub fn seek_to_first(&mut self) -> Result<bool> {
-        self.iter.seek_to_first().map_err(Error::from)
+        let valid = self.iter.seek_to_first().map_err(Error::from)?;
+        self.local_metrics.seek += 1;
+        if !valid {
+            self.local_metrics.out_of_bound += 1;
+        }
+        Ok(valid)
     }
 
     pub fn seek_to_last(&mut self) -> Result<bool> {
-        self.iter.seek_to_last().map_err(Error::from)
+        let valid = self.iter.seek_to_last().map_err(Error::from)?;
+        self.local_metrics.seek += 1;
+        if !valid {
+            self.local_metrics.out_of_bound += 1;
+        }
+        Ok(valid)
     }
 
     pub fn seek(&mut self, key: &[u8]) -> Result<bool> {
@@ -394,21 +454,41 @@ where
         });
         self.should_seekable(key)?;
         let key = keys::data_key(key);
-        self.iter.seek(&key).map_err(Error::from)
+        let valid = self.iter.seek(&key).map_err(Error::from)?;
+        self.local_metrics.seek += 1;
+        if !valid {
+            self.local_metrics.out_of_bound += 1;
+        }
+        Ok(valid)
     }
 
     pub fn seek_for_prev(&mut self, key: &[u8]) -> Result<bool> {
         self.should_seekable(key)?;
         let key = keys::data_key(key);
-        self.iter.seek_for_prev(&key).map_err(Error::from)
+        let valid = self.iter.seek_for_prev(&key).map_err(Error::from)?;
+        self.local_metrics.seek_for_prev += 1;
+        if !valid {
+            self.local_metrics.out_of_bound += 1;
+        }
+        Ok(valid)
     }
 
     pub fn prev(&mut self) -> Result<bool> {
-        self.iter.prev().map_err(Error::from)
+        let valid = self.iter.prev().map_err(Error::from)?;
+        self.local_metrics.prev += 1;
+        if !valid {
+            self.local_metrics.out_of_bound += 1;
+        }
+        Ok(valid)
     }
 
     pub fn next(&mut self) -> Result<bool> {
-        self.iter.next().map_err(Error::from)
+        let valid = self.iter.next().map_err(Error::from)?;
+        self.local_metrics.next += 1;
+        if !valid {
+            self.local_metrics.out_of_bound += 1;
+        }
+        Ok(valid)
     }
 
     #[inline]
@@ -435,6 +515,15 @@ where
     }
 }
 
+impl<S: Snapshot> Drop for RegionIterator<S> {
+    fn drop(&mut self) {
+        self.shared_metrics
+            .lock()
+            .unwrap()
+            .merge(&self.local_metrics);
+    }
+}
+
 #[inline(never)]
 fn handle_check_key_in_region_error(e: crate::Error) -> Result<()> {
     // Split out the error case to reduce hot-path code size.
@@ -811,4 +900,43 @@ mod tests {
         res.sort();
         assert_eq!(res, test_data[1..3].to_vec());
     }
+
+    #[test]
+    fn test_iter_metrics() {
+        let path = Builder::new().prefix("test-raftstore").tempdir().unwrap();
+        let engines = new_temp_engine(&path);
+        let (store, _) = load_default_dataset(engines);
+        let snap = RegionSnapshot::<KvTestSnapshot>::new(&store);
+
+        assert_eq!(snap.iter_metrics().seek, 0);
+
+        {
+            let mut iter = snap.iter(CF_DEFAULT, IterOptions::default()).unwrap();
+            // In range but past the last key: `seek` lands out of bound.
+            assert!(iter.seek(b"a3").unwrap());
+            assert!(!iter.seek(b"a7").unwrap());
+            // Reseek to a valid key, then walk off the end with `next`.
+            assert!(iter.seek_for_prev(b"a5").unwrap());
+            assert!(!iter.next().unwrap());
+            // Reseek again, then walk back with `prev`.
+            assert!(iter.seek_for_prev(b"a5").unwrap());
+            assert!(iter.prev().unwrap());
+            // Metrics are only merged into the snapshot on drop.
+            assert_eq!(snap.iter_metrics().seek, 0);
+        }
+
+        let metrics = snap.iter_metrics();
+        assert_eq!(metrics.seek, 2);
+        assert_eq!(metrics.seek_for_prev, 2);
+        assert_eq!(metrics.next, 1);
+        assert_eq!(metrics.prev, 1);
+        assert_eq!(metrics.out_of_bound, 2);
+
+        // Metrics accumulate across iterators created from the same snapshot.
+        {
+            let mut iter = snap.iter(CF_DEFAULT, IterOptions::default()).unwrap();
+            assert!(iter.seek_to_first().unwrap());
+        }
+        assert_eq!(snap.iter_metrics().seek, 3);
+    }
 }
@@ -43,9 +43,9 @@ use tikv::{
     },
 };
 use tikv_util::{
-    config::{RaftDataStateMachine, ensure_dir_exist},
+    config::{DataDirInfo, RaftDataStateMachine, ensure_dir_exist},
     math::MovingAvgU32,
-    metrics::INSTANCE_BACKEND_CPU_QUOTA,
+    metrics::{INSTANCE_BACKEND_CPU_QUOTA, TIKV_STORE_DATA_DIR_ROTATIONAL},
     quota_limiter::QuotaLimiter,
     sys::{SysQuota, cpu_time::ProcessStat, disk, path_in_diff_mount_point},
     time::Instant,
@@ -449,30 +449,82 @@ pub fn check_system_config(config: &TikvConfig) {
         // open files here
         rocksdb_max_open_files *= 2;
     }
-    if let Err(e) = tikv_util::config::check_max_open_fds(
+    let fd_limit_mode = if config.enforce_fd_limit {
+        tikv_util::config::FdLimitMode::Enforce
+    } else {
+        tikv_util::config::FdLimitMode::Warn
+    };
+    match tikv_util::config::check_max_open_fds(
         RESERVED_OPEN_FDS + (rocksdb_max_open_files + config.raftdb.max_open_files) as u64,
+        fd_limit_mode,
     ) {
-        fatal!("{}", e);
+        Ok(limits) => info!("file descriptor limits"; "limits" => %limits),
+        Err(e) => fatal!("{}", e),
     }
 
     // Check RocksDB data dir
-    if let Err(e) = tikv_util::config::check_data_dir(&config.storage.data_dir) {
-        warn!(
+    match tikv_util::config::check_data_dir(&config.storage.data_dir) {
+        Ok(findings) => {
+            warn_data_dir_findings("rocksdb-data-dir", &config.storage.data_dir, &findings)
+        }
+        Err(e) => warn!(
             "check: rocksdb-data-dir";
             "path" => &config.storage.data_dir,
             "err" => %e
-        );
+        ),
     }
+    report_data_dir_info("rocksdb-data-dir", &config.storage.data_dir);
     // Check raft data dir
-    if let Err(e) = tikv_util::config::check_data_dir(&config.raft_store.raftdb_path) {
-        warn!(
+    match tikv_util::config::check_data_dir(&config.raft_store.raftdb_path) {
+        Ok(findings) => {
+            warn_data_dir_findings("raftdb-path", &config.raft_store.raftdb_path, &findings)
+        }
+        Err(e) => warn!(
             "check: raftdb-path";
             "path" => &config.raft_store.raftdb_path,
             "err" => %e
+        ),
+    }
+    report_data_dir_info("raftdb-path", &config.raft_store.raftdb_path);
+}
+
+fn warn_data_dir_findings(
+    check: &str,
+    path: &str,
+    findings: &[tikv_util::config::DataDirFinding],
+) {
+    for finding in findings {
+        warn!(
+            "check: data-dir";
+            "check" => check,
+            "path" => path,
+            "finding" => ?finding.kind,
+            "message" => &finding.message
         );
     }
 }
 
+/// Collects filesystem/device facts about a checked data directory, exposes
+/// them as the `tikv_store_data_dir_rotational` gauge, and records them so
+/// the diagnostics `SysInfo` RPC can report them too. `name` identifies the
+/// checked directory (e.g. `"rocksdb-data-dir"`) and becomes the gauge's and
+/// diagnostics item's label/name.
+fn report_data_dir_info(name: &str, path: &str) {
+    let info: DataDirInfo = match tikv_util::config::collect_data_dir_info(path) {
+        Ok(info) => info,
+        Err(e) => {
+            warn!("check: data-dir info"; "check" => name, "path" => path, "err" => %e);
+            return;
+        }
+    };
+    if let Some(rotational) = info.rotational {
+        TIKV_STORE_DATA_DIR_ROTATIONAL
+            .with_label_values(&[name, &info.fs_type])
+            .set(rotational as i64);
+    }
+    tikv_util::config::record_data_dir_info(name, info);
+}
+
 pub struct EnginesResourceInfo {
     tablet_registry: TabletRegistry<RocksEngine>,
     // The initial value of max_compactions.
@@ -773,7 +825,9 @@ impl ConfiguredRaftEngine for RocksEngine {
             &config.raft_engine.config().dir,
             &config.raft_store.raftdb_path,
         );
-        let should_dump = raft_data_state_machine.before_open_target();
+        let should_dump = raft_data_state_machine
+            .before_open_target()
+            .unwrap_or_else(|e| fatal!("{}", e));
 
         let raft_db_path = &config.raft_store.raftdb_path;
         let config_raftdb = &config.raftdb;
@@ -823,7 +877,9 @@ impl ConfiguredRaftEngine for RaftLogEngine {
             &config.raft_store.raftdb_path,
             &config.raft_engine.config().dir,
         );
-        let should_dump = raft_data_state_machine.before_open_target();
+        let should_dump = raft_data_state_machine
+            .before_open_target()
+            .unwrap_or_else(|e| fatal!("{}", e));
 
         let raft_config = config.raft_engine.config();
         let raft_engine =
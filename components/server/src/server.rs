@@ -73,8 +73,8 @@ use raftstore::{
     router::{CdcRaftRouter, ServerRaftStoreRouter},
     store::{
         AutoSplitController, CheckLeaderRunner, DiskCheckRunner, ForcePartitionRangeManager,
-        LocalReader, SnapManager, SnapManagerBuilder, SplitCheckRunner, SplitConfigManager,
-        StoreMetaDelegate,
+        HashChainConsistencyCheckObserver, LocalReader, SnapManager, SnapManagerBuilder,
+        SplitCheckRunner, SplitConfigManager, StoreMetaDelegate,
         config::RaftstoreConfigManager,
         fsm::{
             self,
@@ -133,7 +133,7 @@ use tikv_alloc::{
 };
 use tikv_util::{
     Either, check_environment_variables,
-    config::VersionTrack,
+    config::{KernelParamSeverity, VersionTrack},
     memory::MemoryQuota,
     mpsc as TikvMpsc,
     quota_limiter::{QuotaLimitConfigManager, QuotaLimiter},
@@ -1089,6 +1089,9 @@ where
             ConsistencyCheckMethod::Raw => {
                 BoxConsistencyCheckObserver::new(RawConsistencyCheckObserver::default())
             }
+            ConsistencyCheckMethod::HashChain => {
+                BoxConsistencyCheckObserver::new(HashChainConsistencyCheckObserver)
+            }
         };
         self.coprocessor_host
             .as_mut()
@@ -1933,11 +1936,14 @@ where
 /// - if the "TZ" environment variable is not set on unix
 fn pre_start() {
     check_environment_variables();
-    for e in tikv_util::config::check_kernel() {
-        warn!(
-            "check: kernel";
-            "err" => %e
-        );
+    for result in tikv_util::config::check_kernel() {
+        match result.check.severity {
+            KernelParamSeverity::Warn => warn!(
+                "check: kernel";
+                "err" => %result.error
+            ),
+            KernelParamSeverity::Abort => fatal!("check: kernel; err {}", result.error),
+        }
     }
 }
 
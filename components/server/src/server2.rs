@@ -59,8 +59,9 @@ use raftstore::{
         RawConsistencyCheckObserver,
     },
     store::{
-        AutoSplitController, CheckLeaderRunner, SplitConfigManager, TabletSnapManager,
-        config::RaftstoreConfigManager, memory::MEMTRACE_ROOT as MEMTRACE_RAFTSTORE,
+        AutoSplitController, CheckLeaderRunner, HashChainConsistencyCheckObserver,
+        SplitConfigManager, TabletSnapManager, config::RaftstoreConfigManager,
+        memory::MEMTRACE_ROOT as MEMTRACE_RAFTSTORE,
     },
 };
 use raftstore_v2::{
@@ -111,7 +112,7 @@ use tikv_alloc::{
 };
 use tikv_util::{
     Either, check_environment_variables,
-    config::VersionTrack,
+    config::{KernelParamSeverity, VersionTrack},
     memory::MemoryQuota,
     mpsc as TikvMpsc,
     quota_limiter::{QuotaLimitConfigManager, QuotaLimiter},
@@ -934,6 +935,9 @@ where
             ConsistencyCheckMethod::Raw => {
                 BoxConsistencyCheckObserver::new(RawConsistencyCheckObserver::default())
             }
+            ConsistencyCheckMethod::HashChain => {
+                BoxConsistencyCheckObserver::new(HashChainConsistencyCheckObserver)
+            }
         };
         self.coprocessor_host
             .as_mut()
@@ -1738,11 +1742,14 @@ impl<CER: ConfiguredRaftEngine> TikvServer<CER> {
 /// - if the "TZ" environment variable is not set on unix
 fn pre_start() {
     check_environment_variables();
-    for e in tikv_util::config::check_kernel() {
-        warn!(
-            "check: kernel";
-            "err" => %e
-        );
+    for result in tikv_util::config::check_kernel() {
+        match result.check.severity {
+            KernelParamSeverity::Warn => warn!(
+                "check: kernel";
+                "err" => %result.error
+            ),
+            KernelParamSeverity::Abort => fatal!("check: kernel; err {}", result.error),
+        }
     }
 }
 
@@ -66,7 +66,9 @@ pub fn setup_for_ci() {
 
     tikv_util::check_environment_variables();
 
-    if let Err(e) = tikv_util::config::check_max_open_fds(4096) {
+    if let Err(e) =
+        tikv_util::config::check_max_open_fds(4096, tikv_util::config::FdLimitMode::Enforce)
+    {
         panic!(
             "To run test, please make sure the maximum number of open file descriptors not \
              less than 4096: {:?}",
@@ -170,6 +170,11 @@ where
 {
     type ParameterType = BytesRef<'static>;
 
+    /// Compares the incoming value against the running extremum with
+    /// `C::sort_compare` directly on the raw bytes, rather than materializing
+    /// a `sort_key` for either side. This keeps MAX/MIN over a collated
+    /// string column allocation-free per row; only computing an index or
+    /// ORDER BY sort key still needs `C::sort_key`.
     #[inline]
     unsafe fn update_concrete_unsafe(
         &mut self,
@@ -748,11 +753,15 @@ mod tests {
             (Collation::Utf8Mb4GeneralCi, true, vec!["B", "a"], "B"),
             (Collation::Utf8Mb4UnicodeCi, true, vec!["ß", "sr"], "ß"),
             (Collation::Utf8Mb4BinNoPadding, true, vec!["B", "a"], "a"),
+            // Pad-space collations must treat "a" and "a " as equal, so the
+            // running extremum keeps whichever value arrived first.
+            (Collation::Utf8Mb4GeneralCi, true, vec!["a ", "a"], "a "),
             (Collation::Binary, false, vec!["B", "a"], "B"),
             (Collation::Utf8Mb4Bin, false, vec!["B", "a"], "B"),
             (Collation::Utf8Mb4GeneralCi, false, vec!["B", "a"], "a"),
             (Collation::Utf8Mb4UnicodeCi, false, vec!["ß", "st"], "ß"),
             (Collation::Utf8Mb4BinNoPadding, false, vec!["B", "a"], "B"),
+            (Collation::Utf8Mb4GeneralCi, false, vec!["a ", "a"], "a "),
         ];
         for (coll, is_max, args, expected) in cases {
             let function = match_template_collator! {
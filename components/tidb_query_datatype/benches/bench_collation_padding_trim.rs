@@ -0,0 +1,49 @@
+// Copyright 2026 TiKV Project Authors. Licensed under Apache-2.0.
+
+use criterion::{Criterion, black_box, criterion_group, criterion_main};
+use tidb_query_datatype::codec::collation::{Collator, collator::CollatorUtf8Mb4Bin};
+
+const ONE_MIB: usize = 1024 * 1024;
+
+// Two 1MiB values that share the same non-space body and differ only in how
+// much trailing padding they carry, simulating an adversarial or buggy
+// client writing multi-megabyte all-space (or nearly all-space) CHAR values.
+fn padded_1mib_values() -> (Vec<u8>, Vec<u8>) {
+    let body = b"the quick brown fox jumps over the lazy dog";
+    let mut a = body.to_vec();
+    a.resize(ONE_MIB, b' ');
+    let mut b = body.to_vec();
+    b.resize(ONE_MIB - 1, b' ');
+    (a, b)
+}
+
+fn bench_sort_compare_1mib_padding(c: &mut Criterion) {
+    let (a, b) = padded_1mib_values();
+
+    c.bench_function("sort_compare_1mib_padding", |bencher| {
+        bencher.iter(|| {
+            black_box(
+                CollatorUtf8Mb4Bin::sort_compare(black_box(&a), black_box(&b), false).unwrap(),
+            );
+        });
+    });
+}
+
+fn bench_write_sort_key_1mib_padding(c: &mut Criterion) {
+    let (a, _) = padded_1mib_values();
+
+    c.bench_function("write_sort_key_1mib_padding", |bencher| {
+        bencher.iter(|| {
+            let mut out = Vec::new();
+            CollatorUtf8Mb4Bin::write_sort_key(&mut out, black_box(&a)).unwrap();
+            black_box(out);
+        });
+    });
+}
+
+criterion_group!(
+    benches,
+    bench_sort_compare_1mib_padding,
+    bench_write_sort_key_1mib_padding,
+);
+criterion_main!(benches);
@@ -0,0 +1,183 @@
+// Copyright 2026 TiKV Project Authors. Licensed under Apache-2.0.
+
+use criterion::{Criterion, black_box, criterion_group, criterion_main};
+use tidb_query_datatype::{
+    Collation,
+    codec::collation::{
+        Collator,
+        collator::{CollatorUtf8Mb4Bin, CollatorUtf8Mb4GeneralCi, CollatorUtf8Mb4UnicodeCi},
+        encode_sort_keys_batch_for_collation,
+    },
+    match_template_collator,
+};
+
+// Simulates encoding a batch of index sort keys into one growing buffer, the
+// way `encode_index_seek_key`-style callers compose collated columns with a
+// memcomparable encoder.
+fn bench_sort_key_via_intermediate_vec(c: &mut Criterion) {
+    let values: Vec<&[u8]> = vec![b"Straße", b"", b"a", b"the quick brown fox jumps"];
+
+    c.bench_function("sort_key_via_intermediate_vec", |b| {
+        b.iter(|| {
+            let mut out = Vec::new();
+            for v in &values {
+                let key = CollatorUtf8Mb4Bin::sort_key(black_box(v)).unwrap();
+                out.extend_from_slice(&key);
+            }
+            black_box(out);
+        });
+    });
+}
+
+fn bench_sort_key_via_write_sort_key(c: &mut Criterion) {
+    let values: Vec<&[u8]> = vec![b"Straße", b"", b"a", b"the quick brown fox jumps"];
+
+    c.bench_function("sort_key_via_write_sort_key", |b| {
+        b.iter(|| {
+            let mut out = Vec::new();
+            for v in &values {
+                CollatorUtf8Mb4Bin::write_sort_key(&mut out, black_box(v)).unwrap();
+            }
+            black_box(out);
+        });
+    });
+}
+
+// `match_template_collator!` compiles to the same dispatch used by the query
+// engine; kept as a smoke check that the streaming path type-checks the same
+// way across every collation, not just a hand-picked one.
+fn bench_sort_key_all_collations(c: &mut Criterion) {
+    use tidb_query_datatype::Collation;
+
+    let collation = Collation::Utf8Mb4GeneralCi;
+    let value = b"the quick brown fox jumps";
+
+    c.bench_function("sort_key_all_collations_write_sort_key", |b| {
+        b.iter(|| {
+            let mut out = Vec::new();
+            match_template_collator! {
+                TT, match collation {
+                    Collation::TT => TT::write_sort_key(&mut out, black_box(value)).unwrap(),
+                }
+            };
+            black_box(out);
+        });
+    });
+}
+
+// Simulates the two ways a MAX/MIN aggregate can track its running extremum
+// over a collated string column: rebuilding a `sort_key` for every row and
+// comparing keys, versus comparing the raw bytes directly with
+// `sort_compare`. The latter is what `AggFnStateExtremum4Bytes` uses.
+fn bench_extremum_via_sort_key<C: Collator>(c: &mut Criterion, name: &str) {
+    let values: Vec<&[u8]> = vec![b"straße", b"apple", b"Banana", b"cherry pie", b"a"];
+
+    c.bench_function(&format!("extremum_via_sort_key_{name}"), |b| {
+        b.iter(|| {
+            let mut extremum: Option<Vec<u8>> = None;
+            for v in &values {
+                let key = C::sort_key(black_box(v)).unwrap();
+                if extremum.as_ref().is_none_or(|e| key > *e) {
+                    extremum = Some(key);
+                }
+            }
+            black_box(extremum);
+        });
+    });
+}
+
+fn bench_extremum_via_sort_compare<C: Collator>(c: &mut Criterion, name: &str) {
+    let values: Vec<&[u8]> = vec![b"straße", b"apple", b"Banana", b"cherry pie", b"a"];
+
+    c.bench_function(&format!("extremum_via_sort_compare_{name}"), |b| {
+        b.iter(|| {
+            let mut extremum: Option<&[u8]> = None;
+            for v in &values {
+                let v = black_box(*v);
+                let replace = match extremum {
+                    None => true,
+                    Some(e) => C::sort_compare(v, e, false).unwrap() == std::cmp::Ordering::Greater,
+                };
+                if replace {
+                    extremum = Some(v);
+                }
+            }
+            black_box(extremum);
+        });
+    });
+}
+
+fn bench_extremum_general_ci(c: &mut Criterion) {
+    bench_extremum_via_sort_key::<CollatorUtf8Mb4GeneralCi>(c, "general_ci");
+    bench_extremum_via_sort_compare::<CollatorUtf8Mb4GeneralCi>(c, "general_ci");
+}
+
+fn bench_extremum_unicode_ci(c: &mut Criterion) {
+    bench_extremum_via_sort_key::<CollatorUtf8Mb4UnicodeCi>(c, "unicode_ci");
+    bench_extremum_via_sort_compare::<CollatorUtf8Mb4UnicodeCi>(c, "unicode_ci");
+}
+
+// Simulates how a TopN/ORDER BY batch would build its comparison keys today:
+// re-resolving the collator (via `match_template_collator!`) on every row.
+fn bench_sort_keys_per_row(c: &mut Criterion) {
+    let collation = Collation::Utf8Mb4GeneralCi;
+    let values: [Option<&[u8]>; 5] = [
+        Some(b"the quick brown fox jumps"),
+        None,
+        Some(b""),
+        Some(b"Straße"),
+        Some(b"a"),
+    ];
+
+    c.bench_function("sort_keys_per_row_general_ci", |b| {
+        b.iter(|| {
+            let mut buf = Vec::new();
+            for v in &values {
+                match_template_collator! {
+                    TT, match collation {
+                        Collation::TT => match black_box(*v) {
+                            None => {}
+                            Some(v) => { TT::write_sort_key(&mut buf, v).unwrap(); }
+                        }
+                    }
+                }
+            }
+            black_box(buf);
+        });
+    });
+}
+
+// Same batch as `bench_sort_keys_per_row`, but resolving the collator once
+// for the whole column via `encode_sort_keys_batch_for_collation`.
+fn bench_sort_keys_batched(c: &mut Criterion) {
+    let collation = Collation::Utf8Mb4GeneralCi;
+    let values: [Option<&[u8]>; 5] = [
+        Some(b"the quick brown fox jumps"),
+        None,
+        Some(b""),
+        Some(b"Straße"),
+        Some(b"a"),
+    ];
+
+    c.bench_function("sort_keys_batched_general_ci", |b| {
+        b.iter(|| {
+            let mut buf = Vec::new();
+            let ranges =
+                encode_sort_keys_batch_for_collation(collation, black_box(values), &mut buf)
+                    .unwrap();
+            black_box((buf, ranges));
+        });
+    });
+}
+
+criterion_group!(
+    benches,
+    bench_sort_key_via_intermediate_vec,
+    bench_sort_key_via_write_sort_key,
+    bench_sort_key_all_collations,
+    bench_extremum_general_ci,
+    bench_extremum_unicode_ci,
+    bench_sort_keys_per_row,
+    bench_sort_keys_batched,
+);
+criterion_main!(benches);
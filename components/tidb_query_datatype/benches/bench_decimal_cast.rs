@@ -0,0 +1,54 @@
+// Copyright 2026 TiKV Project Authors. Licensed under Apache-2.0.
+
+use criterion::{Criterion, black_box, criterion_group, criterion_main};
+use tidb_query_datatype::codec::mysql::{Decimal, Res, RoundMode};
+
+// Simulates casting a string column to a fixed-precision DECIMAL the way
+// `produce_dec_with_specified_tp` does today: parse, then round as a
+// separate pass over the parsed digits.
+fn bench_two_step(c: &mut Criterion) {
+    let values: Vec<&[u8]> = vec![
+        b"12345.6789",
+        b"   -98765.432100  ",
+        b"0.000000001",
+        b"99999999999999.999999999",
+        b"1.23456789e3",
+    ];
+
+    c.bench_function("decimal_cast_two_step", |b| {
+        b.iter(|| {
+            for v in &values {
+                let d = Decimal::from_bytes(black_box(v)).unwrap().unwrap();
+                black_box(d.round(4, RoundMode::HalfEven));
+            }
+        });
+    });
+}
+
+fn bench_fused(c: &mut Criterion) {
+    let values: Vec<&[u8]> = vec![
+        b"12345.6789",
+        b"   -98765.432100  ",
+        b"0.000000001",
+        b"99999999999999.999999999",
+        b"1.23456789e3",
+    ];
+
+    c.bench_function("decimal_cast_fused", |b| {
+        b.iter(|| {
+            for v in &values {
+                let res: Res<Decimal> = Decimal::from_bytes_with_prec_and_frac(
+                    black_box(v),
+                    30,
+                    4,
+                    RoundMode::HalfEven,
+                )
+                .unwrap();
+                black_box(res);
+            }
+        });
+    });
+}
+
+criterion_group!(benches, bench_two_step, bench_fused);
+criterion_main!(benches);
@@ -74,6 +74,64 @@ pub type CharsetGbk = CharsetUtf8mb4;
 // gb18030 character data actually stored with utf8mb4 character encoding.
 pub type CharsetGb18030 = CharsetUtf8mb4;
 
+/// Validates `s` as utf8mb4 in a single pass, returning the byte offset of
+/// the first invalid sequence on failure.
+///
+/// This takes the SIMD-accelerated `str::from_utf8` fast path instead of
+/// decoding one character at a time; `Utf8Error::valid_up_to` already
+/// pinpoints the first bad byte from that same pass, so no separate
+/// byte-at-a-time scan is needed to locate it.
+pub fn validate_utf8mb4(s: &[u8]) -> std::result::Result<(), usize> {
+    str::from_utf8(s).map(|_| ()).map_err(|e| e.valid_up_to())
+}
+
+/// Validates `s` as utf8, which (unlike utf8mb4) only covers the Basic
+/// Multilingual Plane: a well-formed 4-byte sequence is still rejected, at
+/// the offset where it starts.
+pub fn validate_utf8_bmp(s: &[u8]) -> std::result::Result<(), usize> {
+    validate_utf8mb4(s)?;
+    // `s` was just confirmed to be well-formed UTF-8 above.
+    let text = str::from_utf8(s).unwrap();
+    match text.char_indices().find(|(_, ch)| *ch as u32 > 0xFFFF) {
+        Some((i, _)) => Err(i),
+        None => Ok(()),
+    }
+}
+
+/// Validates `s` as 7-bit ASCII, returning the offset of the first byte
+/// outside the ASCII range.
+pub fn validate_ascii(s: &[u8]) -> std::result::Result<(), usize> {
+    match s.iter().position(|b| !b.is_ascii()) {
+        Some(i) => Err(i),
+        None => Ok(()),
+    }
+}
+
+/// Validates `input` against `charset`, returning the byte offset of the
+/// first sequence that cannot be represented in it.
+///
+/// This is the offset-returning counterpart of [`Charset::validate`], used
+/// where a caller (e.g. a `CAST(... AS ... CHARACTER SET ...)` conversion)
+/// needs to report exactly where a string stopped being convertible, per
+/// MySQL error 3854.
+pub fn validate_for_charset(
+    charset: crate::Charset,
+    input: &[u8],
+) -> std::result::Result<(), usize> {
+    match charset {
+        crate::Charset::Binary => Ok(()),
+        crate::Charset::Ascii => validate_ascii(input),
+        crate::Charset::Utf8 => validate_utf8_bmp(input),
+        crate::Charset::Utf8Mb4 => validate_utf8mb4(input),
+        // Latin1 is a single-byte charset where every byte 0x00-0xFF is a
+        // valid code point (see `CollatorLatin1Bin`/`CollatorLatin1GeneralCi`,
+        // both `CharsetBinary`), so nothing is ever unrepresentable.
+        crate::Charset::Latin1 => Ok(()),
+        crate::Charset::Gbk => super::encoding::validate_gbk(input),
+        crate::Charset::Gb18030 => super::encoding::validate_gb18030(input),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -105,4 +163,96 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_validate_utf8mb4() {
+        assert_eq!(validate_utf8mb4("hello, 你好 🐶".as_bytes()), Ok(()));
+        assert_eq!(validate_utf8mb4(b""), Ok(()));
+
+        // Malformed byte at the start.
+        let mut bad_start = vec![0xAA];
+        bad_start.extend_from_slice("ok".as_bytes());
+        assert_eq!(validate_utf8mb4(&bad_start), Err(0));
+
+        // Malformed byte in the middle.
+        let mut bad_middle = "ok".as_bytes().to_vec();
+        let middle_offset = bad_middle.len();
+        bad_middle.push(0xC3);
+        bad_middle.push(0x28);
+        bad_middle.extend_from_slice("ok".as_bytes());
+        assert_eq!(validate_utf8mb4(&bad_middle), Err(middle_offset));
+
+        // Malformed (truncated) sequence at the end.
+        let mut bad_end = "ok".as_bytes().to_vec();
+        let end_offset = bad_end.len();
+        bad_end.push(0xE4);
+        assert_eq!(validate_utf8mb4(&bad_end), Err(end_offset));
+    }
+
+    #[test]
+    fn test_validate_utf8_bmp() {
+        assert_eq!(validate_utf8_bmp("hello, 你好".as_bytes()), Ok(()));
+        assert_eq!(validate_utf8_bmp(b""), Ok(()));
+
+        // Malformed UTF-8 is rejected the same way `validate_utf8mb4` rejects
+        // it, at the same offset.
+        let mut bad_middle = "ok".as_bytes().to_vec();
+        let middle_offset = bad_middle.len();
+        bad_middle.push(0xC3);
+        bad_middle.push(0x28);
+        assert_eq!(validate_utf8_bmp(&bad_middle), Err(middle_offset));
+
+        // Well-formed but outside the BMP: rejected even though
+        // `validate_utf8mb4` accepts it.
+        let dog_at_start = "🐶".as_bytes().to_vec();
+        assert_eq!(validate_utf8mb4(&dog_at_start), Ok(()));
+        assert_eq!(validate_utf8_bmp(&dog_at_start), Err(0));
+
+        let mut dog_in_middle = "ok".as_bytes().to_vec();
+        let dog_offset = dog_in_middle.len();
+        dog_in_middle.extend_from_slice("🐶".as_bytes());
+        assert_eq!(validate_utf8_bmp(&dog_in_middle), Err(dog_offset));
+    }
+
+    #[test]
+    fn test_validate_ascii() {
+        assert_eq!(validate_ascii(b"hello, world"), Ok(()));
+        assert_eq!(validate_ascii(b""), Ok(()));
+        assert_eq!(validate_ascii(&[0xAA, b'o', b'k']), Err(0));
+        assert_eq!(validate_ascii(&[b'o', b'k', 0xAA]), Err(2));
+    }
+
+    #[test]
+    fn test_validate_for_charset() {
+        assert_eq!(
+            validate_for_charset(crate::Charset::Binary, &[0xFF, 0xFE]),
+            Ok(())
+        );
+        assert_eq!(
+            validate_for_charset(crate::Charset::Ascii, &[b'o', 0xFF]),
+            Err(1)
+        );
+        assert_eq!(
+            validate_for_charset(crate::Charset::Utf8, "🐶".as_bytes()),
+            Err(0)
+        );
+        assert_eq!(
+            validate_for_charset(crate::Charset::Utf8Mb4, "🐶".as_bytes()),
+            Ok(())
+        );
+        // Latin1 is single-byte: every byte value is a valid code point, even
+        // ones that don't form a valid UTF-8 sequence.
+        assert_eq!(
+            validate_for_charset(crate::Charset::Latin1, &[0xC3, 0x28]),
+            Ok(())
+        );
+        assert_eq!(
+            validate_for_charset(crate::Charset::Gbk, &[0x81, 0x30]),
+            Err(0)
+        );
+        assert_eq!(
+            validate_for_charset(crate::Charset::Gb18030, &[0xD6, 0xD0]),
+            Ok(())
+        );
+    }
 }
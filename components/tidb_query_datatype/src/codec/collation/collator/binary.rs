@@ -30,7 +30,7 @@ impl Collator for CollatorBinary {
     }
 
     #[inline]
-    fn sort_hash<H: Hasher>(state: &mut H, bstr: &[u8]) -> Result<()> {
+    fn sort_hash<H: Hasher>(state: &mut H, bstr: &[u8], _force_no_pad: bool) -> Result<()> {
         bstr.hash(state);
         Ok(())
     }
@@ -0,0 +1,96 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+//! Round-trip helpers for the weight tables backing the GBK and GB18030
+//! collators: building an index-range scan boundary from a literal prefix
+//! needs to go the other way across those tables, and needs a sort key
+//! that's guaranteed to sort just past a given prefix.
+
+use std::collections::HashMap;
+
+use crate::codec::Result;
+
+/// Two-way mapping between a UTF-8 string and the per-character weight bytes
+/// a collation's sort key is built from, plus a successor operation on those
+/// sort key bytes.
+///
+/// Implemented per collation rather than per charset, because a `_bin`
+/// collation's weight table is an injective encoding of the character while
+/// a `_chinese_ci`-style collation's table folds multiple characters onto
+/// the same weight; decoding the latter can only recover a representative
+/// character, which is enough for building scan range boundaries.
+pub trait CharsetCodec {
+    /// Encodes `s` into this collation's weight-byte sequence, the same
+    /// bytes `Collator::write_sort_key` would produce for it. Characters
+    /// with no entry in the weight table are substituted with `?`, mirroring
+    /// `write_sort_key`.
+    fn charset_encode(s: &str) -> Vec<u8>;
+
+    /// Decodes a weight-byte sequence back into a UTF-8 string. For a
+    /// `_chinese_ci`-style collation this recovers a representative
+    /// character for each weight rather than necessarily the original one.
+    fn charset_decode(bytes: &[u8]) -> Result<String>;
+
+    /// The smallest sort key that is strictly greater than every sort key
+    /// having `sort_key_prefix` as a prefix. Used as the exclusive upper
+    /// bound of a scan range built from a prefix or `LIKE` predicate.
+    fn sort_key_successor(sort_key_prefix: &[u8]) -> Result<Vec<u8>> {
+        sort_key_byte_successor(sort_key_prefix)
+    }
+}
+
+/// Smallest byte string that is strictly greater than every byte string
+/// having `prefix` as a prefix.
+///
+/// Sort keys are already byte-comparable, so this only needs to operate on
+/// the raw bytes: it's the same "prefix next" construction used elsewhere in
+/// TiKV for building exclusive range ends, independent of any charset.
+pub fn sort_key_byte_successor(prefix: &[u8]) -> Result<Vec<u8>> {
+    let mut successor = prefix.to_vec();
+    while let Some(&last) = successor.last() {
+        if last == 0xFF {
+            successor.pop();
+        } else {
+            *successor.last_mut().unwrap() += 1;
+            return Ok(successor);
+        }
+    }
+    Err(box_err!(
+        "{:?} has no successor: empty, or made up entirely of 0xFF bytes",
+        prefix
+    ))
+}
+
+/// Builds the reverse of a `char -> weight` table: `weight -> a
+/// representative char`. Earlier code points win ties over later ones that
+/// fold to the same weight, which is the natural "representative character"
+/// choice for a `_chinese_ci`-style collation.
+pub(super) fn reverse_weight_table<W: Copy + Eq + std::hash::Hash>(
+    max_code_point: u32,
+    char_weight: impl Fn(char) -> W,
+) -> HashMap<W, char> {
+    let mut map = HashMap::new();
+    for r in 0..=max_code_point {
+        if let Some(ch) = char::from_u32(r) {
+            map.entry(char_weight(ch)).or_insert(ch);
+        }
+    }
+    map
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sort_key_byte_successor() {
+        assert_eq!(sort_key_byte_successor(b"ab").unwrap(), b"ac");
+        assert_eq!(sort_key_byte_successor(b"a\xff").unwrap(), b"b");
+        assert!(
+            sort_key_byte_successor(b"\xff\xff\xff")
+                .unwrap_err()
+                .to_string()
+                .contains("no successor")
+        );
+        assert!(sort_key_byte_successor(b"").is_err());
+    }
+}
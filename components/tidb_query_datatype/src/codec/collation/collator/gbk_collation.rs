@@ -1,6 +1,8 @@
 // Copyright 2021 TiKV Project Authors. Licensed under Apache-2.0.
 
-use super::*;
+use std::{collections::HashMap, sync::OnceLock};
+
+use super::{charset_codec::reverse_weight_table, *};
 
 trait GbkCollator: 'static + Send + Sync + std::fmt::Debug {
     const IS_CASE_INSENSITIVE: bool;
@@ -30,32 +32,22 @@ impl<T: GbkCollator> Collator for T {
 
     #[inline]
     fn write_sort_key<W: BufferWriter>(writer: &mut W, bstr: &[u8]) -> Result<usize> {
-        let mut bstr_rest = trim_end_padding(bstr);
-        let mut n = 0;
-        while !bstr_rest.is_empty() {
-            match next_utf8_char(bstr_rest) {
-                Some((ch, b_next)) => {
-                    let weight = Self::char_weight(ch);
-                    if weight > 0xFF {
-                        writer.write_u16_be(weight)?;
-                        n += 2;
-                    } else {
-                        writer.write_u8(weight as u8)?;
-                        n += 1;
-                    }
-                    bstr_rest = b_next
-                }
-                _ => {
-                    if Self::NEED_TRUNCATE_INVALID_UTF8_RUNE {
-                        break;
-                    }
-                    writer.write_u8(b'?')?;
-                    n += 1;
-                    bstr_rest = &bstr_rest[1..]
-                }
-            }
-        }
-        Ok(n * std::mem::size_of::<u8>())
+        write_gbk_sort_key(
+            writer,
+            trim_end_padding(bstr),
+            Self::NEED_TRUNCATE_INVALID_UTF8_RUNE,
+            Self::char_weight,
+        )
+    }
+
+    #[inline]
+    fn write_sort_key_no_pad<W: BufferWriter>(writer: &mut W, bstr: &[u8]) -> Result<usize> {
+        write_gbk_sort_key(
+            writer,
+            bstr,
+            Self::NEED_TRUNCATE_INVALID_UTF8_RUNE,
+            Self::char_weight,
+        )
     }
 
     #[inline]
@@ -101,8 +93,8 @@ impl<T: GbkCollator> Collator for T {
     }
 
     #[inline]
-    fn sort_hash<H: Hasher>(state: &mut H, bstr: &[u8]) -> Result<()> {
-        let mut bstr_rest = trim_end_padding(bstr);
+    fn sort_hash<H: Hasher>(state: &mut H, bstr: &[u8], force_no_pad: bool) -> Result<()> {
+        let mut bstr_rest = if force_no_pad { bstr } else { trim_end_padding(bstr) };
         while !bstr_rest.is_empty() {
             match next_utf8_char(bstr_rest) {
                 Some((ch_b, b_next)) => {
@@ -122,6 +114,43 @@ impl<T: GbkCollator> Collator for T {
     }
 }
 
+/// Writes the GBK SortKey of an already pad-adjusted `bstr_rest`, shared by
+/// [`write_sort_key`](Collator::write_sort_key) and
+/// [`write_sort_key_no_pad`](Collator::write_sort_key_no_pad), which differ
+/// only in whether trailing spaces were trimmed before calling this.
+fn write_gbk_sort_key<W: BufferWriter>(
+    writer: &mut W,
+    mut bstr_rest: &[u8],
+    truncate_invalid_utf8: bool,
+    char_weight: impl Fn(char) -> u16,
+) -> Result<usize> {
+    let mut n = 0;
+    while !bstr_rest.is_empty() {
+        match next_utf8_char(bstr_rest) {
+            Some((ch, b_next)) => {
+                let weight = char_weight(ch);
+                if weight > 0xFF {
+                    writer.write_u16_be(weight)?;
+                    n += 2;
+                } else {
+                    writer.write_u8(weight as u8)?;
+                    n += 1;
+                }
+                bstr_rest = b_next
+            }
+            _ => {
+                if truncate_invalid_utf8 {
+                    break;
+                }
+                writer.write_u8(b'?')?;
+                n += 1;
+                bstr_rest = &bstr_rest[1..]
+            }
+        }
+    }
+    Ok(n * std::mem::size_of::<u8>())
+}
+
 /// Collator for `gbk_bin` collation with padding behavior (trims right spaces).
 #[derive(Debug)]
 pub struct CollatorGbkBin;
@@ -145,6 +174,47 @@ impl GbkCollator for CollatorGbkChineseCi {
     const WEIGHT_TABLE: &'static [u8; TABLE_SIZE_FOR_GBK] = GBK_CHINESE_CI_TABLE;
 }
 
+impl<T: GbkCollator> CharsetCodec for T {
+    fn charset_encode(s: &str) -> Vec<u8> {
+        let mut out = Vec::with_capacity(s.len());
+        for ch in s.chars() {
+            let weight = Self::char_weight(ch);
+            if weight > 0xFF {
+                out.extend_from_slice(&weight.to_be_bytes());
+            } else {
+                out.push(weight as u8);
+            }
+        }
+        out
+    }
+
+    fn charset_decode(bytes: &[u8]) -> Result<String> {
+        // A `static` in a generic impl is shared by every `T`, so each of the
+        // two GBK collators keeps its reverse table in a static of its own.
+        static BIN_REVERSE_TABLE: OnceLock<HashMap<u16, char>> = OnceLock::new();
+        static CHINESE_CI_REVERSE_TABLE: OnceLock<HashMap<u16, char>> = OnceLock::new();
+        let cell = if <T as GbkCollator>::IS_CASE_INSENSITIVE {
+            &CHINESE_CI_REVERSE_TABLE
+        } else {
+            &BIN_REVERSE_TABLE
+        };
+        let table = cell.get_or_init(|| reverse_weight_table(0xFFFF, Self::char_weight));
+
+        let mut out = String::new();
+        let mut rest = bytes;
+        while let Some(&lead) = rest.first() {
+            let (weight, consumed) = if (0x81..=0xFE).contains(&lead) && rest.len() >= 2 {
+                (u16::from_be_bytes([rest[0], rest[1]]), 2)
+            } else {
+                (lead as u16, 1)
+            };
+            out.push(table.get(&weight).copied().unwrap_or('?'));
+            rest = &rest[consumed..];
+        }
+        Ok(out)
+    }
+}
+
 const TABLE_SIZE_FOR_GBK: usize = (0xffff + 1) * 2;
 
 // GBK_BIN_TABLE are the encoding tables from Unicode to GBK code, it is totally
@@ -156,3 +226,43 @@ const GBK_BIN_TABLE: &[u8; TABLE_SIZE_FOR_GBK] = include_bytes!("gbk_bin.data");
 // If there is no mapping code in GBK, use 0x3F(?) instead. It should not
 // happened.
 const GBK_CHINESE_CI_TABLE: &[u8; TABLE_SIZE_FOR_GBK] = include_bytes!("gbk_chinese_ci.data");
+
+#[cfg(test)]
+mod tests {
+    use crate::codec::collation::collator::{CharsetCodec, CollatorGbkBin, CollatorGbkChineseCi};
+
+    #[test]
+    fn test_gbk_bin_charset_round_trip() {
+        // `gbk_bin`'s weight table is an injective encoding of the
+        // character, so encode/decode round-trips exactly.
+        for s in ["hello", "中文测试", "中a文b"] {
+            let encoded = CollatorGbkBin::charset_encode(s);
+            assert_eq!(CollatorGbkBin::charset_decode(&encoded).unwrap(), s);
+        }
+    }
+
+    #[test]
+    fn test_gbk_chinese_ci_charset_decode_is_representative() {
+        // `gbk_chinese_ci` folds case together, so decoding only has to
+        // recover *a* character with the same weight, not necessarily the
+        // original one.
+        for s in ["hello", "中文测试", "HELLO"] {
+            let encoded = CollatorGbkChineseCi::charset_encode(s);
+            let decoded = CollatorGbkChineseCi::charset_decode(&encoded).unwrap();
+            assert_eq!(
+                CollatorGbkChineseCi::charset_encode(&decoded),
+                encoded,
+                "decoding {:?} should produce a representative with the same weights",
+                s,
+            );
+        }
+    }
+
+    #[test]
+    fn test_gbk_charset_decode_unmappable_falls_back_to_question_mark() {
+        // A weight with no entry in the reverse table decodes to '?',
+        // mirroring the '?' substitution `write_sort_key` uses for
+        // unmappable characters.
+        assert_eq!(CollatorGbkBin::charset_decode(&[0x81]).unwrap(), "?");
+    }
+}
@@ -29,19 +29,26 @@ impl Collator for CollatorLatin1Bin {
     }
 
     #[inline]
-    fn sort_compare(mut a: &[u8], mut b: &[u8], force_no_pad: bool) -> Result<Ordering> {
-        if !force_no_pad {
-            a = a.trim_end_with(|c| c == PADDING_SPACE);
-        }
-        if !force_no_pad {
-            b = b.trim_end_with(|c| c == PADDING_SPACE);
+    fn write_sort_key_no_pad<W: BufferWriter>(writer: &mut W, bstr: &[u8]) -> Result<usize> {
+        writer.write_bytes(bstr)?;
+        Ok(bstr.len())
+    }
+
+    #[inline]
+    fn sort_compare(a: &[u8], b: &[u8], force_no_pad: bool) -> Result<Ordering> {
+        if force_no_pad {
+            return Ok(a.cmp(b));
         }
-        Ok(a.cmp(b))
+        Ok(compare_padded_bytes(a, b))
     }
 
     #[inline]
-    fn sort_hash<H: Hasher>(state: &mut H, bstr: &[u8]) -> Result<()> {
-        B(bstr).trim_end_with(|c| c == PADDING_SPACE).hash(state);
+    fn sort_hash<H: Hasher>(state: &mut H, bstr: &[u8], force_no_pad: bool) -> Result<()> {
+        if force_no_pad {
+            bstr.hash(state);
+        } else {
+            B(bstr).trim_end_with(|c| c == PADDING_SPACE).hash(state);
+        }
         Ok(())
     }
 }
@@ -0,0 +1,116 @@
+// Copyright 2026 TiKV Project Authors. Licensed under Apache-2.0.
+
+use std::cmp::Ordering;
+
+use super::*;
+
+/// Case- and accent-folding table for `latin1_swedish_ci`, indexed by raw
+/// Latin-1 byte value, matching MySQL's `sort_order_latin1` table. Ascii
+/// letters fold to upper case, and so do the letters of the Latin-1
+/// supplement range; most accented letters there fold further to their
+/// unaccented upper-case base letter. The exceptions are `Æ`/`æ` (0xC6/0xE6),
+/// which weigh 92 (`\`), `Ø`/`ø` (0xD8/0xF8), which weigh 216, and `ÿ`
+/// (0xFF), which keeps its own value. All other bytes are left unchanged.
+#[rustfmt::skip]
+static LATIN1_GENERAL_CI_WEIGHT: [u8; 256] = [
+    0,   1,   2,   3,   4,   5,   6,   7,
+    8,   9,   10,  11,  12,  13,  14,  15,
+    16,  17,  18,  19,  20,  21,  22,  23,
+    24,  25,  26,  27,  28,  29,  30,  31,
+    32,  33,  34,  35,  36,  37,  38,  39,
+    40,  41,  42,  43,  44,  45,  46,  47,
+    48,  49,  50,  51,  52,  53,  54,  55,
+    56,  57,  58,  59,  60,  61,  62,  63,
+    64,  65,  66,  67,  68,  69,  70,  71,
+    72,  73,  74,  75,  76,  77,  78,  79,
+    80,  81,  82,  83,  84,  85,  86,  87,
+    88,  89,  90,  91,  92,  93,  94,  95,
+    96,  65,  66,  67,  68,  69,  70,  71,
+    72,  73,  74,  75,  76,  77,  78,  79,
+    80,  81,  82,  83,  84,  85,  86,  87,
+    88,  89,  90,  123, 124, 125, 126, 127,
+    128, 129, 130, 131, 132, 133, 134, 135,
+    136, 137, 138, 139, 140, 141, 142, 143,
+    144, 145, 146, 147, 148, 149, 150, 151,
+    152, 153, 154, 155, 156, 157, 158, 159,
+    160, 161, 162, 163, 164, 165, 166, 167,
+    168, 169, 170, 171, 172, 173, 174, 175,
+    176, 177, 178, 179, 180, 181, 182, 183,
+    184, 185, 186, 187, 188, 189, 190, 191,
+    65,  65,  65,  65,  65,  65,  92,  67,
+    69,  69,  69,  69,  73,  73,  73,  73,
+    68,  78,  79,  79,  79,  79,  79, 215,
+    216, 85,  85,  85,  85,  89,  222, 223,
+    65,  65,  65,  65,  65,  65,  92,  67,
+    69,  69,  69,  69,  73,  73,  73,  73,
+    68,  78,  79,  79,  79,  79,  79, 247,
+    216, 85,  85,  85,  85,  89,  222, 255,
+];
+
+/// Collator for `latin1_swedish_ci`, a case- and accent-insensitive collation
+/// over the single-byte Latin-1 range. Unlike [`CollatorLatin1Bin`], this
+/// folds ASCII case and Latin-1 supplement accents before comparing, so e.g.
+/// `'a'`, `'A'` and `'\u{c0}'` (`À`) all sort and hash as equal.
+#[derive(Debug)]
+pub struct CollatorLatin1GeneralCi;
+
+impl CollatorLatin1GeneralCi {
+    #[inline]
+    fn write_sort_key_impl<W: BufferWriter>(writer: &mut W, s: &[u8]) -> Result<usize> {
+        let mut n = 0;
+        for b in s {
+            writer.write_bytes(&[Self::char_weight(*b)])?;
+            n += 1;
+        }
+        Ok(n)
+    }
+}
+
+impl Collator for CollatorLatin1GeneralCi {
+    type Charset = CharsetBinary;
+    type Weight = u8;
+
+    const IS_CASE_INSENSITIVE: bool = true;
+    const LIKE_PATTERN_MODE: LikePatternMode = LikePatternMode::BinaryRunes;
+
+    #[inline]
+    fn char_weight(ch: u8) -> Self::Weight {
+        LATIN1_GENERAL_CI_WEIGHT[ch as usize]
+    }
+
+    #[inline]
+    fn write_sort_key<W: BufferWriter>(writer: &mut W, bstr: &[u8]) -> Result<usize> {
+        Self::write_sort_key_impl(writer, trim_end_padding(bstr))
+    }
+
+    #[inline]
+    fn write_sort_key_no_pad<W: BufferWriter>(writer: &mut W, bstr: &[u8]) -> Result<usize> {
+        Self::write_sort_key_impl(writer, bstr)
+    }
+
+    #[inline]
+    fn sort_compare(mut a: &[u8], mut b: &[u8], force_no_pad: bool) -> Result<Ordering> {
+        if !force_no_pad {
+            a = trim_end_padding(a);
+        }
+        if !force_no_pad {
+            b = trim_end_padding(b);
+        }
+        for (x, y) in a.iter().zip(b.iter()) {
+            let cmp = Self::char_weight(*x).cmp(&Self::char_weight(*y));
+            if cmp != Ordering::Equal {
+                return Ok(cmp);
+            }
+        }
+        Ok(a.len().cmp(&b.len()))
+    }
+
+    #[inline]
+    fn sort_hash<H: Hasher>(state: &mut H, bstr: &[u8], force_no_pad: bool) -> Result<()> {
+        let s = if force_no_pad { bstr } else { trim_end_padding(bstr) };
+        for b in s {
+            Self::char_weight(*b).hash(state);
+        }
+        Ok(())
+    }
+}
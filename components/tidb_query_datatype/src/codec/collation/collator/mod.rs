@@ -1,9 +1,11 @@
 // Copyright 2020 TiKV Project Authors. Licensed under Apache-2.0.
 
 mod binary;
+mod charset_codec;
 mod gb18030_collation;
 mod gbk_collation;
 mod latin1_bin;
+mod latin1_general_ci;
 mod utf8mb4_binary;
 mod utf8mb4_general_ci;
 mod utf8mb4_uca;
@@ -15,9 +17,11 @@ use std::{
 
 pub use binary::*;
 use codec::prelude::*;
+pub use charset_codec::{CharsetCodec, sort_key_byte_successor};
 pub use gb18030_collation::*;
 pub use gbk_collation::*;
 pub use latin1_bin::*;
+pub use latin1_general_ci::*;
 pub use utf8mb4_binary::*;
 pub use utf8mb4_general_ci::*;
 pub use utf8mb4_uca::*;
@@ -27,13 +31,70 @@ use crate::codec::Result;
 
 pub const PADDING_SPACE: char = 0x20 as char;
 
+/// All-space `usize`-sized chunk, used by [`trim_end_padding`] to skip a
+/// whole machine word of trailing padding at a time instead of one byte at a
+/// time.
+const SPACE_CHUNK: usize = usize::from_ne_bytes([PADDING_SPACE as u8; size_of::<usize>()]);
+
+/// Trims trailing `PADDING_SPACE` bytes off `s`.
+///
+/// Scans backwards a whole `usize` word at a time as long as the trailing
+/// word is all spaces, only falling back to a byte-by-byte scan for the
+/// (at most one word's worth of) remainder. This keeps a multi-megabyte
+/// all-space value from costing one comparison per byte.
 pub(crate) fn trim_end_padding(mut s: &[u8]) -> &[u8] {
-    while s.ends_with(&[PADDING_SPACE as u8]) {
+    while s.len() >= size_of::<usize>() {
+        let tail = &s[s.len() - size_of::<usize>()..];
+        if usize::from_ne_bytes(tail.try_into().unwrap()) != SPACE_CHUNK {
+            break;
+        }
+        s = &s[..s.len() - size_of::<usize>()];
+    }
+    while s.last() == Some(&(PADDING_SPACE as u8)) {
         s = &s[..s.len() - 1];
     }
     s
 }
 
+/// Compares two padded byte strings the way a `_bin` collator's
+/// `sort_compare` does: the result is the order of the two values with their
+/// trailing `PADDING_SPACE` bytes trimmed off.
+///
+/// A naive implementation trims both sides in full before comparing, which
+/// costs an extra O(len) pass over both values even when they differ in
+/// their first byte. Comparing the common prefix first lets a mismatch
+/// in that prefix return without trimming unless one side ends in padding,
+/// and otherwise confines the padding trim to the tail that's left over
+/// once one side runs out.
+pub(crate) fn compare_padded_bytes(a: &[u8], b: &[u8]) -> Ordering {
+    let common_len = a.len().min(b.len());
+    match a[..common_len].cmp(&b[..common_len]) {
+        Ordering::Equal => (),
+        ord => {
+            // A side whose bytes from the first mismatch on are all padding
+            // ends there once trimmed, so it sorts first whatever byte the
+            // other side has at that position.
+            let pad = Some(&(PADDING_SPACE as u8));
+            if a.last() == pad || b.last() == pad {
+                let i = a.iter().zip(b).position(|(x, y)| x != y).unwrap();
+                if trim_end_padding(&a[i..]).is_empty() {
+                    return Ordering::Less;
+                }
+                if trim_end_padding(&b[i..]).is_empty() {
+                    return Ordering::Greater;
+                }
+            }
+            return ord;
+        }
+    }
+    match a.len().cmp(&b.len()) {
+        Ordering::Equal => Ordering::Equal,
+        Ordering::Less if trim_end_padding(&b[common_len..]).is_empty() => Ordering::Equal,
+        Ordering::Greater if trim_end_padding(&a[common_len..]).is_empty() => Ordering::Equal,
+        ord => ord,
+    }
+}
+
 pub(crate) fn next_utf8_char(s: &[u8]) -> Option<(char, &[u8])> {
     let len = match s.first()? {
         0x00..=0x7F => 1,
@@ -50,6 +111,53 @@ pub(crate) fn next_utf8_char(s: &[u8]) -> Option<(char, &[u8])> {
     Some((ch, tail))
 }
 
+/// Walks a utf8mb4 value char by char, the way collators used to do with
+/// repeated `next_utf8_char` calls, but validates the whole value once up
+/// front (the SIMD-accelerated fast path) instead of decoding and
+/// re-checking one byte sequence at a time.
+///
+/// [`Self::next`] reports `None` in exactly the two situations
+/// `next_utf8_char` did: the value is exhausted, or the next bytes are not
+/// valid utf8mb4. [`Self::rest_len`] mirrors the byte length of whatever
+/// `next_utf8_char` would still have had left to decode, so callers that
+/// used to compare `a_rest.len()` against `b_rest.len()` once one side runs
+/// out can keep doing exactly that.
+pub(crate) struct Utf8Mb4Cursor<'a> {
+    len: usize,
+    valid_len: usize,
+    chars: std::str::CharIndices<'a>,
+    pos: usize,
+}
+
+impl<'a> Utf8Mb4Cursor<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Self {
+        let valid_len = match validate_utf8mb4(bytes) {
+            Ok(()) => bytes.len(),
+            Err(offset) => offset,
+        };
+        let valid = std::str::from_utf8(&bytes[..valid_len]).unwrap();
+        Utf8Mb4Cursor {
+            len: bytes.len(),
+            valid_len,
+            chars: valid.char_indices(),
+            pos: 0,
+        }
+    }
+
+    pub(crate) fn rest_len(&self) -> usize {
+        self.len - self.pos
+    }
+
+    pub(crate) fn next(&mut self) -> Option<char> {
+        if self.pos >= self.valid_len {
+            return None;
+        }
+        let (_, ch) = self.chars.next()?;
+        self.pos += ch.len_utf8();
+        Some(ch)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{Collation, codec::collation::Collator, match_template_collator};
@@ -288,7 +396,7 @@ mod tests {
                         Collation::TT => {
                             let eval_hash = |s| {
                                 let mut hasher = DefaultHasher::default();
-                                TT::sort_hash(&mut hasher, s).unwrap();
+                                TT::sort_hash(&mut hasher, s, false).unwrap();
                                 hasher.finish()
                             };
 
@@ -548,7 +656,7 @@ mod tests {
         for (sa, sb, od) in cases {
             let eval_hash = |s| {
                 let mut hasher = DefaultHasher::default();
-                CollatorLatin1Bin::sort_hash(&mut hasher, s).unwrap();
+                CollatorLatin1Bin::sort_hash(&mut hasher, s, false).unwrap();
                 hasher.finish()
             };
 
@@ -573,4 +681,352 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_latin1_general_ci() {
+        use std::{cmp::Ordering, collections::hash_map::DefaultHasher, hash::Hasher};
+
+        use crate::codec::collation::collator::CollatorLatin1GeneralCi;
+
+        let cases = vec![
+            (b"abc".to_vec(), b"ABC".to_vec(), Ordering::Equal),
+            (b"abc".to_vec(), b"abc ".to_vec(), Ordering::Equal),
+            (vec![0xE0], vec![0x41], Ordering::Equal), // à == A
+            (vec![0xC0], vec![0x61], Ordering::Equal), // À == a
+            (b"abc".to_vec(), b"abd".to_vec(), Ordering::Less),
+        ];
+
+        for (sa, sb, od) in cases {
+            let eval_hash = |s| {
+                let mut hasher = DefaultHasher::default();
+                CollatorLatin1GeneralCi::sort_hash(&mut hasher, s, false).unwrap();
+                hasher.finish()
+            };
+
+            let cmp =
+                CollatorLatin1GeneralCi::sort_compare(sa.as_slice(), sb.as_slice(), false)
+                    .unwrap();
+            let ha = eval_hash(sa.as_slice());
+            let hb = eval_hash(sb.as_slice());
+
+            assert_eq!(cmp, od, "when comparing {:?} and {:?}", sa, sb);
+
+            if od == Ordering::Equal {
+                assert_eq!(
+                    ha, hb,
+                    "when comparing the hash of {:?} and {:?}, which should be equal",
+                    sa, sb
+                );
+            } else {
+                assert_ne!(
+                    ha, hb,
+                    "when comparing the hash of {:?} and {:?}, which should not be equal",
+                    sa, sb
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_sort_key_prefix_upper_bound() {
+        use rand::Rng;
+
+        use crate::Collation;
+
+        let collations = [
+            Collation::Utf8Mb4GeneralCi,
+            Collation::Utf8Mb4UnicodeCi,
+            Collation::Utf8Mb40900AiCi,
+            Collation::GbkChineseCi,
+            Collation::Gb18030ChineseCi,
+        ];
+        let prefixes = ["", "a", "A", "abc", "\u{00DF}", "中", "中文"];
+        let alphabet: Vec<char> = "abcABC012 中文\u{00DF}Straße".chars().collect();
+        let mut rng = rand::thread_rng();
+
+        for collation in collations {
+            for prefix in prefixes {
+                let (prefix_key, upper_bound) = match_template_collator! {
+                    TT, match collation {
+                        Collation::TT => (
+                            TT::sort_key(prefix.as_bytes()).unwrap(),
+                            TT::sort_key_prefix_upper_bound(prefix.as_bytes()).unwrap(),
+                        )
+                    }
+                };
+                // An upper bound, when one exists, must strictly exceed the
+                // prefix's own SortKey.
+                assert!(
+                    upper_bound.is_empty() || upper_bound.as_slice() > prefix_key.as_slice(),
+                    "upper bound for {:?} under {:?} did not exceed the prefix's own SortKey",
+                    prefix, collation,
+                );
+
+                // Strings built by extending the prefix always land inside
+                // the range; fully random strings exercise both sides of the
+                // `is_sort_key_prefix` classification.
+                let mut candidates: Vec<String> = (0..20)
+                    .map(|_| {
+                        let len = rng.gen_range(0, 6);
+                        let suffix: String = (0..len)
+                            .map(|_| alphabet[rng.gen_range(0, alphabet.len())])
+                            .collect();
+                        format!("{}{}", prefix, suffix)
+                    })
+                    .collect();
+                candidates.extend((0..20).map(|_| {
+                    let len = rng.gen_range(0, 6);
+                    (0..len)
+                        .map(|_| alphabet[rng.gen_range(0, alphabet.len())])
+                        .collect()
+                }));
+
+                for candidate in candidates {
+                    let (is_prefix, candidate_key) = match_template_collator! {
+                        TT, match collation {
+                            Collation::TT => (
+                                TT::is_sort_key_prefix(
+                                    prefix.as_bytes(), candidate.as_bytes(),
+                                ).unwrap(),
+                                TT::sort_key(candidate.as_bytes()).unwrap(),
+                            )
+                        }
+                    };
+                    assert_eq!(
+                        is_prefix,
+                        candidate_key.starts_with(&prefix_key),
+                        "is_sort_key_prefix({:?}, {:?}) disagreed with the SortKeys under {:?}",
+                        prefix, candidate, collation,
+                    );
+                    if is_prefix {
+                        assert!(
+                            upper_bound.is_empty() || candidate_key < upper_bound,
+                            "SortKey of {:?} (which matches prefix {:?}) was not below \
+                             the upper bound under {:?}",
+                            candidate, prefix, collation,
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_write_sort_key_matches_sort_key_for_random_strings() {
+        use rand::Rng;
+
+        use crate::Collation;
+
+        let collations = [
+            Collation::Utf8Mb4Bin,
+            Collation::Utf8Mb4BinNoPadding,
+            Collation::Utf8Mb4GeneralCi,
+            Collation::Utf8Mb4UnicodeCi,
+            Collation::Latin1Bin,
+            Collation::GbkBin,
+            Collation::GbkChineseCi,
+            Collation::Utf8Mb40900AiCi,
+            Collation::Utf8Mb40900Bin,
+            Collation::Gb18030Bin,
+            Collation::Gb18030ChineseCi,
+        ];
+        // A mix of ASCII, multi-byte UTF-8 (including characters with
+        // contractions under UCA-based collations) and empty/short inputs.
+        let alphabet: Vec<char> = "abcABC012 中文\u{00DF}Straße".chars().collect();
+        let mut rng = rand::thread_rng();
+
+        for collation in collations {
+            for len in 0..20 {
+                let s: String = (0..len)
+                    .map(|_| alphabet[rng.gen_range(0, alphabet.len())])
+                    .collect();
+                let input = s.as_bytes();
+
+                let expected = match_template_collator! {
+                    TT, match collation {
+                        Collation::TT => TT::sort_key(input).unwrap()
+                    }
+                };
+                let mut streamed = Vec::new();
+                let written = match_template_collator! {
+                    TT, match collation {
+                        Collation::TT => TT::write_sort_key(&mut streamed, input).unwrap()
+                    }
+                };
+                assert_eq!(written, streamed.len());
+                assert_eq!(
+                    streamed, expected,
+                    "streamed sort key diverged from sort_key() for {:?} under {:?}",
+                    s, collation
+                );
+            }
+        }
+    }
+
+    /// `force_no_pad=true` (used by the binary-comparison operator and
+    /// `weight_string(... AS BINARY)`) must make a PAD collation treat
+    /// trailing spaces literally in `sort_compare`, `sort_hash` and
+    /// `sort_key_no_pad` alike, while a NO PAD collation must behave
+    /// identically regardless of the flag.
+    #[test]
+    fn test_force_no_pad() {
+        use std::{cmp::Ordering, collections::hash_map::DefaultHasher, hash::Hasher};
+
+        let pad_collations = [
+            Collation::Utf8Mb4Bin,
+            Collation::Utf8Mb4GeneralCi,
+            Collation::Utf8Mb4UnicodeCi,
+            Collation::Latin1Bin,
+            Collation::GbkBin,
+            Collation::GbkChineseCi,
+            Collation::Gb18030Bin,
+            Collation::Gb18030ChineseCi,
+        ];
+        let no_pad_collations = [
+            Collation::Utf8Mb4BinNoPadding,
+            Collation::Utf8Mb40900AiCi,
+            Collation::Utf8Mb40900Bin,
+        ];
+
+        for collation in pad_collations {
+            match_template_collator! {
+                TT, match collation {
+                    Collation::TT => {
+                        assert_eq!(
+                            TT::sort_compare(b"a", b"a ", false).unwrap(),
+                            Ordering::Equal,
+                            "{:?} should pad by default", collation
+                        );
+                        assert_eq!(
+                            TT::sort_compare(b"a", b"a ", true).unwrap(),
+                            Ordering::Less,
+                            "{:?} should compare the literal trailing space when forced", collation
+                        );
+
+                        let mut padded = DefaultHasher::default();
+                        TT::sort_hash(&mut padded, b"a", false).unwrap();
+                        let mut unpadded = DefaultHasher::default();
+                        TT::sort_hash(&mut unpadded, b"a ", false).unwrap();
+                        assert_eq!(
+                            padded.finish(), unpadded.finish(),
+                            "{:?} should hash padding-equal strings the same by default", collation
+                        );
+
+                        let mut forced_a = DefaultHasher::default();
+                        TT::sort_hash(&mut forced_a, b"a", true).unwrap();
+                        let mut forced_a_space = DefaultHasher::default();
+                        TT::sort_hash(&mut forced_a_space, b"a ", true).unwrap();
+                        assert_ne!(
+                            forced_a.finish(), forced_a_space.finish(),
+                            "{:?} should hash the literal trailing space when forced", collation
+                        );
+
+                        assert_eq!(
+                            TT::sort_key_no_pad(b"a ").unwrap(),
+                            TT::sort_key_no_pad(b"a ").unwrap()
+                        );
+                        assert_ne!(
+                            TT::sort_key_no_pad(b"a").unwrap(),
+                            TT::sort_key_no_pad(b"a ").unwrap(),
+                            "{:?}'s no-pad SortKey should keep the trailing space", collation
+                        );
+                    }
+                }
+            }
+        }
+
+        for collation in no_pad_collations {
+            match_template_collator! {
+                TT, match collation {
+                    Collation::TT => {
+                        assert_eq!(
+                            TT::sort_compare(b"a", b"a ", false).unwrap(),
+                            TT::sort_compare(b"a", b"a ", true).unwrap(),
+                            "{:?} never pads, so force_no_pad should not change the outcome", collation
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_trim_end_padding() {
+        use super::{PADDING_SPACE, trim_end_padding};
+
+        assert_eq!(trim_end_padding(b""), b"");
+        assert_eq!(trim_end_padding(b"a"), b"a");
+        assert_eq!(trim_end_padding(b"a "), b"a");
+        assert_eq!(trim_end_padding(b"a  "), b"a");
+        assert_eq!(trim_end_padding(b"   "), b"");
+        assert_eq!(trim_end_padding(b"a b"), b"a b");
+
+        // Long enough to cross several `usize`-sized chunks in the fast
+        // path, both for an all-space value and for one with a single
+        // non-space byte right at a chunk boundary.
+        let all_spaces = vec![PADDING_SPACE as u8; 1024];
+        assert_eq!(trim_end_padding(&all_spaces), b"");
+
+        let mut boundary = vec![PADDING_SPACE as u8; 1024];
+        boundary[1024 - size_of::<usize>()] = b'x';
+        let trimmed = trim_end_padding(&boundary);
+        assert_eq!(trimmed.len(), 1024 - size_of::<usize>() + 1);
+        assert_eq!(trimmed.last(), Some(&b'x'));
+    }
+
+    #[test]
+    fn test_compare_padded_bytes() {
+        use std::cmp::Ordering;
+
+        use super::{PADDING_SPACE, compare_padded_bytes, trim_end_padding};
+
+        assert_eq!(compare_padded_bytes(b"a", b"a"), Ordering::Equal);
+        assert_eq!(compare_padded_bytes(b"a", b"a "), Ordering::Equal);
+        assert_eq!(compare_padded_bytes(b"a ", b"a"), Ordering::Equal);
+        assert_eq!(compare_padded_bytes(b"a", b"a  "), Ordering::Equal);
+        assert_eq!(compare_padded_bytes(b"", b"   "), Ordering::Equal);
+        assert_eq!(compare_padded_bytes(b"a", b"ab"), Ordering::Less);
+        assert_eq!(compare_padded_bytes(b"ab", b"a"), Ordering::Greater);
+        assert_eq!(compare_padded_bytes(b"a", b"b"), Ordering::Less);
+        assert_eq!(compare_padded_bytes(b"b", b"a"), Ordering::Less.reverse());
+        // A mismatch in the common prefix must win even when the shorter
+        // side, once its tail is considered, would otherwise look like a
+        // padding-only difference.
+        assert_eq!(compare_padded_bytes(b"ax", b"b "), Ordering::Less);
+
+        // Agrees with the naive "trim both sides fully, then compare" for a
+        // large battery of length/padding combinations, including values
+        // long enough to exercise `trim_end_padding`'s chunked fast path.
+        fn naive_compare(a: &[u8], b: &[u8]) -> Ordering {
+            trim_end_padding(a).cmp(trim_end_padding(b))
+        }
+        let bodies: Vec<Vec<u8>> = vec![
+            b"".to_vec(),
+            b"a".to_vec(),
+            b"ab".to_vec(),
+            b"abc".to_vec(),
+            vec![b'a'; 100],
+            vec![b'a'; 1024],
+        ];
+        let pad_lens = [0, 1, 7, 8, 9, 100];
+        let mut values = Vec::new();
+        for body in &bodies {
+            for &pad_len in &pad_lens {
+                let mut v = body.clone();
+                v.extend(std::iter::repeat_n(PADDING_SPACE as u8, pad_len));
+                values.push(v);
+            }
+        }
+        for a in &values {
+            for b in &values {
+                assert_eq!(
+                    compare_padded_bytes(a, b),
+                    naive_compare(a, b),
+                    "mismatch comparing {:?} against {:?}",
+                    a,
+                    b
+                );
+            }
+        }
+    }
 }
@@ -26,16 +26,23 @@ impl Collator for CollatorUtf8Mb4Bin {
         Ok(bstr.len())
     }
 
+    #[inline]
+    fn write_sort_key_no_pad<W: BufferWriter>(writer: &mut W, bstr: &[u8]) -> Result<usize> {
+        writer.write_bytes(bstr)?;
+        Ok(bstr.len())
+    }
+
     #[inline]
     fn sort_compare(a: &[u8], b: &[u8], force_no_pad: bool) -> Result<Ordering> {
-        let a = if force_no_pad { a } else { trim_end_padding(a) };
-        let b = if force_no_pad { b } else { trim_end_padding(b) };
-        Ok(a.cmp(b))
+        if force_no_pad {
+            return Ok(a.cmp(b));
+        }
+        Ok(compare_padded_bytes(a, b))
     }
 
     #[inline]
-    fn sort_hash<H: Hasher>(state: &mut H, bstr: &[u8]) -> Result<()> {
-        let bstr = trim_end_padding(bstr);
+    fn sort_hash<H: Hasher>(state: &mut H, bstr: &[u8], force_no_pad: bool) -> Result<()> {
+        let bstr = if force_no_pad { bstr } else { trim_end_padding(bstr) };
         bstr.hash(state);
         Ok(())
     }
@@ -69,7 +76,7 @@ impl Collator for CollatorUtf8Mb4BinNoPadding {
     }
 
     #[inline]
-    fn sort_hash<H: Hasher>(state: &mut H, bstr: &[u8]) -> Result<()> {
+    fn sort_hash<H: Hasher>(state: &mut H, bstr: &[u8], _force_no_pad: bool) -> Result<()> {
         bstr.hash(state);
         Ok(())
     }
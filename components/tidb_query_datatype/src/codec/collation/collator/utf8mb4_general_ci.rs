@@ -29,20 +29,12 @@ impl Collator for CollatorUtf8Mb4GeneralCi {
 
     #[inline]
     fn write_sort_key<W: BufferWriter>(writer: &mut W, bstr: &[u8]) -> Result<usize> {
-        let mut bstr_rest = trim_end_padding(bstr);
-        let mut n = 0;
+        Self::write_sort_key_impl(writer, trim_end_padding(bstr))
+    }
 
-        while !bstr_rest.is_empty() {
-            match next_utf8_char(bstr_rest) {
-                Some((ch_b, b_next)) => {
-                    writer.write_u16_be(Self::char_weight(ch_b))?;
-                    n += 1;
-                    bstr_rest = b_next
-                }
-                _ => break,
-            }
-        }
-        Ok(n * std::mem::size_of::<u16>())
+    #[inline]
+    fn write_sort_key_no_pad<W: BufferWriter>(writer: &mut W, bstr: &[u8]) -> Result<usize> {
+        Self::write_sort_key_impl(writer, bstr)
     }
 
     #[inline]
@@ -50,42 +42,50 @@ impl Collator for CollatorUtf8Mb4GeneralCi {
         let a = if force_no_pad { a } else { trim_end_padding(a) };
         let b = if force_no_pad { b } else { trim_end_padding(b) };
 
-        let mut a_rest = a;
-        let mut b_rest = b;
+        let mut a_cur = Utf8Mb4Cursor::new(a);
+        let mut b_cur = Utf8Mb4Cursor::new(b);
 
-        while !a_rest.is_empty() && !b_rest.is_empty() {
-            match (next_utf8_char(a_rest), next_utf8_char(b_rest)) {
-                (Some((ch_a, a_next)), Some((ch_b, b_next))) => {
+        loop {
+            if a_cur.rest_len() == 0 || b_cur.rest_len() == 0 {
+                return Ok(a_cur.rest_len().cmp(&b_cur.rest_len()));
+            }
+            match (a_cur.next(), b_cur.next()) {
+                (Some(ch_a), Some(ch_b)) => {
                     let ord = Self::char_weight(ch_a).cmp(&Self::char_weight(ch_b));
                     if ord != Ordering::Equal {
                         return Ok(ord);
                     }
-                    a_rest = a_next;
-                    b_rest = b_next;
                 }
                 _ => return Ok(Ordering::Equal),
             }
         }
-
-        Ok(a_rest.len().cmp(&b_rest.len()))
     }
 
     #[inline]
-    fn sort_hash<H: Hasher>(state: &mut H, bstr: &[u8]) -> Result<()> {
-        let mut bstr_rest = trim_end_padding(bstr);
-        while !bstr_rest.is_empty() {
-            match next_utf8_char(bstr_rest) {
-                Some((ch_b, b_next)) => {
-                    Self::char_weight(ch_b).hash(state);
-                    bstr_rest = b_next
-                }
-                _ => break,
-            }
+    fn sort_hash<H: Hasher>(state: &mut H, bstr: &[u8], force_no_pad: bool) -> Result<()> {
+        let bstr_rest = if force_no_pad { bstr } else { trim_end_padding(bstr) };
+        let mut cursor = Utf8Mb4Cursor::new(bstr_rest);
+        while let Some(ch_b) = cursor.next() {
+            Self::char_weight(ch_b).hash(state);
         }
         Ok(())
     }
 }
 
+impl CollatorUtf8Mb4GeneralCi {
+    #[inline]
+    fn write_sort_key_impl<W: BufferWriter>(writer: &mut W, bstr_rest: &[u8]) -> Result<usize> {
+        let mut cursor = Utf8Mb4Cursor::new(bstr_rest);
+        let mut n = 0;
+
+        while let Some(ch_b) = cursor.next() {
+            writer.write_u16_be(Self::char_weight(ch_b))?;
+            n += 1;
+        }
+        Ok(n * std::mem::size_of::<u16>())
+    }
+}
+
 static GENERAL_CI_PLANE_00: [u16; 256] = [
     0x0000, 0x0001, 0x0002, 0x0003, 0x0004, 0x0005, 0x0006, 0x0007, 0x0008, 0x0009, 0x000A, 0x000B,
     0x000C, 0x000D, 0x000E, 0x000F, 0x0010, 0x0011, 0x0012, 0x0013, 0x0014, 0x0015, 0x0016, 0x0017,
@@ -393,3 +393,54 @@ static GENERAL_CI_PLANE_TABLE: [Option<&[u16; 256]>; 256] = [
     None, None, None, None, None, None, None, None, None, None,
     Some(&GENERAL_CI_PLANE_FF),
 ];
+
+#[cfg(test)]
+mod tests {
+    use std::{cmp::Ordering, collections::hash_map::DefaultHasher, hash::Hasher};
+
+    use super::*;
+
+    #[test]
+    fn test_sort_compare_with_malformed_utf8() {
+        // Malformed byte at the start: both sides fail to decode together
+        // and compare as equal, the same as two `next_utf8_char` calls both
+        // returning `None` used to.
+        assert_eq!(
+            CollatorUtf8Mb4GeneralCi::sort_compare(b"\xFFa", b"a", false).unwrap(),
+            Ordering::Equal
+        );
+
+        // Malformed byte in the middle: bytes after it are never compared,
+        // even though they differ ('d' vs 'z').
+        assert_eq!(
+            CollatorUtf8Mb4GeneralCi::sort_compare(b"ab\xFFcd", b"ab\xFFcz", false).unwrap(),
+            Ordering::Equal
+        );
+
+        // Truncated sequence at the end: the side with leftover bytes
+        // (whether valid or not) compares as greater once the other side is
+        // exhausted cleanly.
+        assert_eq!(
+            CollatorUtf8Mb4GeneralCi::sort_compare(b"ab\xE4", b"ab", false).unwrap(),
+            Ordering::Greater
+        );
+    }
+
+    #[test]
+    fn test_write_sort_key_truncates_at_malformed_byte() {
+        let mut with_garbage = Vec::new();
+        CollatorUtf8Mb4GeneralCi::write_sort_key(&mut with_garbage, b"ab\xFFcd").unwrap();
+        let mut truncated = Vec::new();
+        CollatorUtf8Mb4GeneralCi::write_sort_key(&mut truncated, b"ab").unwrap();
+        assert_eq!(with_garbage, truncated);
+    }
+
+    #[test]
+    fn test_sort_hash_truncates_at_malformed_byte() {
+        let mut with_garbage = DefaultHasher::new();
+        CollatorUtf8Mb4GeneralCi::sort_hash(&mut with_garbage, b"ab\xFFcd", false).unwrap();
+        let mut truncated = DefaultHasher::new();
+        CollatorUtf8Mb4GeneralCi::sort_hash(&mut truncated, b"ab", false).unwrap();
+        assert_eq!(with_garbage.finish(), truncated.finish());
+    }
+}
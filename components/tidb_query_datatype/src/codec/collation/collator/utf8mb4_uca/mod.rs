@@ -41,60 +41,44 @@ impl<T: UnicodeVersion> Collator for CollatorUca<T> {
 
     #[inline]
     fn like_pattern_compare(a: &[u8], b: &[u8]) -> Result<bool> {
-        let a = next_utf8_char(a).map(|(ch, _)| ch);
-        let b = next_utf8_char(b).map(|(ch, _)| ch);
+        let a = Utf8Mb4Cursor::new(a).next();
+        let b = Utf8Mb4Cursor::new(b).next();
         Ok(matches!((a, b), (Some(a), Some(b)) if T::like_pattern_match(a, b)))
     }
 
     #[inline]
     fn write_sort_key<W: BufferWriter>(writer: &mut W, bstr: &[u8]) -> Result<usize> {
-        let mut bstr_rest = T::preprocess(bstr);
-
-        let mut n = 0;
-
-        while !bstr_rest.is_empty() {
-            match next_utf8_char(bstr_rest) {
-                Some((ch_b, b_next)) => {
-                    let mut weight = Self::char_weight(ch_b);
-                    while weight != 0 {
-                        writer.write_u16_be((weight & 0xFFFF) as u16)?;
-                        n += 1;
-                        weight >>= 16
-                    }
-                    bstr_rest = b_next
-                }
-                _ => break,
-            }
-        }
-        Ok(n * std::mem::size_of::<u16>())
+        write_uca_sort_key::<T, W>(writer, T::preprocess(bstr))
+    }
+
+    #[inline]
+    fn write_sort_key_no_pad<W: BufferWriter>(writer: &mut W, bstr: &[u8]) -> Result<usize> {
+        write_uca_sort_key::<T, W>(writer, bstr)
     }
 
     #[inline]
     fn sort_compare(a: &[u8], b: &[u8], force_no_pad: bool) -> Result<Ordering> {
-        let mut sa = if force_no_pad { a } else { T::preprocess(a) };
-        let mut sb = if force_no_pad { b } else { T::preprocess(b) };
+        let sa = if force_no_pad { a } else { T::preprocess(a) };
+        let sb = if force_no_pad { b } else { T::preprocess(b) };
+
+        let mut a_cur = Utf8Mb4Cursor::new(sa);
+        let mut b_cur = Utf8Mb4Cursor::new(sb);
 
         let mut an = 0;
         let mut bn = 0;
 
         loop {
-            while an == 0 && !sa.is_empty() {
-                match next_utf8_char(sa) {
-                    Some((ch_a, a_next)) => {
-                        an = Self::char_weight(ch_a);
-                        sa = a_next;
-                    }
-                    _ => return Ok(Ordering::Equal),
+            while an == 0 && a_cur.rest_len() != 0 {
+                match a_cur.next() {
+                    Some(ch_a) => an = Self::char_weight(ch_a),
+                    None => return Ok(Ordering::Equal),
                 }
             }
 
-            while bn == 0 && !sb.is_empty() {
-                match next_utf8_char(sb) {
-                    Some((ch_b, b_next)) => {
-                        bn = Self::char_weight(ch_b);
-                        sb = b_next;
-                    }
-                    _ => return Ok(Ordering::Equal),
+            while bn == 0 && b_cur.rest_len() != 0 {
+                match b_cur.next() {
+                    Some(ch_b) => bn = Self::char_weight(ch_b),
+                    None => return Ok(Ordering::Equal),
                 }
             }
 
@@ -119,21 +103,87 @@ impl<T: UnicodeVersion> Collator for CollatorUca<T> {
     }
 
     #[inline]
-    fn sort_hash<H: Hasher>(state: &mut H, bstr: &[u8]) -> Result<()> {
-        let mut bstr_rest = T::preprocess(bstr);
-        while !bstr_rest.is_empty() {
-            match next_utf8_char(bstr_rest) {
-                Some((ch_b, b_next)) => {
-                    let mut weight = Self::char_weight(ch_b);
-                    while weight != 0 {
-                        (weight & 0xFFFF).hash(state);
-                        weight >>= 16;
-                    }
-                    bstr_rest = b_next
-                }
-                _ => break,
+    fn sort_hash<H: Hasher>(state: &mut H, bstr: &[u8], force_no_pad: bool) -> Result<()> {
+        let bstr_rest = if force_no_pad { bstr } else { T::preprocess(bstr) };
+        let mut cursor = Utf8Mb4Cursor::new(bstr_rest);
+        while let Some(ch_b) = cursor.next() {
+            let mut weight = Self::char_weight(ch_b);
+            while weight != 0 {
+                (weight & 0xFFFF).hash(state);
+                weight >>= 16;
             }
         }
         Ok(())
     }
 }
+
+/// Writes the UCA SortKey of an already pad-adjusted `bstr_rest`, shared by
+/// [`write_sort_key`](Collator::write_sort_key) and
+/// [`write_sort_key_no_pad`](Collator::write_sort_key_no_pad).
+fn write_uca_sort_key<T: UnicodeVersion, W: BufferWriter>(
+    writer: &mut W,
+    bstr_rest: &[u8],
+) -> Result<usize> {
+    let mut cursor = Utf8Mb4Cursor::new(bstr_rest);
+    let mut n = 0;
+
+    while let Some(ch_b) = cursor.next() {
+        let mut weight = T::char_weight(ch_b);
+        while weight != 0 {
+            writer.write_u16_be((weight & 0xFFFF) as u16)?;
+            n += 1;
+            weight >>= 16
+        }
+    }
+    Ok(n * std::mem::size_of::<u16>())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cmp::Ordering, collections::hash_map::DefaultHasher, hash::Hasher};
+
+    use super::*;
+
+    #[test]
+    fn test_sort_compare_with_malformed_utf8() {
+        // Any decode failure — whether at the start, in the middle, or a
+        // truncated sequence at the end — makes the collator give up and
+        // report the two values as equal, the same as `next_utf8_char`
+        // returning `None` used to.
+        assert_eq!(
+            CollatorUtf8Mb4UnicodeCi::sort_compare(b"\xFFa", b"a", false).unwrap(),
+            Ordering::Equal
+        );
+        assert_eq!(
+            CollatorUtf8Mb4UnicodeCi::sort_compare(b"ab\xFFcd", b"ab\xFFcz", false).unwrap(),
+            Ordering::Equal
+        );
+        assert_eq!(
+            CollatorUtf8Mb4UnicodeCi::sort_compare(b"ab\xE4", b"ab", false).unwrap(),
+            Ordering::Equal
+        );
+    }
+
+    #[test]
+    fn test_like_pattern_compare_with_malformed_utf8() {
+        assert!(!CollatorUtf8Mb4UnicodeCi::like_pattern_compare(b"\xFFa", b"a").unwrap());
+    }
+
+    #[test]
+    fn test_write_sort_key_truncates_at_malformed_byte() {
+        let mut with_garbage = Vec::new();
+        CollatorUtf8Mb4UnicodeCi::write_sort_key(&mut with_garbage, b"ab\xFFcd").unwrap();
+        let mut truncated = Vec::new();
+        CollatorUtf8Mb4UnicodeCi::write_sort_key(&mut truncated, b"ab").unwrap();
+        assert_eq!(with_garbage, truncated);
+    }
+
+    #[test]
+    fn test_sort_hash_truncates_at_malformed_byte() {
+        let mut with_garbage = DefaultHasher::new();
+        CollatorUtf8Mb4UnicodeCi::sort_hash(&mut with_garbage, b"ab\xFFcd", false).unwrap();
+        let mut truncated = DefaultHasher::new();
+        CollatorUtf8Mb4UnicodeCi::sort_hash(&mut truncated, b"ab", false).unwrap();
+        assert_eq!(with_garbage.finish(), truncated.finish());
+    }
+}
@@ -18,12 +18,9 @@ pub struct EncodingAscii;
 impl Encoding for EncodingAscii {
     #[inline]
     fn decode(data: BytesRef<'_>) -> Result<Bytes> {
-        for x in data {
+        for (i, x) in data.iter().enumerate() {
             if !x.is_ascii() {
-                return Err(Error::cannot_convert_string(
-                    format_invalid_char(data).as_str(),
-                    "ascii",
-                ));
+                return Err(Error::cannot_convert_string_at(data, "ascii", i));
             }
         }
         Ok(Bytes::from(data))
@@ -24,6 +24,62 @@ lazy_static! {
         .collect();
 }
 
+/// Validates `data` as gb18030 in a single pass, returning the byte offset
+/// of the first sequence that cannot be decoded.
+///
+/// Mirrors the structure of [`EncodingGb18030::decode`] but stops at the
+/// first failure instead of accumulating decoded output.
+pub fn validate_gb18030(data: &[u8]) -> std::result::Result<(), usize> {
+    let l = data.len();
+    let mut base = 0;
+    while base < l {
+        let offset = match data[base] {
+            ..=0x7f => 1,
+            0x81..=0xfe => {
+                if base + 1 >= l {
+                    return Err(base);
+                }
+                if 0x40 <= data[base + 1] && data[base + 1] <= 0xfe && data[base + 1] != 0x7f {
+                    2
+                } else if base + 3 < l
+                    && data[base + 1] >= 0x30
+                    && data[base + 1] <= 0x39
+                    && data[base + 2] >= 0x81
+                    && data[base + 2] <= 0xfe
+                    && data[base + 3] >= 0x30
+                    && data[base + 3] <= 0x39
+                {
+                    4
+                } else {
+                    return Err(base);
+                }
+            }
+            _ => return Err(base),
+        };
+
+        let v: u32 = match offset {
+            1 => u32::from(data[base]),
+            2 => u32::from(data[base]) << 8 | u32::from(data[base + 1]),
+            4 => {
+                u32::from(data[base]) << 24
+                    | u32::from(data[base + 1]) << 16
+                    | u32::from(data[base + 2]) << 8
+                    | u32::from(data[base + 3])
+            }
+            _ => unreachable!(),
+        };
+        if !DECODE_MAP.contains_key(&v)
+            && GB18030
+                .decode_without_bom_handling_and_without_replacement(&data[base..base + offset])
+                .is_none()
+        {
+            return Err(base);
+        }
+        base += offset;
+    }
+    Ok(())
+}
+
 #[derive(Debug)]
 pub struct EncodingGb18030 {}
 
@@ -43,10 +99,7 @@ impl Encoding for EncodingGb18030 {
                 ..=0x7f => offset = 1,
                 0x81..=0xfe => {
                     if base + 1 >= l {
-                        return Err(Error::cannot_convert_string(
-                            format_invalid_char(data).as_str(),
-                            "gb18030",
-                        ));
+                        return Err(Error::cannot_convert_string_at(data, "gb18030", base));
                     }
                     if 0x40 <= data[base + 1] && data[base + 1] <= 0xfe && data[base + 1] != 0x7f {
                         offset = 2;
@@ -60,17 +113,11 @@ impl Encoding for EncodingGb18030 {
                     {
                         offset = 4;
                     } else {
-                        return Err(Error::cannot_convert_string(
-                            format_invalid_char(data).as_str(),
-                            "gb18030",
-                        ));
+                        return Err(Error::cannot_convert_string_at(data, "gb18030", base));
                     }
                 }
                 _ => {
-                    return Err(Error::cannot_convert_string(
-                        format_invalid_char(data).as_str(),
-                        "gb18030",
-                    ));
+                    return Err(Error::cannot_convert_string_at(data, "gb18030", base));
                 }
             }
 
@@ -85,10 +132,7 @@ impl Encoding for EncodingGb18030 {
                         | u32::from(data[base + 3])
                 }
                 _ => {
-                    return Err(Error::cannot_convert_string(
-                        format_invalid_char(data).as_str(),
-                        "gb18030",
-                    ));
+                    return Err(Error::cannot_convert_string_at(data, "gb18030", base));
                 }
             };
             if DECODE_MAP.contains_key(&v) {
@@ -107,10 +151,7 @@ impl Encoding for EncodingGb18030 {
                         res.extend(v.as_bytes());
                     }
                     None => {
-                        return Err(Error::cannot_convert_string(
-                            format_invalid_char(data).as_str(),
-                            "gb18030",
-                        ));
+                        return Err(Error::cannot_convert_string_at(data, "gb18030", base));
                     }
                 }
             }
@@ -277,6 +318,17 @@ mod tests {
 
     use crate::codec::collation::{Encoding, encoding::EncodingGb18030};
 
+    #[test]
+    fn test_validate_gb18030() {
+        use super::validate_gb18030;
+
+        assert_eq!(validate_gb18030(&[0xD6, 0xD0, 0xCE, 0xC4]), Ok(()));
+        assert_eq!(validate_gb18030(b"ok"), Ok(()));
+        // 0xFF is not a valid gb18030 lead byte.
+        assert_eq!(validate_gb18030(&[0xFF]), Err(0));
+        assert_eq!(validate_gb18030(&[b'o', b'k', 0xFF]), Err(2));
+    }
+
     #[test]
     fn test_encode() {
         let cases = vec![
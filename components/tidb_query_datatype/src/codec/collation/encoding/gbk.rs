@@ -5,6 +5,33 @@ use encoding_rs::GBK;
 use super::*;
 use crate::codec::data_type::{BytesGuard, BytesWriter};
 
+/// Validates `data` as GBK in a single pass, returning the byte offset of
+/// the first sequence that cannot be decoded.
+///
+/// GBK has no shift state, so each byte (or lead/trail byte pair) can be
+/// validated independently; this checks one code unit at a time instead of
+/// decoding the whole buffer, which is what [`EncodingGbk::decode`] needs to
+/// locate the failure.
+pub fn validate_gbk(data: &[u8]) -> std::result::Result<(), usize> {
+    let mut i = 0;
+    while i < data.len() {
+        if data[i] < 0x80 {
+            i += 1;
+            continue;
+        }
+        if i + 1 < data.len()
+            && GBK
+                .decode_without_bom_handling_and_without_replacement(&data[i..i + 2])
+                .is_some()
+        {
+            i += 2;
+        } else {
+            return Err(i);
+        }
+    }
+    Ok(())
+}
+
 #[derive(Debug)]
 pub struct EncodingGbk;
 
@@ -13,10 +40,10 @@ impl Encoding for EncodingGbk {
     fn decode(data: BytesRef<'_>) -> Result<Bytes> {
         match GBK.decode_without_bom_handling_and_without_replacement(data) {
             Some(v) => Ok(Bytes::from(v.as_bytes())),
-            None => Err(Error::cannot_convert_string(
-                format_invalid_char(data).as_str(),
-                "gbk",
-            )),
+            None => {
+                let offset = validate_gbk(data).err().unwrap_or(0);
+                Err(Error::cannot_convert_string_at(data, "gbk", offset))
+            }
         }
     }
 
@@ -65,3 +92,21 @@ impl Encoding for EncodingGbk {
         writer.write_from_char_iter(res)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_gbk() {
+        // 0xD6D0 is "中" in both GBK and gb18030.
+        assert_eq!(validate_gbk(&[0xD6, 0xD0]), Ok(()));
+        assert_eq!(validate_gbk(b"ok"), Ok(()));
+        // Not a valid GBK lead/trail pair.
+        assert_eq!(validate_gbk(&[0x81, 0x30]), Err(0));
+        // Valid ASCII prefix, then an invalid pair.
+        assert_eq!(validate_gbk(&[b'o', b'k', 0x81, 0x30]), Err(2));
+        // Truncated lead byte at the end.
+        assert_eq!(validate_gbk(&[b'o', b'k', 0xD6]), Err(2));
+    }
+}
@@ -488,7 +488,7 @@ impl ToInt for DateTime {
 impl ToInt for Duration {
     #[inline]
     fn to_int(&self, ctx: &mut EvalContext, tp: FieldTypeTp) -> Result<i64> {
-        let dur = self.round_frac(DEFAULT_FSP)?;
+        let dur = self.round_frac(ctx, DEFAULT_FSP)?;
         let dec: Decimal = dur.convert(ctx)?;
         let val = dec.as_i64_with_ctx(ctx)?;
         val.to_int(ctx, tp)
@@ -496,7 +496,7 @@ impl ToInt for Duration {
 
     #[inline]
     fn to_uint(&self, ctx: &mut EvalContext, tp: FieldTypeTp) -> Result<u64> {
-        let dur = self.round_frac(DEFAULT_FSP)?;
+        let dur = self.round_frac(ctx, DEFAULT_FSP)?;
         let dec: Decimal = dur.convert(ctx)?;
         decimal_as_u64(ctx, dec, tp)
     }
@@ -7,8 +7,7 @@ use tipb::FieldType;
 
 use super::*;
 use crate::{
-    Collation, EvalType, FieldTypeAccessor, codec::collation::Collator, match_template_collator,
-    match_template_evaltype,
+    Collation, EvalType, FieldTypeAccessor, match_template_collator, match_template_evaltype,
 };
 
 /// A scalar value container, a.k.a. datum, for all concrete eval types.
@@ -347,21 +346,24 @@ impl ScalarValueRef<'_> {
         ctx: &mut EvalContext,
         output: &mut Vec<u8>,
     ) -> Result<()> {
-        use crate::codec::datum_codec::EvaluableDatumEncoder;
+        use crate::codec::collation::write_sort_key_datum;
 
         match self {
             ScalarValueRef::Bytes(val) => {
+                match_template_collator! {
+                    TT, match field_type.collation().map_err(crate::codec::Error::from)? {
+                        Collation::TT => write_sort_key_datum::<TT>(output, *val)?
+                    }
+                }
+                Ok(())
+            }
+            ScalarValueRef::Json(val) => {
                 match val {
                     None => {
                         output.write_evaluable_datum_null()?;
                     }
                     Some(val) => {
-                        let sort_key = match_template_collator! {
-                            TT, match field_type.collation().map_err(crate::codec::Error::from)? {
-                                Collation::TT => TT::sort_key(val)?
-                            }
-                        };
-                        output.write_evaluable_datum_bytes(&sort_key)?;
+                        output.write_evaluable_datum_json_sort_key(*val)?;
                     }
                 }
                 Ok(())
@@ -377,7 +379,7 @@ impl ScalarValueRef<'_> {
         field_type: &FieldType,
     ) -> crate::codec::Result<Ordering> {
         Ok(match_template! {
-            TT = [Real, Decimal, DateTime, Duration, Json, Enum, VectorFloat32],
+            TT = [Real, Decimal, DateTime, Duration, Enum, VectorFloat32],
             match (self, other) {
                 (ScalarValueRef::TT(v1), ScalarValueRef::TT(v2)) => v1.cmp(v2),
                 (ScalarValueRef::Int(v1), ScalarValueRef::Int(v2)) => compare_int(&v1.cloned(), &v2.cloned(), field_type),
@@ -391,6 +393,15 @@ impl ScalarValueRef<'_> {
                         }
                     }
                 }
+                // `JsonRef`'s `Ord` panics on corrupted/truncated binary JSON;
+                // go through `checked_cmp` so a bad value surfaces as a normal
+                // coprocessor error instead of taking down the executor.
+                (ScalarValueRef::Json(None), ScalarValueRef::Json(None)) => Ordering::Equal,
+                (ScalarValueRef::Json(Some(_)), ScalarValueRef::Json(None)) => Ordering::Greater,
+                (ScalarValueRef::Json(None), ScalarValueRef::Json(Some(_))) => Ordering::Less,
+                (ScalarValueRef::Json(Some(v1)), ScalarValueRef::Json(Some(v2))) => {
+                    v1.checked_cmp(v2)?
+                }
                 _ => panic!("Cannot compare two ScalarValueRef in different type"),
             }
         })
@@ -531,3 +542,48 @@ impl PartialEq<ScalarValueRef<'_>> for ScalarValue {
         other == self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::codec::mysql::{Json, Time};
+
+    use super::*;
+
+    /// Two JSON datetimes that are `==` (same instant, different `fsp`)
+    /// must produce the same GROUP BY key, or they'd be grouped separately.
+    #[test]
+    fn test_encode_sort_key_groups_equal_json_datetimes_together() {
+        let mut ctx = EvalContext::default();
+        let t3 = Time::parse_datetime(&mut ctx, "2020-01-01 12:00:00.500", 3, false).unwrap();
+        let t6 = Time::parse_datetime(&mut ctx, "2020-01-01 12:00:00.500000", 6, false).unwrap();
+        assert_eq!(t3, t6);
+
+        let j3 = Json::from_time(t3).unwrap();
+        let j6 = Json::from_time(t6).unwrap();
+        let field_type = FieldType::default();
+
+        let mut key3 = Vec::new();
+        let mut key6 = Vec::new();
+        ScalarValueRef::Json(Some(j3.as_ref()))
+            .encode_sort_key(&field_type, &mut ctx, &mut key3)
+            .unwrap();
+        ScalarValueRef::Json(Some(j6.as_ref()))
+            .encode_sort_key(&field_type, &mut ctx, &mut key6)
+            .unwrap();
+        assert_eq!(key3, key6);
+    }
+
+    /// A `checked_cmp` failure on a corrupted JSON value must surface through
+    /// `cmp_sort_key` as an `Err`, not a panic, so a bad row cannot bring
+    /// down the TopN/sort executors.
+    #[test]
+    fn test_cmp_sort_key_on_truncated_json_returns_err_not_panic() {
+        use crate::codec::mysql::JsonType;
+
+        let truncated = JsonRef::new(JsonType::String, &[]);
+        let field_type = FieldType::default();
+        let result = ScalarValueRef::Json(Some(truncated))
+            .cmp_sort_key(&ScalarValueRef::Json(Some(truncated)), &field_type);
+        assert!(result.is_err());
+    }
+}
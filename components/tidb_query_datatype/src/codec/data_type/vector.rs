@@ -482,24 +482,46 @@ impl VectorValue {
         ctx: &mut EvalContext,
         output: &mut Vec<u8>,
     ) -> Result<()> {
-        use crate::{
-            Collation,
-            codec::{collation::Collator, datum_codec::EvaluableDatumEncoder},
-        };
+        use crate::{Collation, codec::collation::write_sort_key_datum};
 
         match self {
             VectorValue::Bytes(ref vec) => {
-                match vec.get_option_ref(row_index) {
-                    None => {
-                        output.write_evaluable_datum_null()?;
+                let val = vec.get_option_ref(row_index);
+                match_template_collator! {
+                    TT, match field_type.collation()? {
+                        Collation::TT => write_sort_key_datum::<TT>(output, val)?
                     }
-                    Some(val) => {
-                        let sort_key = match_template_collator! {
-                            TT, match field_type.collation()? {
-                                Collation::TT => TT::sort_key(val)?
-                            }
-                        };
-                        output.write_evaluable_datum_bytes(&sort_key)?;
+                }
+                Ok(())
+            }
+            _ => self.encode(row_index, field_type, ctx, output),
+        }
+    }
+
+    /// Same as [`Self::encode_sort_key`], but for `Bytes` columns reuses
+    /// `cache`'s last computed sort key when this row's raw value is equal
+    /// to the previous row's (see [`write_sort_key_datum_cached`] for why
+    /// that's always safe). Intended for group-by key construction over a
+    /// column that's a vector of rows, where consecutive equal values --
+    /// e.g. from a streamed, already-sorted range scan -- are common.
+    ///
+    /// [`write_sort_key_datum_cached`]: crate::codec::collation::write_sort_key_datum_cached
+    pub fn encode_sort_key_cached(
+        &self,
+        row_index: usize,
+        field_type: &impl FieldTypeAccessor,
+        ctx: &mut EvalContext,
+        output: &mut Vec<u8>,
+        cache: &mut crate::codec::collation::SortKeyCache,
+    ) -> Result<()> {
+        use crate::{Collation, codec::collation::write_sort_key_datum_cached};
+
+        match self {
+            VectorValue::Bytes(ref vec) => {
+                let val = vec.get_option_ref(row_index);
+                match_template_collator! {
+                    TT, match field_type.collation()? {
+                        Collation::TT => write_sort_key_datum_cached::<TT>(output, val, cache)?
                     }
                 }
                 Ok(())
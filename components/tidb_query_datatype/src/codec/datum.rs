@@ -18,9 +18,9 @@ use tikv_util::{codec::BytesSlice, escape};
 use super::{
     Result,
     mysql::{
-        self, DEFAULT_FSP, Decimal, DecimalDecoder, DecimalEncoder, Duration, Enum, Json,
-        JsonDecoder, JsonEncoder, MAX_FSP, PathExpression, Set, Time, VectorFloat32Decoder,
-        VectorFloat32Encoder, parse_json_path_expr,
+        self, DEFAULT_FSP, Decimal, DecimalDecoder, DecimalEncoder, Duration, DurationIndexDecoder,
+        DurationIndexEncoder, Enum, Json, JsonDecoder, JsonEncoder, MAX_FSP, PathExpression, Set,
+        Time, VectorFloat32Decoder, VectorFloat32Encoder, parse_json_path_expr,
     },
 };
 use crate::{
@@ -917,10 +917,10 @@ pub trait DatumDecoder:
             NIL_FLAG => Datum::Null,
             FLOAT_FLAG => self.read_f64().map(Datum::F64)?,
             DURATION_FLAG => {
-                // Decode the i64 into `Duration` with `MAX_FSP`, then unflatten it with
-                // concrete `FieldType` information
-                let nanos = self.read_i64()?;
-                let dur = Duration::from_nanos(nanos, MAX_FSP)?;
+                // Decode with `MAX_FSP`, then unflatten it with concrete `FieldType`
+                // information. The encoding is fsp-independent, so this also decodes
+                // index keys written before `DurationIndexEncoder` existed.
+                let dur = self.read_duration_index_value(MAX_FSP)?;
                 Datum::Dur(dur)
             }
             DECIMAL_FLAG => self.read_decimal().map(Datum::Dec)?,
@@ -1008,7 +1008,7 @@ pub trait DatumEncoder:
                 }
                 Datum::Dur(ref d) => {
                     self.write_u8(DURATION_FLAG)?;
-                    self.write_i64(d.to_nanos())?;
+                    self.write_duration_index_value(*d)?;
                 }
                 Datum::Dec(ref d) => {
                     self.write_u8(DECIMAL_FLAG)?;
@@ -2098,4 +2098,45 @@ mod tests {
             assert_eq!(got, exp);
         }
     }
+
+    #[test]
+    fn test_composite_duration_bytes_key_order() {
+        // A composite index on (TIME, collated VARCHAR) is built by encoding a
+        // `Datum::Dur` followed by a `Datum::Bytes` holding the string's
+        // already-computed collation sort key. Two rows must compare the same
+        // way once encoded as they do as raw values, regardless of the
+        // duration column's declared fsp.
+        let mut ctx = EvalContext::default();
+        let rows = vec![
+            ("-1 00:00:00", 0, b"a".to_vec()),
+            ("-1 00:00:00", 6, b"z".to_vec()),
+            ("0:0:0", 3, b"a".to_vec()),
+            ("0:0:0", 3, b"b".to_vec()),
+            ("0:0:1", 0, b"a".to_vec()),
+            ("11:30:45.5", 1, b"a".to_vec()),
+            ("11:30:45.5", 4, b"a".to_vec()),
+            ("838:59:59", 0, b"zzz".to_vec()),
+        ];
+        let mut encoded: Vec<(Duration, Vec<u8>, Vec<u8>)> = rows
+            .into_iter()
+            .map(|(dur, fsp, bs)| {
+                let d = Duration::parse(&mut EvalContext::default(), dur, fsp).unwrap();
+                let key = encode_key(&mut ctx, &[Datum::Dur(d), Datum::Bytes(bs.clone())]).unwrap();
+                (d, bs, key)
+            })
+            .collect();
+        encoded.sort_by(|a, b| a.2.cmp(&b.2));
+        let mut by_value = encoded.clone();
+        by_value.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.cmp(&b.1)));
+        assert_eq!(
+            encoded
+                .iter()
+                .map(|(d, bs, _)| (*d, bs.clone()))
+                .collect::<Vec<_>>(),
+            by_value
+                .iter()
+                .map(|(d, bs, _)| (*d, bs.clone()))
+                .collect::<Vec<_>>(),
+        );
+    }
 }
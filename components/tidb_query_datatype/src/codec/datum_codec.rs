@@ -309,6 +309,18 @@ pub trait DatumFlagAndPayloadEncoder: BufferWriter + DatumPayloadEncoder {
         Ok(())
     }
 
+    /// Like `write_datum_json`, but normalizes embedded date/datetime/
+    /// timestamp values first (see `JsonRef::to_sort_key_json`), so JSON
+    /// values that are `==` under `PartialOrd` encode to identical bytes.
+    /// Use this instead of `write_datum_json` when the encoded bytes are a
+    /// sort/group key.
+    fn write_datum_json_sort_key(&mut self, val: JsonRef<'_>) -> Result<()> {
+        self.write_u8(datum::JSON_FLAG)?;
+        self.write_json_sort_key(val).map_err(|_| {
+            Error::InvalidDataType("Failed to encode datum payload from json".to_owned())
+        })
+    }
+
     fn write_datum_vector_float32(&mut self, val: VectorFloat32Ref<'_>) -> Result<()> {
         self.write_u8(datum::VECTOR_FLOAT32_FLAG)?;
         self.write_datum_payload_vector_float32(val)?;
@@ -373,6 +385,12 @@ pub trait EvaluableDatumEncoder: DatumFlagAndPayloadEncoder {
         self.write_datum_json(val)
     }
 
+    /// See `DatumPayloadEncoder::write_datum_json_sort_key`.
+    #[inline]
+    fn write_evaluable_datum_json_sort_key(&mut self, val: JsonRef<'_>) -> Result<()> {
+        self.write_datum_json_sort_key(val)
+    }
+
     #[inline]
     fn write_evaluable_datum_vector_float32(&mut self, val: VectorFloat32Ref<'_>) -> Result<()> {
         self.write_datum_vector_float32(val)
@@ -26,6 +26,29 @@ pub const ERR_INCORRECT_PARAMETERS: i32 = 1583;
 pub const ERR_DATA_OUT_OF_RANGE: i32 = 1690;
 pub const ERR_CANNOT_CONVERT_STRING: i32 = 3854;
 
+/// Renders the bytes of `data` starting at `offset` as a quoted,
+/// hex-escaped snippet capped at a handful of bytes, MySQL-error-message
+/// style.
+fn format_invalid_char_at(data: &[u8], offset: usize) -> String {
+    const MAX_BYTES_TO_SHOW: usize = 5;
+    let tail = &data[offset.min(data.len())..];
+    let mut buf = String::with_capacity(32);
+    buf.push('\'');
+    for (i, b) in tail.iter().enumerate() {
+        if i > MAX_BYTES_TO_SHOW {
+            buf.push_str("...");
+            break;
+        }
+        if b.is_ascii() {
+            buf.push(char::from(*b));
+        } else {
+            buf.push_str(format!("\\x{:X}", b).as_str());
+        }
+    }
+    buf.push('\'');
+    buf
+}
+
 #[derive(Debug, Error)]
 pub enum Error {
     #[error("invalid data type: {0}")]
@@ -101,6 +124,21 @@ impl Error {
         Error::Eval(msg, ERR_CANNOT_CONVERT_STRING)
     }
 
+    /// Like [`Error::cannot_convert_string`], but for callers that know the
+    /// exact byte offset of the first invalid sequence (e.g. from
+    /// [`crate::codec::collation::charset::validate_for_charset`]) and can
+    /// report it alongside a snippet starting at that offset, rather than
+    /// always showing the start of the input.
+    pub fn cannot_convert_string_at(input: &[u8], charset: &str, offset: usize) -> Error {
+        let msg = format!(
+            "Cannot convert string {} from binary to {} (invalid at byte offset {})",
+            format_invalid_char_at(input, offset),
+            charset,
+            offset
+        );
+        Error::Eval(msg, ERR_CANNOT_CONVERT_STRING)
+    }
+
     pub fn datetime_function_overflow() -> Error {
         let msg = "Datetime function field overflow";
         Error::Eval(msg.into(), ERR_DATETIME_FUNCTION_OVERFLOW)
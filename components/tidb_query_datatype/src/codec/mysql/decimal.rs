@@ -1722,6 +1722,49 @@ impl Decimal {
         Ok(d)
     }
 
+    /// Parses a `Decimal` from `s`, then rounds it to `frac` fractional
+    /// digits, clamping to the maximum/minimum value representable with
+    /// `prec` total digits if it doesn't fit -- all in a single call.
+    ///
+    /// This is the fused counterpart to calling [`Decimal::from_bytes`]
+    /// followed by the same prec/frac clamp-and-round [`Decimal::convert_to`]
+    /// performs: casting a string column to `DECIMAL(prec, frac)` does
+    /// exactly that today as two separate calls, each re-scanning the
+    /// parsed digits. The truncated/overflow flags this function returns
+    /// are identical to what the two-step path produces for the same
+    /// input, including inputs with leading/trailing spaces, exponents, and
+    /// more fractional digits than `frac` allows.
+    pub fn from_bytes_with_prec_and_frac(
+        s: &[u8],
+        prec: u8,
+        frac: u8,
+        round_mode: RoundMode,
+    ) -> Result<Res<Decimal>> {
+        if prec < frac {
+            return Err(Error::m_bigger_than_d(""));
+        }
+        let parsed = Decimal::from_bytes(s)?;
+        let parse_overflow = parsed.is_overflow();
+        let parse_truncated = parsed.is_truncated();
+        let dec = parsed.unwrap();
+        let (dec_prec, dec_frac) = dec.prec_and_frac();
+        if !dec.is_zero() && dec_prec - dec_frac > prec - frac {
+            return Ok(Res::Overflow(max_or_min_dec(dec.negative, prec, frac)));
+        }
+        let rounded = if dec_frac == frac {
+            Res::Ok(dec)
+        } else {
+            dec.round(frac as i8, round_mode)
+        };
+        Ok(if parse_overflow || rounded.is_overflow() {
+            Res::Overflow(rounded.unwrap())
+        } else if parse_truncated || rounded.is_truncated() {
+            Res::Truncated(rounded.unwrap())
+        } else {
+            rounded
+        })
+    }
+
     /// Get the approximate needed capacity to encode this decimal.
     ///
     /// see also `encode_decimal`.
@@ -2998,6 +3041,76 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_from_bytes_with_prec_and_frac() {
+        // `Decimal::from_bytes_with_prec_and_frac` must behave exactly like
+        // calling `Decimal::from_bytes` followed by the prec/frac
+        // clamp-and-round that `Decimal::convert_to` performs, for every
+        // input in the generated corpus below -- including inputs with
+        // leading/trailing spaces, exponents, and more fractional digits
+        // than `frac` allows.
+        fn two_step(s: &[u8], prec: u8, frac: u8, round_mode: RoundMode) -> Result<Res<Decimal>> {
+            let parsed = Decimal::from_bytes(s)?;
+            let overflow = parsed.is_overflow();
+            let truncated = parsed.is_truncated();
+            let dec = parsed.unwrap();
+            let (dec_prec, dec_frac) = dec.prec_and_frac();
+            let rounded = if !dec.is_zero() && dec_prec - dec_frac > prec - frac {
+                Res::Overflow(max_or_min_dec(dec.negative, prec, frac))
+            } else {
+                dec.round(frac as i8, round_mode)
+            };
+            Ok(if overflow || rounded.is_overflow() {
+                Res::Overflow(rounded.unwrap())
+            } else if truncated || rounded.is_truncated() {
+                Res::Truncated(rounded.unwrap())
+            } else {
+                rounded
+            })
+        }
+
+        let numbers = [
+            "0", "1", "-1", "0.0", "15.1", "-15.1", "15.5", "15.9", "999999999", "123456789.1",
+            "0.000123456789", "99999999999999999999999999999999999999999999999999999999999999999",
+            "-15.17", "1.23456789e3", "1.23456789e-3", "1.23456789e300", "1.23456789e-300",
+            "  42.42  ", "\t-0.001\t", "5.4abc", "1e", "abc",
+        ];
+        let precs_and_fracs = [(1u8, 0u8), (10, 0), (10, 2), (18, 4), (30, 10), (65, 30)];
+        let round_modes = [RoundMode::HalfEven, RoundMode::Truncate, RoundMode::Ceiling];
+
+        for s in numbers {
+            for &(prec, frac) in &precs_and_fracs {
+                for round_mode in round_modes.iter().cloned() {
+                    let fused = Decimal::from_bytes_with_prec_and_frac(
+                        s.as_bytes(),
+                        prec,
+                        frac,
+                        round_mode.clone(),
+                    );
+                    let expected = two_step(s.as_bytes(), prec, frac, round_mode.clone());
+                    match (fused, expected) {
+                        (Ok(fused), Ok(expected)) => {
+                            assert_eq!(
+                                fused.map(|d| d.to_string_value()),
+                                expected.map(|d| d.to_string_value()),
+                                "mismatch for {:?} prec={} frac={} mode={:?}",
+                                s,
+                                prec,
+                                frac,
+                                round_mode,
+                            );
+                        }
+                        (Err(_), Err(_)) => {}
+                        (fused, expected) => panic!(
+                            "mismatch for {:?} prec={} frac={} mode={:?}: fused={:?} expected={:?}",
+                            s, prec, frac, round_mode, fused, expected
+                        ),
+                    }
+                }
+            }
+        }
+    }
+
     #[test]
     #[rustfmt::skip]
     fn test_string() {
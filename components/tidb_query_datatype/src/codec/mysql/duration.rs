@@ -8,7 +8,7 @@ use std::{
 use codec::prelude::*;
 use tipb::FieldType;
 
-use super::{DEFAULT_FSP, Decimal, check_fsp};
+use super::{DEFAULT_FSP, Decimal, FspMode, check_fsp, check_fsp_with_mode};
 use crate::{
     FieldTypeAccessor,
     codec::{
@@ -502,8 +502,11 @@ impl Duration {
     /// We will use the “round half up” rule, e.g, >= 0.5 -> 1, < 0.5 -> 0,
     /// so 10:10:10.999999 round with fsp: 1 -> 10:10:11.0
     /// and 10:10:10.000000 round with fsp: 0 -> 10:10:11
-    pub fn round_frac(self, fsp: i8) -> Result<Self> {
-        let fsp = check_fsp(fsp)?;
+    pub fn round_frac(self, ctx: &mut EvalContext, fsp: i8) -> Result<Self> {
+        let (fsp, warning) = check_fsp_with_mode(fsp, FspMode::Clamp)?;
+        if let Some(warning) = warning {
+            ctx.warnings.append_warning(warning);
+        }
 
         if fsp >= self.fsp {
             return Ok(Duration { fsp, ..self });
@@ -708,6 +711,42 @@ pub trait DurationDecoder: NumberDecoder {
 
 impl<T: BufferReader> DurationDecoder for T {}
 
+/// Canonical, memory-comparable encoding of a `Duration` for use in
+/// composite index keys.
+///
+/// The payload is the full nanosecond value (sign included), written with
+/// the same order-preserving `i64` encoding used for any other comparable
+/// integer column. Unlike [`DurationEncoder::write_duration_to_chunk`],
+/// which is little-endian and only meant for row storage, this ordering is
+/// what makes two durations that only differ in the fsp of the column they
+/// were read from compare identically once encoded, so index lookups keep
+/// working regardless of the declared fsp.
+pub trait DurationIndexEncoder: NumberEncoder {
+    #[inline]
+    fn write_duration_index_value(&mut self, val: Duration) -> Result<()> {
+        self.write_i64(val.to_nanos())?;
+        Ok(())
+    }
+}
+
+impl<T: BufferWriter> DurationIndexEncoder for T {}
+
+pub trait DurationIndexDecoder: NumberDecoder {
+    /// Decodes a `Duration` written by
+    /// [`DurationIndexEncoder::write_duration_index_value`].
+    ///
+    /// The payload has always been a plain comparable `i64` of nanoseconds,
+    /// so this also reads index keys built before this trait existed (e.g.
+    /// through `Datum`'s `DURATION_FLAG`) without any format migration.
+    #[inline]
+    fn read_duration_index_value(&mut self, fsp: i8) -> Result<Duration> {
+        let nanos = self.read_i64()?;
+        Duration::from_nanos(nanos, fsp)
+    }
+}
+
+impl<T: BufferReader> DurationIndexDecoder for T {}
+
 impl crate::codec::data_type::AsMySqlBool for Duration {
     #[inline]
     fn as_mysql_bool(&self, _context: &mut crate::expr::EvalContext) -> crate::codec::Result<bool> {
@@ -1041,7 +1080,7 @@ mod tests {
         for (input, fsp, exp) in cases {
             let t = Duration::parse(&mut EvalContext::default(), input, MAX_FSP)
                 .unwrap()
-                .round_frac(fsp)
+                .round_frac(&mut EvalContext::default(), fsp)
                 .unwrap();
             let res = format!("{}", t);
             assert_eq!(exp, res);
@@ -1072,6 +1111,82 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_index_value_codec() {
+        let cases = vec![
+            ("11:30:45.123456", 4),
+            ("11:30:45.123456", 6),
+            ("11:30:45.123456", 0),
+            ("11:59:59.999999", 3),
+            ("1 11:30:45.123456", 1),
+            ("1 11:30:45.999999", 4),
+            ("-1 11:30:45.999999", 0),
+            ("-1 11:59:59.9999", 2),
+            ("0:0:0", 0),
+        ];
+        for (input, fsp) in cases {
+            let t = Duration::parse(&mut EvalContext::default(), input, fsp).unwrap();
+            let mut buf = vec![];
+            buf.write_duration_index_value(t).unwrap();
+            let got = buf.as_slice().read_duration_index_value(fsp).unwrap();
+            assert_eq!(t, got, "round trip failed for {} at fsp {}", input, fsp);
+        }
+    }
+
+    #[test]
+    fn test_index_value_is_fsp_independent() {
+        // The same instant encoded at every fsp must produce identical bytes,
+        // so composite index lookups keep working regardless of the declared
+        // fsp of the duration column.
+        let cases = ["11:30:45.123456", "-1 11:30:45.999999", "0:0:0"];
+        for input in cases {
+            let mut encodings = vec![];
+            for fsp in MIN_FSP..=MAX_FSP {
+                let t = Duration::parse(&mut EvalContext::default(), input, fsp).unwrap();
+                let mut buf = vec![];
+                buf.write_duration_index_value(t).unwrap();
+                encodings.push(buf);
+            }
+            assert!(
+                encodings.windows(2).all(|w| w[0] == w[1]),
+                "encodings of {} differ across fsp: {:?}",
+                input,
+                encodings
+            );
+        }
+    }
+
+    #[test]
+    fn test_index_value_orders_by_value() {
+        // Negative durations must sort before positive ones, and within the
+        // same sign the encoding must preserve the natural duration order,
+        // independent of fsp.
+        let cases = vec![
+            ("-1 00:00:00", 0),
+            ("-0:00:00.5", 1),
+            ("0:0:0", 6),
+            ("0:0:0.1", 1),
+            ("11:30:45.123456", 6),
+            ("838:59:59", 0),
+        ];
+        let mut encoded: Vec<(Duration, Vec<u8>)> = cases
+            .into_iter()
+            .map(|(input, fsp)| {
+                let t = Duration::parse(&mut EvalContext::default(), input, fsp).unwrap();
+                let mut buf = vec![];
+                buf.write_duration_index_value(t).unwrap();
+                (t, buf)
+            })
+            .collect();
+        encoded.sort_by(|a, b| a.1.cmp(&b.1));
+        let mut by_value = encoded.clone();
+        by_value.sort_by_key(|(t, _)| *t);
+        assert_eq!(
+            encoded.iter().map(|(t, _)| *t).collect::<Vec<_>>(),
+            by_value.iter().map(|(t, _)| *t).collect::<Vec<_>>(),
+        );
+    }
+
     #[test]
     fn test_checked_add_and_sub_duration() {
         /// `MAX_TIME_IN_SECS` is the maximum for mysql time type.
@@ -1319,7 +1434,7 @@ mod benches {
         );
         b.iter(|| {
             let (duration, fsp) = (test::black_box(duration), test::black_box(fsp));
-            let _ = test::black_box(duration.round_frac(fsp).unwrap());
+            let _ = test::black_box(duration.round_frac(&mut EvalContext::default(), fsp).unwrap());
         })
     }
 
@@ -9,12 +9,14 @@ use codec::prelude::*;
 use tipb::FieldType;
 
 use crate::{
-    FieldTypeTp,
+    Collation, FieldTypeTp,
     codec::{
         Result,
+        collation::Collator,
         convert::{ToInt, ToStringValue},
     },
     expr::EvalContext,
+    match_template_collator,
 };
 
 #[derive(Clone, Debug)]
@@ -146,6 +148,19 @@ impl<'a> EnumRef<'a> {
     pub fn len(&self) -> usize {
         8 + self.name.len()
     }
+
+    /// Compares this enum's name against `other`'s under `collation`,
+    /// matching MySQL's `ENUM = <string>` semantics, where equality is
+    /// determined by the member's string value rather than its numeric
+    /// index, so a case-insensitive collation makes e.g. `'green'` and
+    /// `'Green'` compare equal.
+    pub fn cmp_with_collation(&self, other: &Self, collation: Collation) -> Result<Ordering> {
+        match_template_collator! {
+            TT, match collation {
+                Collation::TT => TT::sort_compare(self.name, other.name, false),
+            }
+        }
+    }
 }
 
 impl Display for EnumRef<'_> {
@@ -333,6 +348,71 @@ mod tests {
         assert!(s.as_ref().is_empty());
     }
 
+    #[test]
+    fn test_cmp_with_collation() {
+        let green = Enum::new(b"Green".to_vec(), 1);
+        let trailing_space = Enum::new(b"Green ".to_vec(), 2);
+        let red = Enum::new(b"Red".to_vec(), 3);
+        // An enum with value == 0 always has an empty name, regardless of
+        // the index it was decoded with being out of range for the
+        // column's `elems`.
+        let invalid = Enum::new(b"whatever".to_vec(), 0);
+
+        // Case differs but general_ci is case-insensitive.
+        assert_eq!(
+            green
+                .as_ref()
+                .cmp_with_collation(&red.as_ref(), Collation::Utf8Mb4GeneralCi)
+                .unwrap(),
+            red.as_ref()
+                .cmp_with_collation(&green.as_ref(), Collation::Utf8Mb4GeneralCi)
+                .unwrap()
+                .reverse()
+        );
+        assert_eq!(
+            Enum::new(b"green".to_vec(), 9)
+                .as_ref()
+                .cmp_with_collation(&green.as_ref(), Collation::Utf8Mb4GeneralCi)
+                .unwrap(),
+            Ordering::Equal
+        );
+        // ... but not under a binary collation, even though the numeric
+        // indexes (9 vs 1) would also compare unequal.
+        assert_ne!(
+            Enum::new(b"green".to_vec(), 9)
+                .as_ref()
+                .cmp_with_collation(&green.as_ref(), Collation::Utf8Mb4Bin)
+                .unwrap(),
+            Ordering::Equal
+        );
+
+        // A padding collation ignores the trailing space.
+        assert_eq!(
+            green
+                .as_ref()
+                .cmp_with_collation(&trailing_space.as_ref(), Collation::Utf8Mb4Bin)
+                .unwrap(),
+            Ordering::Equal
+        );
+
+        // An enum decoded with an invalid index has an empty name, so it
+        // compares equal to `''` and unequal to any non-empty member.
+        assert_eq!(
+            invalid
+                .as_ref()
+                .cmp_with_collation(&Enum::new(vec![], 0).as_ref(), Collation::Utf8Mb4Bin)
+                .unwrap(),
+            Ordering::Equal
+        );
+        assert_ne!(
+            invalid
+                .as_ref()
+                .cmp_with_collation(&green.as_ref(), Collation::Utf8Mb4Bin)
+                .unwrap(),
+            Ordering::Equal
+        );
+    }
+
     fn get_enum_field_type() -> FieldType {
         let mut field_type = FieldType::new();
         field_type.set_tp(FieldTypeTp::Enum.to_u8().unwrap() as i32);
@@ -3,10 +3,17 @@
 use std::{
     cmp::{Ord, Ordering},
     f64,
+    sync::Arc,
 };
 
 use super::{super::Result, ERR_CONVERT_FAILED, Json, JsonRef, JsonType, constants::*};
-use crate::codec::convert::ToStringValue;
+use crate::{
+    codec::{
+        convert::{ToStringValue, get_valid_float_prefix},
+        mysql::{Duration, MAX_FSP, Time},
+    },
+    expr::{EvalConfig, EvalContext, Flag},
+};
 
 fn compare<T: Ord>(x: T, y: T) -> Ordering {
     x.cmp(&y)
@@ -64,9 +71,134 @@ impl JsonRef<'_> {
     }
 }
 
+impl JsonRef<'_> {
+    /// Fallible version of `Ord::cmp`/`PartialOrd::partial_cmp`.
+    ///
+    /// `partial_cmp` legitimately returns `None` when the binary JSON payload
+    /// is corrupted or truncated (e.g. `get_str_bytes`/`array_get_elem`
+    /// failing partway through a comparison). Callers that cannot guarantee
+    /// their input has already been validated -- notably the sort/TopN
+    /// executors -- must use this instead of `Ord::cmp`, which panics in that
+    /// case, and propagate the error as a normal coprocessor error.
+    pub fn checked_cmp(&self, right: &JsonRef<'_>) -> Result<Ordering> {
+        self.partial_cmp(right).ok_or_else(|| {
+            invalid_type!(
+                "{} when comparing corrupted or truncated json values",
+                ERR_CONVERT_FAILED
+            )
+        })
+    }
+
+    /// Like [`JsonRef::checked_cmp`], but when one side is a Date/Datetime/
+    /// Time value and the other is a String, first tries to parse the
+    /// string into the matching temporal type using `ctx` and compares by
+    /// value instead of by type precedence, matching TiDB's
+    /// `CompareBinary`. Falls back to the precedence-only comparison when
+    /// the string doesn't parse as that type.
+    pub fn checked_cmp_with_ctx(
+        &self,
+        ctx: &mut EvalContext,
+        right: &JsonRef<'_>,
+    ) -> Result<Ordering> {
+        if let Some(ord) = self.temporal_cmp_with_string(ctx, right) {
+            return Ok(ord);
+        }
+        if let Some(ord) = right.temporal_cmp_with_string(ctx, self) {
+            return Ok(ord.reverse());
+        }
+        if let Some(ord) = self.string_number_cmp_with_ctx(ctx, right) {
+            return Ok(ord);
+        }
+        if let Some(ord) = right.string_number_cmp_with_ctx(ctx, self) {
+            return Ok(ord.reverse());
+        }
+        self.checked_cmp(right)
+    }
+
+    /// When `self` is a Date/Datetime/Time value and `right` is a String,
+    /// parses `right` into the matching temporal type and compares by
+    /// value. Returns `None` -- so the caller falls back to precedence
+    /// comparison -- when `right` is not a String or doesn't parse as the
+    /// matching temporal type.
+    fn temporal_cmp_with_string(
+        &self,
+        ctx: &mut EvalContext,
+        right: &JsonRef<'_>,
+    ) -> Option<Ordering> {
+        if right.get_type() != JsonType::String {
+            return None;
+        }
+        let right_str = std::str::from_utf8(right.get_str_bytes().ok()?).ok()?;
+        match self.get_type() {
+            JsonType::Time => {
+                let left = self.get_duration().ok()?;
+                let right = Duration::parse(ctx, right_str, MAX_FSP).ok()?;
+                Some(left.cmp(&right))
+            }
+            JsonType::Date | JsonType::Datetime | JsonType::Timestamp => {
+                let left = self.get_time().ok()?;
+                let right = Time::parse(ctx, right_str, left.get_time_type(), MAX_FSP, true).ok()?;
+                Some(left.cmp(&right))
+            }
+            _ => None,
+        }
+    }
+
+    /// Opt-in version of MySQL's `CAST(json_string AS JSON)` vs. number
+    /// comparison: when `ctx`'s query flags set
+    /// [`Flag::JSON_COMPARE_COERCE_STRING_NUMBER`] and `self` is a String
+    /// while `right` is a Number, parses `self` as a MySQL float (leading
+    /// whitespace and a numeric prefix are enough, same rules as
+    /// `CAST(... AS DOUBLE)`) and compares by value instead of by type
+    /// precedence.
+    ///
+    /// Returns `None` -- so the caller falls back to precedence comparison
+    /// -- when the flag is off, `self` isn't a String, `right` isn't a
+    /// Number, or `self` doesn't parse as a number at all (e.g. a
+    /// whitespace-only or non-numeric string).
+    fn string_number_cmp_with_ctx(
+        &self,
+        ctx: &mut EvalContext,
+        right: &JsonRef<'_>,
+    ) -> Option<Ordering> {
+        if !ctx
+            .cfg
+            .flag
+            .contains(Flag::JSON_COMPARE_COERCE_STRING_NUMBER)
+        {
+            return None;
+        }
+        if self.get_type() != JsonType::String
+            || !matches!(
+                right.get_type(),
+                JsonType::I64 | JsonType::U64 | JsonType::Double
+            )
+        {
+            return None;
+        }
+        let s = std::str::from_utf8(self.get_str_bytes().ok()?).ok()?.trim();
+        // The prefix is taken with truncation ignored, so a numeric prefix is
+        // enough whatever `ctx`'s truncation flags are. With no numeric
+        // prefix at all the helper returns "0", which is not a prefix of `s`.
+        let mut lenient = EvalContext::new(Arc::new(EvalConfig::from_flag(Flag::IGNORE_TRUNCATE)));
+        let prefix = get_valid_float_prefix(&mut lenient, s).ok()?;
+        if !s.starts_with(prefix) {
+            return None;
+        }
+        let left = prefix.parse::<f64>().ok()?;
+        let right = right.as_f64().ok()?;
+        compare_f64_with_epsilon(left, right)
+    }
+}
+
 impl Eq for JsonRef<'_> {}
 
 impl Ord for JsonRef<'_> {
+    /// # Panics
+    ///
+    /// Panics if either value is a corrupted or truncated binary JSON that
+    /// `partial_cmp` cannot compare. Only use this on values already known to
+    /// be well-formed; use [`JsonRef::checked_cmp`] otherwise.
     fn cmp(&self, right: &JsonRef<'_>) -> Ordering {
         self.partial_cmp(right).unwrap()
     }
@@ -183,8 +315,19 @@ impl PartialOrd for JsonRef<'_> {
     }
 }
 
+impl Json {
+    /// Fallible version of `Ord::cmp`. See [`JsonRef::checked_cmp`].
+    pub fn checked_cmp(&self, right: &Json) -> Result<Ordering> {
+        self.as_ref().checked_cmp(&right.as_ref())
+    }
+}
+
 impl Eq for Json {}
 impl Ord for Json {
+    /// # Panics
+    ///
+    /// See [`JsonRef::cmp`]. Use [`Json::checked_cmp`] on possibly-corrupted
+    /// input.
     fn cmp(&self, right: &Json) -> Ordering {
         self.as_ref().partial_cmp(&right.as_ref()).unwrap()
     }
@@ -204,13 +347,15 @@ impl PartialOrd for Json {
 
 #[cfg(test)]
 mod tests {
+    use std::sync::Arc;
+
     use super::*;
     use crate::{
         codec::{
             data_type::Duration,
             mysql::{Time, TimeType},
         },
-        expr::EvalContext,
+        expr::{EvalConfig, EvalContext},
     };
 
     #[test]
@@ -430,4 +575,184 @@ mod tests {
             assert_eq!(l.cmp(&r), result)
         }
     }
+
+    #[test]
+    fn test_checked_cmp_with_ctx_parses_string_as_time() {
+        let mut ctx = EvalContext::default();
+        let time = Json::from_duration(Duration::parse(&mut ctx, "12:00:00", 0).unwrap()).unwrap();
+        let earlier = Json::from_str_val("11:59:59").unwrap();
+        let later = Json::from_str_val("12:00:01").unwrap();
+
+        assert_eq!(
+            time.as_ref()
+                .checked_cmp_with_ctx(&mut ctx, &earlier.as_ref())
+                .unwrap(),
+            Ordering::Greater,
+        );
+        assert_eq!(
+            time.as_ref()
+                .checked_cmp_with_ctx(&mut ctx, &later.as_ref())
+                .unwrap(),
+            Ordering::Less,
+        );
+        // Symmetric: the String may be on either side of the comparison.
+        assert_eq!(
+            earlier
+                .as_ref()
+                .checked_cmp_with_ctx(&mut ctx, &time.as_ref())
+                .unwrap(),
+            Ordering::Less,
+        );
+    }
+
+    #[test]
+    fn test_checked_cmp_with_ctx_parses_string_as_datetime() {
+        let mut ctx = EvalContext::default();
+        let datetime = Json::from_time(
+            Time::parse(&mut ctx, "1998-06-13 12:13:14", TimeType::DateTime, 0, false).unwrap(),
+        )
+        .unwrap();
+        let earlier = Json::from_str_val("1998-06-13 00:00:00").unwrap();
+
+        assert_eq!(
+            datetime
+                .as_ref()
+                .checked_cmp_with_ctx(&mut ctx, &earlier.as_ref())
+                .unwrap(),
+            Ordering::Greater,
+        );
+    }
+
+    #[test]
+    fn test_checked_cmp_with_ctx_falls_back_to_precedence_on_unparseable_string() {
+        // Without the fix, Time is always greater than String by precedence;
+        // that fallback must still apply when the string isn't a valid time.
+        let mut ctx = EvalContext::default();
+        let time = Json::from_duration(Duration::parse(&mut ctx, "12:00:00", 0).unwrap()).unwrap();
+        let not_a_time = Json::from_str_val("not a time").unwrap();
+
+        assert_eq!(
+            time.as_ref()
+                .checked_cmp_with_ctx(&mut ctx, &not_a_time.as_ref())
+                .unwrap(),
+            Ordering::Greater,
+        );
+        assert_eq!(
+            not_a_time
+                .as_ref()
+                .checked_cmp_with_ctx(&mut ctx, &time.as_ref())
+                .unwrap(),
+            Ordering::Less,
+        );
+    }
+
+    #[test]
+    fn test_checked_cmp_with_ctx_coerces_string_to_number_when_flag_set() {
+        let mut ctx = EvalContext::new(Arc::new(EvalConfig::from_flag(
+            Flag::JSON_COMPARE_COERCE_STRING_NUMBER,
+        )));
+        let number = Json::from_i64(12).unwrap();
+        let equal_str = Json::from_str_val("12").unwrap();
+        let partial_str = Json::from_str_val("12abc").unwrap();
+        let greater_str = Json::from_str_val("13").unwrap();
+
+        assert_eq!(
+            number
+                .as_ref()
+                .checked_cmp_with_ctx(&mut ctx, &equal_str.as_ref())
+                .unwrap(),
+            Ordering::Equal,
+        );
+        // MySQL's float parsing only needs a valid numeric prefix.
+        assert_eq!(
+            number
+                .as_ref()
+                .checked_cmp_with_ctx(&mut ctx, &partial_str.as_ref())
+                .unwrap(),
+            Ordering::Equal,
+        );
+        // Symmetric: the String may be on either side of the comparison.
+        assert_eq!(
+            greater_str
+                .as_ref()
+                .checked_cmp_with_ctx(&mut ctx, &number.as_ref())
+                .unwrap(),
+            Ordering::Greater,
+        );
+    }
+
+    #[test]
+    fn test_checked_cmp_with_ctx_ignores_numeric_string_when_flag_unset() {
+        // Without the flag, String vs. Number still falls back to type
+        // precedence, where Number is always less than String.
+        let mut ctx = EvalContext::default();
+        let number = Json::from_i64(12).unwrap();
+        let equal_str = Json::from_str_val("12").unwrap();
+
+        assert_eq!(
+            number
+                .as_ref()
+                .checked_cmp_with_ctx(&mut ctx, &equal_str.as_ref())
+                .unwrap(),
+            Ordering::Less,
+        );
+    }
+
+    #[test]
+    fn test_checked_cmp_with_ctx_falls_back_to_precedence_on_whitespace_only_string() {
+        // A whitespace-only string has no numeric prefix at all, so it
+        // doesn't parse as a number and must fall back to precedence, even
+        // with the flag set.
+        let mut ctx = EvalContext::new(Arc::new(EvalConfig::from_flag(
+            Flag::JSON_COMPARE_COERCE_STRING_NUMBER,
+        )));
+        let number = Json::from_i64(12).unwrap();
+        let blank_str = Json::from_str_val("   ").unwrap();
+
+        assert_eq!(
+            number
+                .as_ref()
+                .checked_cmp_with_ctx(&mut ctx, &blank_str.as_ref())
+                .unwrap(),
+            Ordering::Less,
+        );
+    }
+
+    /// A `String`-typed `JsonRef` with an empty value payload: the length
+    /// varint has nothing to decode, so `get_str_bytes` fails gracefully
+    /// instead of panicking, simulating a truncated binary JSON value.
+    fn truncated_string_json_ref() -> JsonRef<'static> {
+        JsonRef::new(JsonType::String, &[])
+    }
+
+    #[test]
+    fn test_checked_cmp_on_truncated_json_returns_err_not_panic() {
+        let truncated = truncated_string_json_ref();
+        let other = truncated_string_json_ref();
+        assert!(truncated.checked_cmp(&other).is_err());
+        assert!(truncated.partial_cmp(&other).is_none());
+    }
+
+    #[test]
+    fn test_checked_cmp_on_truncated_json_inside_array() {
+        // An array with one element whose type byte is not a valid JsonType,
+        // as if the payload had been damaged in place.
+        let mut value = Vec::new();
+        value.extend_from_slice(&1u32.to_le_bytes()); // element count
+        value.extend_from_slice(&0u32.to_le_bytes()); // total size (unused here)
+        value.push(0xff); // invalid element type
+        value.extend_from_slice(&0u32.to_le_bytes()); // element value offset
+        let array = JsonRef::new(JsonType::Array, &value);
+        assert!(array.checked_cmp(&array).is_err());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_ord_cmp_on_truncated_json_panics() {
+        // Documents the pre-existing, still-panicking behavior of `Ord::cmp`
+        // for values that have not been validated; executors must use
+        // `checked_cmp` instead, which is covered above.
+        let truncated = truncated_string_json_ref();
+        let _ = truncated.cmp(&truncated);
+    }
 }
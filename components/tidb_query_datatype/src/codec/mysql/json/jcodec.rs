@@ -27,6 +27,16 @@ pub trait JsonEncoder: NumberEncoder {
         self.write_bytes(data.value()).map_err(Error::from)
     }
 
+    /// Like `write_json`, but first normalizes any embedded date/datetime/
+    /// timestamp value (see `JsonRef::to_sort_key_json`), so two JSON values
+    /// that are `==` under `PartialOrd` always encode to identical bytes.
+    /// Use this instead of `write_json` when the encoded bytes are a
+    /// sort/group key rather than the value's persisted form.
+    fn write_json_sort_key(&mut self, data: JsonRef<'_>) -> Result<()> {
+        let canonical = data.to_sort_key_json()?;
+        self.write_json(canonical.as_ref())
+    }
+
     // See `appendBinaryObject` in TiDB `types/json/binary.go`
     fn write_json_obj_from_keys_values(
         &mut self,
@@ -229,6 +239,17 @@ pub trait JsonDatumPayloadChunkEncoder: BufferWriter {
         self.write_bytes(src_payload)?;
         Ok(())
     }
+
+    /// Like `write_json_to_chunk_by_datum_payload`, but normalizes embedded
+    /// date/datetime/timestamp values first (see
+    /// `JsonRef::to_sort_key_json`), so datum payloads that decode to `==`
+    /// JSON values land in the chunk with identical bytes. Used by the
+    /// aggregation hash path when grouping by a JSON column.
+    fn write_json_sort_key_to_chunk_by_datum_payload(&mut self, src_payload: &[u8]) -> Result<()> {
+        let mut buf = src_payload;
+        let json = buf.read_json()?;
+        self.write_json_sort_key(json.as_ref())
+    }
 }
 impl<T: BufferWriter> JsonDatumPayloadChunkEncoder for T {}
 
@@ -0,0 +1,257 @@
+// Copyright 2026 TiKV Project Authors. Licensed under Apache-2.0.
+
+//! A hash of a JSON value that agrees with `PartialEq`/`PartialOrd` (see
+//! `comparison.rs`): object keys are hashed in sorted order regardless of
+//! insertion order, and an i64/u64/double that represent the same integer
+//! hash identically. Plain binary hashing of `JsonRef::value` doesn't have
+//! either property, so it can't be used directly to build a hash join or
+//! hash-based dedup on JSON columns.
+
+use std::hash::{Hash, Hasher};
+
+use super::{super::Result, Json, JsonRef, JsonType};
+
+/// Default recursion limit for [`JsonRef::canonical_hash`] and
+/// [`JsonRef::canonicalize`], protecting against a stack overflow on a
+/// maliciously deep document. Use the `_with_depth_limit` variants to
+/// override it.
+pub const MAX_CANONICAL_DEPTH: usize = 100;
+
+fn too_deep(limit: usize) -> crate::codec::Error {
+    invalid_type!(
+        "json document exceeds max nesting depth of {} for canonicalization",
+        limit
+    )
+}
+
+/// Hashes a number the same way regardless of whether it was stored as an
+/// i64, u64 or double, matching the cross-type numeric equality rules in
+/// `comparison.rs` (e.g. `9i64 == 9u64 == 9.0f64`).
+///
+/// Note this can't fully agree with `comparison.rs`'s epsilon-tolerant
+/// comparison between two doubles that are merely close, rather than equal:
+/// an exact hash necessarily distinguishes values that fall on either side
+/// of the epsilon, even though `PartialEq` treats them the same.
+fn hash_number<H: Hasher>(h: &mut H, value: f64) {
+    if value.fract() == 0.0 && value >= i64::MIN as f64 && value <= i64::MAX as f64 {
+        (value as i64).hash(h);
+    } else {
+        value.to_bits().hash(h);
+    }
+}
+
+impl JsonRef<'_> {
+    /// See the module-level docs.
+    pub fn canonical_hash<H: Hasher>(&self, h: &mut H) -> Result<()> {
+        self.canonical_hash_with_depth_limit(h, MAX_CANONICAL_DEPTH)
+    }
+
+    /// Like [`Self::canonical_hash`], but with an explicit recursion limit.
+    pub fn canonical_hash_with_depth_limit<H: Hasher>(
+        &self,
+        h: &mut H,
+        depth_limit: usize,
+    ) -> Result<()> {
+        self.canonical_hash_impl(h, depth_limit, depth_limit)
+    }
+
+    fn canonical_hash_impl<H: Hasher>(
+        &self,
+        h: &mut H,
+        remaining_depth: usize,
+        depth_limit: usize,
+    ) -> Result<()> {
+        let remaining_depth = remaining_depth
+            .checked_sub(1)
+            .ok_or_else(|| too_deep(depth_limit))?;
+        (self.get_type() as u8).hash(h);
+        match self.get_type() {
+            JsonType::Object => {
+                let count = self.get_elem_count();
+                let mut indices: Vec<usize> = (0..count).collect();
+                indices.sort_by_key(|&i| self.object_get_key(i));
+                count.hash(h);
+                for i in indices {
+                    self.object_get_key(i).hash(h);
+                    self.object_get_val(i)?
+                        .canonical_hash_impl(h, remaining_depth, depth_limit)?;
+                }
+            }
+            JsonType::Array => {
+                let count = self.get_elem_count();
+                count.hash(h);
+                for i in 0..count {
+                    self.array_get_elem(i)?
+                        .canonical_hash_impl(h, remaining_depth, depth_limit)?;
+                }
+            }
+            JsonType::Literal => self.get_literal().hash(h),
+            JsonType::I64 => hash_number(h, self.get_i64() as f64),
+            JsonType::U64 => hash_number(h, self.get_u64() as f64),
+            JsonType::Double => hash_number(h, self.get_double()),
+            JsonType::String => self.get_str_bytes()?.hash(h),
+            JsonType::Opaque => {
+                (self.get_opaque_type()? as i32).hash(h);
+                self.get_opaque_bytes()?.hash(h);
+            }
+            JsonType::Date | JsonType::Datetime | JsonType::Timestamp => {
+                self.get_time()?.normalized().to_string().hash(h);
+            }
+            JsonType::Time => self.get_duration()?.to_nanos().hash(h),
+        }
+        Ok(())
+    }
+
+    /// Returns an owned copy of this JSON value with object keys sorted and
+    /// embedded datetime/timestamp values normalized as
+    /// [`Self::to_sort_key_json`] does (dates are kept as they are),
+    /// recursing into arrays and objects.
+    ///
+    /// Returns an error, rather than overflowing the stack, on a document
+    /// nested deeper than [`MAX_CANONICAL_DEPTH`].
+    pub fn canonicalize(&self) -> Result<Json> {
+        self.canonicalize_with_depth_limit(MAX_CANONICAL_DEPTH)
+    }
+
+    /// Like [`Self::canonicalize`], but with an explicit recursion limit.
+    pub fn canonicalize_with_depth_limit(&self, depth_limit: usize) -> Result<Json> {
+        self.canonicalize_impl(depth_limit, depth_limit)
+    }
+
+    fn canonicalize_impl(&self, remaining_depth: usize, depth_limit: usize) -> Result<Json> {
+        let remaining_depth = remaining_depth
+            .checked_sub(1)
+            .ok_or_else(|| too_deep(depth_limit))?;
+        Ok(match self.get_type() {
+            JsonType::Object => {
+                let count = self.get_elem_count();
+                let mut entries = Vec::with_capacity(count);
+                for i in 0..count {
+                    let key = self.object_get_key(i);
+                    let val = self
+                        .object_get_val(i)?
+                        .canonicalize_impl(remaining_depth, depth_limit)?;
+                    entries.push((key, val));
+                }
+                Json::from_kv_pairs(entries.iter().map(|(k, v)| (*k, v.as_ref())).collect())?
+            }
+            JsonType::Array => {
+                let count = self.get_elem_count();
+                let mut elems = Vec::with_capacity(count);
+                for i in 0..count {
+                    elems.push(
+                        self.array_get_elem(i)?
+                            .canonicalize_impl(remaining_depth, depth_limit)?,
+                    );
+                }
+                Json::from_array(elems)?
+            }
+            JsonType::Datetime | JsonType::Timestamp => {
+                Json::from_time(self.get_time()?.normalized())?
+            }
+            _ => self.to_owned(),
+        })
+    }
+}
+
+impl Json {
+    /// See [`JsonRef::canonical_hash`].
+    pub fn canonical_hash<H: Hasher>(&self, h: &mut H) -> Result<()> {
+        self.as_ref().canonical_hash(h)
+    }
+
+    /// See [`JsonRef::canonicalize`].
+    pub fn canonicalize(&self) -> Result<Json> {
+        self.as_ref().canonicalize()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::hash_map::DefaultHasher,
+        hash::{Hash, Hasher},
+    };
+
+    use super::*;
+
+    fn hash_of(j: &Json) -> u64 {
+        let mut h = DefaultHasher::new();
+        j.canonical_hash(&mut h).unwrap();
+        h.finish()
+    }
+
+    #[test]
+    fn test_hash_matches_eq_for_corpus() {
+        let corpus = [
+            "null",
+            "true",
+            "false",
+            "0",
+            "9",
+            "-9",
+            "9.0",
+            "9.5",
+            r#""hello""#,
+            r#""world""#,
+            "[]",
+            "{}",
+            r#"{"a": 1, "b": 2}"#,
+            r#"{"b": 2, "a": 1}"#,
+            r#"[1, 2, 3]"#,
+            r#"[3, 2, 1]"#,
+            r#"{"a": [1, {"c": 3, "b": 2}], "d": 4}"#,
+            r#"{"d": 4, "a": [1, {"b": 2, "c": 3}]}"#,
+            r#"[9, 9.0]"#,
+        ];
+        let parsed: Vec<Json> = corpus.iter().map(|s| s.parse().unwrap()).collect();
+
+        for (i, a) in parsed.iter().enumerate() {
+            for (j, b) in parsed.iter().enumerate() {
+                let eq = a == b;
+                let hash_eq = hash_of(a) == hash_of(b);
+                assert!(
+                    !eq || hash_eq,
+                    "#{} ({:?}) == #{} ({:?}) but hashes differ",
+                    i,
+                    corpus[i],
+                    j,
+                    corpus[j]
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_mixed_number_types_hash_equal() {
+        let i = Json::from_i64(9).unwrap();
+        let u = Json::from_u64(9).unwrap();
+        let d = Json::from_f64(9.0).unwrap();
+        assert_eq!(i, u);
+        assert_eq!(u, d);
+        assert_eq!(hash_of(&i), hash_of(&u));
+        assert_eq!(hash_of(&u), hash_of(&d));
+    }
+
+    #[test]
+    fn test_object_key_order_does_not_affect_hash() {
+        let a: Json = r#"{"a": 1, "b": {"x": 1, "y": 2}}"#.parse().unwrap();
+        let b: Json = r#"{"b": {"y": 2, "x": 1}, "a": 1}"#.parse().unwrap();
+        assert_eq!(a, b);
+        assert_eq!(hash_of(&a), hash_of(&b));
+    }
+
+    #[test]
+    fn test_depth_limit_errors_instead_of_overflowing() {
+        let mut j = Json::from_array(vec![]).unwrap();
+        for _ in 0..10 {
+            j = Json::from_array(vec![j]).unwrap();
+        }
+        let mut h = DefaultHasher::new();
+        assert!(j.as_ref().canonical_hash_with_depth_limit(&mut h, 5).is_err());
+        assert!(j.as_ref().canonicalize_with_depth_limit(5).is_err());
+        // The default limit is generous enough for this small test document.
+        let mut h = DefaultHasher::new();
+        assert!(j.canonical_hash(&mut h).is_ok());
+    }
+}
@@ -62,6 +62,13 @@ impl Json {
                 }
             }
 
+            // A missing target key merges as if the target were the
+            // non-object literal `false` (see the RFC 7396 recursion base
+            // case above): build that sentinel once instead of allocating a
+            // fresh throwaway `Json` for every absent key, which otherwise
+            // dominates the allocation count on a deeply nested patch that
+            // mostly adds new keys.
+            let absent_target = Json::from_bool(false)?;
             let mut tmp: Json;
             let elem_count = patch.get_elem_count();
             for i in 0..elem_count {
@@ -79,7 +86,7 @@ impl Json {
                         tmp = Self::merge_patch(target_kv.as_ref(), val)?;
                         key_val_map.insert(k, tmp);
                     } else {
-                        tmp = Self::merge_patch(Json::from_bool(false).unwrap().as_ref(), val)?;
+                        tmp = Self::merge_patch(absent_target.as_ref(), val)?;
                         key_val_map.insert(k, tmp);
                     }
                 }
@@ -212,4 +219,70 @@ mod tests {
             assert_eq!(res, expect);
         }
     }
+
+    #[test]
+    fn test_merge_patch() {
+        // RFC 7396 examples: https://datatracker.ietf.org/doc/html/rfc7396
+        let test_cases = vec![
+            (r#"{"a":"b"}"#, r#"{"a":"c"}"#, r#"{"a": "c"}"#),
+            (r#"{"a":"b"}"#, r#"{"b":"c"}"#, r#"{"a": "b", "b": "c"}"#),
+            (r#"{"a":"b"}"#, r#"{"a":null}"#, r#"{}"#),
+            (
+                r#"{"a":"b", "b":"c"}"#,
+                r#"{"a":null}"#,
+                r#"{"b": "c"}"#,
+            ),
+            (r#"{"a":["b"]}"#, r#"{"a":"c"}"#, r#"{"a": "c"}"#),
+            (r#"{"a":"c"}"#, r#"{"a":["b"]}"#, r#"{"a": ["b"]}"#),
+            (
+                r#"{"a":{"b":"c"}}"#,
+                r#"{"a":{"b":"d","c":null}}"#,
+                r#"{"a": {"b": "d"}}"#,
+            ),
+            (r#"["a","b"]"#, r#"["c","d"]"#, r#"["c", "d"]"#),
+            (r#"{"a":"b"}"#, r#"["c"]"#, r#"["c"]"#),
+            (r#"{"a":"foo"}"#, r#"null"#, r#"null"#),
+            (r#"{"a":"foo"}"#, r#""bar""#, r#""bar""#),
+            (r#"{"e":null}"#, r#"{"a":1}"#, r#"{"e": null, "a": 1}"#),
+        ];
+        for (target, patch, expect) in test_cases {
+            let target: Json = target.parse().unwrap();
+            let patch: Json = patch.parse().unwrap();
+            let expect: Json = expect.parse().unwrap();
+            let res = Json::merge_patch(target.as_ref(), patch.as_ref()).unwrap();
+            assert_eq!(res, expect, "target={target}, patch={patch}");
+        }
+    }
+
+    #[test]
+    fn test_merge_patch_duplicate_keys_in_binary_encoding() {
+        // MySQL's binary JSON format doesn't forbid an object from storing
+        // the same key twice; `from_kv_pairs` (unlike `from_object`, which
+        // dedups through a `BTreeMap`) lets a test construct one directly.
+        // `merge_patch` walks entries in encoded order and applies each in
+        // turn, so among duplicates the one that sorts last (ties broken by
+        // original position, since the encoder's sort is stable) wins.
+        let one = Json::from_i64(1).unwrap();
+        let two = Json::from_i64(2).unwrap();
+        let target: Json = r#"{"a": 0}"#.parse().unwrap();
+        let patch = Json::from_kv_pairs(vec![
+            (b"a".as_ref(), one.as_ref()),
+            (b"a".as_ref(), two.as_ref()),
+        ])
+        .unwrap();
+        let res = Json::merge_patch(target.as_ref(), patch.as_ref()).unwrap();
+        let expect: Json = r#"{"a": 2}"#.parse().unwrap();
+        assert_eq!(res, expect);
+
+        // A later duplicate that is JSON null still deletes the key, even
+        // though an earlier duplicate of the same key set it to a value.
+        let patch = Json::from_kv_pairs(vec![
+            (b"a".as_ref(), one.as_ref()),
+            (b"a".as_ref(), Json::none().unwrap().as_ref()),
+        ])
+        .unwrap();
+        let res = Json::merge_patch(target.as_ref(), patch.as_ref()).unwrap();
+        let expect: Json = r#"{}"#.parse().unwrap();
+        assert_eq!(res, expect);
+    }
 }
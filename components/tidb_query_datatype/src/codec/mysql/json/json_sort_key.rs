@@ -0,0 +1,118 @@
+// Copyright 2026 TiKV Project Authors. Licensed under Apache-2.0.
+
+//! Canonical binary form used when a JSON value is turned into a sort/group
+//! key (GROUP BY, DISTINCT) instead of being persisted.
+//!
+//! `Json`'s own `PartialOrd`/`Ord` treat two date/datetime/timestamp values
+//! as equal whenever the underlying `Time` compares equal, and `Time`
+//! ignores `fsp` when doing so (see `Time::eq`). The plain binary encoding
+//! used for storage keeps `fsp`, since e.g. `JSON_EXTRACT` must round-trip
+//! the value the user inserted. That means two JSON values considered equal
+//! by `PartialOrd` can still encode to different bytes, so hashing or
+//! sorting on the raw encoding groups them separately.
+//! [`JsonRef::to_sort_key_json`] rebuilds the value with every embedded
+//! datetime/timestamp normalized so equal values always encode identically.
+//! A date carries no `fsp` and is kept as it is, so it still encodes apart
+//! from a datetime, which it never compares equal to.
+
+use super::{Json, JsonRef, JsonType, Result};
+use crate::codec::mysql::Time;
+
+impl JsonRef<'_> {
+    /// Returns an owned copy of this JSON value with every embedded
+    /// datetime/timestamp value rewritten as a datetime with `fsp` 0 (see
+    /// [`Time::normalized`]), recursing into arrays and objects; dates are
+    /// copied unchanged. Use the result (or [`Json::to_sort_key_json`])
+    /// wherever a JSON value is encoded as a sort/group key rather than
+    /// persisted.
+    pub fn to_sort_key_json(&self) -> Result<Json> {
+        Ok(match self.get_type() {
+            JsonType::Datetime | JsonType::Timestamp => {
+                Json::from_time(self.get_time()?.normalized())?
+            }
+            JsonType::Array => {
+                let count = self.get_elem_count();
+                let mut elems = Vec::with_capacity(count);
+                for i in 0..count {
+                    elems.push(self.array_get_elem(i)?.to_sort_key_json()?);
+                }
+                Json::from_array(elems)?
+            }
+            JsonType::Object => {
+                let count = self.get_elem_count();
+                let mut entries = Vec::with_capacity(count);
+                for i in 0..count {
+                    let key = self.object_get_key(i);
+                    let val = self.object_get_val(i)?.to_sort_key_json()?;
+                    entries.push((key, val));
+                }
+                Json::from_kv_pairs(entries.iter().map(|(k, v)| (*k, v.as_ref())).collect())?
+            }
+            _ => Json::new(self.get_type(), self.value().to_vec()),
+        })
+    }
+}
+
+impl Json {
+    /// See [`JsonRef::to_sort_key_json`].
+    pub fn to_sort_key_json(&self) -> Result<Json> {
+        self.as_ref().to_sort_key_json()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expr::EvalContext;
+
+    fn datetime(ctx: &mut EvalContext, s: &str, fsp: i8) -> Time {
+        Time::parse_datetime(ctx, s, fsp, false).unwrap()
+    }
+
+    #[test]
+    fn test_scalar_datetime_round_trips_and_normalizes() {
+        let mut ctx = EvalContext::default();
+        let t3 = datetime(&mut ctx, "2020-01-01 12:00:00.500", 3);
+        let t6 = datetime(&mut ctx, "2020-01-01 12:00:00.500000", 6);
+        assert_eq!(t3, t6);
+
+        let j3 = Json::from_time(t3).unwrap();
+        let j6 = Json::from_time(t6).unwrap();
+        // Different fsp -> different raw encoding.
+        assert_ne!(j3.as_ref().value(), j6.as_ref().value());
+
+        let k3 = j3.to_sort_key_json().unwrap();
+        let k6 = j6.to_sort_key_json().unwrap();
+        assert_eq!(k3.as_ref().value(), k6.as_ref().value());
+    }
+
+    #[test]
+    fn test_normalizes_inside_array_and_object() {
+        let mut ctx = EvalContext::default();
+        let t3 = datetime(&mut ctx, "2020-01-01 12:00:00.500", 3);
+        let t6 = datetime(&mut ctx, "2020-01-01 12:00:00.500000", 6);
+
+        let arr3 = Json::from_array(vec![Json::from_time(t3).unwrap()]).unwrap();
+        let arr6 = Json::from_array(vec![Json::from_time(t6).unwrap()]).unwrap();
+        assert_eq!(
+            arr3.to_sort_key_json().unwrap().as_ref().value(),
+            arr6.to_sort_key_json().unwrap().as_ref().value()
+        );
+
+        let jt3 = Json::from_time(t3).unwrap();
+        let jt6 = Json::from_time(t6).unwrap();
+        let obj3 = Json::from_kv_pairs(vec![(b"t".as_ref(), jt3.as_ref())]).unwrap();
+        let obj6 = Json::from_kv_pairs(vec![(b"t".as_ref(), jt6.as_ref())]).unwrap();
+        assert_eq!(
+            obj3.to_sort_key_json().unwrap().as_ref().value(),
+            obj6.to_sort_key_json().unwrap().as_ref().value()
+        );
+    }
+
+    #[test]
+    fn test_non_time_values_unaffected() {
+        let j = Json::from_i64(42).unwrap();
+        let canonical = j.to_sort_key_json().unwrap();
+        assert_eq!(j.as_ref().value(), canonical.as_ref().value());
+    }
+}
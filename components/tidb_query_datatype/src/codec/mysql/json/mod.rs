@@ -64,7 +64,9 @@ mod jcodec;
 mod modifier;
 mod path_expr;
 mod serde;
+mod validate;
 // json functions
+mod json_canonical_hash;
 mod json_contains;
 mod json_depth;
 mod json_extract;
@@ -74,6 +76,7 @@ mod json_memberof;
 mod json_merge;
 mod json_modify;
 mod json_remove;
+mod json_sort_key;
 mod json_type;
 pub mod json_unquote;
 
@@ -89,8 +92,10 @@ use tikv_util::is_even;
 
 pub use self::{
     jcodec::{JsonDatumPayloadChunkEncoder, JsonDecoder, JsonEncoder},
+    json_canonical_hash::MAX_CANONICAL_DEPTH,
     json_modify::ModifyType,
     path_expr::{PathExpression, parse_json_path_expr},
+    validate::{JsonValidationError, JsonValidationErrorKind},
 };
 use super::super::{Error, Result, datum::Datum};
 use crate::{
@@ -0,0 +1,458 @@
+// Copyright 2026 TiKV Project Authors. Licensed under Apache-2.0.
+
+//! Structural validation of the binary JSON layout.
+//!
+//! Most of this module's sibling code (`array_get_elem`, `get_str_bytes`,
+//! ...) assumes the buffer it is handed was produced by this crate's own
+//! encoder: it reports some defects (e.g. an unknown type tag) as errors,
+//! but panics on others, such as an offset past the end of the buffer. That is fine for values we wrote
+//! ourselves, but region data can get corrupted, or an older, buggy TiKV
+//! version may have written a malformed value; in that case the panic is
+//! the first symptom, deep inside whatever happened to touch the value
+//! first. [`JsonRef::validate`] instead walks the whole layout up front,
+//! using only checked arithmetic and bounds checks, and reports the JSON
+//! path (e.g. `$.a[3].b`) of the first structural violation it finds.
+
+use codec::number::NumberCodec;
+use thiserror::Error;
+
+use super::{
+    JsonRef, JsonType,
+    constants::{
+        DURATION_LEN, ELEMENT_COUNT_LEN, HEADER_LEN, KEY_ENTRY_LEN, KEY_OFFSET_LEN, LITERAL_LEN,
+        NUMBER_LEN, TIME_LEN, TYPE_LEN, VALUE_ENTRY_LEN,
+    },
+};
+
+/// The kind of structural violation found by [`JsonRef::validate`].
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum JsonValidationErrorKind {
+    #[error("buffer of {actual} byte(s) is too short, expected at least {expected}")]
+    BufferTooShort { expected: usize, actual: usize },
+    #[error(
+        "offset {offset} with length {len} is out of bounds for a buffer of {buffer_len} byte(s)"
+    )]
+    OffsetOutOfBounds {
+        offset: usize,
+        len: usize,
+        buffer_len: usize,
+    },
+    #[error("unknown JSON type tag {0:#x}")]
+    InvalidTypeTag(u8),
+    #[error("malformed variable-length size prefix")]
+    InvalidLengthPrefix,
+    #[error("object key is not valid UTF-8")]
+    InvalidKeyUtf8,
+    #[error("object keys are not sorted in ascending order")]
+    KeysNotSorted,
+}
+
+/// A structural violation found by [`JsonRef::validate`], together with the
+/// JSON path of the value it was found in (e.g. `$.a[3].b`).
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("invalid JSON at {path}: {kind}")]
+pub struct JsonValidationError {
+    pub path: String,
+    pub kind: JsonValidationErrorKind,
+}
+
+fn err(path: &str, kind: JsonValidationErrorKind) -> JsonValidationError {
+    JsonValidationError {
+        path: path.to_owned(),
+        kind,
+    }
+}
+
+/// Returns `buf[start..end]`, or an error describing which bound was out of
+/// range instead of panicking.
+fn checked_slice<'a>(
+    buf: &'a [u8],
+    start: usize,
+    end: usize,
+    path: &str,
+) -> Result<&'a [u8], JsonValidationError> {
+    if start > end || end > buf.len() {
+        return Err(err(
+            path,
+            JsonValidationErrorKind::OffsetOutOfBounds {
+                offset: start,
+                len: end.saturating_sub(start),
+                buffer_len: buf.len(),
+            },
+        ));
+    }
+    Ok(&buf[start..end])
+}
+
+fn checked_add(a: usize, b: usize, path: &str) -> Result<usize, JsonValidationError> {
+    a.checked_add(b).ok_or_else(|| {
+        err(
+            path,
+            JsonValidationErrorKind::OffsetOutOfBounds {
+                offset: a,
+                len: b,
+                buffer_len: usize::MAX,
+            },
+        )
+    })
+}
+
+fn checked_mul(a: usize, b: usize, path: &str) -> Result<usize, JsonValidationError> {
+    a.checked_mul(b).ok_or_else(|| {
+        err(
+            path,
+            JsonValidationErrorKind::OffsetOutOfBounds {
+                offset: a,
+                len: b,
+                buffer_len: usize::MAX,
+            },
+        )
+    })
+}
+
+fn read_u16_le(buf: &[u8], offset: usize, path: &str) -> Result<u16, JsonValidationError> {
+    let slice = checked_slice(buf, offset, checked_add(offset, 2, path)?, path)?;
+    Ok(NumberCodec::decode_u16_le(slice))
+}
+
+fn read_u32_le(buf: &[u8], offset: usize, path: &str) -> Result<u32, JsonValidationError> {
+    let slice = checked_slice(buf, offset, checked_add(offset, 4, path)?, path)?;
+    Ok(NumberCodec::decode_u32_le(slice))
+}
+
+/// Appends an object member to a JSON path, e.g. `$.a` + `b` -> `$.a.b`.
+fn push_key(path: &str, key: &str) -> String {
+    format!("{}.{}", path, key)
+}
+
+/// Appends an array index to a JSON path, e.g. `$.a` + `3` -> `$.a[3]`.
+fn push_index(path: &str, idx: usize) -> String {
+    format!("{}[{}]", path, idx)
+}
+
+impl JsonRef<'_> {
+    /// Walks the binary layout of this JSON value, checking element counts,
+    /// offsets, type tags, key encoding and key ordering, and returns the
+    /// path of the first violation found.
+    ///
+    /// A value that passes `validate` is safe to traverse with the rest of
+    /// this module's (panicking) accessors.
+    pub fn validate(&self) -> Result<(), JsonValidationError> {
+        self.validate_at("$")
+    }
+
+    fn validate_at(&self, path: &str) -> Result<(), JsonValidationError> {
+        match self.get_type() {
+            JsonType::Object => validate_object(self.value(), path),
+            JsonType::Array => validate_array(self.value(), path),
+            JsonType::Literal => validate_fixed_len(self.value(), LITERAL_LEN, path),
+            JsonType::I64 | JsonType::U64 | JsonType::Double => {
+                validate_fixed_len(self.value(), NUMBER_LEN, path)
+            }
+            JsonType::Date | JsonType::Datetime | JsonType::Timestamp => {
+                validate_fixed_len(self.value(), TIME_LEN, path)
+            }
+            JsonType::Time => validate_fixed_len(self.value(), DURATION_LEN, path),
+            JsonType::String => validate_string(self.value(), path),
+            JsonType::Opaque => validate_opaque(self.value(), path),
+        }
+    }
+}
+
+fn validate_fixed_len(buf: &[u8], want: usize, path: &str) -> Result<(), JsonValidationError> {
+    checked_slice(buf, 0, want, path).map(|_| ())
+}
+
+fn validate_string(buf: &[u8], path: &str) -> Result<(), JsonValidationError> {
+    let (str_len, len_len) = NumberCodec::try_decode_var_u64(buf)
+        .map_err(|_| err(path, JsonValidationErrorKind::InvalidLengthPrefix))?;
+    let end = checked_add(len_len, str_len as usize, path)?;
+    checked_slice(buf, len_len, end, path)?;
+    Ok(())
+}
+
+fn validate_opaque(buf: &[u8], path: &str) -> Result<(), JsonValidationError> {
+    checked_slice(buf, 0, 1, path)?;
+    let (data_len, len_len) = NumberCodec::try_decode_var_u64(&buf[1..])
+        .map_err(|_| err(path, JsonValidationErrorKind::InvalidLengthPrefix))?;
+    let start = checked_add(1, len_len, path)?;
+    let end = checked_add(start, data_len as usize, path)?;
+    checked_slice(buf, start, end, path)?;
+    Ok(())
+}
+
+/// Validates the value-entry at `val_entry_off` within `buf` (the current
+/// object's or array's own buffer), recursing into the value it refers to.
+///
+/// Mirrors the layout `JsonRef::val_entry_get` assumes, except every access
+/// is bounds-checked instead of panicking.
+fn validate_value_entry(
+    buf: &[u8],
+    val_entry_off: usize,
+    child_path: &str,
+) -> Result<(), JsonValidationError> {
+    let type_byte = *checked_slice(buf, val_entry_off, val_entry_off + 1, child_path)?
+        .first()
+        .unwrap();
+    let val_type = JsonType::try_from(type_byte)
+        .map_err(|_| err(child_path, JsonValidationErrorKind::InvalidTypeTag(type_byte)))?;
+    let val_offset = read_u32_le(buf, val_entry_off + TYPE_LEN, child_path)? as usize;
+
+    match val_type {
+        JsonType::Literal => {
+            let offset = val_entry_off + TYPE_LEN;
+            validate_fixed_at(buf, offset, LITERAL_LEN, child_path)
+        }
+        JsonType::I64 | JsonType::U64 | JsonType::Double => {
+            validate_fixed_at(buf, val_offset, NUMBER_LEN, child_path)
+        }
+        JsonType::Date | JsonType::Datetime | JsonType::Timestamp => {
+            validate_fixed_at(buf, val_offset, TIME_LEN, child_path)
+        }
+        JsonType::Time => validate_fixed_at(buf, val_offset, DURATION_LEN, child_path),
+        JsonType::String => {
+            let rest = checked_slice(buf, val_offset, buf.len(), child_path)?;
+            validate_string(rest, child_path)
+        }
+        JsonType::Opaque => {
+            let rest = checked_slice(buf, val_offset, buf.len(), child_path)?;
+            validate_opaque(rest, child_path)
+        }
+        JsonType::Object | JsonType::Array => {
+            let size = read_u32_le(buf, val_offset + ELEMENT_COUNT_LEN, child_path)? as usize;
+            let end = checked_add(val_offset, size, child_path)?;
+            let child = checked_slice(buf, val_offset, end, child_path)?;
+            if val_type == JsonType::Object {
+                validate_object(child, child_path)
+            } else {
+                validate_array(child, child_path)
+            }
+        }
+    }
+}
+
+fn validate_fixed_at(
+    buf: &[u8],
+    offset: usize,
+    len: usize,
+    path: &str,
+) -> Result<(), JsonValidationError> {
+    let end = checked_add(offset, len, path)?;
+    checked_slice(buf, offset, end, path).map(|_| ())
+}
+
+fn validate_array(buf: &[u8], path: &str) -> Result<(), JsonValidationError> {
+    checked_slice(buf, 0, HEADER_LEN, path)?;
+    let elem_count = NumberCodec::decode_u32_le(buf) as usize;
+    let size = NumberCodec::decode_u32_le(&buf[ELEMENT_COUNT_LEN..]) as usize;
+    checked_slice(buf, 0, size.max(HEADER_LEN), path)?;
+
+    let value_entries_len = checked_mul(elem_count, VALUE_ENTRY_LEN, path)?;
+    let value_entries_start = checked_add(HEADER_LEN, value_entries_len, path)?;
+    checked_slice(buf, 0, value_entries_start, path)?;
+
+    for i in 0..elem_count {
+        let entry_off = checked_add(HEADER_LEN, checked_mul(i, VALUE_ENTRY_LEN, path)?, path)?;
+        validate_value_entry(buf, entry_off, &push_index(path, i))?;
+    }
+    Ok(())
+}
+
+fn validate_object(buf: &[u8], path: &str) -> Result<(), JsonValidationError> {
+    checked_slice(buf, 0, HEADER_LEN, path)?;
+    let elem_count = NumberCodec::decode_u32_le(buf) as usize;
+    let size = NumberCodec::decode_u32_le(&buf[ELEMENT_COUNT_LEN..]) as usize;
+    checked_slice(buf, 0, size.max(HEADER_LEN), path)?;
+
+    let key_entries_start = HEADER_LEN;
+    let key_entries_len = checked_mul(elem_count, KEY_ENTRY_LEN, path)?;
+    let value_entries_start = checked_add(key_entries_start, key_entries_len, path)?;
+    let value_entries_len = checked_mul(elem_count, VALUE_ENTRY_LEN, path)?;
+    let value_entries_end = checked_add(value_entries_start, value_entries_len, path)?;
+    checked_slice(buf, 0, value_entries_end, path)?;
+
+    let mut prev_key: Option<&[u8]> = None;
+    let mut keys: Vec<(&str, &[u8])> = Vec::with_capacity(elem_count);
+    for i in 0..elem_count {
+        let key_entry_len = checked_mul(i, KEY_ENTRY_LEN, path)?;
+        let key_entry_off = checked_add(key_entries_start, key_entry_len, path)?;
+        let key_off = read_u32_le(buf, key_entry_off, path)? as usize;
+        let key_len = read_u16_le(buf, key_entry_off + KEY_OFFSET_LEN, path)? as usize;
+        let key_bytes = checked_slice(buf, key_off, checked_add(key_off, key_len, path)?, path)?;
+
+        if let Some(prev) = prev_key {
+            if key_bytes < prev {
+                return Err(err(path, JsonValidationErrorKind::KeysNotSorted));
+            }
+        }
+        prev_key = Some(key_bytes);
+
+        let key_str = std::str::from_utf8(key_bytes)
+            .map_err(|_| err(path, JsonValidationErrorKind::InvalidKeyUtf8))?;
+        keys.push((key_str, key_bytes));
+    }
+
+    for (i, (key_str, _)) in keys.into_iter().enumerate() {
+        let entry_off = checked_add(
+            value_entries_start,
+            checked_mul(i, VALUE_ENTRY_LEN, path)?,
+            path,
+        )?;
+        validate_value_entry(buf, entry_off, &push_key(path, key_str))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::mysql::json::Json;
+
+    fn object_value(elem_count: u32, size: u32, rest: &[u8]) -> Vec<u8> {
+        let mut v = Vec::new();
+        v.extend_from_slice(&elem_count.to_le_bytes());
+        v.extend_from_slice(&size.to_le_bytes());
+        v.extend_from_slice(rest);
+        v
+    }
+
+    #[test]
+    fn test_validate_well_formed_values() {
+        let json: Json = r#"{"a":[1,2,{"b":"c"}],"z":true}"#.parse().unwrap();
+        assert_eq!(json.as_ref().validate(), Ok(()));
+
+        let json: Json = "[1,2,3]".parse().unwrap();
+        assert_eq!(json.as_ref().validate(), Ok(()));
+
+        let json: Json = "null".parse().unwrap();
+        assert_eq!(json.as_ref().validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_validate_buffer_too_short_for_header() {
+        let json_ref = JsonRef::new(JsonType::Object, &[0x01, 0x00]);
+        let err = json_ref.validate().unwrap_err();
+        assert_eq!(err.path, "$");
+        assert!(matches!(
+            err.kind,
+            JsonValidationErrorKind::OffsetOutOfBounds { .. }
+        ));
+    }
+
+    #[test]
+    fn test_validate_invalid_type_tag_reports_path() {
+        // One array element, whose value-entry has an unknown type tag.
+        let mut value = Vec::new();
+        value.extend_from_slice(&1u32.to_le_bytes()); // element count
+        value.extend_from_slice(&0u32.to_le_bytes()); // size, unused here
+        value.push(0xff); // invalid type tag
+        value.extend_from_slice(&0u32.to_le_bytes()); // offset
+        let array = JsonRef::new(JsonType::Array, &value);
+
+        let err = array.validate().unwrap_err();
+        assert_eq!(err.path, "$[0]");
+        assert_eq!(err.kind, JsonValidationErrorKind::InvalidTypeTag(0xff));
+    }
+
+    #[test]
+    fn test_validate_offset_out_of_bounds() {
+        // One array element, a U64 value-entry pointing past the buffer.
+        let mut value = Vec::new();
+        value.extend_from_slice(&1u32.to_le_bytes());
+        value.extend_from_slice(&0u32.to_le_bytes());
+        value.push(JsonType::U64 as u8);
+        value.extend_from_slice(&1000u32.to_le_bytes()); // out-of-bounds offset
+        let array = JsonRef::new(JsonType::Array, &value);
+
+        let err = array.validate().unwrap_err();
+        assert_eq!(err.path, "$[0]");
+        assert!(matches!(
+            err.kind,
+            JsonValidationErrorKind::OffsetOutOfBounds { .. }
+        ));
+    }
+
+    #[test]
+    fn test_validate_invalid_key_utf8() {
+        // One object member whose key bytes are not valid UTF-8.
+        let key = [0xff, 0xfe];
+        let key_off = HEADER_LEN + KEY_ENTRY_LEN + VALUE_ENTRY_LEN;
+        let mut rest = Vec::new();
+        rest.extend_from_slice(&(key_off as u32).to_le_bytes());
+        rest.extend_from_slice(&(key.len() as u16).to_le_bytes());
+        rest.push(JsonType::Literal as u8);
+        rest.extend_from_slice(&[0x01, 0, 0, 0]); // inlined `true`
+        rest.extend_from_slice(&key);
+        let value = object_value(1, 0, &rest);
+        let object = JsonRef::new(JsonType::Object, &value);
+
+        let err = object.validate().unwrap_err();
+        assert_eq!(err.path, "$");
+        assert_eq!(err.kind, JsonValidationErrorKind::InvalidKeyUtf8);
+    }
+
+    #[test]
+    fn test_validate_keys_not_sorted() {
+        // Two object members, "b" before "a": violates the sorted-key
+        // invariant `object_search_key`'s binary search relies on.
+        let key_entries_len = 2 * KEY_ENTRY_LEN;
+        let value_entries_len = 2 * VALUE_ENTRY_LEN;
+        let b_off = HEADER_LEN + key_entries_len + value_entries_len;
+        let a_off = b_off + 1;
+
+        let mut rest = Vec::new();
+        rest.extend_from_slice(&(b_off as u32).to_le_bytes());
+        rest.extend_from_slice(&1u16.to_le_bytes());
+        rest.extend_from_slice(&(a_off as u32).to_le_bytes());
+        rest.extend_from_slice(&1u16.to_le_bytes());
+        for _ in 0..2 {
+            rest.push(JsonType::Literal as u8);
+            rest.extend_from_slice(&[0x01, 0, 0, 0]);
+        }
+        rest.extend_from_slice(b"b");
+        rest.extend_from_slice(b"a");
+        let value = object_value(2, 0, &rest);
+        let object = JsonRef::new(JsonType::Object, &value);
+
+        let err = object.validate().unwrap_err();
+        assert_eq!(err.path, "$");
+        assert_eq!(err.kind, JsonValidationErrorKind::KeysNotSorted);
+    }
+
+    #[test]
+    fn test_validate_nested_path_through_object_and_array() {
+        let json: Json = r#"{"a":[0,1,{"b":"ok"}]}"#.parse().unwrap();
+        assert_eq!(json.as_ref().validate(), Ok(()));
+
+        // Build by hand an array whose only element is an object with one
+        // member "b" carrying an invalid type tag, so the violation is
+        // reported through both an array index and an object key, at
+        // `$[0].b`.
+        let mut inner_obj = Vec::new();
+        inner_obj.extend_from_slice(&1u32.to_le_bytes());
+        inner_obj.extend_from_slice(&0u32.to_le_bytes());
+        let key_off = HEADER_LEN + KEY_ENTRY_LEN + VALUE_ENTRY_LEN;
+        inner_obj.extend_from_slice(&(key_off as u32).to_le_bytes());
+        inner_obj.extend_from_slice(&1u16.to_le_bytes());
+        inner_obj.push(0xee); // invalid type tag for member "b"
+        inner_obj.extend_from_slice(&0u32.to_le_bytes());
+        inner_obj.extend_from_slice(b"b");
+
+        let mut outer_array = Vec::new();
+        outer_array.extend_from_slice(&1u32.to_le_bytes());
+        outer_array.extend_from_slice(&0u32.to_le_bytes());
+        outer_array.push(JsonType::Object as u8);
+        let obj_off = HEADER_LEN + VALUE_ENTRY_LEN;
+        outer_array.extend_from_slice(&(obj_off as u32).to_le_bytes());
+        // size field (ELEMENT_COUNT_LEN offset within inner_obj) must match.
+        let inner_len = inner_obj.len() as u32;
+        inner_obj[ELEMENT_COUNT_LEN..ELEMENT_COUNT_LEN + 4]
+            .copy_from_slice(&inner_len.to_le_bytes());
+        outer_array.extend_from_slice(&inner_obj);
+
+        let array = JsonRef::new(JsonType::Array, &outer_array);
+        let err = array.validate().unwrap_err();
+        assert_eq!(err.path, "$[0].b");
+        assert_eq!(err.kind, JsonValidationErrorKind::InvalidTypeTag(0xee));
+    }
+}
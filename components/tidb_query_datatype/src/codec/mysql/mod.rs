@@ -1,6 +1,6 @@
 // Copyright 2016 TiKV Project Authors. Licensed under Apache-2.0.
 
-use super::Result;
+use super::{Error, Result};
 
 /// `UNSPECIFIED_FSP` is the unspecified fractional seconds part.
 pub const UNSPECIFIED_FSP: i8 = -1;
@@ -14,15 +14,44 @@ pub const DEFAULT_FSP: i8 = 0;
 /// `DEFAULT_DIV_FRAC_INCR` is the default value of decimal divide precision
 /// inrements.
 pub const DEFAULT_DIV_FRAC_INCR: u8 = 4;
+/// `MAX_DIV_FRAC_INCR` is the maximum decimal divide precision increment a
+/// session may request via `div_precision_increment`, matching MySQL.
+pub const MAX_DIV_FRAC_INCR: u8 = 30;
 
 pub fn check_fsp(fsp: i8) -> Result<u8> {
+    check_fsp_with_mode(fsp, FspMode::Strict).map(|(fsp, _)| fsp)
+}
+
+/// Controls how [`check_fsp_with_mode`] treats an `fsp` outside
+/// `MIN_FSP..=MAX_FSP`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FspMode {
+    /// Reject an out-of-range `fsp` with an error, same as [`check_fsp`].
+    Strict,
+    /// Clamp an out-of-range `fsp` into `MIN_FSP..=MAX_FSP` instead of
+    /// rejecting it, returning a truncation warning that the caller should
+    /// record on its `EvalContext` (e.g. via `ctx.warnings.append_warning`).
+    Clamp,
+}
+
+/// Like [`check_fsp`], but lets the caller choose via `mode` whether an
+/// out-of-range `fsp` (other than [`UNSPECIFIED_FSP`]) is rejected or
+/// clamped into range.
+pub fn check_fsp_with_mode(fsp: i8, mode: FspMode) -> Result<(u8, Option<Error>)> {
     if fsp == UNSPECIFIED_FSP {
-        return Ok(DEFAULT_FSP as u8);
+        return Ok((DEFAULT_FSP as u8, None));
     }
     if !(MIN_FSP..=MAX_FSP).contains(&fsp) {
-        return Err(invalid_type!("Invalid fsp {}", fsp));
+        return match mode {
+            FspMode::Strict => Err(invalid_type!("Invalid fsp {}", fsp)),
+            FspMode::Clamp => {
+                let clamped = fsp.clamp(MIN_FSP, MAX_FSP) as u8;
+                let warning = Error::truncated_wrong_val("FSP", fsp.to_string());
+                Ok((clamped, Some(warning)))
+            }
+        };
     }
-    Ok(fsp as u8)
+    Ok((fsp as u8, None))
 }
 
 pub mod binary_literal;
@@ -37,7 +66,9 @@ pub mod vector;
 
 pub use self::{
     decimal::{Decimal, DecimalDecoder, DecimalEncoder, Res, RoundMode, dec_encoded_len},
-    duration::{Duration, DurationDecoder, DurationEncoder},
+    duration::{
+        Duration, DurationDecoder, DurationEncoder, DurationIndexDecoder, DurationIndexEncoder,
+    },
     enums::{Enum, EnumDecoder, EnumEncoder, EnumRef},
     json::{
         Json, JsonDatumPayloadChunkEncoder, JsonDecoder, JsonEncoder, JsonType, ModifyType,
@@ -47,3 +78,52 @@ pub use self::{
     time::{Time, TimeDecoder, TimeEncoder, TimeType, Tz},
     vector::{VectorFloat32, VectorFloat32Decoder, VectorFloat32Encoder, VectorFloat32Ref},
 };
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expr::EvalContext;
+
+    #[test]
+    fn test_check_fsp_with_mode_strict_matches_check_fsp() {
+        for fsp in [-2, 7, 100] {
+            assert!(check_fsp(fsp).is_err());
+            assert!(check_fsp_with_mode(fsp, FspMode::Strict).is_err());
+        }
+        assert_eq!(
+            check_fsp(3).unwrap(),
+            check_fsp_with_mode(3, FspMode::Strict).unwrap().0
+        );
+    }
+
+    #[test]
+    fn test_check_fsp_with_mode_clamp_negative_fsp() {
+        let (fsp, warning) = check_fsp_with_mode(-2, FspMode::Clamp).unwrap();
+        assert_eq!(fsp, MIN_FSP as u8);
+        assert!(warning.is_some());
+    }
+
+    #[test]
+    fn test_check_fsp_with_mode_clamp_fsp_too_large() {
+        let (fsp, warning) = check_fsp_with_mode(7, FspMode::Clamp).unwrap();
+        assert_eq!(fsp, MAX_FSP as u8);
+        assert!(warning.is_some());
+    }
+
+    #[test]
+    fn test_check_fsp_with_mode_clamp_unspecified_fsp_is_not_a_warning() {
+        let (fsp, warning) = check_fsp_with_mode(UNSPECIFIED_FSP, FspMode::Clamp).unwrap();
+        assert_eq!(fsp, DEFAULT_FSP as u8);
+        assert!(warning.is_none());
+    }
+
+    #[test]
+    fn test_check_fsp_with_mode_clamp_warning_recorded_on_eval_context() {
+        let mut ctx = EvalContext::default();
+        let dur = Duration::parse(&mut ctx, "10:10:10", MAX_FSP).unwrap();
+        assert_eq!(ctx.warnings.warning_cnt, 0);
+
+        dur.round_frac(&mut ctx, 8).unwrap();
+        assert_eq!(ctx.warnings.warning_cnt, 1);
+    }
+}
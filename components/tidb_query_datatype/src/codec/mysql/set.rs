@@ -8,6 +8,12 @@ use std::{
 
 use tikv_util::buffer_vec::BufferVec;
 
+use crate::{
+    Collation,
+    codec::{Result, collation::Collator},
+    match_template_collator,
+};
+
 /// `Set` stores set.
 ///
 /// Inside `ChunkedVecSet`:
@@ -101,6 +107,22 @@ impl<'a> SetRef<'a> {
     pub fn value(&self) -> u64 {
         self.value
     }
+
+    /// Compares this set's member string (members joined with `,` in the
+    /// column's defined order, matching [`Display`]) against `other`'s
+    /// under `collation`, following MySQL's `SET = <string>` semantics:
+    /// equality is determined by the set's ordered string form rather than
+    /// by its bitmap value, so a case-insensitive collation makes e.g.
+    /// `'a,B'` and `'A,b'` compare equal.
+    pub fn cmp_with_collation(&self, other: &Self, collation: Collation) -> Result<Ordering> {
+        let lhs = self.to_string();
+        let rhs = other.to_string();
+        match_template_collator! {
+            TT, match collation {
+                Collation::TT => TT::sort_compare(lhs.as_bytes(), rhs.as_bytes(), false),
+            }
+        }
+    }
 }
 
 impl Display for SetRef<'_> {
@@ -210,4 +232,54 @@ mod tests {
 
         assert!(s.as_ref().is_empty());
     }
+
+    #[test]
+    fn test_cmp_with_collation() {
+        fn make_set(names: &[&str], value: u64) -> Set {
+            let mut buf = BufferVec::new();
+            for v in names {
+                buf.push(v)
+            }
+            Set {
+                data: Arc::new(buf),
+                value,
+            }
+        }
+
+        // "a,B" vs "A,b": same order, differing case.
+        let lhs = make_set(&["a", "B", "c"], 0b011);
+        let rhs = make_set(&["A", "b", "c"], 0b011);
+        assert_eq!(
+            lhs.as_ref()
+                .cmp_with_collation(&rhs.as_ref(), Collation::Utf8Mb4GeneralCi)
+                .unwrap(),
+            Ordering::Equal
+        );
+        assert_ne!(
+            lhs.as_ref()
+                .cmp_with_collation(&rhs.as_ref(), Collation::Utf8Mb4Bin)
+                .unwrap(),
+            Ordering::Equal
+        );
+
+        // Member order matters, matching MySQL's SET string form: even
+        // under a case-insensitive collation, the same two members in the
+        // opposite order ("a,b" vs "b,a") must not compare equal.
+        let reordered = make_set(&["b", "a", "c"], 0b011);
+        assert_ne!(
+            lhs.as_ref()
+                .cmp_with_collation(&reordered.as_ref(), Collation::Utf8Mb4GeneralCi)
+                .unwrap(),
+            Ordering::Equal
+        );
+
+        // A padding collation ignores a trailing space on a member name.
+        let trailing_space = make_set(&["a", "B ", "c"], 0b011);
+        assert_eq!(
+            lhs.as_ref()
+                .cmp_with_collation(&trailing_space.as_ref(), Collation::Utf8Mb4Bin)
+                .unwrap(),
+            Ordering::Equal
+        );
+    }
 }
@@ -2818,6 +2818,18 @@ impl ConvertTo<Duration> for Time {
     }
 }
 
+impl Time {
+    /// Returns a copy of `self` with its whole fsp/type field cleared, i.e.
+    /// as a `DateTime` with `fsp` 0 (a `Date` or `Timestamp` comes back as a
+    /// `DateTime`). `Time::eq`/`cmp`/`hash` already ignore that field this
+    /// way; use this when a caller instead needs an actual value (e.g. bytes
+    /// to encode) that is guaranteed identical for `Time`s that compare equal.
+    pub fn normalized(mut self) -> Self {
+        self.set_fsp_tt(0);
+        self
+    }
+}
+
 impl PartialEq for Time {
     fn eq(&self, other: &Self) -> bool {
         let mut a = *self;
@@ -115,6 +115,7 @@ pub enum Collation {
     Utf8Mb40900AiCi = -255,
     Utf8Mb40900Bin = -309,
     Latin1Bin = -47,
+    Latin1GeneralCi = -8,
     GbkBin = -87,
     GbkChineseCi = -28,
     Gb18030ChineseCi = -248,
@@ -132,6 +133,7 @@ impl Collation {
             -33 | -45 => Ok(Collation::Utf8Mb4GeneralCi),
             -46 | -83 | -65 => Ok(Collation::Utf8Mb4Bin),
             -47 => Ok(Collation::Latin1Bin),
+            -8 => Ok(Collation::Latin1GeneralCi),
             -63 | 63 | 47 => Ok(Collation::Binary),
             -224 | -192 => Ok(Collation::Utf8Mb4UnicodeCi),
             -87 => Ok(Collation::GbkBin),
@@ -151,6 +153,78 @@ impl Collation {
             Collation::Utf8Mb4Bin | Collation::Latin1Bin | Collation::Utf8Mb40900Bin
         )
     }
+
+    /// Returns whether this collation pads values with trailing spaces before
+    /// comparison (PAD SPACE), or compares the raw bytes/characters as-is (NO
+    /// PAD).
+    ///
+    /// This match is exhaustive on purpose: adding a new `Collation` variant
+    /// without extending this method is a compile error, so the author is
+    /// forced to decide its padding behavior instead of leaving callers to
+    /// infer it from ad-hoc variant comparisons.
+    pub fn pad_attribute(&self) -> PadAttribute {
+        match self {
+            Collation::Binary
+            | Collation::Utf8Mb4BinNoPadding
+            | Collation::Utf8Mb40900AiCi
+            | Collation::Utf8Mb40900Bin => PadAttribute::NoPad,
+            Collation::Utf8Mb4Bin
+            | Collation::Utf8Mb4GeneralCi
+            | Collation::Utf8Mb4UnicodeCi
+            | Collation::Latin1Bin
+            | Collation::Latin1GeneralCi
+            | Collation::GbkBin
+            | Collation::GbkChineseCi
+            | Collation::Gb18030ChineseCi
+            | Collation::Gb18030Bin => PadAttribute::PadSpace,
+        }
+    }
+
+    /// Returns the canonical charset name backing this collation, i.e. the
+    /// same string `Charset::from_name` accepts.
+    pub fn charset(&self) -> &'static str {
+        match self {
+            Collation::Binary => "binary",
+            Collation::Utf8Mb4Bin
+            | Collation::Utf8Mb4BinNoPadding
+            | Collation::Utf8Mb4GeneralCi
+            | Collation::Utf8Mb4UnicodeCi
+            | Collation::Utf8Mb40900AiCi
+            | Collation::Utf8Mb40900Bin => "utf8mb4",
+            Collation::Latin1Bin | Collation::Latin1GeneralCi => "latin1",
+            Collation::GbkBin | Collation::GbkChineseCi => "gbk",
+            Collation::Gb18030ChineseCi | Collation::Gb18030Bin => "gb18030",
+        }
+    }
+
+    /// Returns whether this collation compares strings case-insensitively.
+    pub fn is_case_insensitive(&self) -> bool {
+        match self {
+            Collation::Binary
+            | Collation::Utf8Mb4Bin
+            | Collation::Utf8Mb4BinNoPadding
+            | Collation::Utf8Mb40900Bin
+            | Collation::Latin1Bin
+            | Collation::GbkBin
+            | Collation::Gb18030Bin => false,
+            Collation::Utf8Mb4GeneralCi
+            | Collation::Utf8Mb4UnicodeCi
+            | Collation::Utf8Mb40900AiCi
+            | Collation::Latin1GeneralCi
+            | Collation::GbkChineseCi
+            | Collation::Gb18030ChineseCi => true,
+        }
+    }
+}
+
+/// Whether a collation pads values with trailing spaces before comparison
+/// (PAD SPACE), or compares them as-is (NO PAD).
+///
+/// See [`Collation::pad_attribute`].
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum PadAttribute {
+    PadSpace,
+    NoPad,
 }
 
 impl fmt::Display for Collation {
@@ -346,7 +420,7 @@ pub trait FieldTypeAccessor {
                 || self.is_varchar_like())
             && self
                 .collation()
-                .map(|col| col != Collation::Utf8Mb40900Bin)
+                .map(|col| col.pad_attribute() == PadAttribute::PadSpace)
                 .unwrap_or(false)
     }
 }
@@ -612,8 +686,8 @@ mod tests {
             (FieldTypeTp::VarString, Collation::Utf8Mb4GeneralCi, true),
             (FieldTypeTp::String, Collation::Utf8Mb4UnicodeCi, true),
             (FieldTypeTp::VarString, Collation::Utf8Mb4UnicodeCi, true),
-            (FieldTypeTp::String, Collation::Utf8Mb40900AiCi, true),
-            (FieldTypeTp::VarString, Collation::Utf8Mb40900AiCi, true),
+            (FieldTypeTp::String, Collation::Utf8Mb40900AiCi, false),
+            (FieldTypeTp::VarString, Collation::Utf8Mb40900AiCi, false),
             (FieldTypeTp::String, Collation::Utf8Mb40900Bin, false),
             (FieldTypeTp::VarString, Collation::Utf8Mb40900Bin, false),
             (FieldTypeTp::String, Collation::GbkBin, true),
@@ -627,4 +701,45 @@ mod tests {
             assert_eq!(ft.need_restored_data(), result)
         }
     }
+
+    /// `pad_attribute` must agree with how each collator actually compares a
+    /// string against the same string with a trailing space: PAD SPACE
+    /// collations treat them as equal, NO PAD collations do not. This keeps
+    /// the two from silently drifting apart as collations are added.
+    #[test]
+    fn test_pad_attribute_matches_sort_compare() {
+        use crate::{codec::collation::Collator, match_template_collator};
+
+        let collations = [
+            Collation::Binary,
+            Collation::Utf8Mb4Bin,
+            Collation::Utf8Mb4BinNoPadding,
+            Collation::Utf8Mb4GeneralCi,
+            Collation::Utf8Mb4UnicodeCi,
+            Collation::Utf8Mb40900AiCi,
+            Collation::Utf8Mb40900Bin,
+            Collation::Latin1Bin,
+            Collation::Latin1GeneralCi,
+            Collation::GbkBin,
+            Collation::GbkChineseCi,
+            Collation::Gb18030Bin,
+            Collation::Gb18030ChineseCi,
+        ];
+
+        for collation in collations {
+            let pads = match_template_collator! {
+                TT, match collation {
+                    Collation::TT => {
+                        TT::sort_compare(b"a", b"a ", false).unwrap() == std::cmp::Ordering::Equal
+                    }
+                }
+            };
+            assert_eq!(
+                pads,
+                collation.pad_attribute() == PadAttribute::PadSpace,
+                "collation {:?}",
+                collation,
+            );
+        }
+    }
 }
@@ -6,7 +6,7 @@ use bitflags::bitflags;
 use tipb::DagRequest;
 
 use super::{Error, Result};
-use crate::codec::mysql::{DEFAULT_DIV_FRAC_INCR, Tz};
+use crate::codec::mysql::{DEFAULT_DIV_FRAC_INCR, MAX_DIV_FRAC_INCR, Tz};
 
 bitflags! {
     /// Please refer to SQLMode in `mysql/const.go` in repo `pingcap/parser` for details.
@@ -49,6 +49,14 @@ bitflags! {
         const DIVIDED_BY_ZERO_AS_WARNING = 1 << 8;
         /// `IN_LOAD_DATA_STMT` indicates if this is a LOAD DATA statement.
         const IN_LOAD_DATA_STMT = 1 << 10;
+        /// `JSON_COMPARE_COERCE_STRING_NUMBER` indicates that comparing a
+        /// JSON string against a JSON number should first try parsing the
+        /// string as a number (MySQL's usual leading-prefix float parsing)
+        /// and compare by value, instead of always ordering by JSON type
+        /// precedence. Off by default: unset, a plain `CAST('12' AS JSON) =
+        /// 12` keeps comparing by precedence, matching TiKV's historical
+        /// behavior.
+        const JSON_COMPARE_COERCE_STRING_NUMBER = 1 << 11;
     }
 }
 
@@ -136,8 +144,11 @@ impl EvalConfig {
         self
     }
 
+    /// Sets the decimal divide precision increment, clamping `new_value` to
+    /// `[0, MAX_DIV_FRAC_INCR]` the way MySQL clamps `div_precision_increment`
+    /// rather than rejecting out-of-range values.
     pub fn set_div_precision_incr(&mut self, new_value: u8) -> &mut Self {
-        self.div_precision_increment = new_value;
+        self.div_precision_increment = new_value.min(MAX_DIV_FRAC_INCR);
         self
     }
 
@@ -380,6 +391,17 @@ mod tests {
         assert_eq!(warnings.warnings.len(), eval_cfg.max_warning_cnt);
     }
 
+    #[test]
+    fn test_set_div_precision_incr_clamps_to_max() {
+        let mut cfg = EvalConfig::new();
+        cfg.set_div_precision_incr(10);
+        assert_eq!(cfg.div_precision_increment, 10);
+        cfg.set_div_precision_incr(MAX_DIV_FRAC_INCR);
+        assert_eq!(cfg.div_precision_increment, MAX_DIV_FRAC_INCR);
+        cfg.set_div_precision_incr(u8::MAX);
+        assert_eq!(cfg.div_precision_increment, MAX_DIV_FRAC_INCR);
+    }
+
     #[test]
     fn test_handle_division_by_zero() {
         let cases = vec![
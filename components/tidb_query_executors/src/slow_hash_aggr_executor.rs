@@ -13,7 +13,10 @@ use tidb_query_aggr::*;
 use tidb_query_common::{Result, storage::IntervalRange};
 use tidb_query_datatype::{
     EvalType, FieldTypeAccessor,
-    codec::batch::{LazyBatchColumn, LazyBatchColumnVec},
+    codec::{
+        batch::{LazyBatchColumn, LazyBatchColumnVec},
+        collation::SortKeyCache,
+    },
     expr::{EvalConfig, EvalContext},
 };
 use tidb_query_expr::{RpnExpression, RpnExpressionBuilder, RpnStackNode};
@@ -193,7 +196,8 @@ impl<Src: BatchExecutor> BatchSlowHashAggregationExecutor<Src> {
         for (i, extra_col_index) in extra_group_by_col_index.iter().enumerate() {
             original_group_by_col_index[*extra_col_index] = group_by_exps.len() + i;
         }
-        let group_by_col_len = group_by_exps.len() + extra_group_by_col_index.len();
+        let group_by_exps_len = group_by_exps.len();
+        let group_by_col_len = group_by_exps_len + extra_group_by_col_index.len();
         let aggr_impl = SlowHashAggregationImpl {
             states: Vec::with_capacity(1024),
             groups: HashMap::default(),
@@ -205,6 +209,7 @@ impl<Src: BatchExecutor> BatchSlowHashAggregationExecutor<Src> {
             states_offset_each_logical_row: Vec::with_capacity(crate::runner::BATCH_MAX_SIZE),
             group_by_results_unsafe: Vec::with_capacity(group_by_col_len),
             cached_encoded_result: vec![None; group_by_col_len],
+            sort_key_caches: (0..group_by_exps_len).map(|_| Default::default()).collect(),
         };
 
         Ok(Self(AggregationExecutor::new(
@@ -266,6 +271,13 @@ pub struct SlowHashAggregationImpl {
 
     /// Cached encoded results for calculated Scalar results
     cached_encoded_result: Vec<Option<Vec<u8>>>,
+
+    /// One [`SortKeyCache`] per `group_by_exps` column, so that a run of
+    /// consecutive equal values in a `Vector` group-by column -- typical of
+    /// a streamed, already-sorted range scan -- reuses the last computed
+    /// sort key instead of recomputing it through `Collator::sort_key` for
+    /// every row.
+    sort_key_caches: Vec<SortKeyCache>,
 }
 
 unsafe impl Send for SlowHashAggregationImpl {}
@@ -325,11 +337,12 @@ impl<Src: BatchExecutor> AggregationExecutorImpl<Src> for SlowHashAggregationImp
             for (i, group_by_result) in self.group_by_results_unsafe.iter().enumerate() {
                 match group_by_result {
                     RpnStackNode::Vector { value, field_type } => {
-                        value.as_ref().encode_sort_key(
+                        value.as_ref().encode_sort_key_cached(
                             value.logical_rows_struct().get_idx(logical_row_idx),
                             *field_type,
                             context,
                             &mut self.group_key_buffer,
+                            &mut self.sort_key_caches[i],
                         )?;
                         self.group_key_offsets.push(self.group_key_buffer.len());
                     }
@@ -5,6 +5,7 @@ use std::sync::Arc;
 use async_trait::async_trait;
 use tidb_query_common::{Result, storage::IntervalRange};
 use tidb_query_datatype::{
+    EvalType, FieldTypeAccessor,
     codec::{batch::LazyBatchColumnVec, data_type::*},
     expr::{EvalConfig, EvalContext, EvalWarnings},
 };
@@ -14,7 +15,7 @@ use tipb::{Expr, FieldType, TopN};
 use crate::{
     interface::*,
     util::{
-        top_n_heap::{HeapItemSourceData, HeapItemUnsafe, TopNHeap},
+        top_n_heap::{BatchSortKeys, HeapItemSourceData, HeapItemUnsafe, TopNHeap},
         *,
     },
 };
@@ -251,11 +252,29 @@ impl<Src: BatchExecutor> BatchTopNExecutor<Src> {
             )?;
         }
 
+        let row_count = pinned_source_data.logical_rows.len();
+        let sort_keys = Arc::new(BatchSortKeys::build(self.order_exprs.len(), |column_idx| {
+            let field_type = &self.order_exprs_field_type[column_idx];
+            if EvalType::try_from(field_type.as_accessor().tp()).ok() != Some(EvalType::Bytes) {
+                return None;
+            }
+            let collation = field_type.collation().ok()?;
+            let node = &self.eval_columns_buffer_unsafe[eval_offset + column_idx];
+            let values = Box::new((0..row_count).map(move |logical_row_index| {
+                match node.get_logical_scalar_ref(logical_row_index) {
+                    ScalarValueRef::Bytes(value) => value,
+                    _ => unreachable!("field type says Bytes but value isn't"),
+                }
+            }));
+            Some((collation, values as Box<dyn Iterator<Item = Option<&[u8]>> + '_>))
+        })?);
+
         for logical_row_index in 0..pinned_source_data.logical_rows.len() {
             let row = HeapItemUnsafe {
                 order_is_desc_ptr: (*self.order_is_desc).into(),
                 order_exprs_field_type_ptr: (*self.order_exprs_field_type).into(),
                 source_data: pinned_source_data.clone(),
+                sort_keys: sort_keys.clone(),
                 eval_columns_buffer_ptr: self.eval_columns_buffer_unsafe.as_ref().into(),
                 eval_columns_offset: eval_offset,
                 logical_row_index,
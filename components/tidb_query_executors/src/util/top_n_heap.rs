@@ -1,11 +1,15 @@
 // Copyright 2023 TiKV Project Authors. Licensed under Apache-2.0.
 
-use std::{cmp::Ordering, collections::BinaryHeap, ptr::NonNull, sync::Arc};
+use std::{cmp::Ordering, collections::BinaryHeap, ops::Range, ptr::NonNull, sync::Arc};
 
 use tidb_query_common::Result;
-use tidb_query_datatype::codec::{
-    batch::{LazyBatchColumn, LazyBatchColumnVec},
-    data_type::*,
+use tidb_query_datatype::{
+    Collation,
+    codec::{
+        batch::{LazyBatchColumn, LazyBatchColumnVec},
+        collation::encode_sort_keys_batch_for_collation,
+        data_type::*,
+    },
 };
 use tidb_query_expr::RpnStackNode;
 use tikv_util::error;
@@ -142,6 +146,62 @@ pub struct HeapItemSourceData {
     pub logical_rows: Vec<usize>,
 }
 
+/// Precomputed ORDER BY sort keys for every Bytes-typed order-by column of
+/// one `process_batch_input` batch, built once up front so that comparing
+/// two rows while sifting the heap (`HeapItemUnsafe::cmp_sort_key`) never
+/// has to resolve a collator itself -- `match_template_collator!` is
+/// dispatched once per column here instead of once per row comparison.
+///
+/// Indexed the same way `HeapItemUnsafe::eval_columns_buffer_ptr` is: column
+/// index first (`0..order_by_len`), then by `logical_row_index`.
+#[derive(Default)]
+pub struct BatchSortKeys {
+    columns: Vec<Option<SortKeyColumn>>,
+}
+
+struct SortKeyColumn {
+    buf: Vec<u8>,
+    ranges: Vec<Option<Range<usize>>>,
+}
+
+impl BatchSortKeys {
+    /// Builds the precomputed sort keys for one batch of `order_by_len`
+    /// order-by columns. `column` is called once per column index and must
+    /// return `Some((collation, values))` for a Bytes-typed column, where
+    /// `values` yields that column's value for every logical row of the
+    /// batch in order, or `None` for any other column (such a column keeps
+    /// comparing the old way, per row, in `cmp_sort_key`).
+    pub fn build<'a>(
+        order_by_len: usize,
+        mut column: impl FnMut(usize) -> Option<(Collation, Box<dyn Iterator<Item = Option<&'a [u8]>> + 'a>)>,
+    ) -> Result<Self> {
+        let mut columns = Vec::with_capacity(order_by_len);
+        for column_idx in 0..order_by_len {
+            columns.push(match column(column_idx) {
+                None => None,
+                Some((collation, values)) => {
+                    let mut buf = Vec::new();
+                    let ranges = encode_sort_keys_batch_for_collation(collation, values, &mut buf)?;
+                    Some(SortKeyColumn { buf, ranges })
+                }
+            });
+        }
+        Ok(Self { columns })
+    }
+
+    /// Returns `Some(value)` for a column that has precomputed keys, where
+    /// `value` is the row's sort key bytes (or `None` for a NULL row), or
+    /// `None` if `column_idx` has no precomputed keys at all and must be
+    /// compared the old way.
+    fn get(&self, column_idx: usize, logical_row_index: usize) -> Option<Option<&[u8]>> {
+        self.columns[column_idx].as_ref().map(|column| {
+            column.ranges[logical_row_index]
+                .as_ref()
+                .map(|range| &column.buf[range.clone()])
+        })
+    }
+}
+
 /// The item in the heap of `BatchTopNExecutor`.
 ///
 /// WARN: The content of this structure is valid only if `BatchTopNExecutor` is
@@ -156,6 +216,11 @@ pub struct HeapItemUnsafe {
     /// The source data that evaluated column in this structure is using.
     pub source_data: Arc<HeapItemSourceData>,
 
+    /// The precomputed sort keys of this row's batch, shared with every
+    /// other row from the same `process_batch_input` call. See
+    /// [`BatchSortKeys`].
+    pub sort_keys: Arc<BatchSortKeys>,
+
     /// A pointer to the `eval_columns_buffer` field in `BatchTopNExecutor`.
     pub eval_columns_buffer_ptr: NonNull<Vec<RpnStackNode<'static>>>,
 
@@ -196,15 +261,29 @@ impl HeapItemUnsafe {
         let eval_columns_rhs = other.get_eval_columns(columns_len);
 
         for column_idx in 0..columns_len {
-            let lhs_node = &eval_columns_lhs[column_idx];
-            let rhs_node = &eval_columns_rhs[column_idx];
-            let lhs = lhs_node.get_logical_scalar_ref(self.logical_row_index);
-            let rhs = rhs_node.get_logical_scalar_ref(other.logical_row_index);
-
-            // There is panic inside, but will never panic, since the data type of
-            // corresponding column should be consistent for each
-            // `HeapItemUnsafe`.
-            let ord = lhs.cmp_sort_key(&rhs, &order_exprs_field_type[column_idx])?;
+            let precomputed = (
+                self.sort_keys.get(column_idx, self.logical_row_index),
+                other.sort_keys.get(column_idx, other.logical_row_index),
+            );
+
+            let ord = if let (Some(lhs_key), Some(rhs_key)) = precomputed {
+                match (lhs_key, rhs_key) {
+                    (None, None) => Ordering::Equal,
+                    (Some(_), None) => Ordering::Greater,
+                    (None, Some(_)) => Ordering::Less,
+                    (Some(lhs_key), Some(rhs_key)) => lhs_key.cmp(rhs_key),
+                }
+            } else {
+                let lhs_node = &eval_columns_lhs[column_idx];
+                let rhs_node = &eval_columns_rhs[column_idx];
+                let lhs = lhs_node.get_logical_scalar_ref(self.logical_row_index);
+                let rhs = rhs_node.get_logical_scalar_ref(other.logical_row_index);
+
+                // There is panic inside, but will never panic, since the data type
+                // of corresponding column should be consistent for each
+                // `HeapItemUnsafe`.
+                lhs.cmp_sort_key(&rhs, &order_exprs_field_type[column_idx])?
+            };
 
             if ord == Ordering::Equal {
                 continue;
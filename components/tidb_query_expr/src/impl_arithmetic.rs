@@ -1246,6 +1246,15 @@ mod tests {
             (Some("2.2"), Some("1.3"), Some("1.69231"), 4),
             (None, Some("2"), None, 4),
             (Some("123"), None, None, 4),
+            // An out-of-range increment is clamped to MAX_DIV_FRAC_INCR (30),
+            // matching how MySQL clamps `div_precision_increment`, instead of
+            // being rejected or wrapping.
+            (
+                Some("1"),
+                Some("3"),
+                Some("0.333333333333333333333333333333"),
+                255,
+            ),
         ];
         for (lhs, rhs, expected, frac_incr) in cases2 {
             let mut cfg = EvalConfig::new();
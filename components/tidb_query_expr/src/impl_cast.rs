@@ -158,10 +158,14 @@ fn get_cast_fn_rpn_meta(
         }
         (EvalType::Real, EvalType::Decimal) => cast_real_as_decimal_fn_meta(),
         (EvalType::Bytes, EvalType::Decimal) => {
-            if to_field_type.is_unsigned() {
-                cast_string_as_unsigned_decimal_fn_meta()
-            } else {
-                cast_bytes_as_decimal_fn_meta()
+            match (
+                is_from_constant && from_field_type.is_binary_string_like(),
+                to_field_type.is_unsigned(),
+            ) {
+                (true, true) => cast_binary_string_as_unsigned_decimal_fn_meta(),
+                (true, false) => cast_binary_string_as_signed_decimal_fn_meta(),
+                (false, true) => cast_string_as_unsigned_decimal_fn_meta(),
+                (false, false) => cast_bytes_as_decimal_fn_meta(),
             }
         }
         (EvalType::Decimal, EvalType::Decimal) => {
@@ -868,6 +872,48 @@ fn cast_string_as_unsigned_decimal(
     }
 }
 
+#[rpn_fn(nullable, capture = [ctx, extra])]
+#[inline]
+fn cast_binary_string_as_signed_decimal(
+    ctx: &mut EvalContext,
+    extra: &RpnFnCallExtra,
+    val: Option<BytesRef>,
+) -> Result<Option<Decimal>> {
+    match val {
+        None => Ok(None),
+        Some(val) => {
+            let dec = binary_literal::to_decimal(ctx, val)?;
+            Ok(Some(produce_dec_with_specified_tp(
+                ctx,
+                dec,
+                extra.ret_field_type,
+            )?))
+        }
+    }
+}
+
+#[rpn_fn(nullable, capture = [ctx, extra])]
+#[inline]
+fn cast_binary_string_as_unsigned_decimal(
+    ctx: &mut EvalContext,
+    extra: &RpnFnCallExtra,
+    val: Option<BytesRef>,
+) -> Result<Option<Decimal>> {
+    match val {
+        None => Ok(None),
+        Some(val) => {
+            // A binary literal's value is never negative, so unlike
+            // `cast_string_as_unsigned_decimal` there's no `in_union` clamping to do here.
+            let dec = binary_literal::to_decimal(ctx, val)?;
+            Ok(Some(produce_dec_with_specified_tp(
+                ctx,
+                dec,
+                extra.ret_field_type,
+            )?))
+        }
+    }
+}
+
 #[rpn_fn(nullable, capture = [ctx, extra])]
 #[inline]
 fn cast_decimal_as_signed_decimal(
@@ -1009,20 +1055,23 @@ fn cast_time_as_duration(
         None => Ok(None),
         Some(val) => {
             let dur: Duration = val.convert(ctx)?;
-            Ok(Some(dur.round_frac(extra.ret_field_type.decimal() as i8)?))
+            Ok(Some(dur.round_frac(ctx, extra.ret_field_type.decimal() as i8)?))
         }
     }
 }
 
-#[rpn_fn(nullable, capture = [extra])]
+#[rpn_fn(nullable, capture = [ctx, extra])]
 #[inline]
 fn cast_duration_as_duration(
+    ctx: &mut EvalContext,
     extra: &RpnFnCallExtra,
     val: Option<&Duration>,
 ) -> Result<Option<Duration>> {
     match val {
         None => Ok(None),
-        Some(val) => Ok(Some(val.round_frac(extra.ret_field_type.decimal() as i8)?)),
+        Some(val) => Ok(Some(
+            val.round_frac(ctx, extra.ret_field_type.decimal() as i8)?,
+        )),
     }
 }
 
@@ -1142,7 +1191,7 @@ pub fn cast_json_as_duration(
             let time = v.get_time()?;
             let dur: Duration = time.convert(ctx)?;
 
-            Ok(Some(dur.round_frac(extra.ret_field_type.decimal() as i8)?))
+            Ok(Some(dur.round_frac(ctx, extra.ret_field_type.decimal() as i8)?))
         }
         JsonType::Time => Ok(Some(v.get_duration()?)),
         JsonType::String => cast_bytes_like_as_duration(ctx, extra, v.unquote()?.as_bytes(), false),
@@ -1417,12 +1466,22 @@ fn cast_string_as_json(
     }
 }
 
-#[rpn_fn(nullable)]
+#[rpn_fn(nullable, capture = [ctx])]
 #[inline]
-fn cast_json_as_json(val: Option<JsonRef>) -> Result<Option<Json>> {
+fn cast_json_as_json(ctx: &mut EvalContext, val: Option<JsonRef>) -> Result<Option<Json>> {
     match val {
         None => Ok(None),
-        Some(val) => Ok(Some(val.to_owned())),
+        Some(val) => {
+            // In strict sql mode, reject a structurally corrupted JSON value
+            // here instead of letting it panic deep inside some later
+            // accessor.
+            if ctx.cfg.sql_mode.is_strict() {
+                if let Err(e) = val.validate() {
+                    return Err(Error::CorruptedData(e.to_string()).into());
+                }
+            }
+            Ok(Some(val.to_owned()))
+        }
     }
 }
 
@@ -1621,7 +1680,7 @@ mod tests {
         builder::FieldTypeBuilder,
         codec::{
             convert::produce_dec_with_specified_tp,
-            data_type::{Bytes, Int, Real},
+            data_type::{Bytes, Int, JsonRef, JsonType, Real},
             error::{
                 ERR_DATA_OUT_OF_RANGE, ERR_DATA_TOO_LONG, ERR_TRUNCATE_WRONG_VALUE, ERR_UNKNOWN,
                 WARN_DATA_TRUNCATED,
@@ -1632,7 +1691,7 @@ mod tests {
                 decimal::{max_decimal, max_or_min_dec},
             },
         },
-        expr::{EvalConfig, EvalContext, Flag},
+        expr::{EvalConfig, EvalContext, Flag, SqlMode},
     };
     use tikv_util::buffer_vec::BufferVec;
     use tipb::ScalarFuncSig;
@@ -1662,18 +1721,6 @@ mod tests {
         assert!(r.is_none());
     }
 
-    fn test_none_with_extra<F, Input, Ret>(func: F)
-    where
-        F: Fn(&RpnFnCallExtra, Option<Input>) -> Result<Option<Ret>>,
-    {
-        let ret_field_type: FieldType = FieldType::default();
-        let extra = RpnFnCallExtra {
-            ret_field_type: &ret_field_type,
-        };
-        let r = func(&extra, None).unwrap();
-        assert!(r.is_none());
-    }
-
     fn test_none_with_args_and_extra<F, Input, Ret>(func: F)
     where
         F: Fn(&[RpnStackNode<'_>], &RpnFnCallExtra, Option<Input>) -> Result<Option<Ret>>,
@@ -6624,7 +6671,7 @@ mod tests {
 
     #[test]
     fn test_duration_as_duration() {
-        test_none_with_extra(cast_duration_as_duration);
+        test_none_with_ctx_and_extra(cast_duration_as_duration);
 
         let cs = vec![
             ("11:30:45.123456", 6, 0, "11:30:45"),
@@ -6647,7 +6694,7 @@ mod tests {
             let mut ctx = EvalContext::default();
             let dur = Duration::parse(&mut ctx, input, input_fsp).unwrap();
             let expect = Duration::parse(&mut ctx, expect, output_fsp).unwrap();
-            let r = cast_duration_as_duration(&extra, Some(&dur));
+            let r = cast_duration_as_duration(&mut ctx, &extra, Some(&dur));
 
             let result_str = r.as_ref().map(|x| x.map(|x| x.to_string()));
             let log = format!(
@@ -7205,7 +7252,7 @@ mod tests {
 
     #[test]
     fn test_json_as_json() {
-        test_none_with_nothing(cast_json_as_json);
+        test_none_with_ctx(cast_json_as_json);
 
         let mut jo1: BTreeMap<String, Json> = BTreeMap::new();
         jo1.insert("a".to_string(), Json::from_string("b".to_string()).unwrap());
@@ -7229,11 +7276,27 @@ mod tests {
             Json::none().unwrap(),
         ];
 
+        let mut ctx = EvalContext::default();
         for input in cs {
             let expect = input.clone();
-            let result = cast_json_as_json(Some(input.as_ref()));
+            let result = cast_json_as_json(&mut ctx, Some(input.as_ref()));
             let log = make_log(&input, &expect, &result);
             check_result(Some(&expect), &result, log.as_str());
         }
     }
+
+    #[test]
+    fn test_json_as_json_strict_mode_rejects_corrupted_value() {
+        // An empty String value: there are no bytes for the length prefix,
+        // let alone the string data it should describe.
+        let corrupted = JsonRef::new(JsonType::String, &[]);
+
+        let mut ctx = EvalContext::default();
+        assert!(cast_json_as_json(&mut ctx, Some(corrupted)).is_ok());
+
+        let mut cfg = EvalConfig::default();
+        cfg.set_sql_mode(SqlMode::STRICT_ALL_TABLES);
+        let mut strict_ctx = EvalContext::new(Arc::new(cfg));
+        assert!(cast_json_as_json(&mut strict_ctx, Some(corrupted)).is_err());
+    }
 }
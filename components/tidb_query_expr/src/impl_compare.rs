@@ -8,23 +8,32 @@ use std::{
 use tidb_query_codegen::rpn_fn;
 use tidb_query_common::Result;
 use tidb_query_datatype::{
+    FieldTypeAccessor,
     codec::{Error, collation::Collator, data_type::*, mysql::Time},
     expr::EvalContext,
 };
 
+use crate::RpnFnCallExtra;
+
 #[rpn_fn(nullable)]
 #[inline]
 pub fn compare<C: Comparer>(lhs: Option<&C::T>, rhs: Option<&C::T>) -> Result<Option<i64>> {
     C::compare(lhs, rhs)
 }
 
-#[rpn_fn(nullable)]
+#[rpn_fn(nullable, capture = [ctx])]
 #[inline]
-pub fn compare_json<F: CmpOp>(lhs: Option<JsonRef>, rhs: Option<JsonRef>) -> Result<Option<i64>> {
+pub fn compare_json<F: CmpOp>(
+    ctx: &mut EvalContext,
+    lhs: Option<JsonRef>,
+    rhs: Option<JsonRef>,
+) -> Result<Option<i64>> {
     Ok(match (lhs, rhs) {
         (None, None) => F::compare_null(),
         (None, _) | (_, None) => F::compare_partial_null(),
-        (Some(lhs), Some(rhs)) => Some(F::compare_order(lhs.cmp(&rhs)) as i64),
+        (Some(lhs), Some(rhs)) => {
+            Some(F::compare_order(lhs.checked_cmp_with_ctx(ctx, &rhs)?) as i64)
+        }
     })
 }
 
@@ -57,6 +66,66 @@ pub fn compare_bytes<C: Collator, F: CmpOp>(
     })
 }
 
+/// Like [`compare_bytes`], but compares under the binary-comparison operator:
+/// `C::sort_compare` is forced to ignore PAD behavior (`force_no_pad = true`)
+/// even if `C` is a padding collation, matching MySQL's `CAST(... AS
+/// BINARY)`/`weight_string(... AS BINARY)` semantics.
+#[rpn_fn(nullable)]
+#[inline]
+pub fn compare_bytes_force_no_pad<C: Collator, F: CmpOp>(
+    lhs: Option<BytesRef>,
+    rhs: Option<BytesRef>,
+) -> Result<Option<i64>> {
+    Ok(match (lhs, rhs) {
+        (None, None) => F::compare_null(),
+        (None, _) | (_, None) => F::compare_partial_null(),
+        (Some(lhs), Some(rhs)) => {
+            let ord = C::sort_compare(lhs, rhs, true)?;
+            Some(F::compare_order(ord) as i64)
+        }
+    })
+}
+
+/// Compares two `ENUM`s by their member name under the column's collation,
+/// matching MySQL's `ENUM = <string>` semantics, which compares by string
+/// value rather than by numeric index.
+#[rpn_fn(nullable, capture = [extra])]
+#[inline]
+pub fn compare_enum<F: CmpOp>(
+    extra: &RpnFnCallExtra,
+    lhs: Option<EnumRef>,
+    rhs: Option<EnumRef>,
+) -> Result<Option<i64>> {
+    Ok(match (lhs, rhs) {
+        (None, None) => F::compare_null(),
+        (None, _) | (_, None) => F::compare_partial_null(),
+        (Some(lhs), Some(rhs)) => {
+            let collation = extra.ret_field_type.as_accessor().collation().map_err(Error::from)?;
+            Some(F::compare_order(lhs.cmp_with_collation(&rhs, collation)?) as i64)
+        }
+    })
+}
+
+/// Compares two `SET`s by their ordered member-name string under the
+/// column's collation, matching MySQL's `SET = <string>` semantics, which
+/// compares by string value rather than by bitmap value.
+#[rpn_fn(nullable, capture = [extra])]
+#[inline]
+pub fn compare_set<F: CmpOp>(
+    extra: &RpnFnCallExtra,
+    lhs: Option<SetRef>,
+    rhs: Option<SetRef>,
+) -> Result<Option<i64>> {
+    Ok(match (lhs, rhs) {
+        (None, None) => F::compare_null(),
+        (None, _) | (_, None) => F::compare_partial_null(),
+        (Some(lhs), Some(rhs)) => {
+            let collation = extra.ret_field_type.as_accessor().collation().map_err(Error::from)?;
+            Some(F::compare_order(lhs.cmp_with_collation(&rhs, collation)?) as i64)
+        }
+    })
+}
+
 pub trait Comparer {
     type T: Evaluable + EvaluableRet;
 
@@ -362,9 +362,19 @@ fn unquote_string(s: &str) -> Result<String> {
     }
 }
 
-#[rpn_fn(nullable, raw_varg, min_args = 2, extra_validator = json_with_paths_validator)]
+#[rpn_fn(
+    nullable,
+    raw_varg,
+    min_args = 2,
+    capture = [metadata],
+    metadata_mapper = init_json_path_list_data::<1>,
+    extra_validator = json_with_paths_validator
+)]
 #[inline]
-fn json_extract(args: &[ScalarValueRef]) -> Result<Option<Json>> {
+fn json_extract(
+    metadata: &Vec<Option<PathExpression>>,
+    args: &[ScalarValueRef],
+) -> Result<Option<Json>> {
     assert!(args.len() >= 2);
     let j: Option<JsonRef> = args[0].as_json();
     let j = match j {
@@ -372,7 +382,7 @@ fn json_extract(args: &[ScalarValueRef]) -> Result<Option<Json>> {
         Some(j) => j.to_owned(),
     };
 
-    let path_expr_list = try_opt!(parse_json_path_list(&args[1..]));
+    let path_expr_list = try_opt!(resolve_json_path_list(metadata, &args[1..]));
 
     Ok(j.as_ref().extract(&path_expr_list)?)
 }
@@ -383,28 +393,50 @@ fn json_with_path_validator(expr: &tipb::Expr) -> Result<()> {
     valid_paths(expr)
 }
 
-#[rpn_fn(nullable, raw_varg,min_args= 1, max_args = 2, extra_validator = json_with_path_validator)]
+#[rpn_fn(
+    nullable,
+    raw_varg,
+    min_args = 1,
+    max_args = 2,
+    capture = [metadata],
+    metadata_mapper = init_json_path_list_data::<1>,
+    extra_validator = json_with_path_validator
+)]
 #[inline]
-fn json_keys(args: &[ScalarValueRef]) -> Result<Option<Json>> {
+fn json_keys(
+    metadata: &Vec<Option<PathExpression>>,
+    args: &[ScalarValueRef],
+) -> Result<Option<Json>> {
     assert!(!args.is_empty() && args.len() <= 2);
     if let Some(j) = args[0].as_json() {
-        if let Some(list) = parse_json_path_list(&args[1..])? {
+        if let Some(list) = resolve_json_path_list(metadata, &args[1..])? {
             return Ok(j.keys(&list)?);
         }
     }
     Ok(None)
 }
 
-#[rpn_fn(nullable, raw_varg,min_args= 1, max_args = 2, extra_validator = json_with_path_validator)]
+#[rpn_fn(
+    nullable,
+    raw_varg,
+    min_args = 1,
+    max_args = 2,
+    capture = [metadata],
+    metadata_mapper = init_json_path_list_data::<1>,
+    extra_validator = json_with_path_validator
+)]
 #[inline]
-fn json_length(args: &[ScalarValueRef]) -> Result<Option<Int>> {
+fn json_length(
+    metadata: &Vec<Option<PathExpression>>,
+    args: &[ScalarValueRef],
+) -> Result<Option<Int>> {
     assert!(!args.is_empty() && args.len() <= 2);
     let j: Option<JsonRef> = args[0].as_json();
     let j = match j {
         None => return Ok(None),
         Some(j) => j.to_owned(),
     };
-    Ok(match parse_json_path_list(&args[1..])? {
+    Ok(match resolve_json_path_list(metadata, &args[1..])? {
         Some(path_expr_list) => j.as_ref().json_length(&path_expr_list)?,
         None => None,
     })
@@ -423,9 +455,20 @@ fn json_contains_validator(expr: &tipb::Expr) -> Result<()> {
     Ok(())
 }
 
-#[rpn_fn(nullable, raw_varg,min_args= 2, max_args = 3, extra_validator = json_contains_validator)]
+#[rpn_fn(
+    nullable,
+    raw_varg,
+    min_args = 2,
+    max_args = 3,
+    capture = [metadata],
+    metadata_mapper = init_json_path_list_data::<2>,
+    extra_validator = json_contains_validator
+)]
 #[inline]
-fn json_contains(args: &[ScalarValueRef]) -> Result<Option<i64>> {
+fn json_contains(
+    metadata: &Vec<Option<PathExpression>>,
+    args: &[ScalarValueRef],
+) -> Result<Option<i64>> {
     assert!(args.len() == 2 || args.len() == 3);
     let j: Option<JsonRef> = args[0].as_json();
     let mut j = match j {
@@ -439,7 +482,7 @@ fn json_contains(args: &[ScalarValueRef]) -> Result<Option<i64>> {
     };
 
     if args.len() == 3 {
-        match parse_json_path_list(&args[2..])? {
+        match resolve_json_path_list(metadata, &args[2..])? {
             Some(path_expr_list) => {
                 if path_expr_list.len() == 1 && path_expr_list[0].contains_any_asterisk() {
                     return Ok(None);
@@ -485,9 +528,19 @@ fn member_of(args: &[ScalarValueRef]) -> Result<Option<i64>> {
     Ok(Some(value.as_ref().member_of(json_array)? as i64))
 }
 
-#[rpn_fn(nullable, raw_varg, min_args = 2, extra_validator = json_with_paths_validator)]
+#[rpn_fn(
+    nullable,
+    raw_varg,
+    min_args = 2,
+    capture = [metadata],
+    metadata_mapper = init_json_path_list_data::<1>,
+    extra_validator = json_with_paths_validator
+)]
 #[inline]
-fn json_remove(args: &[ScalarValueRef]) -> Result<Option<Json>> {
+fn json_remove(
+    metadata: &Vec<Option<PathExpression>>,
+    args: &[ScalarValueRef],
+) -> Result<Option<Json>> {
     assert!(args.len() >= 2);
     let j: Option<JsonRef> = args[0].as_json();
     let j = match j {
@@ -495,17 +548,54 @@ fn json_remove(args: &[ScalarValueRef]) -> Result<Option<Json>> {
         Some(j) => j.to_owned(),
     };
 
-    let path_expr_list = try_opt!(parse_json_path_list(&args[1..]));
+    let path_expr_list = try_opt!(resolve_json_path_list(metadata, &args[1..]));
 
     Ok(Some(j.as_ref().remove(&path_expr_list)?))
 }
 
-fn parse_json_path_list(args: &[ScalarValueRef]) -> Result<Option<Vec<PathExpression>>> {
-    let mut path_expr_list = Vec::with_capacity(args.len());
-    for arg in args {
-        let json_path: Option<BytesRef> = arg.as_bytes();
+// Parses the path arguments of a JSON function once at expression-build
+// time, so that constant paths (the overwhelming majority in practice) are
+// only ever parsed once instead of on every row. `PATH_START_IDX` is the
+// index of the first path argument among `expr`'s children (1 for functions
+// taking a single JSON document, 2 for `json_contains` which takes a target
+// document too). Non-constant path arguments are left as `None` here and
+// fall back to per-row parsing in `resolve_json_path_list`.
+fn init_json_path_list_data<const PATH_START_IDX: usize>(
+    expr: &mut tipb::Expr,
+) -> Result<Vec<Option<PathExpression>>> {
+    expr.get_children()[PATH_START_IDX..]
+        .iter()
+        .map(|child| match child.get_tp() {
+            // An empty `val` cannot be told apart from a constant that
+            // simply wasn't embedded in the descriptor, so it is treated the
+            // same as a non-constant argument and resolved per row instead.
+            tipb::ExprType::Bytes | tipb::ExprType::String if !child.get_val().is_empty() => {
+                let json_path = std::str::from_utf8(child.get_val())
+                    .map_err(tidb_query_datatype::codec::Error::from)?;
+                Ok(Some(parse_json_path_expr(json_path)?))
+            }
+            _ => Ok(None),
+        })
+        .collect()
+}
 
-        path_expr_list.push(try_opt!(parse_json_path(json_path)));
+// Resolves each path argument using the value cached in `metadata` by
+// `init_json_path_list_data` when available, falling back to parsing `args`
+// per row for paths that weren't constants at expression-build time.
+fn resolve_json_path_list(
+    metadata: &[Option<PathExpression>],
+    args: &[ScalarValueRef],
+) -> Result<Option<Vec<PathExpression>>> {
+    let mut path_expr_list = Vec::with_capacity(args.len());
+    for (i, arg) in args.iter().enumerate() {
+        let path = match metadata.get(i).and_then(Option::as_ref) {
+            Some(parsed) => parsed.clone(),
+            None => {
+                let json_path: Option<BytesRef> = arg.as_bytes();
+                try_opt!(parse_json_path(json_path))
+            }
+        };
+        path_expr_list.push(path);
     }
     Ok(Some(path_expr_list))
 }
@@ -931,6 +1021,48 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_json_path_metadata_cache() {
+        // A constant path parsed ahead of time by `init_json_path_list_data`
+        // and cached in the metadata...
+        let mut expr = tipb::Expr::default();
+        let mut path_child = tipb::Expr::default();
+        path_child.set_tp(tipb::ExprType::Bytes);
+        path_child.set_val(b"$[1]".to_vec());
+        expr.set_children(vec![tipb::Expr::default(), path_child].into());
+        let cached_metadata = init_json_path_list_data::<1>(&mut expr).unwrap();
+        assert_eq!(cached_metadata, vec![Some(parse_json_path_expr("$[1]").unwrap())]);
+
+        let path: ScalarValue = Some(b"$[1]".to_vec()).into();
+        let args = [path.as_scalar_value_ref()];
+        let with_cached_metadata = resolve_json_path_list(&cached_metadata, &args).unwrap();
+
+        // ...must resolve to the same path as a non-constant argument, which
+        // has no cached entry and is parsed per row instead.
+        let with_no_cached_metadata = resolve_json_path_list(&[None], &args).unwrap();
+        assert_eq!(with_cached_metadata, with_no_cached_metadata);
+
+        let doc = Json::from_str("[10, 20, [30, 40]]").unwrap();
+        let extracted = doc.as_ref().extract(&with_cached_metadata.unwrap()).unwrap();
+        assert_eq!(extracted, Some(Json::from_str("20").unwrap()));
+    }
+
+    #[test]
+    fn test_json_path_metadata_cache_invalid_constant_path_errors_at_build_time() {
+        // A malformed constant path is rejected as soon as it is parsed by
+        // the metadata mapper, i.e. while the expression is being built,
+        // instead of only failing once a row is evaluated.
+        let mut expr = tipb::Expr::default();
+        let mut doc_child = tipb::Expr::default();
+        doc_child.set_tp(tipb::ExprType::Json);
+        let mut path_child = tipb::Expr::default();
+        path_child.set_tp(tipb::ExprType::String);
+        path_child.set_val(b"invalid path".to_vec());
+        expr.set_children(vec![doc_child, path_child].into());
+
+        init_json_path_list_data::<1>(&mut expr).unwrap_err();
+    }
+
     #[test]
     fn test_json_length() {
         let cases: Vec<(Vec<ScalarValue>, Option<i64>)> = vec![
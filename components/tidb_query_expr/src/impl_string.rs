@@ -829,6 +829,48 @@ pub fn find_in_set<C: Collator>(s: BytesRef, str_list: BytesRef) -> Result<Optio
     Ok(result)
 }
 
+/// Returns the sort key `arg` collates to, matching MySQL's
+/// `WEIGHT_STRING(expr)`. Handy for debugging collation issues and for
+/// end-to-end tests asserting sort-key parity with MySQL/TiDB.
+///
+/// `tipb::ScalarFuncSig` has no variant for `WEIGHT_STRING` yet (TiDB does
+/// not push it down), so this is not wired into the signature dispatch in
+/// `lib.rs`; it can be registered there once such a variant exists.
+#[rpn_fn]
+#[inline]
+pub fn weight_string<C: Collator>(arg: BytesRef) -> Result<Option<Bytes>> {
+    Ok(Some(C::sort_key(arg)?))
+}
+
+/// Like [`weight_string`], but for the `WEIGHT_STRING(expr AS CHAR(len))`
+/// form: `arg` is first truncated or space-padded to `len` UTF-8 characters,
+/// then the sort key of the result is returned. A negative or out-of-range
+/// `len` evaluates to `NULL`, matching how [`lpad_utf8`]/[`rpad_utf8`] treat
+/// an invalid target length.
+#[rpn_fn]
+#[inline]
+pub fn weight_string_with_as_char<C: Collator>(
+    arg: BytesRef,
+    len: &Int,
+) -> Result<Option<Bytes>> {
+    let (target_len, target_len_positive) = i64_to_usize(*len, false);
+    if !target_len_positive || target_len.saturating_mul(4) > MAX_BLOB_WIDTH as usize {
+        return Ok(None);
+    }
+
+    let input = str::from_utf8(arg)?;
+    let input_len = input.chars().count();
+    if target_len <= input_len {
+        let utf8_byte_end = get_utf8_byte_index(input, target_len);
+        Ok(Some(C::sort_key(&input.as_bytes()[..utf8_byte_end])?))
+    } else {
+        let mut padded = Vec::with_capacity(input.len() + (target_len - input_len));
+        padded.extend_from_slice(input.as_bytes());
+        padded.resize(padded.len() + (target_len - input_len), SPACE);
+        Ok(Some(C::sort_key(&padded)?))
+    }
+}
+
 #[rpn_fn(writer)]
 #[inline]
 pub fn trim_1_arg(arg: BytesRef, writer: BytesWriter) -> Result<BytesGuard> {
@@ -4011,6 +4053,55 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_weight_string() {
+        use tidb_query_datatype::codec::collation::collator::{
+            CollatorGbkBin, CollatorUtf8Mb4Bin, CollatorUtf8Mb4GeneralCi,
+        };
+
+        // Byte vectors below are taken from
+        // `collation::collator::tests::test_utf8mb4_sort_key`.
+        assert_eq!(
+            weight_string::<CollatorUtf8Mb4Bin>(b"a").unwrap(),
+            Some(vec![0x61])
+        );
+        assert_eq!(
+            weight_string::<CollatorUtf8Mb4GeneralCi>(b"a").unwrap(),
+            Some(vec![0x00, 0x41])
+        );
+        assert_eq!(
+            weight_string::<CollatorUtf8Mb4Bin>("中文".as_bytes()).unwrap(),
+            Some(vec![0xE4, 0xB8, 0xAD, 0xE6, 0x96, 0x87])
+        );
+        assert_eq!(
+            weight_string::<CollatorGbkBin>("中文".as_bytes()).unwrap(),
+            Some(vec![0xD6, 0xD0, 0xCE, 0xC4])
+        );
+    }
+
+    #[test]
+    fn test_weight_string_with_as_char() {
+        use tidb_query_datatype::codec::collation::collator::CollatorUtf8Mb4Bin;
+
+        // Truncates to the requested number of characters before collating.
+        assert_eq!(
+            weight_string_with_as_char::<CollatorUtf8Mb4Bin>(b"abc", &2).unwrap(),
+            Some(b"ab".to_vec())
+        );
+        // Space-pads (PAD-space) up to the requested length before collating,
+        // matching how `Collator::sort_key` for a padding collation would
+        // treat trailing spaces (see the "A " case in `test_utf8mb4_sort_key`).
+        assert_eq!(
+            weight_string_with_as_char::<CollatorUtf8Mb4Bin>(b"A", &2).unwrap(),
+            Some(vec![0x41])
+        );
+        // A negative length is invalid and evaluates to NULL.
+        assert_eq!(
+            weight_string_with_as_char::<CollatorUtf8Mb4Bin>(b"abc", &-1).unwrap(),
+            None
+        );
+    }
+
     #[test]
     fn test_trim_1_arg() {
         let test_cases = vec![
@@ -12,7 +12,7 @@ use tidb_query_datatype::{
         convert::ConvertTo,
         data_type::*,
         mysql::{
-            Duration, MAX_FSP, RoundMode, Time, TimeType, Tz, check_fsp,
+            Duration, FspMode, MAX_FSP, RoundMode, Time, TimeType, Tz, check_fsp_with_mode,
             duration::{
                 MAX_HOUR_PART, MAX_MINUTE_PART, MAX_NANOS, MAX_NANOS_PART, MAX_SECOND_PART,
                 NANOS_PER_SEC,
@@ -1027,9 +1027,11 @@ fn add_date(
         datetime.add_months(month)?;
     }
 
-    if let Ok(fsp) = check_fsp(result_fsp) {
-        datetime.set_fsp(fsp);
+    let (fsp, warning) = check_fsp_with_mode(result_fsp, FspMode::Clamp)?;
+    if let Some(warning) = warning {
+        ctx.warnings.append_warning(warning);
     }
+    datetime.set_fsp(fsp);
 
     Ok(datetime)
 }
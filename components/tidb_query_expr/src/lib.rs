@@ -45,7 +45,7 @@ pub mod impl_vec;
 use tidb_query_common::Result;
 #[allow(unused_imports)]
 use tidb_query_datatype::{
-    Charset, Collation, FieldTypeAccessor, FieldTypeFlag,
+    Charset, Collation, EvalType, FieldTypeAccessor, FieldTypeFlag,
     codec::{
         collation::{
             Charset as _, Collator, LikePatternMode,
@@ -83,10 +83,31 @@ fn map_from_binary_fn_sig(expr: &Expr) -> Result<RpnFnMeta> {
     })
 }
 
-fn map_string_compare_sig<Cmp: CmpOp>(ret_field_type: &FieldType) -> Result<RpnFnMeta> {
+fn map_string_compare_sig<Cmp: CmpOp>(
+    ret_field_type: &FieldType,
+    children: &[Expr],
+) -> Result<RpnFnMeta> {
+    // `enum_col = 'Green'` and `set_col = 'a,b'` are pushed down with the same
+    // `*String` signatures as plain string comparisons, so check the actual
+    // argument types here rather than introducing dedicated signatures.
+    for child in children {
+        match box_try!(EvalType::try_from(child.get_field_type().as_accessor().tp())) {
+            EvalType::Enum => return Ok(compare_enum_fn_meta::<Cmp>()),
+            EvalType::Set => return Ok(compare_set_fn_meta::<Cmp>()),
+            _ => {}
+        }
+    }
+    // `BINARY` is set on the comparison's own field type when one side was
+    // cast to binary (e.g. `a = CAST(b AS BINARY)`), which forces the
+    // comparison to ignore PAD behavior even under a padding collation.
+    let force_no_pad = ret_field_type.flag().contains(FieldTypeFlag::BINARY);
     Ok(match_template_collator! {
         TT, match ret_field_type.as_accessor().collation().map_err(tidb_query_datatype::codec::Error::from)? {
-            Collation::TT => compare_bytes_fn_meta::<TT, Cmp>()
+            Collation::TT => if force_no_pad {
+                compare_bytes_force_no_pad_fn_meta::<TT, Cmp>()
+            } else {
+                compare_bytes_fn_meta::<TT, Cmp>()
+            }
         }
     })
 }
@@ -523,7 +544,7 @@ fn map_expr_node_to_rpn_func(expr: &Expr) -> Result<RpnFnMeta> {
         ScalarFuncSig::LtInt => map_int_sig(value, children, compare_mapper::<CmpOpLt>)?,
         ScalarFuncSig::LtReal => compare_fn_meta::<BasicComparer<Real, CmpOpLt>>(),
         ScalarFuncSig::LtDecimal => compare_fn_meta::<BasicComparer<Decimal, CmpOpLt>>(),
-        ScalarFuncSig::LtString => map_string_compare_sig::<CmpOpLt>(ft)?,
+        ScalarFuncSig::LtString => map_string_compare_sig::<CmpOpLt>(ft, children)?,
         ScalarFuncSig::LtTime => compare_fn_meta::<BasicComparer<DateTime, CmpOpLt>>(),
         ScalarFuncSig::LtDuration => compare_fn_meta::<BasicComparer<Duration, CmpOpLt>>(),
         ScalarFuncSig::LtJson => compare_json_fn_meta::<CmpOpLt>(),
@@ -531,7 +552,7 @@ fn map_expr_node_to_rpn_func(expr: &Expr) -> Result<RpnFnMeta> {
         ScalarFuncSig::LeInt => map_int_sig(value, children, compare_mapper::<CmpOpLe>)?,
         ScalarFuncSig::LeReal => compare_fn_meta::<BasicComparer<Real, CmpOpLe>>(),
         ScalarFuncSig::LeDecimal => compare_fn_meta::<BasicComparer<Decimal, CmpOpLe>>(),
-        ScalarFuncSig::LeString => map_string_compare_sig::<CmpOpLe>(ft)?,
+        ScalarFuncSig::LeString => map_string_compare_sig::<CmpOpLe>(ft, children)?,
         ScalarFuncSig::LeTime => compare_fn_meta::<BasicComparer<DateTime, CmpOpLe>>(),
         ScalarFuncSig::LeDuration => compare_fn_meta::<BasicComparer<Duration, CmpOpLe>>(),
         ScalarFuncSig::LeJson => compare_json_fn_meta::<CmpOpLe>(),
@@ -559,7 +580,7 @@ fn map_expr_node_to_rpn_func(expr: &Expr) -> Result<RpnFnMeta> {
         ScalarFuncSig::GtInt => map_int_sig(value, children, compare_mapper::<CmpOpGt>)?,
         ScalarFuncSig::GtReal => compare_fn_meta::<BasicComparer<Real, CmpOpGt>>(),
         ScalarFuncSig::GtDecimal => compare_fn_meta::<BasicComparer<Decimal, CmpOpGt>>(),
-        ScalarFuncSig::GtString => map_string_compare_sig::<CmpOpGt>(ft)?,
+        ScalarFuncSig::GtString => map_string_compare_sig::<CmpOpGt>(ft, children)?,
         ScalarFuncSig::GtTime => compare_fn_meta::<BasicComparer<DateTime, CmpOpGt>>(),
         ScalarFuncSig::GtDuration => compare_fn_meta::<BasicComparer<Duration, CmpOpGt>>(),
         ScalarFuncSig::GtJson => compare_json_fn_meta::<CmpOpGt>(),
@@ -567,7 +588,7 @@ fn map_expr_node_to_rpn_func(expr: &Expr) -> Result<RpnFnMeta> {
         ScalarFuncSig::GeInt => map_int_sig(value, children, compare_mapper::<CmpOpGe>)?,
         ScalarFuncSig::GeReal => compare_fn_meta::<BasicComparer<Real, CmpOpGe>>(),
         ScalarFuncSig::GeDecimal => compare_fn_meta::<BasicComparer<Decimal, CmpOpGe>>(),
-        ScalarFuncSig::GeString => map_string_compare_sig::<CmpOpGe>(ft)?,
+        ScalarFuncSig::GeString => map_string_compare_sig::<CmpOpGe>(ft, children)?,
         ScalarFuncSig::GeTime => compare_fn_meta::<BasicComparer<DateTime, CmpOpGe>>(),
         ScalarFuncSig::GeDuration => compare_fn_meta::<BasicComparer<Duration, CmpOpGe>>(),
         ScalarFuncSig::GeJson => compare_json_fn_meta::<CmpOpGe>(),
@@ -575,7 +596,7 @@ fn map_expr_node_to_rpn_func(expr: &Expr) -> Result<RpnFnMeta> {
         ScalarFuncSig::NeInt => map_int_sig(value, children, compare_mapper::<CmpOpNe>)?,
         ScalarFuncSig::NeReal => compare_fn_meta::<BasicComparer<Real, CmpOpNe>>(),
         ScalarFuncSig::NeDecimal => compare_fn_meta::<BasicComparer<Decimal, CmpOpNe>>(),
-        ScalarFuncSig::NeString => map_string_compare_sig::<CmpOpNe>(ft)?,
+        ScalarFuncSig::NeString => map_string_compare_sig::<CmpOpNe>(ft, children)?,
         ScalarFuncSig::NeTime => compare_fn_meta::<BasicComparer<DateTime, CmpOpNe>>(),
         ScalarFuncSig::NeDuration => compare_fn_meta::<BasicComparer<Duration, CmpOpNe>>(),
         ScalarFuncSig::NeJson => compare_json_fn_meta::<CmpOpNe>(),
@@ -583,7 +604,7 @@ fn map_expr_node_to_rpn_func(expr: &Expr) -> Result<RpnFnMeta> {
         ScalarFuncSig::EqInt => map_int_sig(value, children, compare_mapper::<CmpOpEq>)?,
         ScalarFuncSig::EqReal => compare_fn_meta::<BasicComparer<Real, CmpOpEq>>(),
         ScalarFuncSig::EqDecimal => compare_fn_meta::<BasicComparer<Decimal, CmpOpEq>>(),
-        ScalarFuncSig::EqString => map_string_compare_sig::<CmpOpEq>(ft)?,
+        ScalarFuncSig::EqString => map_string_compare_sig::<CmpOpEq>(ft, children)?,
         ScalarFuncSig::EqTime => compare_fn_meta::<BasicComparer<DateTime, CmpOpEq>>(),
         ScalarFuncSig::EqDuration => compare_fn_meta::<BasicComparer<Duration, CmpOpEq>>(),
         ScalarFuncSig::EqJson => compare_json_fn_meta::<CmpOpEq>(),
@@ -591,7 +612,7 @@ fn map_expr_node_to_rpn_func(expr: &Expr) -> Result<RpnFnMeta> {
         ScalarFuncSig::NullEqInt => map_int_sig(value, children, compare_mapper::<CmpOpNullEq>)?,
         ScalarFuncSig::NullEqReal => compare_fn_meta::<BasicComparer<Real, CmpOpNullEq>>(),
         ScalarFuncSig::NullEqDecimal => compare_fn_meta::<BasicComparer<Decimal, CmpOpNullEq>>(),
-        ScalarFuncSig::NullEqString => map_string_compare_sig::<CmpOpNullEq>(ft)?,
+        ScalarFuncSig::NullEqString => map_string_compare_sig::<CmpOpNullEq>(ft, children)?,
         ScalarFuncSig::NullEqTime => compare_fn_meta::<BasicComparer<DateTime, CmpOpNullEq>>(),
         ScalarFuncSig::NullEqDuration => compare_fn_meta::<BasicComparer<Duration, CmpOpNullEq>>(),
         ScalarFuncSig::NullEqJson => compare_json_fn_meta::<CmpOpNullEq>(),
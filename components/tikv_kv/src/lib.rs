@@ -27,8 +27,9 @@ mod stats;
 use std::{
     borrow::Cow,
     cell::UnsafeCell,
-    error,
+    error, fmt, mem,
     num::NonZeroU64,
+    ops::Deref,
     ptr, result,
     sync::Arc,
     time::{Duration, Instant},
@@ -52,7 +53,7 @@ use kvproto::{
 use pd_client::BucketMeta;
 use raftstore::{
     SeekRegionCallback,
-    store::{PessimisticLockPair, TxnExt},
+    store::{IterMetrics, PessimisticLockPair, TxnExt},
 };
 use thiserror::Error;
 use tikv_util::{
@@ -292,6 +293,69 @@ impl WriteData {
     pub fn set_avoid_batch(&mut self, avoid_batch: bool) {
         self.avoid_batch = avoid_batch
     }
+
+    /// Splits `modifies` into consecutive chunks of at most `threshold`
+    /// bytes each (by [`Modify::size`]), so that a batch too large to fit in
+    /// a single raft entry can be proposed as several entries instead of
+    /// being rejected outright by raftstore's `raft-entry-max-size`.
+    ///
+    /// Each returned `WriteData` keeps this batch's `deadline`,
+    /// `disk_full_opt` and `avoid_batch`, since those apply per-proposal.
+    /// `extra.allowed_in_flashback` is copied to every chunk too, as every
+    /// proposal from this batch must pass the same flashback check at apply
+    /// time. `extra.old_values` is kept only on the last chunk so old-value
+    /// tracking (used by CDC) isn't reported once per chunk.
+    ///
+    /// Never splits a 1PC batch: 1PC's atomicity comes from being a single
+    /// raft entry, so `extra.one_pc` batches are always returned whole.
+    ///
+    /// A single `Modify` already over `threshold` is kept in its own chunk
+    /// rather than being further split, since `Modify` has no smaller unit.
+    /// Returns `vec![self]` unchanged when `threshold` is `0` (disabled) or
+    /// the whole batch already fits.
+    pub fn split_by_size(self, threshold: usize) -> Vec<WriteData> {
+        if threshold == 0 || self.extra.one_pc || self.size() <= threshold {
+            return vec![self];
+        }
+        let WriteData { modifies, extra, deadline, disk_full_opt, avoid_batch } = self;
+
+        let mut batches: Vec<Vec<Modify>> = Vec::new();
+        let mut current = Vec::new();
+        let mut current_size = 0;
+        for m in modifies {
+            let m_size = m.size();
+            if !current.is_empty() && current_size + m_size > threshold {
+                batches.push(mem::take(&mut current));
+                current_size = 0;
+            }
+            current_size += m_size;
+            current.push(m);
+        }
+        if !current.is_empty() {
+            batches.push(current);
+        }
+
+        let last_idx = batches.len() - 1;
+        batches
+            .into_iter()
+            .enumerate()
+            .map(|(i, modifies)| WriteData {
+                modifies,
+                extra: TxnExtra {
+                    old_values: if i == last_idx {
+                        extra.old_values.clone()
+                    } else {
+                        Default::default()
+                    },
+                    one_pc: false,
+                    allowed_in_flashback: extra.allowed_in_flashback,
+                },
+                deadline,
+                disk_full_opt,
+                avoid_batch,
+            })
+            .collect()
+    }
 }
 
 /// Events that can subscribed from the `WriteSubscriber`.
@@ -494,6 +558,48 @@ pub trait Engine: Send + Clone + 'static {
     }
 }
 
+/// The value returned by [`Snapshot::get_pinned_cf`].
+///
+/// Either an owned copy, or a pinned engine slice kept alive for as long as
+/// this `PinnedValue` lives (e.g. RocksDB's `PinnableSlice`, wrapped by
+/// `engine_traits::DbVector`). Callers that only need to read the bytes once,
+/// such as copying them into a response buffer, can do so without forcing an
+/// extra allocation.
+pub enum PinnedValue {
+    Owned(Value),
+    Pinned(Box<dyn Deref<Target = [u8]> + Send + Sync>),
+}
+
+impl Deref for PinnedValue {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            PinnedValue::Owned(v) => v,
+            PinnedValue::Pinned(v) => v,
+        }
+    }
+}
+
+impl fmt::Debug for PinnedValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("PinnedValue").field(&&**self).finish()
+    }
+}
+
+impl PartialEq for PinnedValue {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl PinnedValue {
+    /// Copies the bytes into an owned `Value`.
+    pub fn to_vec(&self) -> Value {
+        (**self).to_vec()
+    }
+}
+
 /// A Snapshot is a consistent view of the underlying engine at a given point in
 /// time.
 ///
@@ -516,6 +622,40 @@ pub trait Snapshot: Sync + Send + Clone {
     /// in `opts`
     fn get_cf_opt(&self, opts: ReadOptions, cf: CfName, key: &Key) -> Result<Option<Value>>;
 
+    /// Get the value associated with `key` in `cf` column family, avoiding a
+    /// copy into an owned `Value` when the underlying engine can hand back a
+    /// pinned slice instead.
+    ///
+    /// The default implementation just copies, via [`Snapshot::get_cf_opt`];
+    /// implementations backed by an engine that can pin the read (e.g.
+    /// RocksDB's `PinnableSlice`) should override this to avoid the copy.
+    fn get_pinned_cf(&self, cf: CfName, key: &Key) -> Result<Option<PinnedValue>> {
+        Ok(self
+            .get_cf_opt(ReadOptions::default(), cf, key)?
+            .map(PinnedValue::Owned))
+    }
+
+    /// Get the values associated with `keys` in `cf` column family. The
+    /// returned `Vec` has the same length and order as `keys`; a missing key
+    /// maps to `None`.
+    ///
+    /// The default implementation simply loops over [`Snapshot::get_cf`].
+    /// Implementations that can validate a whole key batch up front or issue
+    /// a single call into the underlying engine should override this.
+    fn multi_get_cf(&self, cf: CfName, keys: &[Key]) -> Result<Vec<Option<Value>>> {
+        keys.iter().map(|key| self.get_cf(cf, key)).collect()
+    }
+
+    /// Cheaply checks, using the column family's bloom filter, whether `key`
+    /// might be present in `cf`. A `false` result is authoritative and lets
+    /// callers skip a real read; `true` is inconclusive.
+    ///
+    /// Conservative by default: always reports the key may exist.
+    #[inline]
+    fn key_may_exist_cf(&self, _cf: CfName, _key: &Key) -> Result<bool> {
+        Ok(true)
+    }
+
     fn iter(&self, cf: CfName, iter_opt: IterOptions) -> Result<Self::Iter>;
 
     // The minimum key this snapshot can retrieve.
@@ -568,11 +708,34 @@ pub trait SnapshotExt {
         None
     }
 
+    /// Cumulative seek/next churn of the iterators produced by this
+    /// snapshot, for diagnosing slow requests. `None` if the engine does
+    /// not track iterator-level metrics.
+    fn iter_metrics(&self) -> Option<IterMetrics> {
+        None
+    }
+
     /// Whether the snapshot acquired hit the in memory engine. It always
     /// returns false if the in memory engine is disabled.
     fn in_memory_engine_hit(&self) -> bool {
         false
     }
+
+    /// Validates that every range in `ranges` lies within the bounds this
+    /// snapshot can actually read, in a single pass, instead of letting
+    /// each range's own iterator seek discover an out-of-bounds range at
+    /// scan time.
+    ///
+    /// `reverse` should reflect the scan direction the caller is about to
+    /// use; it only affects which bound of an out-of-range range is
+    /// reported first, matching the order a reverse scan's iterator would
+    /// actually seek through.
+    ///
+    /// The default implementation has no bounds to check against and
+    /// always succeeds.
+    fn check_key_ranges(&self, _ranges: &[KeyRange], _reverse: bool) -> Result<()> {
+        Ok(())
+    }
 }
 
 pub struct DummySnapshotExt;
@@ -1496,4 +1659,75 @@ mod unit_tests {
             expect_requests
         )
     }
+
+    fn put(key: &str, value_len: usize) -> Modify {
+        Modify::Put(
+            CF_DEFAULT,
+            Key::from_raw(key.as_bytes()),
+            vec![0u8; value_len],
+        )
+    }
+
+    #[test]
+    fn test_split_by_size_disabled_or_already_small() {
+        let data = WriteData::from_modifies(vec![put("k1", 10), put("k2", 10)]);
+        assert_eq!(data.split_by_size(0).len(), 1);
+
+        let data = WriteData::from_modifies(vec![put("k1", 10), put("k2", 10)]);
+        let size = data.size();
+        assert_eq!(data.split_by_size(size).len(), 1);
+    }
+
+    #[test]
+    fn test_split_by_size_splits_into_consecutive_chunks() {
+        let modifies = vec![put("k1", 10), put("k2", 10), put("k3", 10)];
+        let total_size = modifies.iter().map(Modify::size).sum::<usize>();
+        let data = WriteData::from_modifies(modifies.clone());
+
+        // A threshold smaller than the whole batch but big enough for two
+        // modifies splits it into two chunks, preserving order.
+        let per_modify_size = modifies[0].size();
+        let chunks = data.split_by_size(per_modify_size * 2);
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0].modifies, modifies[0..2]);
+        assert_eq!(chunks[1].modifies, modifies[2..3]);
+        assert_eq!(
+            chunks.iter().map(WriteData::size).sum::<usize>(),
+            total_size
+        );
+    }
+
+    #[test]
+    fn test_split_by_size_never_splits_a_single_oversized_modify() {
+        let data = WriteData::from_modifies(vec![put("k1", 100)]);
+        let chunks = data.split_by_size(1);
+        assert_eq!(chunks.len(), 1);
+    }
+
+    #[test]
+    fn test_split_by_size_never_splits_1pc() {
+        let mut data = WriteData::from_modifies(vec![put("k1", 10), put("k2", 10)]);
+        data.extra.one_pc = true;
+        let chunks = data.split_by_size(1);
+        assert_eq!(chunks.len(), 1);
+        assert!(chunks[0].extra.one_pc);
+    }
+
+    #[test]
+    fn test_split_by_size_keeps_old_values_only_on_last_chunk() {
+        let mut data = WriteData::from_modifies(vec![put("k1", 10), put("k2", 10)]);
+        data.extra.allowed_in_flashback = true;
+        data.extra.old_values.insert(
+            Key::from_raw(b"k1").append_ts(1.into()),
+            (txn_types::OldValue::None, None),
+        );
+        let per_modify_size = data.modifies[0].size();
+        let chunks = data.split_by_size(per_modify_size);
+        assert_eq!(chunks.len(), 2);
+        assert!(chunks[0].extra.old_values.is_empty());
+        assert!(!chunks[1].extra.old_values.is_empty());
+        // The flashback flag applies per-proposal, so every chunk keeps it.
+        assert!(chunks[0].extra.allowed_in_flashback);
+        assert!(chunks[1].extra.allowed_in_flashback);
+    }
 }
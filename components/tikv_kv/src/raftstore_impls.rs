@@ -2,17 +2,20 @@
 
 use std::{num::NonZeroU64, sync::Arc};
 
-use engine_traits::{CfName, IterOptions, Peekable, ReadOptions, Snapshot};
-use kvproto::kvrpcpb::ExtraOp as TxnExtraOp;
+use engine_traits::{
+    CfName, Error as EngineError, IterOptions, Peekable, ReadOptions, Snapshot,
+    util::check_key_in_range,
+};
+use kvproto::kvrpcpb::{ExtraOp as TxnExtraOp, KeyRange};
 use pd_client::BucketMeta;
 use raftstore::{
     Error as RaftServerError,
-    store::{RegionIterator, RegionSnapshot, TxnExt},
+    store::{IterMetrics, RegionIterator, RegionSnapshot, TxnExt},
 };
 use txn_types::{Key, Value};
 
 use crate::{
-    self as kv, Error, Error as KvError, ErrorInner, Iterator as EngineIterator,
+    self as kv, Error, Error as KvError, ErrorInner, Iterator as EngineIterator, PinnedValue,
     Snapshot as EngineSnapshot, SnapshotExt,
 };
 
@@ -62,9 +65,50 @@ impl<S: Snapshot> SnapshotExt for RegionSnapshotExt<'_, S> {
         self.snapshot.bucket_meta.clone()
     }
 
+    fn iter_metrics(&self) -> Option<IterMetrics> {
+        Some(self.snapshot.iter_metrics())
+    }
+
     fn in_memory_engine_hit(&self) -> bool {
         self.snapshot.get_snapshot().in_memory_engine_hit()
     }
+
+    fn check_key_ranges(&self, ranges: &[KeyRange], reverse: bool) -> kv::Result<()> {
+        let region_id = self.snapshot.get_region().id;
+        let region_start = self.snapshot.get_start_key();
+        let region_end = self.snapshot.get_end_key();
+
+        let check_end = |end: &[u8]| -> engine_traits::Result<()> {
+            if region_end.is_empty() || end <= region_end {
+                Ok(())
+            } else {
+                Err(EngineError::NotInRange {
+                    key: end.to_vec(),
+                    region_id,
+                    start: region_start.to_vec(),
+                    end: region_end.to_vec(),
+                })
+            }
+        };
+
+        for range in ranges {
+            let (start, end) = (range.get_start_key(), range.get_end_key());
+            if start == end {
+                // An empty range reads no keys, so it's never out of
+                // bounds, even if it sits exactly on (or past) a region
+                // boundary.
+                continue;
+            }
+            if reverse {
+                box_try!(check_end(end));
+                box_try!(check_key_in_range(start, region_id, region_start, region_end));
+            } else {
+                box_try!(check_key_in_range(start, region_id, region_start, region_end));
+                box_try!(check_end(end));
+            }
+        }
+        Ok(())
+    }
 }
 
 impl<S: Snapshot> EngineSnapshot for RegionSnapshot<S> {
@@ -95,6 +139,38 @@ impl<S: Snapshot> EngineSnapshot for RegionSnapshot<S> {
         Ok(v.map(|v| v.to_vec()))
     }
 
+    fn get_pinned_cf(&self, cf: CfName, key: &Key) -> kv::Result<Option<PinnedValue>> {
+        fail_point!("raftkv_snapshot_get_cf", |_| Err(box_err!(
+            "injected error for get_cf"
+        )));
+        let v = box_try!(self.get_value_cf_opt(&ReadOptions::default(), cf, key.as_encoded()));
+        Ok(v.map(|v| PinnedValue::Pinned(Box::new(v))))
+    }
+
+    fn multi_get_cf(&self, cf: CfName, keys: &[Key]) -> kv::Result<Vec<Option<Value>>> {
+        fail_point!("raftkv_snapshot_get_cf", |_| Err(box_err!(
+            "injected error for get_cf"
+        )));
+        // Validate the whole batch against the region bounds once, instead of
+        // paying `RegionSnapshot::get_value_cf_opt`'s per-key check on every
+        // lookup below.
+        for key in keys {
+            box_try!(check_key_in_range(
+                key.as_encoded(),
+                self.get_region().id,
+                self.get_start_key(),
+                self.get_end_key(),
+            ));
+        }
+        keys.iter()
+            .map(|key| {
+                let data_key = keys::data_key(key.as_encoded());
+                let v = box_try!(self.get_snapshot().get_value_cf(cf, &data_key));
+                Ok(v.map(|v| v.to_vec()))
+            })
+            .collect()
+    }
+
     fn iter(&self, cf: CfName, iter_opt: IterOptions) -> kv::Result<Self::Iter> {
         fail_point!("raftkv_snapshot_iter", |_| Err(box_err!(
             "injected error for iter_cf"
@@ -102,6 +178,15 @@ impl<S: Snapshot> EngineSnapshot for RegionSnapshot<S> {
         RegionSnapshot::iter(self, cf, iter_opt).map_err(kv::Error::from)
     }
 
+    fn key_may_exist_cf(&self, cf: CfName, key: &Key) -> kv::Result<bool> {
+        let exists = box_try!(self.key_may_exist_cf_opt(
+            &ReadOptions::default(),
+            cf,
+            key.as_encoded()
+        ));
+        Ok(exists)
+    }
+
     #[inline]
     fn lower_bound(&self) -> Option<&[u8]> {
         Some(self.get_start_key())
@@ -164,3 +249,196 @@ impl<S: Snapshot> EngineIterator for RegionIterator<S> {
         RegionIterator::value(self)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use engine_test::kv::{KvTestEngine, KvTestSnapshot, new_temp_engine};
+    use engine_traits::{CF_DEFAULT, Engines, RaftEngine, SyncMutable};
+    use kvproto::metapb::{Peer, Region};
+    use raftstore::store::{PeerStorage, local_metrics::RaftMetrics};
+    use tempfile::Builder;
+    use tikv_util::worker;
+
+    use super::*;
+
+    fn new_peer_storage<ER: RaftEngine>(
+        engines: Engines<KvTestEngine, ER>,
+        r: &Region,
+    ) -> PeerStorage<KvTestEngine, ER> {
+        let (region_sched, _) = worker::dummy_scheduler();
+        let (raftlog_fetch_sched, _) = worker::dummy_scheduler();
+        PeerStorage::new(
+            engines,
+            r,
+            region_sched,
+            raftlog_fetch_sched,
+            0,
+            "".to_owned(),
+            &RaftMetrics::new(false),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_multi_get_cf_matches_looped_get_cf() {
+        let path = Builder::new().prefix("test-tikv-kv").tempdir().unwrap();
+        let engines = new_temp_engine(&path);
+        let mut r = Region::default();
+        r.mut_peers().push(Peer::default());
+        r.set_id(10);
+        r.set_start_key(b"a2".to_vec());
+        r.set_end_key(b"a7".to_vec());
+        for (k, v) in [(b"a1", b"v1"), (b"a3", b"v3"), (b"a5", b"v5"), (b"a7", b"v7")] {
+            engines.kv.put(&keys::data_key(k), v).unwrap();
+        }
+        let store = new_peer_storage(engines, &r);
+        let snap = RegionSnapshot::<KvTestSnapshot>::new(&store);
+
+        let keys = vec![Key::from_encoded(b"a3".to_vec()), Key::from_encoded(b"a5".to_vec())];
+        let got = EngineSnapshot::multi_get_cf(&snap, CF_DEFAULT, &keys).unwrap();
+        let want: Vec<_> = keys
+            .iter()
+            .map(|k| EngineSnapshot::get_cf(&snap, CF_DEFAULT, k).unwrap())
+            .collect();
+        assert_eq!(got, want);
+        assert_eq!(got, vec![Some(b"v3".to_vec()), Some(b"v5".to_vec())]);
+    }
+
+    #[test]
+    fn test_get_pinned_cf_matches_get_cf() {
+        let path = Builder::new().prefix("test-tikv-kv").tempdir().unwrap();
+        let engines = new_temp_engine(&path);
+        let mut r = Region::default();
+        r.mut_peers().push(Peer::default());
+        r.set_id(10);
+        r.set_start_key(b"a2".to_vec());
+        r.set_end_key(b"a7".to_vec());
+        engines.kv.put(&keys::data_key(b"a3"), b"v3").unwrap();
+        let store = new_peer_storage(engines, &r);
+        let snap = RegionSnapshot::<KvTestSnapshot>::new(&store);
+
+        let key = Key::from_encoded(b"a3".to_vec());
+        let pinned = EngineSnapshot::get_pinned_cf(&snap, CF_DEFAULT, &key).unwrap();
+        let plain = EngineSnapshot::get_cf(&snap, CF_DEFAULT, &key).unwrap();
+        assert_eq!(pinned.as_deref().map(|v| v.to_vec()), plain);
+
+        let missing = Key::from_encoded(b"a4".to_vec());
+        assert_eq!(EngineSnapshot::get_pinned_cf(&snap, CF_DEFAULT, &missing).unwrap(), None);
+    }
+
+    #[test]
+    fn test_get_pinned_cf_readable_after_snapshot_variable_shadowed() {
+        let path = Builder::new().prefix("test-tikv-kv").tempdir().unwrap();
+        let engines = new_temp_engine(&path);
+        let mut r = Region::default();
+        r.mut_peers().push(Peer::default());
+        r.set_id(10);
+        r.set_start_key(b"a2".to_vec());
+        r.set_end_key(b"a7".to_vec());
+        engines.kv.put(&keys::data_key(b"a3"), b"v3").unwrap();
+        let store = new_peer_storage(engines, &r);
+
+        let snap = RegionSnapshot::<KvTestSnapshot>::new(&store);
+        let key = Key::from_encoded(b"a3".to_vec());
+        let pinned = EngineSnapshot::get_pinned_cf(&snap, CF_DEFAULT, &key)
+            .unwrap()
+            .unwrap();
+        // Shadow the original binding with a fresh snapshot: `pinned` must not
+        // depend on `snap` remaining in scope to still be readable.
+        let snap = RegionSnapshot::<KvTestSnapshot>::new(&store);
+        drop(snap);
+        assert_eq!(&*pinned, b"v3".as_ref());
+    }
+
+    #[test]
+    fn test_multi_get_cf_rejects_out_of_range_key_in_batch() {
+        let path = Builder::new().prefix("test-tikv-kv").tempdir().unwrap();
+        let engines = new_temp_engine(&path);
+        let mut r = Region::default();
+        r.mut_peers().push(Peer::default());
+        r.set_id(10);
+        r.set_start_key(b"a2".to_vec());
+        r.set_end_key(b"a7".to_vec());
+        for (k, v) in [(b"a1", b"v1"), (b"a3", b"v3"), (b"a9", b"v9")] {
+            engines.kv.put(&keys::data_key(k), v).unwrap();
+        }
+        let store = new_peer_storage(engines, &r);
+        let snap = RegionSnapshot::<KvTestSnapshot>::new(&store);
+
+        // "a3" is in range, "a9" (and "a1") is not: the whole batch must fail,
+        // matching what looping `get_cf` over the same keys would do.
+        let keys = vec![Key::from_encoded(b"a3".to_vec()), Key::from_encoded(b"a9".to_vec())];
+        assert!(EngineSnapshot::multi_get_cf(&snap, CF_DEFAULT, &keys).is_err());
+    }
+
+    fn new_fabricated_region_snapshot(
+        start: &[u8],
+        end: &[u8],
+    ) -> RegionSnapshot<KvTestSnapshot> {
+        let path = Builder::new().prefix("test-tikv-kv").tempdir().unwrap();
+        let engines = new_temp_engine(&path);
+        let mut r = Region::default();
+        r.mut_peers().push(Peer::default());
+        r.set_id(10);
+        r.set_start_key(start.to_vec());
+        r.set_end_key(end.to_vec());
+        let store = new_peer_storage(engines, &r);
+        RegionSnapshot::<KvTestSnapshot>::new(&store)
+    }
+
+    fn key_range(start: &[u8], end: &[u8]) -> KeyRange {
+        let mut range = KeyRange::default();
+        range.set_start_key(start.to_vec());
+        range.set_end_key(end.to_vec());
+        range
+    }
+
+    #[test]
+    fn test_check_key_ranges_accepts_ranges_within_region() {
+        let snap = new_fabricated_region_snapshot(b"a2", b"a7");
+        let ranges = vec![key_range(b"a2", b"a4"), key_range(b"a4", b"a7")];
+        assert!(snap.ext().check_key_ranges(&ranges, false).is_ok());
+        assert!(snap.ext().check_key_ranges(&ranges, true).is_ok());
+    }
+
+    #[test]
+    fn test_check_key_ranges_rejects_range_starting_before_region() {
+        let snap = new_fabricated_region_snapshot(b"a2", b"a7");
+        let ranges = vec![key_range(b"a1", b"a5")];
+        assert!(snap.ext().check_key_ranges(&ranges, false).is_err());
+    }
+
+    #[test]
+    fn test_check_key_ranges_rejects_range_ending_past_region() {
+        let snap = new_fabricated_region_snapshot(b"a2", b"a7");
+        let ranges = vec![key_range(b"a3", b"a9")];
+        assert!(snap.ext().check_key_ranges(&ranges, false).is_err());
+        assert!(snap.ext().check_key_ranges(&ranges, true).is_err());
+    }
+
+    #[test]
+    fn test_check_key_ranges_accepts_range_touching_exclusive_region_end() {
+        // The range's end, like the region's own end key, is exclusive: a
+        // range ending exactly at the region's end key doesn't read any key
+        // outside the region, so it must not be rejected.
+        let snap = new_fabricated_region_snapshot(b"a2", b"a7");
+        let ranges = vec![key_range(b"a2", b"a7")];
+        assert!(snap.ext().check_key_ranges(&ranges, false).is_ok());
+    }
+
+    #[test]
+    fn test_check_key_ranges_accepts_unbounded_region_end() {
+        let snap = new_fabricated_region_snapshot(b"a2", b"");
+        let ranges = vec![key_range(b"a9", b"b9")];
+        assert!(snap.ext().check_key_ranges(&ranges, false).is_ok());
+    }
+
+    #[test]
+    fn test_check_key_ranges_accepts_empty_range_on_boundary() {
+        // An empty range reads no keys, so it's never out of bounds, even
+        // when it sits exactly on (or past) a region boundary.
+        let snap = new_fabricated_region_snapshot(b"a2", b"a7");
+        let ranges = vec![key_range(b"a7", b"a7"), key_range(b"a9", b"a9")];
+        assert!(snap.ext().check_key_ranges(&ranges, false).is_ok());
+    }
+}
@@ -228,6 +228,12 @@ pub struct Statistics {
     // When getting data from default cf, we can check write cf statistics to decide which method
     // should be used to get the data.
     load_data_hint: LoadDataHintStatistics,
+
+    // Number of times a run of pending default-CF lookups was served by a single batched
+    // prefetch instead of one point get per key, and the total number of keys covered by
+    // those batches.
+    pub prefetch_batches: usize,
+    pub prefetch_keys: usize,
 }
 
 #[derive(Default, Debug)]
@@ -282,6 +288,8 @@ impl Statistics {
         self.write.add(&other.write);
         self.data.add(&other.data);
         self.processed_size += other.processed_size;
+        self.prefetch_batches += other.prefetch_batches;
+        self.prefetch_keys += other.prefetch_keys;
     }
 
     /// Deprecated
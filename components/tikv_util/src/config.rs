@@ -1,9 +1,11 @@
 // Copyright 2016 TiKV Project Authors. Licensed under Apache-2.0.
 
 use std::{
+    cell::Cell,
+    collections::{HashMap, VecDeque},
     error::Error,
     fmt::{self, Write},
-    fs,
+    fs, io,
     net::{SocketAddrV4, SocketAddrV6},
     ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Sub, SubAssign},
     path::{Path, PathBuf},
@@ -16,11 +18,14 @@ use std::{
 };
 
 use chrono::{
-    DateTime, FixedOffset, Local, NaiveTime, TimeZone, Timelike,
+    DateTime, Duration as ChronoDuration, FixedOffset, Local, NaiveTime, Offset, TimeZone,
+    Timelike,
     format::{self, Fixed, Item, Parsed},
 };
+use fail::fail_point;
 pub use heck::KebabCase;
 use online_config::ConfigValue;
+use regex::Regex;
 use serde::{
     Deserialize, Deserializer, Serialize, Serializer,
     de::{self, Unexpected, Visitor},
@@ -31,20 +36,74 @@ use thiserror::Error;
 use super::time::Instant;
 use crate::{slow_log, sys::SysQuota};
 
+/// Structured detail of a single invalid configuration value: the field that
+/// failed, what was expected of it, and what was actually supplied.
+///
+/// Carried by [`ConfigError::Limit`], [`ConfigError::Address`] and
+/// [`ConfigError::Value`] so that machine callers — the status server's
+/// config-update API, or TiDB's config management — can tell apart the kind
+/// of failure without parsing the `Display` message meant for logs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct InvalidConfigValue {
+    /// Name of the config field that failed, e.g. `"raftstore.addr"`.
+    pub field: String,
+    /// Human-readable description of the constraint that was violated, e.g.
+    /// `"a readable byte size (e.g. \"1KiB\")"`.
+    pub constraint: String,
+    /// Rendering of the value that was actually supplied.
+    pub got: String,
+}
+
+impl InvalidConfigValue {
+    pub fn new(
+        field: impl Into<String>,
+        constraint: impl Into<String>,
+        got: impl Into<String>,
+    ) -> Self {
+        InvalidConfigValue {
+            field: field.into(),
+            constraint: constraint.into(),
+            got: got.into(),
+        }
+    }
+}
+
+impl fmt::Display for InvalidConfigValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid value for `{}`: expected {}, got `{}`",
+            self.field, self.constraint, self.got
+        )
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum ConfigError {
     #[error("{0}")]
-    Limit(String),
+    Limit(InvalidConfigValue),
     #[error("config address error: {0}")]
-    Address(String),
+    Address(InvalidConfigValue),
     #[error("store label error: {0}")]
     StoreLabels(String),
     #[error("config value error: {0}")]
-    Value(String),
+    Value(InvalidConfigValue),
     #[error("config fs: {0}")]
     FileSystem(String),
 }
 
+impl error_code::ErrorCodeExt for ConfigError {
+    fn error_code(&self) -> error_code::ErrorCode {
+        match self {
+            ConfigError::Limit(_) => error_code::config::LIMIT,
+            ConfigError::Address(_) => error_code::config::ADDRESS,
+            ConfigError::StoreLabels(_) => error_code::config::STORE_LABELS,
+            ConfigError::Value(_) => error_code::config::VALUE,
+            ConfigError::FileSystem(_) => error_code::config::FILE_SYSTEM,
+        }
+    }
+}
+
 const UNIT: u64 = 1;
 
 const BINARY_DATA_MAGNITUDE: u64 = 1024;
@@ -111,6 +170,54 @@ impl ReadableSize {
     pub fn as_mb_f64(self) -> f64 {
         self.0 as f64 / MIB as f64
     }
+
+    pub fn checked_add(self, rhs: ReadableSize) -> Option<ReadableSize> {
+        self.0.checked_add(rhs.0).map(ReadableSize)
+    }
+
+    pub fn checked_sub(self, rhs: ReadableSize) -> Option<ReadableSize> {
+        self.0.checked_sub(rhs.0).map(ReadableSize)
+    }
+
+    pub fn saturating_add(self, rhs: ReadableSize) -> ReadableSize {
+        ReadableSize(self.0.saturating_add(rhs.0))
+    }
+
+    pub fn saturating_sub(self, rhs: ReadableSize) -> ReadableSize {
+        ReadableSize(self.0.saturating_sub(rhs.0))
+    }
+
+    /// Renders the size as a plain byte count, e.g. `1073741825`, instead of
+    /// the human-readable unit form used by [`fmt::Display`].
+    ///
+    /// Unlike the human form, this never changes shape as the value crosses
+    /// a power-of-two boundary, which makes it suitable for diffing
+    /// machine-generated config dumps.
+    pub fn to_exact_string(&self) -> String {
+        self.0.to_string()
+    }
+}
+
+thread_local! {
+    // When set, `Serialize for ReadableSize` emits the exact byte count and
+    // `Serialize for ReadableDuration` emits the exact millisecond count,
+    // instead of their human-readable unit forms. Scoped with
+    // `with_exact_byte_sizes` so it never leaks past the call that needs it.
+    static SERIALIZE_EXACT_BYTE_SIZES: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Runs `f` with [`ReadableSize`] and [`ReadableDuration`] serialization
+/// switched to their exact-count forms (bytes, milliseconds) on the current
+/// thread, restoring the previous mode afterwards.
+///
+/// Intended for machine-readable config dumps (e.g. the status server's
+/// `/config` endpoint) where noisy unit changes across power-of-two or
+/// time-unit boundaries make diffs hard to read.
+pub fn with_exact_byte_sizes<R>(f: impl FnOnce() -> R) -> R {
+    let previous = SERIALIZE_EXACT_BYTE_SIZES.with(|flag| flag.replace(true));
+    let result = f();
+    SERIALIZE_EXACT_BYTE_SIZES.with(|flag| flag.set(previous));
+    result
 }
 
 impl Div<u64> for ReadableSize {
@@ -129,10 +236,42 @@ impl Div<ReadableSize> for ReadableSize {
     }
 }
 
+impl Add<ReadableSize> for ReadableSize {
+    type Output = ReadableSize;
+
+    fn add(self, rhs: ReadableSize) -> Self::Output {
+        debug_assert!(
+            self.checked_add(rhs).is_some(),
+            "overflow adding ReadableSize: {} + {}",
+            self,
+            rhs,
+        );
+        self.saturating_add(rhs)
+    }
+}
+
+impl AddAssign for ReadableSize {
+    fn add_assign(&mut self, rhs: ReadableSize) {
+        *self = *self + rhs;
+    }
+}
+
 impl Sub<ReadableSize> for ReadableSize {
     type Output = ReadableSize;
     fn sub(self, rhs: ReadableSize) -> Self::Output {
-        ReadableSize(self.0 - rhs.0)
+        debug_assert!(
+            self.checked_sub(rhs).is_some(),
+            "underflow subtracting ReadableSize: {} - {}",
+            self,
+            rhs,
+        );
+        self.saturating_sub(rhs)
+    }
+}
+
+impl SubAssign for ReadableSize {
+    fn sub_assign(&mut self, rhs: ReadableSize) {
+        *self = *self - rhs;
     }
 }
 
@@ -144,10 +283,23 @@ impl Mul<u64> for ReadableSize {
     }
 }
 
+impl std::iter::Sum for ReadableSize {
+    // Saturates rather than going through the `Add` impl's debug_assert:
+    // summing many config fields can legitimately approach `u64::MAX`
+    // without any single pair overflowing, so it shouldn't trip the same
+    // "did someone fat-finger a config value" check as a two-term add.
+    fn sum<I: Iterator<Item = ReadableSize>>(iter: I) -> ReadableSize {
+        iter.fold(ReadableSize(0), |acc, x| acc.saturating_add(x))
+    }
+}
+
 impl fmt::Display for ReadableSize {
     #[inline]
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let size = self.0;
+        if f.alternate() {
+            return write!(f, "{}", self.to_exact_string());
+        }
         if size == 0 {
             write!(f, "{}KiB", size)
         } else if size.is_multiple_of(PIB) {
@@ -171,6 +323,9 @@ impl Serialize for ReadableSize {
     where
         S: Serializer,
     {
+        if SERIALIZE_EXACT_BYTE_SIZES.with(Cell::get) {
+            return serializer.serialize_str(&self.to_exact_string());
+        }
         let mut buffer = String::new();
         write!(buffer, "{}", self).unwrap();
         serializer.serialize_str(&buffer)
@@ -224,7 +379,17 @@ impl FromStr for ReadableSize {
         };
 
         match size.parse::<f64>() {
-            Ok(n) => Ok(ReadableSize((n * unit as f64) as u64)),
+            Ok(n) if n.is_sign_negative() => {
+                Err(format!("size cannot be negative: {s:?}"))
+            }
+            Ok(n) if !n.is_finite() => Err(format!("invalid size string: {s:?}")),
+            Ok(n) => {
+                let bytes = n * unit as f64;
+                if bytes > u64::MAX as f64 {
+                    return Err(format!("size is too large: {s:?}"));
+                }
+                Ok(ReadableSize(bytes as u64))
+            }
             Err(_) => Err(format!("invalid size string: {s:?}")),
         }
     }
@@ -414,14 +579,171 @@ impl<'de> Deserialize<'de> for ReadableSizeOrPercent {
     }
 }
 
+/// A size value that can also be specified as a ratio of system memory,
+/// remembering which form was given so it round-trips through `Display` and
+/// serialization unchanged.
+///
+/// Unlike [`ReadableSizeOrPercent`], which resolves a percentage against
+/// system memory at parse time and forgets the original form, this type
+/// defers resolution to [`ReadableSizeOrRatio::resolve`], so it can be
+/// evaluated against a total other than system memory (for example, when
+/// online config re-resolves it against a memory limit that changed after
+/// startup).
+#[derive(Clone, Debug, Copy, PartialEq)]
+pub enum ReadableSizeOrRatio {
+    Size(ReadableSize),
+    /// A fraction in `(0.0, 1.0]`, parsed from a percentage string.
+    Ratio(f64),
+}
+
+impl ReadableSizeOrRatio {
+    /// Resolves this value to an absolute size, given the total (in bytes)
+    /// that a ratio should be taken of.
+    pub fn resolve(&self, total: u64) -> ReadableSize {
+        match *self {
+            ReadableSizeOrRatio::Size(size) => size,
+            ReadableSizeOrRatio::Ratio(ratio) => ReadableSize((total as f64 * ratio) as u64),
+        }
+    }
+}
+
+impl From<ReadableSize> for ReadableSizeOrRatio {
+    fn from(size: ReadableSize) -> ReadableSizeOrRatio {
+        ReadableSizeOrRatio::Size(size)
+    }
+}
+
+impl From<ReadableSizeOrRatio> for ConfigValue {
+    fn from(size: ReadableSizeOrRatio) -> ConfigValue {
+        ConfigValue::Size(size.resolve(SysQuota::memory_limit_in_bytes()).0)
+    }
+}
+
+impl From<ConfigValue> for ReadableSizeOrRatio {
+    fn from(c: ConfigValue) -> ReadableSizeOrRatio {
+        if let ConfigValue::Size(s) = c {
+            ReadableSizeOrRatio::Size(ReadableSize(s))
+        } else {
+            panic!("expect: ConfigValue::Size, got: {:?}", c);
+        }
+    }
+}
+
+impl fmt::Display for ReadableSizeOrRatio {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            ReadableSizeOrRatio::Size(size) => size.fmt(f),
+            ReadableSizeOrRatio::Ratio(ratio) => write!(f, "{}%", ratio * 100.0),
+        }
+    }
+}
+
+impl Serialize for ReadableSizeOrRatio {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut buffer = String::new();
+        write!(buffer, "{}", self).unwrap();
+        serializer.serialize_str(&buffer)
+    }
+}
+
+impl FromStr for ReadableSizeOrRatio {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<ReadableSizeOrRatio, String> {
+        let size_str = s.trim();
+        if let Some(num_str) = size_str.strip_suffix('%') {
+            let num_str = num_str.trim();
+            return match num_str.parse::<f64>() {
+                Ok(n) if n > 0.0 && n <= 100.0 => Ok(ReadableSizeOrRatio::Ratio(n / 100.0)),
+                Ok(n) => Err(format!(
+                    "percentage value must be in (0, 100], got {n}: {s:?}"
+                )),
+                Err(_) => Err(format!("invalid size string: {s:?}")),
+            };
+        }
+        ReadableSize::from_str(s).map(ReadableSizeOrRatio::Size)
+    }
+}
+
+impl<'de> Deserialize<'de> for ReadableSizeOrRatio {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct SizeOrRatioVisitor;
+
+        impl Visitor<'_> for SizeOrRatioVisitor {
+            type Value = ReadableSizeOrRatio;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+                formatter.write_str("valid size or percentage")
+            }
+
+            fn visit_i64<E>(self, size: i64) -> Result<ReadableSizeOrRatio, E>
+            where
+                E: de::Error,
+            {
+                if size >= 0 {
+                    self.visit_u64(size as u64)
+                } else {
+                    Err(E::invalid_value(Unexpected::Signed(size), &self))
+                }
+            }
+
+            fn visit_u64<E>(self, size: u64) -> Result<ReadableSizeOrRatio, E>
+            where
+                E: de::Error,
+            {
+                Ok(ReadableSizeOrRatio::Size(ReadableSize(size)))
+            }
+
+            fn visit_str<E>(self, size_str: &str) -> Result<ReadableSizeOrRatio, E>
+            where
+                E: de::Error,
+            {
+                size_str.parse().map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_any(SizeOrRatioVisitor)
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Ord, PartialOrd, Default)]
 pub struct ReadableDuration(pub Duration);
 
+impl ReadableDuration {
+    pub fn checked_add(self, rhs: ReadableDuration) -> Option<ReadableDuration> {
+        self.0.checked_add(rhs.0).map(ReadableDuration)
+    }
+
+    pub fn checked_sub(self, rhs: ReadableDuration) -> Option<ReadableDuration> {
+        self.0.checked_sub(rhs.0).map(ReadableDuration)
+    }
+
+    pub fn saturating_add(self, rhs: ReadableDuration) -> ReadableDuration {
+        ReadableDuration(self.0.saturating_add(rhs.0))
+    }
+
+    pub fn saturating_sub(self, rhs: ReadableDuration) -> ReadableDuration {
+        ReadableDuration(self.0.saturating_sub(rhs.0))
+    }
+}
+
 impl Add for ReadableDuration {
     type Output = ReadableDuration;
 
     fn add(self, rhs: ReadableDuration) -> ReadableDuration {
-        Self(self.0 + rhs.0)
+        debug_assert!(
+            self.checked_add(rhs).is_some(),
+            "overflow adding ReadableDuration: {} + {}",
+            self,
+            rhs,
+        );
+        self.saturating_add(rhs)
     }
 }
 
@@ -435,7 +757,13 @@ impl Sub for ReadableDuration {
     type Output = ReadableDuration;
 
     fn sub(self, rhs: ReadableDuration) -> ReadableDuration {
-        Self(self.0 - rhs.0)
+        debug_assert!(
+            self.checked_sub(rhs).is_some(),
+            "underflow subtracting ReadableDuration: {} - {}",
+            self,
+            rhs,
+        );
+        self.saturating_sub(rhs)
     }
 }
 
@@ -445,6 +773,14 @@ impl SubAssign for ReadableDuration {
     }
 }
 
+impl std::iter::Sum for ReadableDuration {
+    // See the matching note on `ReadableSize`'s `Sum` impl: this saturates
+    // directly rather than going through `Add`'s debug_assert.
+    fn sum<I: Iterator<Item = ReadableDuration>>(iter: I) -> ReadableDuration {
+        iter.fold(ReadableDuration::ZERO, |acc, x| acc.saturating_add(x))
+    }
+}
+
 impl Mul<u32> for ReadableDuration {
     type Output = ReadableDuration;
 
@@ -495,11 +831,96 @@ impl From<ConfigValue> for ReadableDuration {
     }
 }
 
+/// Parses the ISO-8601 duration subset `PnDTnHnMnS` (no years, months or
+/// weeks; fractional seconds allowed), accumulating the total in the same
+/// microsecond-scale unit space as the legacy parser below.
+fn parse_iso8601_duration(dur_str: &str) -> Result<Duration, String> {
+    // Caller already checked the leading 'P'.
+    let body = &dur_str.as_bytes()[1..];
+    let (date_part, time_part) = match body.iter().position(|&b| b == b'T') {
+        Some(idx) => (&body[..idx], Some(&body[idx + 1..])),
+        None => (body, None),
+    };
+    if date_part.is_empty() && time_part.map_or(true, |t| t.is_empty()) {
+        return Err(format!("invalid ISO-8601 duration: {}", dur_str));
+    }
+
+    let mut micros = 0f64;
+    parse_iso8601_components(date_part, &[(b'D', DAY)], dur_str, &mut micros)?;
+    if let Some(time_part) = time_part {
+        parse_iso8601_components(
+            time_part,
+            &[(b'H', HOUR), (b'M', MINUTE), (b'S', SECOND)],
+            dur_str,
+            &mut micros,
+        )?;
+    }
+
+    let total_us = micros as u64;
+    let secs = total_us / SECOND;
+    let nanos = ((total_us % SECOND) * 1_000) as u32;
+    Ok(Duration::new(secs, nanos))
+}
+
+/// Parses a run of `<number><unit>` components (e.g. `2H3M` for the time
+/// part), accumulating into `micros`. `units` lists the unit bytes allowed in
+/// this part together with their microsecond multiplier; `Y`/`W`/`M` in the
+/// date part (and any other unrecognized unit) are rejected with a
+/// dedicated error since ISO-8601 years and months have no fixed duration.
+fn parse_iso8601_components(
+    mut part: &[u8],
+    units: &[(u8, u64)],
+    original: &str,
+    micros: &mut f64,
+) -> Result<(), String> {
+    while !part.is_empty() {
+        if part[0] == b'-' {
+            return Err(format!(
+                "ISO-8601 duration must not be negative: {}",
+                original
+            ));
+        }
+        let digit_len = part
+            .iter()
+            .position(|b| !(b.is_ascii_digit() || *b == b'.'))
+            .unwrap_or(0);
+        if digit_len == 0 {
+            return Err(format!("invalid ISO-8601 duration: {}", original));
+        }
+        let (number, rest) = part.split_at(digit_len);
+        let unit = rest[0];
+        let number_str = unsafe { str::from_utf8_unchecked(number) };
+        let number: f64 = number_str
+            .parse()
+            .map_err(|_| format!("invalid ISO-8601 duration: {}", original))?;
+        match units.iter().find(|(u, _)| *u == unit) {
+            Some((_, multiplier)) => *micros += number * *multiplier as f64,
+            None if matches!(unit, b'Y' | b'M' | b'W') => {
+                return Err(format!(
+                    "ISO-8601 duration years, months and weeks are not supported: {}",
+                    original
+                ));
+            }
+            None => {
+                return Err(format!(
+                    "invalid ISO-8601 duration unit '{}': {}",
+                    unit as char, original
+                ));
+            }
+        }
+        part = &rest[1..];
+    }
+    Ok(())
+}
+
 impl FromStr for ReadableDuration {
     type Err = String;
 
     fn from_str(dur_str: &str) -> Result<ReadableDuration, String> {
         let dur_str = dur_str.trim();
+        if dur_str.starts_with('P') {
+            return parse_iso8601_duration(dur_str).map(ReadableDuration);
+        }
         if !dur_str.is_ascii() {
             return Err(format!("unexpect ascii string: {}", dur_str));
         }
@@ -595,6 +1016,17 @@ impl ReadableDuration {
     pub fn is_zero(&self) -> bool {
         self.0.as_nanos() == 0
     }
+
+    /// Renders the duration as a plain millisecond count, e.g. `1500`,
+    /// instead of the human-readable unit form used by [`fmt::Display`].
+    ///
+    /// Unlike the human form, this never changes shape as the value crosses
+    /// a unit boundary, which makes it suitable for diffing machine-generated
+    /// config dumps. See [`ReadableSize::to_exact_string`] for the analogous
+    /// case on sizes.
+    pub fn to_exact_string(&self) -> String {
+        self.as_millis().to_string()
+    }
 }
 
 impl fmt::Display for ReadableDuration {
@@ -643,6 +1075,9 @@ impl Serialize for ReadableDuration {
     where
         S: Serializer,
     {
+        if SERIALIZE_EXACT_BYTE_SIZES.with(Cell::get) {
+            return serializer.serialize_str(&self.to_exact_string());
+        }
         let mut buffer = String::new();
         write!(buffer, "{}", self).unwrap();
         serializer.serialize_str(&buffer)
@@ -675,6 +1110,117 @@ impl<'de> Deserialize<'de> for ReadableDuration {
     }
 }
 
+/// Rewrites the string-shaped [`ReadableSize`] and [`ReadableDuration`]
+/// values inside a config dumped as `serde_json::Value` into their
+/// exact-count forms (bytes, milliseconds), so that two dumps which differ
+/// only in formatting (e.g. `"1GiB"` vs `"1024MiB"`, `"1s"` vs `"1000ms"`)
+/// compare equal.
+///
+/// This is a best-effort textual pass over an already-serialized config: a
+/// string is only rewritten when it both contains at least one alphabetic
+/// unit character and parses as a `ReadableSize` or `ReadableDuration`, so
+/// bare numbers (already-exact forms, or unrelated numeric fields) and
+/// non-size/duration strings (labels, addresses, ...) are left untouched.
+/// Every other JSON shape is recursed into as-is.
+pub trait CanonicalizeConfig {
+    fn canonicalize(self) -> Self;
+}
+
+impl CanonicalizeConfig for Value {
+    fn canonicalize(self) -> Value {
+        match self {
+            Value::String(s) => Value::String(canonicalize_readable_scalar(&s)),
+            Value::Array(items) => Value::Array(
+                items
+                    .into_iter()
+                    .map(CanonicalizeConfig::canonicalize)
+                    .collect(),
+            ),
+            Value::Object(map) => Value::Object(
+                map.into_iter()
+                    .map(|(k, v)| (k, v.canonicalize()))
+                    .collect(),
+            ),
+            other => other,
+        }
+    }
+}
+
+fn canonicalize_readable_scalar(s: &str) -> String {
+    if !s.chars().any(|c| c.is_ascii_alphabetic()) {
+        return s.to_owned();
+    }
+    if let Ok(size) = s.parse::<ReadableSize>() {
+        return size.to_exact_string();
+    }
+    if let Ok(dur) = s.parse::<ReadableDuration>() {
+        return dur.to_exact_string();
+    }
+    s.to_owned()
+}
+
+/// A single semantic difference found by [`config_semantic_diff`]: the
+/// JSON-pointer-style path to the differing field (e.g.
+/// `/raftstore/raft-base-tick-interval`), and its two canonicalized values.
+/// A field present on only one side is reported with [`Value::Null`]
+/// standing in for the missing side.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DiffEntry {
+    pub path: String,
+    pub before: Value,
+    pub after: Value,
+}
+
+/// Compares two config dumps (e.g. two stores' responses from the status
+/// server's `/config` endpoint) field by field after running both through
+/// [`CanonicalizeConfig`], returning every field whose canonicalized value
+/// differs.
+///
+/// Two configs that differ only in how a `ReadableSize` or `ReadableDuration`
+/// happens to be formatted canonicalize to the same value and so contribute
+/// nothing to the result; a genuine difference is reported with the path
+/// leading to it.
+pub fn config_semantic_diff(a: &Value, b: &Value) -> Vec<DiffEntry> {
+    let a = a.clone().canonicalize();
+    let b = b.clone().canonicalize();
+    let mut diffs = Vec::new();
+    diff_at(String::new(), &a, &b, &mut diffs);
+    diffs
+}
+
+fn diff_at(path: String, a: &Value, b: &Value, out: &mut Vec<DiffEntry>) {
+    if let (Value::Object(am), Value::Object(bm)) = (a, b) {
+        let mut keys: Vec<&String> = am.keys().chain(bm.keys()).collect();
+        keys.sort();
+        keys.dedup();
+        for key in keys {
+            let child_path = format!("{path}/{key}");
+            match (am.get(key), bm.get(key)) {
+                (Some(av), Some(bv)) => diff_at(child_path, av, bv, out),
+                (Some(av), None) => out.push(DiffEntry {
+                    path: child_path,
+                    before: av.clone(),
+                    after: Value::Null,
+                }),
+                (None, Some(bv)) => out.push(DiffEntry {
+                    path: child_path,
+                    before: Value::Null,
+                    after: bv.clone(),
+                }),
+                (None, None) => unreachable!("key was taken from one of the two maps"),
+            }
+        }
+        return;
+    }
+    if a != b {
+        out.push(DiffEntry {
+            path,
+            before: a.clone(),
+            after: b.clone(),
+        });
+    }
+}
+
 #[derive(Clone, Debug, Copy, PartialEq)]
 pub struct ReadableOffsetTime(pub NaiveTime, pub FixedOffset);
 
@@ -692,25 +1238,59 @@ impl From<ReadableSchedule> for ConfigValue {
     }
 }
 
-impl From<ConfigValue> for ReadableSchedule {
-    fn from(c: ConfigValue) -> ReadableSchedule {
+impl TryFrom<ConfigValue> for ReadableSchedule {
+    type Error = String;
+
+    fn try_from(c: ConfigValue) -> Result<ReadableSchedule, String> {
         if let ConfigValue::Schedule(otv) = c {
-            ReadableSchedule(
-                otv.into_iter()
-                    .map(|s| ReadableOffsetTime::from_str(s.as_str()).unwrap())
-                    .collect::<Vec<_>>(),
-            )
+            let entries = otv
+                .into_iter()
+                .map(|s| ReadableOffsetTime::from_str(s.as_str()))
+                .try_collect::<Vec<_>>()?;
+            Ok(ReadableSchedule::deduplicated(entries))
         } else {
-            panic!("expect: ConfigValue::Schedule, got: {:?}", c)
+            Err(format!("expect: ConfigValue::Schedule, got: {:?}", c))
         }
     }
 }
 
 impl ReadableSchedule {
+    /// Builds a schedule from already-parsed entries, dropping entries that
+    /// name the same instant as one already kept, once normalized to UTC.
+    fn deduplicated(entries: Vec<ReadableOffsetTime>) -> Self {
+        let mut normalized_seen = Vec::with_capacity(entries.len());
+        let mut deduped = Vec::with_capacity(entries.len());
+        for entry in entries {
+            let normalized = entry.normalized_utc();
+            if !normalized_seen.contains(&normalized) {
+                normalized_seen.push(normalized);
+                deduped.push(entry);
+            }
+        }
+        ReadableSchedule(deduped)
+    }
+
     pub fn is_empty(&self) -> bool {
         self.0.is_empty()
     }
 
+    /// Reports the first entry that coincides with an earlier one once both
+    /// are normalized to UTC, e.g. "01:00 +01:00" and "00:00 +00:00".
+    pub fn validate(&self) -> Result<(), String> {
+        let mut normalized_seen = Vec::with_capacity(self.0.len());
+        for entry in &self.0 {
+            let normalized = entry.normalized_utc();
+            if normalized_seen.contains(&normalized) {
+                return Err(format!(
+                    "duplicate schedule entry {} (coincides with another entry once normalized to UTC)",
+                    entry
+                ));
+            }
+            normalized_seen.push(normalized);
+        }
+        Ok(())
+    }
+
     pub fn is_scheduled_this_hour<Tz: TimeZone>(&self, datetime: &DateTime<Tz>) -> bool {
         self.0.iter().any(|time| time.hour_matches(datetime))
     }
@@ -739,12 +1319,11 @@ impl FromStr for ReadableSchedule {
     type Err = String;
 
     fn from_str(s: &str) -> Result<Self, String> {
-        Ok(ReadableSchedule(
-            parse_string_to_vec(s)?
-                .into_iter()
-                .map(|s| ReadableOffsetTime::from_str(s.as_str()))
-                .try_collect()?,
-        ))
+        let entries = parse_string_to_vec(s)?
+            .into_iter()
+            .map(|s| ReadableOffsetTime::from_str(s.as_str()))
+            .try_collect::<Vec<_>>()?;
+        Ok(ReadableSchedule::deduplicated(entries))
     }
 }
 
@@ -753,17 +1332,26 @@ impl FromStr for ReadableOffsetTime {
 
     fn from_str(ot_str: &str) -> Result<ReadableOffsetTime, String> {
         let (time, offset) = if let Some((time_str, offset_str)) = ot_str.split_once(' ') {
-            let time = NaiveTime::parse_from_str(time_str, "%H:%M").map_err(|e| e.to_string())?;
+            let time = parse_time_of_day(time_str)?;
             let offset = parse_offset(offset_str)?;
             (time, offset)
         } else {
-            let time = NaiveTime::parse_from_str(ot_str, "%H:%M").map_err(|e| e.to_string())?;
+            let time = parse_time_of_day(ot_str)?;
             (time, local_offset())
         };
         Ok(ReadableOffsetTime(time, offset))
     }
 }
 
+/// Parses a time of day as either `%H:%M` or `%H:%M:%S`, trying the more
+/// specific format first so that a trailing `:SS` is not swallowed as part of
+/// an invalid `%H:%M` parse.
+fn parse_time_of_day(time_str: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(time_str, "%H:%M:%S")
+        .or_else(|_| NaiveTime::parse_from_str(time_str, "%H:%M"))
+        .map_err(|e| e.to_string())
+}
+
 /// Returns the `FixedOffset` for the timezone this `tikv` server has been
 /// configured to use.
 fn local_offset() -> FixedOffset {
@@ -789,7 +1377,13 @@ fn parse_offset(offset_str: &str) -> Result<FixedOffset, String> {
 
 impl fmt::Display for ReadableOffsetTime {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{} {}", self.0.format("%H:%M"), self.1)
+        // Only print seconds when they are non-zero, so existing minute-grained
+        // configuration keeps round-tripping through the exact same string.
+        if self.0.second() == 0 {
+            write!(f, "{} {}", self.0.format("%H:%M"), self.1)
+        } else {
+            write!(f, "{} {}", self.0.format("%H:%M:%S"), self.1)
+        }
     }
 }
 
@@ -811,28 +1405,133 @@ impl ReadableOffsetTime {
     fn convert_to_this_offset<Tz: TimeZone>(&self, datetime: &DateTime<Tz>) -> NaiveTime {
         datetime.with_timezone(&self.1).time()
     }
-}
 
-impl Serialize for ReadableOffsetTime {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        let mut buffer = String::new();
-        write!(buffer, "{}", self).unwrap();
-        serializer.serialize_str(&buffer)
+    /// Returns this entry's time of day normalized to UTC, so entries given
+    /// in different offsets that name the same instant each day (e.g.
+    /// "01:00 +01:00" and "00:00 +00:00") compare equal.
+    fn normalized_utc(&self) -> NaiveTime {
+        self.0 - ChronoDuration::seconds(i64::from(self.1.local_minus_utc()))
     }
 }
 
-impl<'de> Deserialize<'de> for ReadableOffsetTime {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        struct OffTimeVisitor;
-
-        impl Visitor<'_> for OffTimeVisitor {
-            type Value = ReadableOffsetTime;
+/// A recurring schedule of the form "starting at `start`, then every
+/// `interval`", e.g. "every 4 hours starting at 01:00 +08:00".
+///
+/// Unlike [`ReadableOffsetTime`], which names a single fixed instant per day,
+/// `ReadableRecurrence` fires repeatedly. The recurrence resets at midnight in
+/// `start`'s offset: if `interval` does not evenly divide 24h, the gap between
+/// the last firing of one day and the first firing of the next day (`start`
+/// itself) is shorter than `interval`, so the wall-clock times of day drift
+/// across the boundary rather than continuing on a fixed cadence. `start`'s
+/// offset is a fixed UTC offset, not a named timezone, so it does not observe
+/// DST transitions.
+#[derive(Clone, Debug, Copy, PartialEq)]
+pub struct ReadableRecurrence {
+    pub start: ReadableOffsetTime,
+    pub interval: ReadableDuration,
+}
+
+impl ReadableRecurrence {
+    /// Builds a recurrence, rejecting an `interval` shorter than a minute
+    /// since firings are only checked at minute granularity.
+    pub fn new(start: ReadableOffsetTime, interval: ReadableDuration) -> Result<Self, String> {
+        if interval.0 < Duration::from_secs(60) {
+            return Err(format!(
+                "recurrence interval must be at least 1 minute, got {}",
+                interval
+            ));
+        }
+        Ok(ReadableRecurrence { start, interval })
+    }
+
+    /// Converts `datetime` to `start`'s offset and returns `true` if the
+    /// current minute is a scheduled firing, i.e. the number of whole minutes
+    /// elapsed since the most recent midnight in `start`'s offset is a
+    /// multiple of `interval` counted from `start`.
+    pub fn is_scheduled_this_minute<Tz: TimeZone>(&self, datetime: &DateTime<Tz>) -> bool {
+        let now = self.start.convert_to_this_offset(datetime);
+        let now_minutes = now.num_seconds_from_midnight() as i64 / 60;
+        let start_minutes = self.start.0.num_seconds_from_midnight() as i64 / 60;
+        let interval_minutes = self.interval.0.as_secs() as i64 / 60;
+        let elapsed = (now_minutes - start_minutes).rem_euclid(24 * 60);
+        elapsed % interval_minutes == 0
+    }
+}
+
+impl fmt::Display for ReadableRecurrence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} every {}", self.start, self.interval)
+    }
+}
+
+impl FromStr for ReadableRecurrence {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        let (start_str, interval_str) = s
+            .split_once(" every ")
+            .ok_or_else(|| format!("expect \"<start> every <interval>\", got: {:?}", s))?;
+        let start = ReadableOffsetTime::from_str(start_str.trim())?;
+        let interval = ReadableDuration::from_str(interval_str.trim())?;
+        ReadableRecurrence::new(start, interval)
+    }
+}
+
+impl Serialize for ReadableRecurrence {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ReadableRecurrence {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct RecurrenceVisitor;
+
+        impl Visitor<'_> for RecurrenceVisitor {
+            type Value = ReadableRecurrence;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+                formatter.write_str("valid recurrence, e.g. \"01:00 +08:00 every 4h\"")
+            }
+
+            fn visit_str<E>(self, s: &str) -> Result<ReadableRecurrence, E>
+            where
+                E: de::Error,
+            {
+                s.parse().map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_str(RecurrenceVisitor)
+    }
+}
+
+impl Serialize for ReadableOffsetTime {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut buffer = String::new();
+        write!(buffer, "{}", self).unwrap();
+        serializer.serialize_str(&buffer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ReadableOffsetTime {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct OffTimeVisitor;
+
+        impl Visitor<'_> for OffTimeVisitor {
+            type Value = ReadableOffsetTime;
 
             fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
                 formatter.write_str("valid duration")
@@ -850,6 +1549,143 @@ impl<'de> Deserialize<'de> for ReadableOffsetTime {
     }
 }
 
+/// A maintenance window expressed as `start - end`, where `start` and `end`
+/// are each an independent [`ReadableOffsetTime`]. The window is allowed to
+/// cross midnight (e.g. `22:00 +08:00 - 02:30 +08:00`) and the two endpoints
+/// are allowed to use different UTC offsets.
+#[derive(Clone, Debug, Copy, PartialEq)]
+pub struct ReadableWindow(pub ReadableOffsetTime, pub ReadableOffsetTime);
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Default)]
+pub struct ReadableWindows(pub Vec<ReadableWindow>);
+
+impl From<ReadableWindows> for ConfigValue {
+    fn from(windows: ReadableWindows) -> ConfigValue {
+        ConfigValue::Windows(windows.0.into_iter().map(|w| w.to_string()).collect())
+    }
+}
+
+impl From<ConfigValue> for ReadableWindows {
+    fn from(c: ConfigValue) -> ReadableWindows {
+        if let ConfigValue::Windows(windows) = c {
+            ReadableWindows(
+                windows
+                    .into_iter()
+                    .map(|s| ReadableWindow::from_str(s.as_str()).unwrap())
+                    .collect::<Vec<_>>(),
+            )
+        } else {
+            panic!("expect: ConfigValue::Windows, got: {:?}", c)
+        }
+    }
+}
+
+impl ReadableWindows {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns `true` if `datetime` falls inside any of the configured
+    /// windows.
+    pub fn is_within_any_window<Tz: TimeZone>(&self, datetime: &DateTime<Tz>) -> bool {
+        self.0.iter().any(|window| window.contains(datetime))
+    }
+}
+
+impl FromStr for ReadableWindows {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        Ok(ReadableWindows(
+            parse_string_to_vec(s)?
+                .into_iter()
+                .map(|s| ReadableWindow::from_str(s.as_str()))
+                .try_collect()?,
+        ))
+    }
+}
+
+impl FromStr for ReadableWindow {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<ReadableWindow, String> {
+        let (start_str, end_str) = s
+            .split_once(" - ")
+            .ok_or_else(|| format!("{:?} is not a valid window, expect \"start - end\"", s))?;
+        Ok(ReadableWindow(
+            ReadableOffsetTime::from_str(start_str.trim())?,
+            ReadableOffsetTime::from_str(end_str.trim())?,
+        ))
+    }
+}
+
+impl fmt::Display for ReadableWindow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} - {}", self.0, self.1)
+    }
+}
+
+impl ReadableWindow {
+    /// Returns `true` if `datetime` falls inside this window.
+    ///
+    /// The window may cross midnight, and `start` and `end` may be specified
+    /// in different UTC offsets: `end` is first re-expressed as a
+    /// time-of-day in `start`'s offset, after which the two are compared on
+    /// a single time axis. A zero-length window (`start == end` once
+    /// normalized) never contains any instant.
+    pub fn contains<Tz: TimeZone>(&self, datetime: &DateTime<Tz>) -> bool {
+        let start = self.0;
+        let end = self.1;
+        let offset_diff_secs =
+            i64::from(start.1.local_minus_utc()) - i64::from(end.1.local_minus_utc());
+        let end_in_start_offset = end.0 + ChronoDuration::seconds(offset_diff_secs);
+        let now = start.convert_to_this_offset(datetime);
+
+        if start.0 <= end_in_start_offset {
+            now >= start.0 && now < end_in_start_offset
+        } else {
+            now >= start.0 || now < end_in_start_offset
+        }
+    }
+}
+
+impl Serialize for ReadableWindow {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut buffer = String::new();
+        write!(buffer, "{}", self).unwrap();
+        serializer.serialize_str(&buffer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ReadableWindow {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct WindowVisitor;
+
+        impl Visitor<'_> for WindowVisitor {
+            type Value = ReadableWindow;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+                formatter.write_str("valid window")
+            }
+
+            fn visit_str<E>(self, window_str: &str) -> Result<ReadableWindow, E>
+            where
+                E: de::Error,
+            {
+                window_str.parse().map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_str(WindowVisitor)
+    }
+}
+
 pub fn normalize_path<P: AsRef<Path>>(path: P) -> PathBuf {
     use std::path::Component;
     let mut components = path.as_ref().components().peekable();
@@ -973,8 +1809,105 @@ pub fn ensure_dir_exist(path: &str) -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+/// Whether a failing [`check_max_open_fds`] should be treated as fatal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FdLimitMode {
+    /// Return an error when the limit cannot be raised high enough.
+    Enforce,
+    /// Log the shortfall but report success anyway, so startup can continue.
+    Warn,
+}
+
+/// The file-descriptor rlimits discovered (and possibly raised) by
+/// [`check_max_open_fds`], kept around so callers can report them for
+/// diagnostics purposes instead of only surfacing a pass/fail result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FdLimits {
+    pub soft: u64,
+    pub hard: u64,
+    /// `Some(limit)` when the soft limit had to be (successfully) raised to
+    /// meet `expect`; `None` when the existing limit was already sufficient.
+    pub raised_to: Option<u64>,
+}
+
+impl fmt::Display for FdLimits {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "soft={}, hard={}", self.soft, self.hard)?;
+        if let Some(raised_to) = self.raised_to {
+            write!(f, ", raised_to={}", raised_to)?;
+        }
+        Ok(())
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref LAST_FD_LIMITS: RwLock<FdLimits> = RwLock::new(FdLimits::default());
+}
+
+/// Returns the `FdLimits` discovered by the most recent [`check_max_open_fds`]
+/// call, for diagnostics reporting. Defaults to all-zero before the first
+/// call.
+pub fn last_fd_limits() -> FdLimits {
+    *LAST_FD_LIMITS.read().unwrap()
+}
+
+pub fn check_max_open_fds(expect: u64, mode: FdLimitMode) -> Result<FdLimits, ConfigError> {
+    let limits = check_max_open_fds_impl(expect, mode)?;
+    *LAST_FD_LIMITS.write().unwrap() = limits;
+    Ok(limits)
+}
+
+/// Decides the outcome of a `check_max_open_fds` call from already-read
+/// current limits, without touching any actual rlimit. Split out from
+/// [`check_max_open_fds_impl`] so tests can exercise the `Warn`-vs-`Enforce`
+/// and raise-succeeds-or-not branches by injecting fake current limits and a
+/// fake raise outcome, instead of depending on the real process' rlimits.
+fn decide_fd_limits(
+    current_soft: u64,
+    current_hard: u64,
+    expect: u64,
+    mode: FdLimitMode,
+    try_raise: impl FnOnce(u64, u64) -> bool,
+) -> Result<FdLimits, ConfigError> {
+    if current_soft >= expect {
+        return Ok(FdLimits {
+            soft: current_soft,
+            hard: current_hard,
+            raised_to: None,
+        });
+    }
+
+    // If the process is not started by privileged user, raising the hard
+    // limit will fail.
+    let new_hard = current_hard.max(expect);
+    if try_raise(expect, new_hard) {
+        return Ok(FdLimits {
+            soft: expect,
+            hard: new_hard,
+            raised_to: Some(expect),
+        });
+    }
+
+    let detail = InvalidConfigValue::new(
+        "max-open-fds",
+        format!("greater or equal to {}", expect),
+        current_soft.to_string(),
+    );
+    match mode {
+        FdLimitMode::Enforce => Err(ConfigError::Limit(detail)),
+        FdLimitMode::Warn => {
+            warn!("{}", detail);
+            Ok(FdLimits {
+                soft: current_soft,
+                hard: current_hard,
+                raised_to: None,
+            })
+        }
+    }
+}
+
 #[cfg(unix)]
-pub fn check_max_open_fds(expect: u64) -> Result<(), ConfigError> {
+fn check_max_open_fds_impl(expect: u64, mode: FdLimitMode) -> Result<FdLimits, ConfigError> {
     #[cfg(target_os = "freebsd")]
     let expect = expect as i64;
 
@@ -982,127 +1915,205 @@ pub fn check_max_open_fds(expect: u64) -> Result<(), ConfigError> {
 
     unsafe {
         let mut fd_limit = mem::zeroed();
-        let mut err = libc::getrlimit(libc::RLIMIT_NOFILE, &mut fd_limit);
+        let err = libc::getrlimit(libc::RLIMIT_NOFILE, &mut fd_limit);
         if err != 0 {
-            return Err(ConfigError::Limit("check_max_open_fds failed".to_owned()));
-        }
-        if fd_limit.rlim_cur >= expect {
-            return Ok(());
+            return Err(ConfigError::Limit(InvalidConfigValue::new(
+                "max-open-fds",
+                "a successful getrlimit(RLIMIT_NOFILE) call",
+                "check_max_open_fds failed",
+            )));
         }
 
-        let prev_limit = fd_limit.rlim_cur;
-        fd_limit.rlim_cur = expect;
-        if fd_limit.rlim_max < expect {
-            // If the process is not started by privileged user, this will fail.
-            fd_limit.rlim_max = expect;
+        decide_fd_limits(
+            fd_limit.rlim_cur as u64,
+            fd_limit.rlim_max as u64,
+            expect as u64,
+            mode,
+            |new_soft, new_hard| {
+                fd_limit.rlim_cur = new_soft as _;
+                fd_limit.rlim_max = new_hard as _;
+                libc::setrlimit(libc::RLIMIT_NOFILE, &fd_limit) == 0
+            },
+        )
+    }
+}
+
+#[cfg(not(unix))]
+fn check_max_open_fds_impl(_: u64, _: FdLimitMode) -> Result<FdLimits, ConfigError> {
+    Ok(FdLimits::default())
+}
+
+/// How a [`KernelParamCheck`]'s observed value must relate to its expected
+/// value to pass.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KernelParamCmp {
+    Eq,
+    Ge,
+    Le,
+}
+
+impl KernelParamCmp {
+    fn holds(&self, got: i64, expect: i64) -> bool {
+        match self {
+            KernelParamCmp::Eq => got == expect,
+            KernelParamCmp::Ge => got >= expect,
+            KernelParamCmp::Le => got <= expect,
         }
-        err = libc::setrlimit(libc::RLIMIT_NOFILE, &fd_limit);
-        if err == 0 {
-            return Ok(());
+    }
+}
+
+/// How a failing [`KernelParamCheck`] should be treated by the caller:
+/// logged as a warning, or treated as fatal at startup.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KernelParamSeverity {
+    Warn,
+    Abort,
+}
+
+/// A single kernel parameter to validate, e.g. an entry under `/proc/sys`.
+/// [`default_kernel_param_checks`] builds the built-in set; callers (e.g.
+/// server startup, driven by configuration) can extend it with
+/// deployment-specific parameters before passing the full list to
+/// [`check_kernel_with`].
+#[derive(Clone, Debug)]
+pub struct KernelParamCheck {
+    pub path: String,
+    pub expect: i64,
+    pub cmp: KernelParamCmp,
+    pub severity: KernelParamSeverity,
+}
+
+impl KernelParamCheck {
+    pub fn new(
+        path: impl Into<String>,
+        expect: i64,
+        cmp: KernelParamCmp,
+        severity: KernelParamSeverity,
+    ) -> Self {
+        Self {
+            path: path.into(),
+            expect,
+            cmp,
+            severity,
         }
-        Err(ConfigError::Limit(format!(
-            "the maximum number of open file descriptors is too \
-             small, got {}, expect greater or equal to {}",
-            prev_limit, expect
-        )))
     }
 }
 
-#[cfg(not(unix))]
-pub fn check_max_open_fds(_: u64) -> Result<(), ConfigError> {
-    Ok(())
+/// The outcome of a [`KernelParamCheck`] that didn't meet expectations.
+#[derive(Debug)]
+pub struct KernelParamCheckResult {
+    pub check: KernelParamCheck,
+    pub error: ConfigError,
+}
+
+/// The built-in kernel parameters checked at startup:
+///   - `net.core.somaxconn` should be greater or equal to 32768.
+///   - `net.ipv4.tcp_syncookies` should be 0
+///   - `vm.swappiness` shoud be 0
+///
+/// All three keep their historical [`KernelParamSeverity::Warn`] severity,
+/// i.e. a failure is only logged, not fatal.
+pub fn default_kernel_param_checks() -> Vec<KernelParamCheck> {
+    vec![
+        KernelParamCheck::new(
+            "/proc/sys/net/core/somaxconn",
+            32768,
+            KernelParamCmp::Ge,
+            KernelParamSeverity::Warn,
+        ),
+        KernelParamCheck::new(
+            "/proc/sys/net/ipv4/tcp_syncookies",
+            0,
+            KernelParamCmp::Eq,
+            KernelParamSeverity::Warn,
+        ),
+        KernelParamCheck::new(
+            "/proc/sys/vm/swappiness",
+            0,
+            KernelParamCmp::Eq,
+            KernelParamSeverity::Warn,
+        ),
+    ]
 }
 
 #[cfg(target_os = "linux")]
 mod check_kernel {
     use std::fs;
 
-    use super::ConfigError;
-
-    // pub for tests.
-    pub type Checker = dyn Fn(i64, i64) -> bool;
+    use super::{ConfigError, InvalidConfigValue, KernelParamCheck, KernelParamCheckResult};
 
     // pub for tests.
-    pub fn check_kernel_params(
-        param_path: &str,
-        expect: i64,
-        checker: Box<Checker>,
-    ) -> Result<(), ConfigError> {
-        let buffer = fs::read_to_string(param_path)
-            .map_err(|e| ConfigError::Limit(format!("check_kernel_params failed {}", e)))?;
+    pub fn check_kernel_param(check: &KernelParamCheck) -> Result<i64, ConfigError> {
+        let buffer = fs::read_to_string(&check.path).map_err(|e| {
+            ConfigError::Limit(InvalidConfigValue::new(
+                &check.path,
+                "a readable kernel parameter file",
+                format!("read failed: {}", e),
+            ))
+        })?;
 
-        let got = buffer
-            .trim_matches('\n')
-            .parse::<i64>()
-            .map_err(|e| ConfigError::Limit(format!("check_kernel_params failed {}", e)))?;
+        let got = buffer.trim_matches('\n').parse::<i64>().map_err(|e| {
+            ConfigError::Limit(InvalidConfigValue::new(
+                &check.path,
+                "an integer value",
+                format!("parse failed: {}", e),
+            ))
+        })?;
 
         let mut param = String::new();
         // skip 3, ["", "proc", "sys", ...]
-        for path in param_path.split('/').skip(3) {
+        for path in check.path.split('/').skip(3) {
             param.push_str(path);
             param.push('.');
         }
         param.pop();
 
-        if !checker(got, expect) {
-            return Err(ConfigError::Limit(format!(
-                "kernel parameters {} got {}, expect {}",
-                param, got, expect
+        if !check.cmp.holds(got, check.expect) {
+            return Err(ConfigError::Limit(InvalidConfigValue::new(
+                param,
+                format!("{:?} {}", check.cmp, check.expect),
+                got.to_string(),
             )));
         }
 
         info!("kernel parameters"; "param" => param, "value" => got);
-        Ok(())
+        Ok(got)
     }
 
-    /// `check_kernel_params` checks kernel parameters, following are checked so
-    /// far:
-    ///   - `net.core.somaxconn` should be greater or equal to 32768.
-    ///   - `net.ipv4.tcp_syncookies` should be 0
-    ///   - `vm.swappiness` shoud be 0
+    /// Checks `checks` against the live kernel parameters (e.g.
+    /// `/proc/sys/...`) and returns the ones that failed, so the caller can
+    /// act differently depending on each failure's
+    /// [`super::KernelParamSeverity`] (e.g. abort vs log).
     ///
     /// Note that: It works on **Linux** only.
-    pub fn check_kernel() -> Vec<ConfigError> {
-        let params: Vec<(&str, i64, Box<Checker>)> = vec![
-            // Check net.core.somaxconn.
-            (
-                "/proc/sys/net/core/somaxconn",
-                32768,
-                Box::new(|got, expect| got >= expect),
-            ),
-            // Check net.ipv4.tcp_syncookies.
-            (
-                "/proc/sys/net/ipv4/tcp_syncookies",
-                0,
-                Box::new(|got, expect| got == expect),
-            ),
-            // Check vm.swappiness.
-            (
-                "/proc/sys/vm/swappiness",
-                0,
-                Box::new(|got, expect| got == expect),
-            ),
-        ];
-
-        let mut errors = Vec::with_capacity(params.len());
-        for (param_path, expect, checker) in params {
-            if let Err(e) = check_kernel_params(param_path, expect, checker) {
-                errors.push(e);
+    pub fn check_kernel_with(checks: &[KernelParamCheck]) -> Vec<KernelParamCheckResult> {
+        let mut results = Vec::new();
+        for check in checks {
+            if let Err(error) = check_kernel_param(check) {
+                results.push(KernelParamCheckResult {
+                    check: check.clone(),
+                    error,
+                });
             }
         }
-
-        errors
+        results
     }
 }
 
 #[cfg(target_os = "linux")]
-pub use self::check_kernel::check_kernel;
+pub use self::check_kernel::{check_kernel_param, check_kernel_with};
 
 #[cfg(not(target_os = "linux"))]
-pub fn check_kernel() -> Vec<ConfigError> {
+pub fn check_kernel_with(_checks: &[KernelParamCheck]) -> Vec<KernelParamCheckResult> {
     Vec::new()
 }
 
+/// Checks the built-in kernel parameters (see
+/// [`default_kernel_param_checks`]) and returns the ones that failed.
+pub fn check_kernel() -> Vec<KernelParamCheckResult> {
+    check_kernel_with(&default_kernel_param_checks())
+}
+
 #[cfg(target_os = "linux")]
 mod check_data_dir {
     use std::{
@@ -1170,9 +2181,11 @@ mod check_data_dir {
         }
     }
 
-    fn get_rotational_info(fsname: &str) -> Result<String, ConfigError> {
-        let op = "data-dir.rotation.get";
-        // get device path
+    // Resolves `fsname` (e.g. `/dev/sda4` or a device-mapper name) to its
+    // `/sys/block/<dev>/queue` directory, so callers can read per-device
+    // queue attributes such as `rotational` or `scheduler`.
+    fn get_device_queue_dir(fsname: &str) -> Result<String, ConfigError> {
+        let op = "data-dir.device.get";
         let device = match fs::canonicalize(fsname) {
             Ok(path) => format!("{}", path.display()),
             Err(_) => String::from(fsname),
@@ -1208,12 +2221,17 @@ mod check_data_dir {
                 )));
             }
         }
+        Ok(format!("{}/queue", device_dir))
+    }
 
-        let rota_path = format!("{}/queue/rotational", device_dir);
+    fn get_rotational_info(fsname: &str) -> Result<String, ConfigError> {
+        let op = "data-dir.rotation.get";
+        let queue_dir = get_device_queue_dir(fsname)?;
+        let rota_path = format!("{}/rotational", queue_dir);
         if !Path::new(&rota_path).exists() {
             return Err(ConfigError::FileSystem(format!(
                 "{}: block {:?} has no rotational file",
-                op, device_dir
+                op, queue_dir
             )));
         }
 
@@ -1223,27 +2241,187 @@ mod check_data_dir {
         Ok(buffer.trim_matches('\n').to_owned())
     }
 
-    // check device && fs
-    pub fn check_data_dir(data_path: &str, mnt_file: &str) -> Result<(), ConfigError> {
-        let op = "data-dir.check";
-        let real_path = match canonicalize_path(data_path) {
-            Ok(path) => path,
+    // Reads the currently active IO scheduler for `fsname`'s block device,
+    // e.g. `/sys/block/nvme0n1/queue/scheduler` containing
+    // `[none] mq-deadline kyber bfq`, from which this returns `"none"`.
+    fn get_active_scheduler(fsname: &str) -> Result<String, ConfigError> {
+        let op = "data-dir.scheduler.get";
+        let queue_dir = get_device_queue_dir(fsname)?;
+        let scheduler_path = format!("{}/scheduler", queue_dir);
+        if !Path::new(&scheduler_path).exists() {
+            return Err(ConfigError::FileSystem(format!(
+                "{}: block {:?} has no scheduler file",
+                op, queue_dir
+            )));
+        }
+        let buffer = fs::read_to_string(&scheduler_path).map_err(|e| {
+            ConfigError::FileSystem(format!("{}: {:?} failed: {:?}", op, scheduler_path, e))
+        })?;
+        buffer
+            .split_whitespace()
+            .find_map(|w| w.strip_prefix('[').and_then(|w| w.strip_suffix(']')))
+            .map(|s| s.to_owned())
+            .ok_or_else(|| {
+                ConfigError::FileSystem(format!(
+                    "{}: {:?} has no active scheduler marked",
+                    op, scheduler_path
+                ))
+            })
+    }
+
+    // `fsname` is either a `/dev/...` path or a device-mapper name; NVMe
+    // namespace devices are always named `nvme<ctrl>n<ns>[p<part>]`.
+    fn is_nvme_device(fsname: &str) -> bool {
+        Path::new(fsname)
+            .file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|n| n.starts_with("nvme"))
+    }
+
+    /// A single non-fatal observation made about a data directory's
+    /// underlying filesystem or block device. `message` is meant to be
+    /// logged as-is; `kind` lets callers (e.g. the diagnostics API) group or
+    /// filter findings without parsing the message.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct DataDirFinding {
+        pub kind: DataDirFindingKind,
+        pub message: String,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum DataDirFindingKind {
+        /// The underlying device is spinning rust rather than an SSD.
+        NotSsd,
+        /// The filesystem is known to not honor `O_DIRECT` reliably.
+        UnstableODirect,
+        /// A mount option that trades durability or `O_DIRECT` correctness
+        /// for throughput is set (e.g. `nobarrier`, `nodelalloc`).
+        UnsafeMountOption,
+        /// An IO scheduler other than `none`/`noop` is active on an NVMe
+        /// device, which usually only adds overhead.
+        SuboptimalScheduler,
+    }
+
+    fn fs_lacks_stable_odirect(tp: &str, opts: &str) -> bool {
+        match tp {
+            "zfs" => true,
+            "btrfs" => !opts.split(',').any(|o| o == "nodatacow"),
+            _ => false,
+        }
+    }
+
+    const UNSAFE_MOUNT_OPTIONS: &[&str] = &["nobarrier", "nodelalloc"];
+
+    /// Filesystem and block-device facts about a data directory: the kind of
+    /// information operators want surfaced verbatim (via metrics or the
+    /// diagnostics API) rather than turned into a pass/fail finding. See
+    /// [`check_data_dir`], which is built on top of this and turns the same
+    /// facts into [`DataDirFinding`]s.
+    #[derive(Debug, Clone, Default, PartialEq, Eq)]
+    pub struct DataDirInfo {
+        pub fs_type: String,
+        pub mount_options: String,
+        pub device: String,
+        /// `Some(true)` for a spinning disk, `Some(false)` for an SSD,
+        /// `None` when the rotational flag could not be read (e.g. the
+        /// device has no `/sys/block` entry).
+        pub rotational: Option<bool>,
+    }
+
+    /// Collects [`DataDirInfo`] for `data_path`, without turning any of it
+    /// into a pass/fail finding.
+    pub fn collect_data_dir_info(
+        data_path: &str,
+        mnt_file: &str,
+    ) -> Result<DataDirInfo, ConfigError> {
+        let op = "data-dir.info.get";
+        let real_path = canonicalize_path(data_path).map_err(|e| {
+            ConfigError::FileSystem(format!(
+                "{}: path: {:?} canonicalize failed: {:?}",
+                op, data_path, e
+            ))
+        })?;
+
+        let fs_info = get_fs_info(&real_path, mnt_file)?;
+        let rotational = match get_rotational_info(&fs_info.fsname) {
+            Ok(rotational) => Some(rotational != "0"),
             Err(e) => {
-                return Err(ConfigError::FileSystem(format!(
-                    "{}: path: {:?} canonicalize failed: {:?}",
-                    op, data_path, e
-                )));
+                warn!("failed to read rotational info"; "data_path" => data_path, "err" => %e);
+                None
             }
         };
 
-        // TODO check ext4 nodelalloc
-        let fs_info = get_fs_info(&real_path, mnt_file)?;
-        info!("data dir"; "data_path" => data_path, "mount_fs" => ?fs_info);
+        Ok(DataDirInfo {
+            fs_type: fs_info.tp,
+            mount_options: fs_info.opts,
+            device: fs_info.fsname,
+            rotational,
+        })
+    }
 
-        if get_rotational_info(&fs_info.fsname)? != "0" {
-            warn!("not on SSD device"; "data_path" => data_path);
+    // check device && fs
+    pub fn check_data_dir(
+        data_path: &str,
+        mnt_file: &str,
+    ) -> Result<Vec<DataDirFinding>, ConfigError> {
+        let info = collect_data_dir_info(data_path, mnt_file)?;
+        info!("data dir"; "data_path" => data_path, "info" => ?info);
+
+        let mut findings = Vec::new();
+
+        if info.rotational == Some(true) {
+            findings.push(DataDirFinding {
+                kind: DataDirFindingKind::NotSsd,
+                message: format!("data_path {:?} is not on an SSD device", data_path),
+            });
         }
-        Ok(())
+
+        if fs_lacks_stable_odirect(&info.fs_type, &info.mount_options) {
+            findings.push(DataDirFinding {
+                kind: DataDirFindingKind::UnstableODirect,
+                message: format!(
+                    "data_path {:?} is on a {} filesystem, which does not reliably support \
+                     O_DIRECT",
+                    data_path, info.fs_type
+                ),
+            });
+        }
+
+        if info.fs_type == "ext4" {
+            for opt in UNSAFE_MOUNT_OPTIONS {
+                if info.mount_options.split(',').any(|o| &o == opt) {
+                    findings.push(DataDirFinding {
+                        kind: DataDirFindingKind::UnsafeMountOption,
+                        message: format!(
+                            "data_path {:?} is mounted with ext4 option {:?}, which can cause \
+                             data loss or corruption on crash",
+                            data_path, opt
+                        ),
+                    });
+                }
+            }
+        }
+
+        if is_nvme_device(&info.device) {
+            match get_active_scheduler(&info.device) {
+                Ok(scheduler) if scheduler != "none" => {
+                    findings.push(DataDirFinding {
+                        kind: DataDirFindingKind::SuboptimalScheduler,
+                        message: format!(
+                            "data_path {:?} is on an NVMe device using the {:?} IO scheduler \
+                             instead of \"none\"",
+                            data_path, scheduler
+                        ),
+                    });
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    warn!("failed to read IO scheduler"; "data_path" => data_path, "err" => %e);
+                }
+            }
+        }
+
+        Ok(findings)
     }
 
     #[cfg(test)]
@@ -1333,17 +2511,173 @@ securityfs /sys/kernel/security securityfs rw,nosuid,nodev,noexec,relatime 0 0
             let get = get_rotational_info(&tmp_device).unwrap();
             assert_eq!(expect, get);
         }
+
+        #[test]
+        fn test_fs_lacks_stable_odirect() {
+            assert!(!fs_lacks_stable_odirect("ext4", ""));
+            assert!(fs_lacks_stable_odirect("zfs", ""));
+            assert!(fs_lacks_stable_odirect("btrfs", ""));
+            assert!(!fs_lacks_stable_odirect("btrfs", "rw,relatime,nodatacow"));
+        }
+
+        #[test]
+        fn test_collect_data_dir_info_populates_struct_contents() {
+            let tmp_dir = Builder::new()
+                .prefix("test-collect-data-dir-info")
+                .tempdir()
+                .unwrap();
+            let data_path = format!("{}/data1", tmp_dir.path().display());
+            ::std::fs::create_dir(&data_path).unwrap();
+
+            let mninfo = format!(
+                "/dev/fake0 {} ext4 rw,relatime,nobarrier 0 0",
+                &data_path
+            );
+            let mnt_file = format!("{}/mnt.txt", tmp_dir.path().display());
+            create_file(&mnt_file, mninfo.as_bytes());
+
+            let info = collect_data_dir_info(&data_path, &mnt_file).unwrap();
+            assert_eq!(info.fs_type, "ext4");
+            assert_eq!(info.mount_options, "rw,relatime,nobarrier");
+            assert_eq!(info.device, "/dev/fake0");
+            // `/dev/fake0` has no `/sys/block` entry, so the rotational flag
+            // is unknown rather than defaulting to either true or false.
+            assert_eq!(info.rotational, None);
+        }
+
+        #[test]
+        fn test_check_data_dir_reports_unsafe_ext4_mount_options() {
+            let tmp_dir = Builder::new()
+                .prefix("test-check-data-dir-mount-opts")
+                .tempdir()
+                .unwrap();
+            let data_path = format!("{}/data1", tmp_dir.path().display());
+            ::std::fs::create_dir(&data_path).unwrap();
+
+            let mninfo = format!(
+                "/dev/fake0 {} ext4 rw,relatime,nobarrier,nodelalloc 0 0",
+                &data_path
+            );
+            let mnt_file = format!("{}/mnt.txt", tmp_dir.path().display());
+            create_file(&mnt_file, mninfo.as_bytes());
+
+            // `/dev/fake0` has no `/sys/block` entry, so the rotational
+            // check fails softly (logged, not fatal) and only the
+            // mount-option findings, which don't need the device to exist,
+            // are returned.
+            let findings = check_data_dir(&data_path, &mnt_file).unwrap();
+            assert!(
+                findings
+                    .iter()
+                    .filter(|f| f.kind == DataDirFindingKind::UnsafeMountOption)
+                    .count()
+                    == 2,
+                "expected findings for both nobarrier and nodelalloc, got {:?}",
+                findings
+            );
+        }
+
+        #[test]
+        fn test_get_active_scheduler() {
+            let tmp_dir = Builder::new()
+                .prefix("test-get-active-scheduler")
+                .tempdir()
+                .unwrap();
+            let queue_dir = tmp_dir.path().join("queue");
+            ::std::fs::create_dir(&queue_dir).unwrap();
+            create_file(
+                queue_dir.join("scheduler").to_str().unwrap(),
+                b"mq-deadline kyber [none] bfq",
+            );
+
+            let scheduler_path = queue_dir.join("scheduler");
+            let buffer = ::std::fs::read_to_string(&scheduler_path).unwrap();
+            let active = buffer
+                .split_whitespace()
+                .find_map(|w| w.strip_prefix('[').and_then(|w| w.strip_suffix(']')))
+                .unwrap();
+            assert_eq!(active, "none");
+        }
+
+        #[test]
+        fn test_is_nvme_device() {
+            assert!(is_nvme_device("/dev/nvme0n1"));
+            assert!(is_nvme_device("/dev/nvme1n1p2"));
+            assert!(!is_nvme_device("/dev/sda1"));
+            assert!(!is_nvme_device("/dev/mapper/data-lv"));
+        }
     }
 }
 
 #[cfg(target_os = "linux")]
-pub fn check_data_dir(data_path: &str) -> Result<(), ConfigError> {
+pub use self::check_data_dir::{DataDirFinding, DataDirFindingKind, DataDirInfo};
+
+#[cfg(target_os = "linux")]
+pub fn check_data_dir(data_path: &str) -> Result<Vec<DataDirFinding>, ConfigError> {
     self::check_data_dir::check_data_dir(data_path, "/proc/mounts")
 }
 
+#[cfg(target_os = "linux")]
+pub fn collect_data_dir_info(data_path: &str) -> Result<DataDirInfo, ConfigError> {
+    self::check_data_dir::collect_data_dir_info(data_path, "/proc/mounts")
+}
+
 #[cfg(not(target_os = "linux"))]
-pub fn check_data_dir(_data_path: &str) -> Result<(), ConfigError> {
-    Ok(())
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataDirFinding {
+    pub kind: DataDirFindingKind,
+    pub message: String,
+}
+
+#[cfg(not(target_os = "linux"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataDirFindingKind {
+    NotSsd,
+    UnstableODirect,
+    UnsafeMountOption,
+    SuboptimalScheduler,
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn check_data_dir(_data_path: &str) -> Result<Vec<DataDirFinding>, ConfigError> {
+    Ok(Vec::new())
+}
+
+/// Filesystem and block-device facts about a data directory. Only populated
+/// on Linux; other platforms get this best-effort all-default value.
+#[cfg(not(target_os = "linux"))]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DataDirInfo {
+    pub fs_type: String,
+    pub mount_options: String,
+    pub device: String,
+    pub rotational: Option<bool>,
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn collect_data_dir_info(_data_path: &str) -> Result<DataDirInfo, ConfigError> {
+    Ok(DataDirInfo::default())
+}
+
+lazy_static::lazy_static! {
+    static ref LAST_DATA_DIR_INFOS: RwLock<HashMap<String, DataDirInfo>> =
+        RwLock::new(HashMap::default());
+}
+
+/// Records `info` under `name` (e.g. `"rocksdb-data-dir"`) so that
+/// [`last_data_dir_infos`] can later report it for diagnostics purposes.
+pub fn record_data_dir_info(name: &str, info: DataDirInfo) {
+    LAST_DATA_DIR_INFOS
+        .write()
+        .unwrap()
+        .insert(name.to_owned(), info);
+}
+
+/// Returns every [`DataDirInfo`] recorded so far via
+/// [`record_data_dir_info`], keyed by the name it was recorded under. Empty
+/// before the first call.
+pub fn last_data_dir_infos() -> HashMap<String, DataDirInfo> {
+    LAST_DATA_DIR_INFOS.read().unwrap().clone()
 }
 
 fn get_file_count(data_path: &str, extension: &str) -> Result<usize, ConfigError> {
@@ -1384,10 +2718,13 @@ pub fn check_data_dir_empty(data_path: &str, extension: &str) -> Result<(), Conf
     if dir.exists() && !dir.is_file() {
         let count = get_file_count(data_path, extension)?;
         if count > 0 {
-            return Err(ConfigError::Limit(format!(
-                "{}: the number of file with extension {} in directory {} is non-zero, \
-                 got {}, expect 0.",
-                op, extension, data_path, count,
+            return Err(ConfigError::Limit(InvalidConfigValue::new(
+                op,
+                format!(
+                    "0 files with extension {} in directory {}",
+                    extension, data_path
+                ),
+                count.to_string(),
             )));
         }
     }
@@ -1415,33 +2752,211 @@ pub fn check_addr(addr: &str) -> Result<bool, ConfigError> {
 
     // ["Host", "Port"]
     if parts.len() != 2 {
-        return Err(ConfigError::Address(format!("invalid addr: {:?}", addr)));
+        return Err(ConfigError::Address(InvalidConfigValue::new(
+            "addr",
+            "a \"Host:Port\" address",
+            addr,
+        )));
     }
 
     // Check Port.
     let port: u16 = parts[1].parse().map_err(|_| {
-        ConfigError::Address(format!("invalid addr, parse port failed: {:?}", addr))
+        ConfigError::Address(InvalidConfigValue::new(
+            "addr",
+            "a numeric port",
+            addr,
+        ))
     })?;
     // Port = 0 is invalid.
     if port == 0 {
-        return Err(ConfigError::Address(format!(
-            "invalid addr, port can not be 0: {:?}",
-            addr
+        return Err(ConfigError::Address(InvalidConfigValue::new(
+            "addr",
+            "a non-zero port",
+            addr,
         )));
     }
 
     // Check Host.
     if let Err(e) = url::Host::parse(parts[0]) {
-        return Err(ConfigError::Address(format!("invalid addr: {:?}", e)));
+        return Err(ConfigError::Address(InvalidConfigValue::new(
+            "addr",
+            "a valid host",
+            format!("{:?}: {}", addr, e),
+        )));
     }
 
     Ok(false)
 }
 
+/// `normalize_addr` parses `addr` using the same rules as [`check_addr`] and
+/// returns its canonical form, so that addresses which differ only in
+/// formatting (case, a trailing DNS root-label dot, or bracketing of IPv6
+/// literals) compare equal once normalized. The host is lowercased, a
+/// trailing root-label dot is stripped, IPv6 literals are always
+/// bracket-wrapped, and the port is preserved.
+pub fn normalize_addr(addr: &str) -> Result<String, ConfigError> {
+    if let Ok(a) = SocketAddrV4::from_str(addr) {
+        return Ok(format!("{}:{}", a.ip(), a.port()));
+    }
+    if let Ok(a) = SocketAddrV6::from_str(addr) {
+        return Ok(format!("[{}]:{}", a.ip(), a.port()));
+    }
+
+    let parts: Vec<&str> = addr
+        .split(':')
+        .filter(|s| !s.is_empty()) // "Host:" or ":Port" are invalid.
+        .collect();
+
+    // ["Host", "Port"]
+    if parts.len() != 2 {
+        return Err(ConfigError::Address(InvalidConfigValue::new(
+            "addr",
+            "a \"Host:Port\" address",
+            addr,
+        )));
+    }
+
+    // Check Port.
+    let port: u16 = parts[1].parse().map_err(|_| {
+        ConfigError::Address(InvalidConfigValue::new(
+            "addr",
+            "a numeric port",
+            addr,
+        ))
+    })?;
+    if port == 0 {
+        return Err(ConfigError::Address(InvalidConfigValue::new(
+            "addr",
+            "a non-zero port",
+            addr,
+        )));
+    }
+
+    // Strip a trailing root-label dot (e.g. "example.com.") before lowercasing,
+    // so "Example.Com." and "example.com" normalize to the same host.
+    let host = parts[0].strip_suffix('.').unwrap_or(parts[0]);
+    let host = host.to_ascii_lowercase();
+
+    let host = url::Host::parse(&host)
+        .map_err(|e| {
+            ConfigError::Address(InvalidConfigValue::new(
+                "addr",
+                "a valid host",
+                format!("{}: {}", addr, e),
+            ))
+        })?
+        .to_string();
+
+    Ok(format!("{}:{}", host, port))
+}
+
+/// Maximum length PD accepts for a store label key or value.
+const MAX_STORE_LABEL_LEN: usize = 256;
+
+/// Prefixes reserved for PD's own bookkeeping labels; a user-supplied label
+/// key starting with one of these is silently shadowed or rejected once it
+/// reaches PD, so it's better to reject it here where the offending key can
+/// still be named.
+const RESERVED_STORE_LABEL_PREFIXES: &[&str] = &["$", "tikv."];
+
+lazy_static::lazy_static! {
+    static ref STORE_LABEL_KEY_FORMAT: Regex =
+        Regex::new("^[a-z0-9]([-a-z0-9_.]*[a-z0-9])?$").unwrap();
+    static ref STORE_LABEL_VALUE_FORMAT: Regex = Regex::new("^[-a-z0-9_.]*$").unwrap();
+}
+
+/// Validates a set of store labels (`server.labels` / `--labels`) against
+/// the charset, length and naming rules PD enforces at store registration,
+/// so a bad label is caught at config-load time instead of failing much
+/// later when this store tries to join the cluster.
+///
+/// Enforces: keys and values are lowercase alphanumerics plus `-`, `_`, `.`
+/// (matching PD's documented label format); keys and values are no longer
+/// than [`MAX_STORE_LABEL_LEN`] bytes; keys don't start with a reserved
+/// prefix (see [`RESERVED_STORE_LABEL_PREFIXES`]); and no two keys collide
+/// once lowercased, since PD treats label keys case-insensitively.
+pub fn validate_store_labels(labels: &HashMap<String, String>) -> Result<(), ConfigError> {
+    // Group keys by their lowercased form first, and independently of
+    // whatever order `labels` iterates in, so two keys that only differ by
+    // case (which PD's label matching treats as the same key) are always
+    // reported the same way instead of depending on map iteration order.
+    let mut by_lowercased: HashMap<String, Vec<&String>> = HashMap::with_capacity(labels.len());
+    for key in labels.keys() {
+        by_lowercased
+            .entry(key.to_ascii_lowercase())
+            .or_default()
+            .push(key);
+    }
+    if let Some((_, mut colliding)) = by_lowercased
+        .into_iter()
+        .find(|(_, keys)| keys.len() > 1)
+    {
+        colliding.sort();
+        return Err(ConfigError::StoreLabels(format!(
+            "store label keys {:?} collide once lowercased",
+            colliding
+        )));
+    }
+
+    for (key, value) in labels {
+        if key.is_empty() || key.len() > MAX_STORE_LABEL_LEN {
+            return Err(ConfigError::StoreLabels(format!(
+                "store label key {:?} must be 1 to {} bytes long",
+                key, MAX_STORE_LABEL_LEN
+            )));
+        }
+        if value.len() > MAX_STORE_LABEL_LEN {
+            return Err(ConfigError::StoreLabels(format!(
+                "store label value {:?} for key {:?} must be at most {} bytes long",
+                value, key, MAX_STORE_LABEL_LEN
+            )));
+        }
+        if let Some(prefix) = RESERVED_STORE_LABEL_PREFIXES
+            .iter()
+            .find(|prefix| key.starts_with(**prefix))
+        {
+            return Err(ConfigError::StoreLabels(format!(
+                "store label key {:?} uses reserved prefix {:?}",
+                key, prefix
+            )));
+        }
+        if !STORE_LABEL_KEY_FORMAT.is_match(key) {
+            return Err(ConfigError::StoreLabels(format!(
+                "store label key {:?} must match {}",
+                key, *STORE_LABEL_KEY_FORMAT
+            )));
+        }
+        if !STORE_LABEL_VALUE_FORMAT.is_match(value) {
+            return Err(ConfigError::StoreLabels(format!(
+                "store label value {:?} for key {:?} must match {}",
+                value, key, *STORE_LABEL_VALUE_FORMAT
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// A single recorded update in a [`VersionTrack`]'s history.
+#[derive(Clone, Debug)]
+pub struct HistoryEntry {
+    pub at: Instant,
+    pub snapshot: String,
+}
+
+/// History-recording state for a [`VersionTrack`], gated behind a
+/// caller-supplied closure so `VersionTrack<T>` doesn't have to require
+/// `T: Clone + Serialize` just to support auditing.
+struct History<T> {
+    capacity: usize,
+    snapshot: Box<dyn Fn(&T) -> String + Send + Sync>,
+    entries: VecDeque<HistoryEntry>,
+}
+
 #[derive(Default)]
 pub struct VersionTrack<T> {
     value: RwLock<T>,
     version: AtomicU64,
+    history: RwLock<Option<History<T>>>,
 }
 
 impl<T> VersionTrack<T> {
@@ -1449,6 +2964,7 @@ impl<T> VersionTrack<T> {
         VersionTrack {
             value: RwLock::new(value),
             version: AtomicU64::new(1),
+            history: RwLock::new(None),
         }
     }
 
@@ -1456,9 +2972,13 @@ impl<T> VersionTrack<T> {
     where
         F: FnOnce(&mut T) -> Result<O, E>,
     {
-        let res = f(&mut self.value.write().unwrap());
+        let mut value = self.value.write().unwrap();
+        let res = f(&mut value);
         if res.is_ok() {
             self.version.fetch_add(1, Ordering::Release);
+            if let Some(history) = self.history.write().unwrap().as_mut() {
+                history.push(&value);
+            }
         }
         res
     }
@@ -1467,6 +2987,34 @@ impl<T> VersionTrack<T> {
         self.value.read().unwrap()
     }
 
+    /// Starts recording a bounded history of successful `update`s. Each
+    /// entry stores the time of the update and whatever `snapshot` returns
+    /// for the value *after* that update; `snapshot` is only ever called on
+    /// success, so a failed update (an `Err` from the closure passed to
+    /// `update`) records nothing.
+    ///
+    /// Calling this again replaces any previously recorded history.
+    pub fn enable_history(
+        &self,
+        capacity: usize,
+        snapshot: impl Fn(&T) -> String + Send + Sync + 'static,
+    ) {
+        *self.history.write().unwrap() = Some(History {
+            capacity,
+            snapshot: Box::new(snapshot),
+            entries: VecDeque::with_capacity(capacity),
+        });
+    }
+
+    /// Returns the recorded history, oldest first, or an empty `Vec` if
+    /// `enable_history` was never called.
+    pub fn history(&self) -> Vec<HistoryEntry> {
+        match self.history.read().unwrap().as_ref() {
+            Some(history) => history.entries.iter().cloned().collect(),
+            None => Vec::new(),
+        }
+    }
+
     pub fn tracker(self: Arc<Self>, tag: String) -> Tracker<T> {
         Tracker {
             tag,
@@ -1476,6 +3024,21 @@ impl<T> VersionTrack<T> {
     }
 }
 
+impl<T> History<T> {
+    fn push(&mut self, value: &T) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() >= self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(HistoryEntry {
+            at: Instant::now(),
+            snapshot: (self.snapshot)(value),
+        });
+    }
+}
+
 #[derive(Clone, Default)]
 pub struct Tracker<T> {
     tag: String,
@@ -1509,13 +3072,15 @@ impl<T> Tracker<T> {
     }
 }
 
-use std::collections::HashMap;
+use std::collections::HashSet;
 
 /// TomlLine use to parse one line content of a toml file
 #[derive(Debug)]
 enum TomlLine {
     // the `Keys` from "[`Keys`]"
     Table(String),
+    // the `Keys` from "[[`Keys`]]"
+    ArrayTable(String),
     // the `Keys` from "`Keys` = value"
     KvPair(String),
     // Comment, empty line, etc.
@@ -1538,6 +3103,13 @@ impl TomlLine {
 
     fn parse(s: &str) -> TomlLine {
         let s = s.trim();
+        // try to parse an array-of-tables header from format of "[[`Keys`]]"
+        if let Some(k) = s.strip_prefix("[[").and_then(|s| s.strip_suffix("]]")) {
+            return match TomlLine::parse_key(k) {
+                Some(k) => TomlLine::ArrayTable(k),
+                None => TomlLine::Unknown,
+            };
+        }
         // try to parse table from format of "[`Keys`]"
         if let Some(k) = s.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
             return match TomlLine::parse_key(k) {
@@ -1608,7 +3180,7 @@ impl TomlWriter {
     pub fn write_change(&mut self, src: String, mut change: HashMap<String, String>) {
         for line in src.lines() {
             match TomlLine::parse(line) {
-                TomlLine::Table(keys) => {
+                TomlLine::Table(keys) | TomlLine::ArrayTable(keys) => {
                     self.write_current_table(&mut change);
                     self.write(line.as_bytes());
                     self.current_table = keys;
@@ -1634,6 +3206,54 @@ impl TomlWriter {
         self.new_line();
     }
 
+    /// Removes the lines for `keys` (given as fully table-qualified dotted
+    /// keys, e.g. `"raftstore.raft-log-gc-tick-interval"`) from `src`, writing
+    /// the result the same way [`Self::write_change`] does. Keys that don't
+    /// exist in `src` are silently ignored.
+    ///
+    /// A table (including an array-of-tables entry, `[[...]]`) whose every
+    /// key gets removed has its now-empty header removed too, so deleting the
+    /// last key of a table that [`Self::write_change`] had just appended
+    /// doesn't leave a dangling `[table]` header behind.
+    pub fn write_delete(&mut self, src: String, keys: HashSet<String>) {
+        let mut current_table = String::new();
+        // `blocks[i] = (header line, surviving lines, any surviving KvPair?)`.
+        // The preamble before the first header is `blocks[0]` with no header.
+        let mut blocks: Vec<(Option<String>, Vec<String>, bool)> = vec![(None, Vec::new(), false)];
+
+        for line in src.lines() {
+            match TomlLine::parse(line) {
+                TomlLine::Table(keys_) | TomlLine::ArrayTable(keys_) => {
+                    current_table = keys_;
+                    blocks.push((Some(line.to_owned()), Vec::new(), false));
+                }
+                TomlLine::KvPair(k) => {
+                    if !keys.contains(&TomlLine::concat_key(&current_table, &k)) {
+                        let block = blocks.last_mut().unwrap();
+                        block.1.push(line.to_owned());
+                        block.2 = true;
+                    }
+                }
+                TomlLine::Unknown => blocks.last_mut().unwrap().1.push(line.to_owned()),
+            }
+        }
+
+        for (header, lines, has_kv) in blocks {
+            // A table's blank lines and comments don't keep it alive on their
+            // own: once every key under it is gone, drop the header and the
+            // leftover formatting with it.
+            if header.is_some() && !has_kv {
+                continue;
+            }
+            if let Some(header) = header {
+                self.write(header.as_bytes());
+            }
+            for line in lines {
+                self.write(line.as_bytes());
+            }
+        }
+    }
+
     fn write_current_table(&mut self, change: &mut HashMap<String, String>) {
         let keys: Vec<_> = change
             .keys()
@@ -1663,7 +3283,9 @@ impl TomlWriter {
 
 #[macro_export]
 macro_rules! numeric_enum_serializing_mod {
-    ($name:ident $enum:ident { $($variant:ident = $value:expr, )* }) => {
+    ($name:ident $enum:ident {
+        $($variant:ident = $value:expr $(, aliases: [$($alias:expr),+ $(,)?])?, )*
+    }) => {
         pub mod $name {
             use std::fmt;
 
@@ -1708,6 +3330,18 @@ macro_rules! numeric_enum_serializing_mod {
                             if value == stringify!($variant).to_kebab_case() {
                                 return Ok($enum::$variant)
                             }
+                            $($(
+                                if value == $alias {
+                                    $crate::warn!(
+                                        "config value is using a deprecated alias, \
+                                         please migrate to the canonical name";
+                                        "field" => stringify!($enum),
+                                        "alias" => $alias,
+                                        "canonical" => stringify!($variant).to_kebab_case(),
+                                    );
+                                    return Ok($enum::$variant)
+                                }
+                            )+)?
                         )*
                         Err(E::invalid_value(Unexpected::Str(value), &self))
                     }
@@ -1752,6 +3386,25 @@ macro_rules! numeric_enum_serializing_mod {
                     let res = format!("e = \"{}\"\n", s.to_kebab_case());
                     toml::from_str::<EnumHolder>(&res).unwrap_err();
                 }
+
+                #[test]
+                fn test_serde_aliases() {
+                    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+                    struct EnumHolder {
+                        #[serde(with = "super")]
+                        e: $enum,
+                    }
+
+                    $($($(
+                        // An alias deserializes to the same variant as the
+                        // canonical name, and always serializes back out in
+                        // the canonical numeric form, never the alias.
+                        let res = format!("e = \"{}\"\n", $alias);
+                        let h: EnumHolder = toml::from_str(&res).unwrap();
+                        assert!(h.e == $enum::$variant);
+                        assert_eq!(toml::to_string(&h).unwrap(), format!("e = {}\n", $value));
+                    )+)?)*
+                }
             }
         }
     }
@@ -1773,6 +3426,12 @@ pub struct RaftDataStateMachine {
     in_progress_marker: PathBuf,
     source: PathBuf,
     target: PathBuf,
+    /// Set when a non-fatal error was hit while trying to make progress
+    /// (e.g. a leftover trash directory couldn't be removed because the
+    /// filesystem was read-only). The state machine still proceeds, but
+    /// [`Self::recover_report`] lets the caller tell the operator there's
+    /// manual cleanup to do.
+    recover_hint: Option<String>,
 }
 
 impl RaftDataStateMachine {
@@ -1786,20 +3445,40 @@ impl RaftDataStateMachine {
             in_progress_marker,
             source,
             target,
+            recover_hint: None,
         }
     }
 
+    /// Describes the manual action an operator should take if the state
+    /// machine hit a non-fatal error while determining or making progress
+    /// on the migration, or `None` if nothing needs attention.
+    pub fn recover_report(&self) -> Option<&str> {
+        self.recover_hint.as_deref()
+    }
+
     /// Checks if the current condition is a valid state.
     pub fn validate(&self, should_exist: bool) -> std::result::Result<(), String> {
-        if Self::data_exists(&self.source)
-            && Self::data_exists(&self.target)
-            && !self.in_progress_marker.exists()
-        {
-            return Err(format!(
-                "Found multiple raft data sets: {}, {}",
-                self.source.display(),
-                self.target.display()
-            ));
+        if Self::data_exists(&self.source) && Self::data_exists(&self.target) {
+            // Both directories having data is only a valid (Migrating) state
+            // if the marker legitimately protects `self.source`/`self.target`
+            // -- not merely if some marker file happens to exist. This
+            // matters most when source and target use the same format (e.g.
+            // moving a raft-engine directory to a new disk), since then
+            // neither side can be told apart from a genuinely separate,
+            // unrelated raft data set just by sniffing its format.
+            let protected = self.in_progress_marker.exists()
+                && matches!(
+                    self.read_marker(),
+                    Ok(Some(real_source))
+                        if real_source == self.source || real_source == self.target
+                );
+            if !protected {
+                return Err(format!(
+                    "Found multiple raft data sets: {}, {}",
+                    self.source.display(),
+                    self.target.display()
+                ));
+            }
         }
         let exists = Self::data_exists(&self.source) || Self::data_exists(&self.target);
         if exists != should_exist {
@@ -1815,34 +3494,83 @@ impl RaftDataStateMachine {
     /// Returns whether a migration is needed. When it's needed, enters the
     /// `Migrating` state. Otherwise prepares the target directory for
     /// opening.
-    pub fn before_open_target(&mut self) -> bool {
+    ///
+    /// Fails with a descriptive error, without deleting anything, if the
+    /// marker file points at neither `self.source` nor `self.target` -- this
+    /// happens if an operator copies a data directory to another machine
+    /// without the absolute paths lining up, since the marker stores the
+    /// source path verbatim. [`Self::force_reset_marker`] is the documented
+    /// recovery: it lets an operator who has already migrated the data by
+    /// hand discard the stale marker.
+    pub fn before_open_target(&mut self) -> std::result::Result<bool, String> {
         // Clean up trash directory if there is any.
         for p in [&self.source, &self.target] {
             let trash = p.with_extension("REMOVE");
             if trash.exists() {
-                fs::remove_dir_all(&trash).unwrap();
+                if let Err(e) = Self::remove_trash(&trash) {
+                    // A read-only filesystem (or similar transient failure)
+                    // can leave a previous trash directory behind. `trash`
+                    // only differs from `p` by its extension, so it never
+                    // shadows `self.source`/`self.target` themselves: it's
+                    // safe to leave it and let a later call clean it up once
+                    // the filesystem recovers, rather than crash-looping on
+                    // it.
+                    warn!(
+                        "failed to remove leftover trash directory, continuing";
+                        "path" => %trash.display(), "err" => %e,
+                    );
+                    self.recover_hint = Some(format!(
+                        "leftover trash directory {} could not be removed ({}); remove it \
+                         manually once the filesystem is writable again",
+                        trash.display(),
+                        e
+                    ));
+                }
             }
         }
         if !Self::data_exists(&self.source) {
             // Recover from Completed state.
             if self.in_progress_marker.exists() {
-                Self::must_remove(&self.in_progress_marker);
+                Self::must_remove(&self.in_progress_marker).unwrap();
             }
-            return false;
+            return Ok(false);
         } else if self.in_progress_marker.exists() {
-            if let Some(real_source) = self.read_marker() {
+            if let Some(real_source) = self.read_marker().unwrap() {
                 // Recover from Migrating state.
                 if real_source == self.target {
                     if Self::data_exists(&self.target) {
-                        Self::must_remove(&self.source);
-                        return false;
+                        Self::must_remove(&self.source).unwrap();
+                        return Ok(false);
                     }
                     // It's actually in Completed state, just in the reverse
                     // direction. Equivalent to Init state.
+                } else if real_source == self.source {
+                    // The previous run may have crashed after finishing the
+                    // copy into `target` (and recording a manifest for it)
+                    // but before `after_dump_data` could remove `source` and
+                    // the marker. Trust a verified-complete target instead of
+                    // discarding and redoing the copy -- for a same-format
+                    // migration (e.g. raft-engine -> raft-engine), "redoing"
+                    // means recopying a potentially large directory tree.
+                    if self.verify_target_complete().unwrap_or(false) {
+                        Self::must_remove(&self.source).unwrap();
+                        Self::must_remove(&self.in_progress_marker).unwrap();
+                        return Ok(false);
+                    }
+                    Self::must_remove(&self.target).unwrap();
+                    return Ok(true);
                 } else {
-                    assert!(real_source == self.source);
-                    Self::must_remove(&self.target);
-                    return true;
+                    return Err(format!(
+                        "marker file {} points at {}, which is neither the configured source \
+                         {} nor target {}. This usually means the data directory was copied \
+                         from another machine without updating the marker. If the data was \
+                         already migrated by hand, remove the marker with \
+                         RaftDataStateMachine::force_reset_marker (e.g. via tikv-ctl) and retry.",
+                        self.in_progress_marker.display(),
+                        real_source.display(),
+                        self.source.display(),
+                        self.target.display(),
+                    ));
                 }
             } else {
                 // Halfway between Init and Migrating.
@@ -1850,16 +3578,74 @@ impl RaftDataStateMachine {
             }
         }
         // Init -> Migrating.
-        self.write_marker();
-        true
+        self.write_marker().unwrap();
+        Ok(true)
+    }
+
+    /// Discards the `MIGRATING-RAFT` marker without touching the source or
+    /// target directories. Intended as a maintenance escape hatch (e.g. for
+    /// `tikv-ctl`) for when [`Self::before_open_target`] reports that the
+    /// marker points outside of `source`/`target` because the data
+    /// directory was migrated by hand. Returns whether a marker was present
+    /// to remove.
+    pub fn force_reset_marker(&self) -> io::Result<bool> {
+        if !self.in_progress_marker.exists() {
+            return Ok(false);
+        }
+        Self::must_remove(&self.in_progress_marker)?;
+        Ok(true)
     }
 
     /// Exits the `Migrating` state and enters the `Completed` state.
     pub fn after_dump_data(&mut self) {
         assert!(Self::data_exists(&self.source));
         assert!(Self::data_exists(&self.target));
-        Self::must_remove_except(&self.source, &self.target); // Enters the `Completed` state.
-        Self::must_remove(&self.in_progress_marker);
+        // Record how `target` looks once the copy is done, so a crash before
+        // `source` and the marker are fully removed doesn't force a
+        // (possibly very expensive) redo of the copy on the next start; see
+        // `verify_target_complete`.
+        self.write_target_manifest().unwrap();
+        Self::must_remove_except(&self.source, &self.target).unwrap(); // Enters the `Completed` state.
+        Self::must_remove(&self.in_progress_marker).unwrap();
+    }
+
+    /// Returns whether `target` currently matches the file-count/total-size
+    /// manifest recorded by [`Self::write_target_manifest`] in the marker
+    /// file, i.e. whether the copy into `target` has already completed.
+    /// `Ok(false)` (not an error) if no manifest was recorded yet, since that
+    /// just means the copy hasn't been checkpointed as complete.
+    fn verify_target_complete(&self) -> io::Result<bool> {
+        let recorded = match self.read_target_manifest()? {
+            Some(m) => m,
+            None => return Ok(false),
+        };
+        Ok(TargetManifest::compute(&self.target)? == recorded)
+    }
+
+    fn write_target_manifest(&self) -> io::Result<()> {
+        use std::io::Write;
+        let manifest = TargetManifest::compute(&self.target)?;
+        let mut f = fs::OpenOptions::new()
+            .append(true)
+            .open(&self.in_progress_marker)?;
+        f.write_all(manifest.to_marker_suffix().as_bytes())?;
+        f.sync_all()?;
+        Self::sync_dir(&self.root)
+    }
+
+    // Returns the manifest appended to the marker by `write_target_manifest`,
+    // or `None` if the marker doesn't have one (yet), including if it was
+    // only partially written before a crash.
+    fn read_target_manifest(&self) -> io::Result<Option<TargetManifest>> {
+        let marker = fs::read_to_string(&self.in_progress_marker)?;
+        let Some(source_end) = marker.find("//") else {
+            return Ok(None);
+        };
+        let suffix = &marker[source_end + 2..];
+        Ok(suffix
+            .strip_prefix("MANIFEST:")
+            .and_then(|body| body.strip_suffix("//"))
+            .and_then(TargetManifest::parse))
     }
 
     // `after_dump_data` involves two atomic operations, insert a check point
@@ -1868,78 +3654,167 @@ impl RaftDataStateMachine {
     fn after_dump_data_with_check<F: Fn()>(&mut self, check: &F) {
         assert!(Self::data_exists(&self.source));
         assert!(Self::data_exists(&self.target));
-        Self::must_remove(&self.source); // Enters the `Completed` state.
+        Self::must_remove(&self.source).unwrap(); // Enters the `Completed` state.
         check();
-        Self::must_remove(&self.in_progress_marker);
+        Self::must_remove(&self.in_progress_marker).unwrap();
     }
 
-    fn write_marker(&self) {
+    fn write_marker(&self) -> io::Result<()> {
         use std::io::Write;
-        let mut f = fs::File::create(&self.in_progress_marker).unwrap();
-        f.write_all(self.source.to_str().unwrap().as_bytes())
-            .unwrap();
-        f.sync_all().unwrap();
-        f.write_all(b"//").unwrap();
-        f.sync_all().unwrap();
-        Self::sync_dir(&self.root);
-    }
-
-    // Assumes there is a marker file. Returns None when the content of marker file
-    // is incomplete.
-    fn read_marker(&self) -> Option<PathBuf> {
-        let marker = fs::read_to_string(&self.in_progress_marker).unwrap();
-        if marker.ends_with("//") {
-            Some(PathBuf::from(&marker[..marker.len() - 2]))
-        } else {
-            None
+        let mut f = fs::File::create(&self.in_progress_marker)?;
+        f.write_all(self.source.to_str().unwrap().as_bytes())?;
+        f.sync_all()?;
+        f.write_all(b"//")?;
+        f.sync_all()?;
+        Self::sync_dir(&self.root)
+    }
+
+    // Assumes there is a marker file. Returns None when the content of the
+    // marker file is incomplete (the write of the base `<source>//` was torn
+    // by a crash). The marker may carry a `MANIFEST:...` suffix appended
+    // later by `write_target_manifest`; deliberately looks for the *first*
+    // "//" rather than requiring the whole file to end with one, so that a
+    // marker torn mid-manifest-append (which only happens once the base
+    // `<source>//` is long since complete) still decodes a valid source
+    // path here -- see `read_target_manifest` for the manifest itself.
+    fn read_marker(&self) -> io::Result<Option<PathBuf>> {
+        let marker = fs::read_to_string(&self.in_progress_marker)?;
+        Ok(marker
+            .find("//")
+            .map(|source_end| PathBuf::from(&marker[..source_end])))
+    }
+
+    // Removes the leftover `trash` directory found by `before_open_target`.
+    // Split out purely so a failpoint can simulate a read-only filesystem.
+    fn remove_trash(trash: &Path) -> io::Result<()> {
+        fail_point!("before_open_target_remove_trash", |_| Err(
+            io::Error::from_raw_os_error(libc::EROFS)
+        ));
+        fs::remove_dir_all(trash)
+    }
+
+    fn must_remove(path: &Path) -> io::Result<()> {
+        if !path.exists() {
+            return Ok(());
         }
-    }
-
-    fn must_remove(path: &Path) {
-        if path.exists() {
-            if path.is_dir() {
-                info!("Removing directory"; "path" => %path.display());
-                let trash = path.with_extension("REMOVE");
-                Self::must_rename_dir(path, &trash);
-                fs::remove_dir_all(&trash).unwrap();
-            } else {
-                info!("Removing file"; "path" => %path.display());
-                fs::remove_file(path).unwrap();
-                Self::sync_dir(path.parent().unwrap());
+        if path.is_dir() {
+            info!("Removing directory"; "path" => %path.display());
+            // Resolve symlinks first: renaming `path` when it's a symlink
+            // only moves the link itself, leaving the real directory (and
+            // the data in it) untouched and computing the trash path from
+            // the wrong place.
+            let real_path = fs::canonicalize(path)?;
+            let trash = real_path.with_extension("REMOVE");
+            Self::must_rename_dir(&real_path, &trash).map_err(|e| {
+                io::Error::new(
+                    e.kind(),
+                    format!(
+                        "failed to move {} to trash {}: {}",
+                        real_path.display(),
+                        trash.display(),
+                        e
+                    ),
+                )
+            })?;
+            fail_point!("must_remove_trash_dir", |_| Err(io::Error::from_raw_os_error(
+                libc::EROFS
+            )));
+            fs::remove_dir_all(&trash).map_err(|e| {
+                io::Error::new(
+                    e.kind(),
+                    format!("failed to remove trash dir {}: {}", trash.display(), e),
+                )
+            })?;
+            if real_path != path {
+                fs::remove_file(path)?;
             }
+        } else {
+            info!("Removing file"; "path" => %path.display());
+            fs::remove_file(path)?;
+            Self::sync_dir(path.parent().unwrap())?;
         }
+        Ok(())
     }
 
     // Remove all files and directories under `remove_path` except `retain_path`.
-    fn must_remove_except(remove_path: &Path, retain_path: &Path) {
+    fn must_remove_except(remove_path: &Path, retain_path: &Path) -> io::Result<()> {
         if !remove_path.exists() {
             info!("Path not exists"; "path" => %remove_path.display());
-            return;
+            return Ok(());
         }
         if !remove_path.is_dir() {
             info!("Path is not a directory, so remove directly"; "path" => %remove_path.display());
-            Self::must_remove(remove_path);
-            return;
+            return Self::must_remove(remove_path);
         }
         if !retain_path.starts_with(remove_path) {
             info!("Removing directory as retain path is not under remove path"; "retain path" => %retain_path.display(), "remove path" => %remove_path.display());
-            Self::must_remove(remove_path);
-            return;
+            return Self::must_remove(remove_path);
         }
 
-        for entry in fs::read_dir(remove_path).unwrap() {
-            let sub_path = entry.unwrap().path();
+        for entry in fs::read_dir(remove_path)? {
+            let sub_path = entry?.path();
             if sub_path != retain_path {
-                Self::must_remove(&sub_path);
+                Self::must_remove(&sub_path)?;
+            }
+        }
+        Ok(())
+    }
+
+    // Split out so a failpoint can force the `EXDEV` fallback path below
+    // without having to actually set up a cross-filesystem test directory.
+    fn try_rename_dir(from: &Path, to: &Path) -> io::Result<()> {
+        fail_point!("must_rename_dir_cross_device", |_| Err(
+            io::Error::from_raw_os_error(libc::EXDEV)
+        ));
+        fs::rename(from, to)
+    }
+
+    // `fs::rename` fails with `EXDEV` when `from` and `to` are on different
+    // filesystems, which happens whenever an operator points the raft data
+    // dir somewhere other than the KV data dir's filesystem. Fall back to a
+    // recursive copy in that case; `from` is only removed once every byte
+    // has been copied and fsynced, so a crash mid-copy leaves `from` intact
+    // and the migration resumes from the `Init` state.
+    fn must_rename_dir(from: &Path, to: &Path) -> io::Result<()> {
+        match Self::try_rename_dir(from, to) {
+            Ok(()) => {
+                let mut dir = to.to_path_buf();
+                assert!(dir.pop());
+                Self::sync_dir(&dir)
             }
+            Err(e) if e.raw_os_error() == Some(libc::EXDEV) => {
+                info!(
+                    "cross-filesystem rename, falling back to copy";
+                    "from" => %from.display(), "to" => %to.display()
+                );
+                Self::copy_dir_cross_device(from, to)
+            }
+            Err(e) => Err(e),
         }
     }
 
-    fn must_rename_dir(from: &Path, to: &Path) {
-        fs::rename(from, to).unwrap();
+    fn copy_dir_cross_device(from: &Path, to: &Path) -> io::Result<()> {
+        Self::copy_dir_all(from, to)?;
         let mut dir = to.to_path_buf();
         assert!(dir.pop());
-        Self::sync_dir(&dir);
+        Self::sync_dir(&dir)?;
+        fs::remove_dir_all(from)?;
+        Self::sync_dir(from.parent().unwrap())
+    }
+
+    fn copy_dir_all(from: &Path, to: &Path) -> io::Result<()> {
+        fs::create_dir_all(to)?;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            let dest = to.join(entry.file_name());
+            if entry.file_type()?.is_dir() {
+                Self::copy_dir_all(&entry.path(), &dest)?;
+            } else {
+                fs::copy(entry.path(), &dest)?;
+                fs::File::open(&dest)?.sync_all()?;
+            }
+        }
+        Self::sync_dir(to)
     }
 
     #[inline]
@@ -1973,8 +3848,57 @@ impl RaftDataStateMachine {
         Self::raftengine_exists(path) || Self::raftdb_exists(path)
     }
 
-    fn sync_dir(dir: &Path) {
-        fs::File::open(dir).and_then(|d| d.sync_all()).unwrap();
+    fn sync_dir(dir: &Path) -> io::Result<()> {
+        fail_point!("sync_dir_erofs", |_| Err(io::Error::from_raw_os_error(
+            libc::EROFS
+        )));
+        fs::File::open(dir)?.sync_all()
+    }
+}
+
+/// A cheap fingerprint of a directory tree's contents, used to tell whether a
+/// copy into `target` fully completed before a crash. Not a substitute for a
+/// checksum: it's meant to catch "the copy was interrupted", not bit rot.
+#[derive(Debug, PartialEq, Eq)]
+struct TargetManifest {
+    file_count: u64,
+    total_size: u64,
+}
+
+impl TargetManifest {
+    fn compute(path: &Path) -> io::Result<Self> {
+        let mut manifest = TargetManifest {
+            file_count: 0,
+            total_size: 0,
+        };
+        manifest.add_dir(path)?;
+        Ok(manifest)
+    }
+
+    fn add_dir(&mut self, path: &Path) -> io::Result<()> {
+        for entry in fs::read_dir(path)? {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            if metadata.is_dir() {
+                self.add_dir(&entry.path())?;
+            } else {
+                self.file_count += 1;
+                self.total_size += metadata.len();
+            }
+        }
+        Ok(())
+    }
+
+    fn to_marker_suffix(&self) -> String {
+        format!("MANIFEST:{},{}//", self.file_count, self.total_size)
+    }
+
+    fn parse(body: &str) -> Option<Self> {
+        let (file_count, total_size) = body.split_once(',')?;
+        Some(TargetManifest {
+            file_count: file_count.parse().ok()?,
+            total_size: total_size.parse().ok()?,
+        })
     }
 }
 
@@ -1982,6 +3906,7 @@ impl RaftDataStateMachine {
 mod tests {
     use std::{fs::File, io::Write, path::Path};
 
+    use serde_json::json;
     use tempfile::Builder;
 
     use super::*;
@@ -2003,6 +3928,74 @@ mod tests {
         assert_eq!(ReadableSize::mb(2) / ReadableSize::kb(1), 2048);
     }
 
+    #[test]
+    fn test_readable_size_arithmetic() {
+        assert_eq!(
+            ReadableSize::mb(1) + ReadableSize::mb(2),
+            ReadableSize::mb(3)
+        );
+        assert_eq!(
+            ReadableSize::mb(3) - ReadableSize::mb(1),
+            ReadableSize::mb(2)
+        );
+
+        assert_eq!(ReadableSize(u64::MAX).checked_add(ReadableSize(1)), None);
+        assert_eq!(ReadableSize(0).checked_sub(ReadableSize(1)), None);
+        assert_eq!(
+            ReadableSize(u64::MAX).saturating_add(ReadableSize(1)),
+            ReadableSize(u64::MAX)
+        );
+        assert_eq!(
+            ReadableSize(0).saturating_sub(ReadableSize(1)),
+            ReadableSize(0)
+        );
+
+        let budgets = vec![
+            ReadableSize::mb(1),
+            ReadableSize::mb(2),
+            ReadableSize::mb(4),
+        ];
+        assert_eq!(
+            budgets.into_iter().sum::<ReadableSize>(),
+            ReadableSize::mb(7)
+        );
+        // `Sum` saturates instead of panicking even in debug builds.
+        assert_eq!(
+            vec![ReadableSize(u64::MAX), ReadableSize(1)]
+                .into_iter()
+                .sum::<ReadableSize>(),
+            ReadableSize(u64::MAX)
+        );
+    }
+
+    #[test]
+    fn test_readable_size_exact_form() {
+        // Just above and below a power-of-two boundary: the human-readable
+        // form changes shape, but the exact form never does.
+        assert_eq!(ReadableSize(GIB).to_exact_string(), "1073741824");
+        assert_eq!(ReadableSize(GIB).to_string(), "1GiB");
+        assert_eq!(ReadableSize(GIB + 1).to_exact_string(), "1073741825");
+        assert_eq!(ReadableSize(GIB + 1).to_string(), "1073741825B");
+
+        assert_eq!(format!("{:#}", ReadableSize(GIB)), "1073741824");
+        assert_eq!(format!("{:#}", ReadableSize(GIB + 1)), "1073741825");
+        assert_eq!(format!("{:#}", ReadableSize(0)), "0");
+
+        #[derive(Serialize)]
+        struct SizeHolder {
+            s: ReadableSize,
+        }
+        let c = SizeHolder {
+            s: ReadableSize(GIB),
+        };
+        assert_eq!(toml::to_string(&c).unwrap(), "s = \"1GiB\"\n");
+        let exact = with_exact_byte_sizes(|| toml::to_string(&c).unwrap());
+        assert_eq!(exact, "s = \"1073741824\"\n");
+        // The scope doesn't leak: serialization reverts to the human form
+        // once `with_exact_byte_sizes` returns.
+        assert_eq!(toml::to_string(&c).unwrap(), "s = \"1GiB\"\n");
+    }
+
     #[test]
     fn test_parse_readable_size() {
         #[derive(Serialize, Deserialize)]
@@ -2080,7 +4073,7 @@ mod tests {
 
         let illegal_cases = vec![
             "0.5kb", "0.5kB", "0.5Kb", "0.5k", "0.5g", "b", "gb", "1b", "B", "1K24B", " 5_KB",
-            "4B7", "5M_",
+            "4B7", "5M_", "-1MB", "-0.5KB", "1e400MB",
         ];
         for src in illegal_cases {
             let src_str = format!("s = {:?}", src);
@@ -2161,6 +4154,53 @@ mod tests {
         toml::from_str::<SizeHolder>("s = \"45%\"").unwrap_err();
     }
 
+    #[test]
+    fn test_readable_size_or_ratio_resolve() {
+        assert_eq!(
+            ReadableSizeOrRatio::Size(ReadableSize::mb(8)).resolve(ReadableSize::gb(1).0),
+            ReadableSize::mb(8)
+        );
+        assert_eq!(
+            ReadableSizeOrRatio::Ratio(0.5).resolve(ReadableSize::gb(1).0),
+            ReadableSize::mb(512)
+        );
+        assert_eq!(
+            "37.5%"
+                .parse::<ReadableSizeOrRatio>()
+                .unwrap()
+                .resolve(ReadableSize::gb(8).0),
+            ReadableSize::gb(3)
+        );
+
+        "0%".parse::<ReadableSizeOrRatio>().unwrap_err();
+        "101%".parse::<ReadableSizeOrRatio>().unwrap_err();
+        "-1%".parse::<ReadableSizeOrRatio>().unwrap_err();
+        "abc%".parse::<ReadableSizeOrRatio>().unwrap_err();
+    }
+
+    #[test]
+    fn test_toml_readable_size_or_ratio() {
+        #[derive(Debug, Serialize, Deserialize)]
+        struct SizeOrRatioHolder {
+            s: ReadableSizeOrRatio,
+        }
+
+        // The percentage form round-trips as a percentage, not a resolved size.
+        let res: SizeOrRatioHolder = toml::from_str("s = \"45%\"").unwrap();
+        assert_eq!(res.s, ReadableSizeOrRatio::Ratio(0.45));
+        let serialized = toml::to_string(&res).unwrap();
+        assert_eq!(serialized, "s = \"45%\"\n");
+
+        // The absolute form round-trips as an absolute size.
+        let res: SizeOrRatioHolder = toml::from_str("s = \"8MiB\"").unwrap();
+        assert_eq!(res.s, ReadableSizeOrRatio::Size(ReadableSize::mb(8)));
+        let serialized = toml::to_string(&res).unwrap();
+        assert_eq!(serialized, "s = \"8MiB\"\n");
+
+        toml::from_str::<SizeOrRatioHolder>("s = \"0%\"").unwrap_err();
+        toml::from_str::<SizeOrRatioHolder>("s = \"101%\"").unwrap_err();
+    }
+
     #[test]
     fn test_duration_construction() {
         let mut dur = ReadableDuration::micros(2_010_010);
@@ -2187,6 +4227,44 @@ mod tests {
         assert_eq!(dur.as_millis(), 7200000);
     }
 
+    #[test]
+    fn test_readable_duration_arithmetic() {
+        assert_eq!(
+            ReadableDuration::secs(1) + ReadableDuration::secs(2),
+            ReadableDuration::secs(3)
+        );
+        assert_eq!(
+            ReadableDuration::secs(3) - ReadableDuration::secs(1),
+            ReadableDuration::secs(2)
+        );
+
+        let max = ReadableDuration(Duration::MAX);
+        assert_eq!(max.checked_add(ReadableDuration::secs(1)), None);
+        assert_eq!(ReadableDuration::ZERO.checked_sub(ReadableDuration::secs(1)), None);
+        assert_eq!(max.saturating_add(ReadableDuration::secs(1)), max);
+        assert_eq!(
+            ReadableDuration::ZERO.saturating_sub(ReadableDuration::secs(1)),
+            ReadableDuration::ZERO
+        );
+
+        let timeouts = vec![
+            ReadableDuration::secs(1),
+            ReadableDuration::secs(2),
+            ReadableDuration::secs(4),
+        ];
+        assert_eq!(
+            timeouts.into_iter().sum::<ReadableDuration>(),
+            ReadableDuration::secs(7)
+        );
+        // `Sum` saturates instead of panicking even in debug builds.
+        assert_eq!(
+            vec![max, ReadableDuration::secs(1)]
+                .into_iter()
+                .sum::<ReadableDuration>(),
+            max
+        );
+    }
+
     #[test]
     fn test_parse_readable_duration() {
         #[derive(Serialize, Deserialize)]
@@ -2219,14 +4297,25 @@ mod tests {
             assert_eq!(res_dur.d.0, d.d.0);
         }
 
-        let decode_cases = vec![(" 0.5 h2m ", 3600 / 2 + 2 * 60, 0)];
+        let decode_cases = vec![
+            (" 0.5 h2m ", 3600 / 2 + 2 * 60, 0),
+            // ISO-8601: a leading "P" selects this parser instead.
+            ("PT0.5S", 0, 500),
+            ("P2DT3H", 2 * 24 * 3600 + 3 * 3600, 0),
+            ("PT1H2M3S", 3600 + 2 * 60 + 3, 0),
+        ];
         for (src, secs, ms) in decode_cases {
             let src = format!("d = {:?}", src);
             let res: DurHolder = toml::from_str(&src).unwrap();
             assert_eq!(res.d.0, Duration::new(secs, ms * 1_000_000));
         }
 
-        let illegal_cases = vec!["1H", "1M", "1S", "1MS", "1h1h", "h"];
+        let illegal_cases = vec![
+            "1H", "1M", "1S", "1MS", "1h1h", "h",
+            "P1M", // months are not a fixed duration.
+            "P1Y", // nor are years.
+            "P", "PT", // no components at all.
+        ];
         for src in illegal_cases {
             let src_str = format!("d = {:?}", src);
             assert!(toml::from_str::<DurHolder>(&src_str).is_err(), "{}", src);
@@ -2234,6 +4323,98 @@ mod tests {
         assert!(toml::from_str::<DurHolder>("d = 23").is_err());
     }
 
+    #[test]
+    fn test_readable_duration_exact_form() {
+        assert_eq!(ReadableDuration::secs(1).to_exact_string(), "1000");
+        assert_eq!(ReadableDuration::millis(1500).to_exact_string(), "1500");
+
+        #[derive(Serialize)]
+        struct DurHolder {
+            d: ReadableDuration,
+        }
+        let c = DurHolder {
+            d: ReadableDuration::secs(1),
+        };
+        assert_eq!(toml::to_string(&c).unwrap(), "d = \"1s\"\n");
+        let exact = with_exact_byte_sizes(|| toml::to_string(&c).unwrap());
+        assert_eq!(exact, "d = \"1000\"\n");
+        assert_eq!(toml::to_string(&c).unwrap(), "d = \"1s\"\n");
+    }
+
+    #[test]
+    fn test_canonicalize_config_normalizes_sizes_and_durations() {
+        let value = json!({
+            "storage": {
+                "block-cache-size": "1GiB",
+                "flush-interval": "1s",
+            },
+            "label": "us-east-1h",
+            "some-count": "5",
+        });
+        let canonical = value.canonicalize();
+        assert_eq!(
+            canonical,
+            json!({
+                "storage": {
+                    "block-cache-size": "1073741824",
+                    "flush-interval": "1000",
+                },
+                // Not a size or duration string, so left untouched even
+                // though it happens to end in a unit-like letter.
+                "label": "us-east-1h",
+                // Bare digit strings are never reinterpreted as sizes.
+                "some-count": "5",
+            })
+        );
+    }
+
+    #[test]
+    fn test_config_semantic_diff_empty_for_equivalent_configs() {
+        let a = json!({
+            "storage": {"block-cache-size": "1GiB"},
+            "raftstore": {"raft-base-tick-interval": "1s"},
+        });
+        let b = json!({
+            "storage": {"block-cache-size": "1024MiB"},
+            "raftstore": {"raft-base-tick-interval": "1000ms"},
+        });
+        assert_eq!(config_semantic_diff(&a, &b), vec![]);
+    }
+
+    #[test]
+    fn test_config_semantic_diff_reports_path_for_real_difference() {
+        let a = json!({
+            "storage": {"block-cache-size": "1GiB"},
+            "raftstore": {"raft-base-tick-interval": "1s"},
+        });
+        let b = json!({
+            "storage": {"block-cache-size": "2GiB"},
+            "raftstore": {"raft-base-tick-interval": "1s"},
+        });
+        assert_eq!(
+            config_semantic_diff(&a, &b),
+            vec![DiffEntry {
+                path: "/storage/block-cache-size".to_owned(),
+                before: Value::String("1073741824".to_owned()),
+                after: Value::String("2147483648".to_owned()),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_config_semantic_diff_reports_fields_present_on_only_one_side() {
+        let a = json!({"storage": {"block-cache-size": "1GiB"}});
+        let b = json!({"storage": {"block-cache-size": "1GiB", "extra": "on"}});
+        assert_eq!(
+            config_semantic_diff(&a, &b),
+            vec![DiffEntry {
+                path: "/storage/extra".to_owned(),
+                before: Value::Null,
+                after: Value::String("on".to_owned()),
+            }]
+        );
+    }
+
     #[test]
     fn test_readable_offset_time() {
         let decode_cases = vec![
@@ -2299,6 +4480,74 @@ mod tests {
         assert!(time.hour_minutes_matches(&dt));
     }
 
+    #[test]
+    fn test_readable_offset_time_seconds() {
+        let with_seconds = "02:30:15 +08:00".parse::<ReadableOffsetTime>().unwrap();
+        assert_eq!(
+            with_seconds,
+            ReadableOffsetTime(
+                NaiveTime::from_hms_opt(2, 30, 15).unwrap(),
+                FixedOffset::east_opt(3600 * 8).unwrap(),
+            )
+        );
+        // Round-trips through Display since the seconds are non-zero.
+        assert_eq!(format!("{}", with_seconds), "02:30:15 +08:00");
+
+        // Existing minute-grained configuration is unaffected: it parses the
+        // same and still round-trips without a seconds component.
+        let without_seconds = "02:30 +08:00".parse::<ReadableOffsetTime>().unwrap();
+        assert_eq!(format!("{}", without_seconds), "02:30 +08:00");
+    }
+
+    #[test]
+    fn test_readable_recurrence() {
+        let recurrence = "01:00 +08:00 every 4h"
+            .parse::<ReadableRecurrence>()
+            .unwrap();
+        assert_eq!(
+            recurrence,
+            ReadableRecurrence::new(
+                ReadableOffsetTime(
+                    NaiveTime::from_hms_opt(1, 0, 0).unwrap(),
+                    FixedOffset::east_opt(3600 * 8).unwrap(),
+                ),
+                ReadableDuration(Duration::from_secs(4 * 3600)),
+            )
+            .unwrap()
+        );
+        assert_eq!(format!("{}", recurrence), "01:00 +08:00 every 4h");
+
+        // Fires at start, then every 4h: 01:00, 05:00, 09:00, ...
+        let scheduled = DateTime::parse_from_rfc3339("2023-10-27T01:00:00+08:00").unwrap();
+        let also_scheduled = DateTime::parse_from_rfc3339("2023-10-27T05:00:00+08:00").unwrap();
+        let not_scheduled = DateTime::parse_from_rfc3339("2023-10-27T06:00:00+08:00").unwrap();
+        assert!(recurrence.is_scheduled_this_minute(&scheduled));
+        assert!(recurrence.is_scheduled_this_minute(&also_scheduled));
+        assert!(!recurrence.is_scheduled_this_minute(&not_scheduled));
+
+        // An interval that does not evenly divide 24h resets at midnight, so
+        // the last firing before midnight and `start` itself are closer
+        // together than `interval`: starting at 01:00 every 5h fires at
+        // 01:00, 06:00, 11:00, 16:00, 21:00, then wraps to 01:00 the next day
+        // (only 4h after 21:00, not 5h).
+        let drifting = "01:00 +00:00 every 5h".parse::<ReadableRecurrence>().unwrap();
+        let last_before_midnight =
+            DateTime::parse_from_rfc3339("2023-10-27T21:00:00+00:00").unwrap();
+        let next_day_start = DateTime::parse_from_rfc3339("2023-10-28T01:00:00+00:00").unwrap();
+        assert!(drifting.is_scheduled_this_minute(&last_before_midnight));
+        assert!(drifting.is_scheduled_this_minute(&next_day_start));
+
+        // Intervals shorter than a minute are rejected outright.
+        assert!(
+            ReadableRecurrence::new(
+                ReadableOffsetTime(NaiveTime::from_hms_opt(0, 0, 0).unwrap(), local_offset()),
+                ReadableDuration(Duration::from_secs(30)),
+            )
+            .is_err()
+        );
+        assert!("01:00 every 30s".parse::<ReadableRecurrence>().is_err());
+    }
+
     #[test]
     fn test_readable_schedule() {
         // Tests HHMM offsets for timezones.
@@ -2369,28 +4618,121 @@ mod tests {
                     .collect::<Vec<_>>(),
             );
 
-            let schedule2 = ReadableSchedule::from_str(strs).unwrap();
-            assert_eq!(schedule, schedule2);
+            let schedule2 = ReadableSchedule::from_str(strs).unwrap();
+            assert_eq!(schedule, schedule2);
+
+            let ConfigValue::Schedule(config_value) = ConfigValue::from(schedule) else {
+                unreachable!()
+            };
+            assert_eq!(config_value, vec_strs);
+            assert_eq!(
+                ReadableSchedule::try_from(ConfigValue::Schedule(config_value)).unwrap(),
+                schedule2
+            );
+        };
+
+        check_parse(
+            vec!["09:30 +00:00".to_owned(), "23:00 +00:00".to_owned()],
+            "[\"09:30 +00:00\", \"23:00 +00:00\"]",
+        );
+
+        check_parse(
+            vec!["11:30 +02:00".to_owned(), "13:00 +02:00".to_owned()],
+            "[\"11:30 +02:00\", \"13:00 +02:00\"]",
+        );
+    }
+
+    #[test]
+    fn test_readable_schedule_try_from_bad_config_value_does_not_panic() {
+        let bad = ConfigValue::Schedule(vec!["not a time".to_owned()]);
+        assert!(ReadableSchedule::try_from(bad).is_err());
+
+        let wrong_variant = ConfigValue::Size(0);
+        assert!(ReadableSchedule::try_from(wrong_variant).is_err());
+    }
+
+    #[test]
+    fn test_readable_schedule_dedups_entries_that_coincide_once_normalized_to_utc() {
+        // "01:00 +01:00" and "00:00 +00:00" both name the same instant.
+        let schedule = ReadableSchedule::from_str("[\"01:00 +01:00\", \"00:00 +00:00\"]").unwrap();
+        assert_eq!(schedule.0.len(), 1);
+        assert!(schedule.validate().is_ok());
+
+        let with_duplicate = ReadableSchedule(vec![
+            ReadableOffsetTime::from_str("01:00 +01:00").unwrap(),
+            ReadableOffsetTime::from_str("00:00 +00:00").unwrap(),
+        ]);
+        assert!(with_duplicate.validate().is_err());
+    }
+
+    #[test]
+    fn test_readable_window_overnight() {
+        // 22:00 - 02:30, both in the same offset, crosses midnight.
+        let window = ReadableWindow::from_str("22:00 +00:00 - 02:30 +00:00").unwrap();
+
+        let inside = [
+            "2023-10-27T23:00:00-00:00",
+            "2023-10-28T00:15:00-00:00",
+            "2023-10-27T22:00:00-00:00",
+        ];
+        for t in inside {
+            let dt = DateTime::parse_from_rfc3339(t).unwrap();
+            assert!(window.contains(&dt), "expected {} inside window", t);
+        }
+
+        let outside = [
+            "2023-10-27T02:30:00-00:00",
+            "2023-10-27T12:00:00-00:00",
+            "2023-10-27T21:59:59-00:00",
+        ];
+        for t in outside {
+            let dt = DateTime::parse_from_rfc3339(t).unwrap();
+            assert!(!window.contains(&dt), "expected {} outside window", t);
+        }
+    }
+
+    #[test]
+    fn test_readable_window_differing_offsets() {
+        // Start at 23:00 +09:00 (== 14:00 UTC), end at 01:00 +00:00 (== 01:00
+        // UTC), so the window is [14:00, 01:00) UTC, crossing midnight.
+        let window = ReadableWindow::from_str("23:00 +09:00 - 01:00 +00:00").unwrap();
+
+        let dt = DateTime::parse_from_rfc3339("2023-10-27T20:00:00-00:00").unwrap();
+        assert!(window.contains(&dt));
+        let dt = DateTime::parse_from_rfc3339("2023-10-27T00:30:00-00:00").unwrap();
+        assert!(window.contains(&dt));
+        let dt = DateTime::parse_from_rfc3339("2023-10-27T10:00:00-00:00").unwrap();
+        assert!(!window.contains(&dt));
+    }
+
+    #[test]
+    fn test_readable_window_zero_length() {
+        let window = ReadableWindow::from_str("10:00 +00:00 - 10:00 +00:00").unwrap();
+        for t in [
+            "2023-10-27T10:00:00-00:00",
+            "2023-10-27T00:00:00-00:00",
+            "2023-10-27T23:59:59-00:00",
+        ] {
+            let dt = DateTime::parse_from_rfc3339(t).unwrap();
+            assert!(!window.contains(&dt));
+        }
+    }
 
-            let ConfigValue::Schedule(config_value) = ConfigValue::from(schedule) else {
-                unreachable!()
-            };
-            assert_eq!(config_value, vec_strs);
-            assert_eq!(
-                ReadableSchedule::from(ConfigValue::Schedule(config_value)),
-                schedule2
-            );
-        };
+    #[test]
+    fn test_readable_window_parse_and_config_value() {
+        let window = ReadableWindow::from_str("22:00 +08:00 - 02:30 +08:00").unwrap();
+        assert_eq!(format!("{}", window), "22:00 +08:00 - 02:30 +08:00");
 
-        check_parse(
-            vec!["09:30 +00:00".to_owned(), "23:00 +00:00".to_owned()],
-            "[\"09:30 +00:00\", \"23:00 +00:00\"]",
-        );
+        let windows = ReadableWindows(vec![window]);
+        let ConfigValue::Windows(config_value) = ConfigValue::from(windows.clone()) else {
+            unreachable!()
+        };
+        assert_eq!(config_value, vec!["22:00 +08:00 - 02:30 +08:00".to_owned()]);
+        assert_eq!(ReadableWindows::from(ConfigValue::Windows(config_value)), windows);
 
-        check_parse(
-            vec!["11:30 +02:00".to_owned(), "13:00 +02:00".to_owned()],
-            "[\"11:30 +02:00\", \"13:00 +02:00\"]",
-        );
+        let parsed =
+            ReadableWindows::from_str("[\"22:00 +08:00 - 02:30 +08:00\"]").unwrap();
+        assert_eq!(parsed, windows);
     }
 
     #[test]
@@ -2462,28 +4804,91 @@ mod tests {
 
     #[cfg(target_os = "linux")]
     #[test]
-    fn test_check_kernel() {
-        use super::check_kernel::{Checker, check_kernel_params};
+    fn test_check_kernel_param_both_comparison_directions() {
+        use super::check_kernel::check_kernel_param;
 
-        // The range of vm.swappiness is from 0 to 100.
-        let table: Vec<(&str, i64, Box<Checker>, bool)> = vec![
-            (
-                "/proc/sys/vm/swappiness",
-                i64::MAX,
-                Box::new(|got, expect| got == expect),
-                false,
+        let dir = Builder::new()
+            .prefix("test_check_kernel_param")
+            .tempdir()
+            .unwrap();
+        let param_path = dir.path().join("somaxconn");
+        fs::write(&param_path, "32768\n").unwrap();
+        let param_path = param_path.to_str().unwrap();
+
+        // `Ge` passes when got >= expect and fails otherwise.
+        assert!(
+            check_kernel_param(&KernelParamCheck::new(
+                param_path,
+                32768,
+                KernelParamCmp::Ge,
+                KernelParamSeverity::Warn,
+            ))
+            .is_ok()
+        );
+        assert!(
+            check_kernel_param(&KernelParamCheck::new(
+                param_path,
+                32769,
+                KernelParamCmp::Ge,
+                KernelParamSeverity::Warn,
+            ))
+            .is_err()
+        );
+
+        // `Eq`/`Le` exercise the other comparison directions.
+        assert!(
+            check_kernel_param(&KernelParamCheck::new(
+                param_path,
+                32768,
+                KernelParamCmp::Eq,
+                KernelParamSeverity::Warn,
+            ))
+            .is_ok()
+        );
+        assert!(
+            check_kernel_param(&KernelParamCheck::new(
+                param_path,
+                32767,
+                KernelParamCmp::Le,
+                KernelParamSeverity::Warn,
+            ))
+            .is_err()
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_check_kernel_with_plumbs_severity_through_failures() {
+        use super::check_kernel::check_kernel_with;
+
+        let dir = Builder::new()
+            .prefix("test_check_kernel_with")
+            .tempdir()
+            .unwrap();
+        let warn_path = dir.path().join("tcp_tw_reuse");
+        let abort_path = dir.path().join("somaxconn");
+        fs::write(&warn_path, "2\n").unwrap();
+        fs::write(&abort_path, "1024\n").unwrap();
+
+        let checks = vec![
+            KernelParamCheck::new(
+                warn_path.to_str().unwrap(),
+                0,
+                KernelParamCmp::Eq,
+                KernelParamSeverity::Warn,
             ),
-            (
-                "/proc/sys/vm/swappiness",
-                i64::MAX,
-                Box::new(|got, expect| got < expect),
-                true,
+            KernelParamCheck::new(
+                abort_path.to_str().unwrap(),
+                32768,
+                KernelParamCmp::Ge,
+                KernelParamSeverity::Abort,
             ),
         ];
 
-        for (path, expect, checker, is_ok) in table {
-            assert_eq!(check_kernel_params(path, expect, checker).is_ok(), is_ok);
-        }
+        let results = check_kernel_with(&checks);
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].check.severity, KernelParamSeverity::Warn);
+        assert_eq!(results[1].check.severity, KernelParamSeverity::Abort);
     }
 
     #[test]
@@ -2538,6 +4943,95 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_normalize_addr() {
+        let table = vec![
+            ("127.0.0.1:8080", "127.0.0.1:8080"),
+            ("[::1]:8080", "[::1]:8080"),
+            ("LocalHost:8080", "localhost:8080"),
+            ("PingCAP.COM:8080", "pingcap.com:8080"),
+            ("pingcap.com.:8080", "pingcap.com:8080"),
+            ("PingCAP.COM.:8080", "pingcap.com:8080"),
+            ("[::ffff:192.0.2.1]:8080", "[::ffff:192.0.2.1]:8080"),
+        ];
+
+        for (addr, expected) in table {
+            assert_eq!(normalize_addr(addr).unwrap(), expected, "addr: {:?}", addr);
+        }
+
+        let fail_table = vec![
+            "",
+            "localhost",
+            "funnydomain:",
+            ":8080",
+            "localhost:0",
+            "localhost:notaport",
+            "root@google.com:8080",
+            "http://google.com:8080",
+        ];
+
+        for addr in fail_table {
+            assert!(normalize_addr(addr).is_err(), "addr: {:?}", addr);
+        }
+    }
+
+    #[test]
+    fn test_validate_store_labels() {
+        fn labels(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+            pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect()
+        }
+
+        let ok_table: Vec<HashMap<String, String>> = vec![
+            labels(&[]),
+            labels(&[("zone", "us-west-1")]),
+            labels(&[("zone", "us-west-1"), ("host", "tikv-0")]),
+            // "engine" is a well-known label (used e.g. to mark a store as
+            // TiFlash) but is not otherwise special-cased by this validator.
+            labels(&[("engine", "tiflash")]),
+            // Empty values are allowed; only keys must be non-empty.
+            labels(&[("rack", "")]),
+            labels(&[("a", "1"), ("a-b_c.d", "1-2_3.4")]),
+        ];
+        for labels in ok_table {
+            assert!(
+                validate_store_labels(&labels).is_ok(),
+                "labels: {:?}",
+                labels
+            );
+        }
+
+        let fail_table: Vec<HashMap<String, String>> = vec![
+            // Uppercase is rejected even though PD would otherwise treat it
+            // as equivalent to the lowercase form.
+            labels(&[("Zone", "us-west-1")]),
+            labels(&[("zone", "US-WEST-1")]),
+            // Non-ASCII (here, unicode) keys never match the charset.
+            labels(&[("région", "eu")]),
+            labels(&[("", "us-west-1")]),
+            // Reserved prefixes.
+            labels(&[("$mode", "strict")]),
+            labels(&[("tikv.internal", "true")]),
+            // Two keys that only differ by case collide once PD lowercases
+            // them, even though each one on its own would be a valid key.
+            labels(&[("zone", "a"), ("ZONE", "b")]),
+            labels(&[("zone", &"a".repeat(MAX_STORE_LABEL_LEN + 1))]),
+            labels(&[(
+                "z".repeat(MAX_STORE_LABEL_LEN + 1).as_str(),
+                "us-west-1",
+            )]),
+        ];
+        for labels in fail_table {
+            assert!(
+                validate_store_labels(&labels).is_err(),
+                "labels: {:?}",
+                labels
+            );
+        }
+    }
+
     fn create_file(fpath: &str, buf: &[u8]) {
         let mut file = File::create(fpath).unwrap();
         file.write_all(buf).unwrap();
@@ -2619,6 +5113,47 @@ mod tests {
         assert!(trackers.iter_mut().all(|tr| tr.any_new().is_none()));
     }
 
+    #[test]
+    fn test_version_track_history() {
+        let vc = VersionTrack::new(0u64);
+        assert!(vc.history().is_empty());
+
+        vc.enable_history(2, |v: &u64| v.to_string());
+
+        // A failed update must not record anything.
+        let _ = vc.update(|_| -> Result<(), ()> { Err(()) });
+        assert!(vc.history().is_empty());
+
+        vc.update(|v| -> Result<(), ()> {
+            *v = 1;
+            Ok(())
+        })
+        .unwrap();
+        vc.update(|v| -> Result<(), ()> {
+            *v = 2;
+            Ok(())
+        })
+        .unwrap();
+
+        let history = vc.history();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].snapshot, "1");
+        assert_eq!(history[1].snapshot, "2");
+        assert!(history[0].at <= history[1].at);
+
+        // A third update must evict the oldest entry, keeping the capacity
+        // bounded rather than growing unboundedly.
+        vc.update(|v| -> Result<(), ()> {
+            *v = 3;
+            Ok(())
+        })
+        .unwrap();
+        let history = vc.history();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].snapshot, "2");
+        assert_eq!(history[1].snapshot, "3");
+    }
+
     #[test]
     fn test_toml_writer() {
         let cfg = r#"
@@ -2682,6 +5217,70 @@ yyy = 100
         assert_eq!(expect.as_bytes(), t.finish().as_slice());
     }
 
+    #[test]
+    fn test_toml_writer_delete() {
+        let cfg = r#"
+log-level = "info"
+
+[readpool.storage]
+normal-concurrency = 1
+high-concurrency = 345
+
+[[security.encryption.master-keys]]
+type = "file"
+path = "/tmp/key"
+
+[rocksdb.defaultcf]
+compression-per-level = ["no", "no", "no"]
+"#;
+        let mut keys = HashSet::new();
+        // A key that exists, one that was table-qualified two levels deep, one
+        // inside an array-of-tables, and one that doesn't exist at all.
+        keys.insert("readpool.storage.high-concurrency".to_owned());
+        keys.insert("security.encryption.master-keys.path".to_owned());
+        keys.insert("does-not-exist".to_owned());
+
+        let mut t = TomlWriter::new();
+        t.write_delete(cfg.to_owned(), keys);
+        let expect = r#"
+log-level = "info"
+
+[readpool.storage]
+normal-concurrency = 1
+
+[[security.encryption.master-keys]]
+type = "file"
+
+[rocksdb.defaultcf]
+compression-per-level = ["no", "no", "no"]
+"#;
+        assert_eq!(expect.as_bytes(), t.finish().as_slice());
+    }
+
+    #[test]
+    fn test_toml_writer_delete_leaves_no_dangling_header() {
+        // Round-trip: write_change appends a brand new table for a key that
+        // wasn't in the file, then write_delete removes that same key. The
+        // table it lives in only ever had that one key, so its header must be
+        // removed too rather than left dangling.
+        let src = "log-level = \"info\"\n".to_owned();
+
+        let mut m = HashMap::new();
+        m.insert("not-in-file.foo".to_owned(), "1".to_owned());
+        let mut t = TomlWriter::new();
+        t.write_change(src, m);
+        let with_appended_table = String::from_utf8(t.finish()).unwrap();
+        assert!(with_appended_table.contains("[not-in-file]"));
+
+        let mut keys = HashSet::new();
+        keys.insert("not-in-file.foo".to_owned());
+        let mut t = TomlWriter::new();
+        t.write_delete(with_appended_table, keys);
+        let result = String::from_utf8(t.finish()).unwrap();
+        assert!(!result.contains("not-in-file"));
+        assert!(result.contains("log-level"));
+    }
+
     #[test]
     fn test_update_empty_content() {
         // empty content
@@ -2740,7 +5339,7 @@ yyy = 100
             target.to_str().unwrap(),
         );
         state.validate(true).unwrap();
-        let should_dump = state.before_open_target();
+        let should_dump = state.before_open_target().unwrap();
         assert!(should_dump);
         fs::remove_dir_all(&root).unwrap();
 
@@ -2775,7 +5374,7 @@ yyy = 100
             target.to_str().unwrap(),
         );
         state.validate(true).unwrap();
-        let should_dump = state.before_open_target();
+        let should_dump = state.before_open_target().unwrap();
         assert!(should_dump);
         fs::remove_dir_all(&root).unwrap();
 
@@ -2809,7 +5408,7 @@ yyy = 100
             target.to_str().unwrap(),
         );
         state.validate(true).unwrap();
-        let should_dump = state.before_open_target();
+        let should_dump = state.before_open_target().unwrap();
         assert!(should_dump);
         fs::remove_dir_all(&root).unwrap();
     }
@@ -2825,7 +5424,7 @@ yyy = 100
             state.validate(true).unwrap();
             check();
             // Dump to target.
-            if state.before_open_target() {
+            if state.before_open_target().unwrap() {
                 check();
                 // Simulate partial writes.
                 let marker = root.join("MIGRATING-RAFT");
@@ -2893,6 +5492,236 @@ yyy = 100
         });
     }
 
+    #[test]
+    fn test_raft_engine_to_raft_engine_migration_survives_crash_before_marker_removal() {
+        // An operator moving raft-engine data to a new disk: both `source`
+        // and `target` are raft-engine directories, which `data_exists`
+        // can't tell apart by format alone.
+        let dir = tempfile::Builder::new().tempdir().unwrap();
+        let root = dir.path().join("root");
+        let source = root.join("source");
+        fs::create_dir_all(&source).unwrap();
+        File::create(source.join("0000000000000001.raftlog")).unwrap();
+        let target = root.join("target");
+
+        let mut state = RaftDataStateMachine::new(
+            root.to_str().unwrap(),
+            source.to_str().unwrap(),
+            target.to_str().unwrap(),
+        );
+        state.validate(true).unwrap();
+        assert!(state.before_open_target().unwrap());
+
+        // The caller copies the directory into `target`.
+        fs::create_dir_all(&target).unwrap();
+        fs::copy(
+            source.join("0000000000000001.raftlog"),
+            target.join("0000000000000001.raftlog"),
+        )
+        .unwrap();
+
+        // `after_dump_data` would record a manifest for the now-complete
+        // `target`, then crash before it can remove `source` and the
+        // marker.
+        state.write_target_manifest().unwrap();
+
+        // Restart: a fresh state machine should see both `source` and
+        // `target` fully populated, trust the recorded manifest instead of
+        // treating this as "Found multiple raft data sets", and finish the
+        // cleanup instead of wiping `target` and asking the caller to copy
+        // everything again.
+        let mut state = RaftDataStateMachine::new(
+            root.to_str().unwrap(),
+            source.to_str().unwrap(),
+            target.to_str().unwrap(),
+        );
+        state.validate(true).unwrap();
+        let should_dump = state.before_open_target().unwrap();
+        assert!(!should_dump);
+        assert!(!RaftDataStateMachine::data_exists(&source));
+        assert!(RaftDataStateMachine::raftengine_exists(&target));
+        assert!(!root.join("MIGRATING-RAFT").exists());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_raft_data_migration_with_symlinked_source() {
+        let dir = tempfile::Builder::new().tempdir().unwrap();
+        let root = dir.path().join("root");
+        let real_source = root.join("real_source");
+        fs::create_dir_all(&real_source).unwrap();
+        File::create(real_source.join("CURRENT")).unwrap();
+        let source = root.join("source");
+        std::os::unix::fs::symlink(&real_source, &source).unwrap();
+        let target = root.join("target");
+        fs::create_dir_all(&target).unwrap();
+
+        let mut state = RaftDataStateMachine::new(
+            root.to_str().unwrap(),
+            source.to_str().unwrap(),
+            target.to_str().unwrap(),
+        );
+        state.validate(true).unwrap();
+        assert!(state.before_open_target().unwrap());
+        fs::copy(
+            real_source.join("CURRENT"),
+            target.join("0000000000000001.raftlog"),
+        )
+        .unwrap();
+        state.after_dump_data();
+
+        // The real directory (not just the symlink pointing at it) must have
+        // been moved out of the way, and the now-dangling symlink itself
+        // cleaned up rather than left behind.
+        assert!(!real_source.exists());
+        assert!(!source.exists());
+        assert!(RaftDataStateMachine::raftengine_exists(&target));
+    }
+
+    #[test]
+    fn test_raft_data_migration_cross_device_rename() {
+        fail::cfg("must_rename_dir_cross_device", "return").unwrap();
+
+        let dir = tempfile::Builder::new().tempdir().unwrap();
+        let root = dir.path().join("root");
+        let source = root.join("source");
+        fs::create_dir_all(&source).unwrap();
+        File::create(source.join("CURRENT")).unwrap();
+        let target = root.join("target");
+        fs::create_dir_all(&target).unwrap();
+
+        let mut state = RaftDataStateMachine::new(
+            root.to_str().unwrap(),
+            source.to_str().unwrap(),
+            target.to_str().unwrap(),
+        );
+        state.validate(true).unwrap();
+        assert!(state.before_open_target().unwrap());
+        fs::copy(
+            source.join("CURRENT"),
+            target.join("0000000000000001.raftlog"),
+        )
+        .unwrap();
+        // `after_dump_data` removes `source` via rename-to-trash; the
+        // failpoint forces that rename to look like it failed with `EXDEV`,
+        // exercising `must_rename_dir`'s copy+fsync+remove fallback.
+        state.after_dump_data();
+
+        assert!(!source.exists());
+        assert!(RaftDataStateMachine::raftengine_exists(&target));
+
+        fail::remove("must_rename_dir_cross_device");
+    }
+
+    #[test]
+    fn test_must_remove_surfaces_trash_removal_error_without_panicking() {
+        let dir = tempfile::Builder::new().tempdir().unwrap();
+        let path = dir.path().join("victim");
+        fs::create_dir_all(&path).unwrap();
+        File::create(path.join("data")).unwrap();
+
+        fail::cfg("must_remove_trash_dir", "return").unwrap();
+        let err = RaftDataStateMachine::must_remove(&path).unwrap_err();
+        assert_eq!(err.raw_os_error(), Some(libc::EROFS));
+        fail::remove("must_remove_trash_dir");
+
+        // The data was already moved into the trash directory before the
+        // simulated EROFS, so it's still there (and the original path is
+        // gone) instead of lost -- an operator can finish the cleanup once
+        // the filesystem is writable again.
+        assert!(!path.exists());
+        let trash = path.with_extension("REMOVE");
+        assert!(trash.join("data").exists());
+        fs::remove_dir_all(&trash).unwrap();
+    }
+
+    #[test]
+    fn test_before_open_target_tolerates_unremovable_trash() {
+        fail::cfg("before_open_target_remove_trash", "return").unwrap();
+
+        let dir = tempfile::Builder::new().tempdir().unwrap();
+        let root = dir.path().join("root");
+        let source = root.join("source");
+        fs::create_dir_all(&source).unwrap();
+        File::create(source.join("CURRENT")).unwrap();
+        let target = root.join("target");
+
+        // Simulate a trash directory left behind by a removal that was
+        // interrupted by a read-only filesystem.
+        let trash = target.with_extension("REMOVE");
+        fs::create_dir_all(&trash).unwrap();
+
+        let mut state = RaftDataStateMachine::new(
+            root.to_str().unwrap(),
+            source.to_str().unwrap(),
+            target.to_str().unwrap(),
+        );
+        state.validate(true).unwrap();
+
+        // The failpoint makes the leftover trash directory unremovable, but
+        // that must not panic or stop the migration from being correctly
+        // detected -- it's just reported via `recover_report`.
+        assert!(state.before_open_target().unwrap());
+        assert!(trash.exists());
+        assert!(
+            state
+                .recover_report()
+                .unwrap()
+                .contains(&trash.display().to_string())
+        );
+
+        fail::remove("before_open_target_remove_trash");
+
+        // Once the filesystem recovers, a later call cleans up the trash
+        // and has nothing left to report.
+        let mut state = RaftDataStateMachine::new(
+            root.to_str().unwrap(),
+            source.to_str().unwrap(),
+            target.to_str().unwrap(),
+        );
+        state.validate(true).unwrap();
+        assert!(state.before_open_target().unwrap());
+        assert!(!trash.exists());
+        assert!(state.recover_report().is_none());
+    }
+
+    #[test]
+    fn test_before_open_target_rejects_foreign_marker() {
+        let dir = tempfile::Builder::new().tempdir().unwrap();
+        let root = dir.path().join("root");
+        let source = root.join("source");
+        fs::create_dir_all(&source).unwrap();
+        File::create(source.join("CURRENT")).unwrap();
+        let target = root.join("target");
+        fs::create_dir_all(&target).unwrap();
+        File::create(target.join("CURRENT")).unwrap();
+
+        // A marker written on another machine, pointing at neither `source`
+        // nor `target` as configured here.
+        let foreign_source = "/some/other/machine/raftdb";
+        fs::write(root.join("MIGRATING-RAFT"), format!("{}//", foreign_source)).unwrap();
+
+        let mut state = RaftDataStateMachine::new(
+            root.to_str().unwrap(),
+            source.to_str().unwrap(),
+            target.to_str().unwrap(),
+        );
+        let err = state.before_open_target().unwrap_err();
+        assert!(err.contains(foreign_source));
+        assert!(err.contains(source.to_str().unwrap()));
+        assert!(err.contains(target.to_str().unwrap()));
+        assert!(err.contains("force_reset_marker"));
+
+        // Neither directory was touched.
+        assert!(source.join("CURRENT").exists());
+        assert!(target.join("CURRENT").exists());
+
+        // The documented recovery: discard the stale marker, then retry.
+        assert!(state.force_reset_marker().unwrap());
+        assert!(!root.join("MIGRATING-RAFT").exists());
+        assert!(!state.force_reset_marker().unwrap());
+    }
+
     #[test]
     fn test_must_remove_except() {
         fn create_raftdb(path: &Path) {
@@ -2945,7 +5774,7 @@ yyy = 100
         let raftengine_dir = test_dir.join("raftengine");
         create_raftdb(&raftdb_dir);
         create_raftengine(&raftengine_dir);
-        RaftDataStateMachine::must_remove_except(&raftdb_dir, &raftengine_dir);
+        RaftDataStateMachine::must_remove_except(&raftdb_dir, &raftengine_dir).unwrap();
         raftengine_must_exist(&raftengine_dir);
         raftdb_must_not_exist(&raftdb_dir);
         fs::remove_dir_all(&test_dir).unwrap();
@@ -2967,7 +5796,7 @@ yyy = 100
         let raftengine_dir = raftdb_dir.join("raftengine");
         create_raftdb(&raftdb_dir);
         create_raftengine(&raftengine_dir);
-        RaftDataStateMachine::must_remove_except(&raftdb_dir, &raftengine_dir);
+        RaftDataStateMachine::must_remove_except(&raftdb_dir, &raftengine_dir).unwrap();
         raftengine_must_exist(&raftengine_dir);
         assert!(!test_dir.join("raftdb/raftdb_data").exists());
         fs::remove_dir_all(&test_dir).unwrap();
@@ -2988,7 +5817,7 @@ yyy = 100
         let raftdb_dir = raftengine_dir.join("raftdb");
         create_raftengine(&raftengine_dir);
         create_raftdb(&raftdb_dir);
-        RaftDataStateMachine::must_remove_except(&raftdb_dir, &raftengine_dir);
+        RaftDataStateMachine::must_remove_except(&raftdb_dir, &raftengine_dir).unwrap();
         raftengine_must_exist(&raftengine_dir);
         raftdb_must_not_exist(&raftdb_dir);
         fs::remove_dir_all(&test_dir).unwrap();
@@ -3008,7 +5837,7 @@ yyy = 100
         fs::File::create(raftdb_data).unwrap();
         let raftengine_dir = test_dir.join("raftengine");
         create_raftengine(&raftengine_dir);
-        RaftDataStateMachine::must_remove_except(&test_dir, &raftengine_dir);
+        RaftDataStateMachine::must_remove_except(&test_dir, &raftengine_dir).unwrap();
         raftengine_must_exist(&raftengine_dir);
         assert!(!test_dir.join("raftdb_data").exists());
         fs::remove_dir_all(&test_dir).unwrap();
@@ -3051,4 +5880,71 @@ yyy = 100
         clear_dir(&test_dir);
         assert!(!RaftDataStateMachine::raftdb_exists(&test_dir));
     }
+
+    #[test]
+    fn test_fd_limits_display() {
+        let limits = FdLimits {
+            soft: 1024,
+            hard: 4096,
+            raised_to: None,
+        };
+        assert_eq!(limits.to_string(), "soft=1024, hard=4096");
+
+        let limits = FdLimits {
+            soft: 4096,
+            hard: 4096,
+            raised_to: Some(4096),
+        };
+        assert_eq!(limits.to_string(), "soft=4096, hard=4096, raised_to=4096");
+    }
+
+    #[test]
+    fn test_decide_fd_limits_already_sufficient() {
+        let limits =
+            decide_fd_limits(4096, 4096, 1024, FdLimitMode::Enforce, |_, _| unreachable!())
+                .unwrap();
+        assert_eq!(
+            limits,
+            FdLimits {
+                soft: 4096,
+                hard: 4096,
+                raised_to: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_decide_fd_limits_raise_succeeds() {
+        let limits = decide_fd_limits(1024, 1024, 4096, FdLimitMode::Enforce, |_, _| true).unwrap();
+        assert_eq!(
+            limits,
+            FdLimits {
+                soft: 4096,
+                hard: 4096,
+                raised_to: Some(4096),
+            }
+        );
+    }
+
+    #[test]
+    fn test_decide_fd_limits_enforce_errors_when_raise_fails() {
+        let err = decide_fd_limits(1024, 1024, 4096, FdLimitMode::Enforce, |_, _| false)
+            .unwrap_err();
+        assert!(matches!(err, ConfigError::Limit(_)));
+    }
+
+    #[test]
+    fn test_decide_fd_limits_warn_does_not_error_when_raise_fails() {
+        // Simulates a low limit (as if injected by a fake getter) that cannot
+        // be raised; `Warn` mode should log and still report success.
+        let limits = decide_fd_limits(1024, 1024, 4096, FdLimitMode::Warn, |_, _| false).unwrap();
+        assert_eq!(
+            limits,
+            FdLimits {
+                soft: 1024,
+                hard: 1024,
+                raised_to: None,
+            }
+        );
+    }
 }
@@ -62,6 +62,7 @@ pub mod resource_control;
 pub mod smoother;
 pub mod store;
 pub mod stream;
+pub mod swap_cell;
 pub mod sys;
 pub mod thread_group;
 pub mod thread_name_prefix;
@@ -105,6 +105,13 @@ lazy_static! {
     );
     pub static ref INSTANCE_BACKEND_CPU_QUOTA: IntGauge =
         register_int_gauge!("tikv_backend_cpu_quota", "cpu quota for backend request").unwrap();
+    pub static ref TIKV_STORE_DATA_DIR_ROTATIONAL: IntGaugeVec = register_int_gauge_vec!(
+        "tikv_store_data_dir_rotational",
+        "Whether a checked data directory's underlying block device is rotational (1) or an \
+         SSD (0), labeled by the checked directory's name and filesystem type.",
+        &["name", "fs_type"]
+    )
+    .unwrap();
 }
 
 pub fn convert_record_pairs(m: HashMap<String, u64>) -> RecordPairVec {
@@ -109,6 +109,12 @@ pub struct RetryExt<E> {
     pub on_failure: Option<Box<dyn FnMut(&E) + Send + Sync + 'static>>,
     pub max_retry_times: usize,
     pub max_retry_delay: Duration,
+    /// A wall-clock budget for the whole retry loop, counted from the first
+    /// attempt. Once exceeded, the loop stops retrying and returns the most
+    /// recent error, even if `max_retry_times` hasn't been reached yet.
+    /// `None` (the default) means no elapsed-time cap, matching the
+    /// pre-existing retry-count-only behavior.
+    pub max_elapsed: Option<Duration>,
 }
 
 impl<E> RetryExt<E> {
@@ -132,6 +138,12 @@ impl<E> RetryExt<E> {
         self.max_retry_delay = max_retry_delay;
         self
     }
+
+    /// Attaches a wall-clock retry budget to the ext.
+    pub fn with_max_elapsed(mut self, max_elapsed: Duration) -> Self {
+        self.max_elapsed = Some(max_elapsed);
+        self
+    }
 }
 
 // If we use the default derive macro, it would complain that `E` isn't
@@ -142,6 +154,7 @@ impl<E> Default for RetryExt<E> {
             on_failure: Default::default(),
             max_retry_times: MAX_RETRY_TIMES,
             max_retry_delay: MAX_RETRY_DELAY,
+            max_elapsed: None,
         }
     }
 }
@@ -186,6 +199,8 @@ macro_rules! retry_expr {
 
             let mut ext: $crate::stream::RetryExt<_> = $ext;
             let max_retry_times = ext.max_retry_times;
+            let max_elapsed = ext.max_elapsed;
+            let start = ::std::time::Instant::now();
             let mut retry_wait_dur = ::std::time::Duration::from_secs(1);
             let mut retry_time = 0;
             loop {
@@ -202,6 +217,9 @@ macro_rules! retry_expr {
                         if retry_time > max_retry_times {
                             return Err(e);
                         }
+                        if max_elapsed.is_some_and(|budget| start.elapsed() >= budget) {
+                            return Err(e);
+                        }
                     }
                 }
                 use __macro_helper::__rand_Rng;
@@ -264,12 +282,12 @@ where
 
 #[cfg(test)]
 mod tests {
-    use std::{cell::RefCell, pin::Pin};
+    use std::{cell::RefCell, pin::Pin, time::Duration};
 
     use futures::{Future, FutureExt};
 
     use super::RetryError;
-    use crate::stream::retry;
+    use crate::stream::{RetryExt, retry, retry_ext};
 
     #[derive(Debug)]
     struct TriviallyRetry;
@@ -312,4 +330,18 @@ mod tests {
         let r = retry(gen_action_fail_for(1)).await;
         assert!(r.is_ok(), "{:?}", r);
     }
+
+    #[tokio::test]
+    async fn test_max_elapsed_budget() {
+        // The first retry always waits ~1s regardless of `max_retry_delay`, so a
+        // budget shorter than that should give up after the very first retry
+        // instead of running out to `max_retry_times`.
+        let ext = RetryExt::<TriviallyRetry>::default()
+            .with_max_retry_times(1000)
+            .with_max_elapsed(Duration::from_millis(1));
+        let start = std::time::Instant::now();
+        let r = retry_ext(gen_action_fail_for(1000), ext).await;
+        assert!(r.is_err(), "{:?}", r);
+        assert!(start.elapsed() < Duration::from_secs(5), "{:?}", start.elapsed());
+    }
 }
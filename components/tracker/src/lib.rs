@@ -196,6 +196,7 @@ pub enum RequestType {
     CoprocessorDag,
     CoprocessorAnalyze,
     CoprocessorChecksum,
+    CoprocessorColumnTypeCheck,
     KvFlush,
     KvBufferBatchGet,
 }
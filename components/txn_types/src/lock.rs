@@ -230,44 +230,54 @@ impl Lock {
     }
 
     pub fn to_bytes(&self) -> Vec<u8> {
+        let mut b = Vec::with_capacity(self.pre_allocate_size());
+        self.write_to(&mut b);
+        b
+    }
+
+    /// Appends this lock's encoded form to `buf`, without clearing it first.
+    /// Unlike [`Self::to_bytes`], this lets a caller that encodes many locks
+    /// in a row reuse one growable buffer instead of allocating a fresh,
+    /// precisely-sized `Vec` for every lock.
+    pub fn write_to(&self, buf: &mut Vec<u8>) {
         assert!(
             self.lock_type != LockType::Shared,
             "use SharedLocks to encode shared locks"
         );
-        let mut b = Vec::with_capacity(self.pre_allocate_size());
-        b.push(self.lock_type.to_u8());
-        b.encode_compact_bytes(&self.primary).unwrap();
-        b.encode_var_u64(self.ts.into_inner()).unwrap();
-        b.encode_var_u64(self.ttl).unwrap();
+        buf.reserve(self.pre_allocate_size());
+        buf.push(self.lock_type.to_u8());
+        buf.encode_compact_bytes(&self.primary).unwrap();
+        buf.encode_var_u64(self.ts.into_inner()).unwrap();
+        buf.encode_var_u64(self.ttl).unwrap();
         if let Some(ref v) = self.short_value {
-            b.push(SHORT_VALUE_PREFIX);
-            b.push(v.len() as u8);
-            b.extend_from_slice(v);
+            buf.push(SHORT_VALUE_PREFIX);
+            buf.push(v.len() as u8);
+            buf.extend_from_slice(v);
         }
         if !self.for_update_ts.is_zero() {
-            b.push(FOR_UPDATE_TS_PREFIX);
-            b.encode_u64(self.for_update_ts.into_inner()).unwrap();
+            buf.push(FOR_UPDATE_TS_PREFIX);
+            buf.encode_u64(self.for_update_ts.into_inner()).unwrap();
         }
         if self.txn_size > 0 {
-            b.push(TXN_SIZE_PREFIX);
-            b.encode_u64(self.txn_size).unwrap();
+            buf.push(TXN_SIZE_PREFIX);
+            buf.encode_u64(self.txn_size).unwrap();
         }
         if !self.min_commit_ts.is_zero() {
-            b.push(MIN_COMMIT_TS_PREFIX);
-            b.encode_u64(self.min_commit_ts.into_inner()).unwrap();
+            buf.push(MIN_COMMIT_TS_PREFIX);
+            buf.encode_u64(self.min_commit_ts.into_inner()).unwrap();
         }
         if self.use_async_commit {
-            b.push(ASYNC_COMMIT_PREFIX);
-            b.encode_var_u64(self.secondaries.len() as _).unwrap();
+            buf.push(ASYNC_COMMIT_PREFIX);
+            buf.encode_var_u64(self.secondaries.len() as _).unwrap();
             for k in &self.secondaries {
-                b.encode_compact_bytes(k).unwrap();
+                buf.encode_compact_bytes(k).unwrap();
             }
         }
         if !self.rollback_ts.is_empty() {
-            b.push(ROLLBACK_TS_PREFIX);
-            b.encode_var_u64(self.rollback_ts.len() as _).unwrap();
+            buf.push(ROLLBACK_TS_PREFIX);
+            buf.encode_var_u64(self.rollback_ts.len() as _).unwrap();
             for ts in &self.rollback_ts {
-                b.encode_u64(ts.into_inner()).unwrap();
+                buf.encode_u64(ts.into_inner()).unwrap();
             }
         }
         if matches!(
@@ -275,22 +285,21 @@ impl Lock {
             LastChange::NotExist | LastChange::Exist { .. }
         ) {
             let (last_change_ts, versions) = self.last_change.to_parts();
-            b.push(LAST_CHANGE_PREFIX);
-            b.encode_u64(last_change_ts.into_inner()).unwrap();
-            b.encode_var_u64(versions).unwrap();
+            buf.push(LAST_CHANGE_PREFIX);
+            buf.encode_u64(last_change_ts.into_inner()).unwrap();
+            buf.encode_var_u64(versions).unwrap();
         }
         if self.txn_source != 0 {
-            b.push(TXN_SOURCE_PREFIX);
-            b.encode_var_u64(self.txn_source).unwrap();
+            buf.push(TXN_SOURCE_PREFIX);
+            buf.encode_var_u64(self.txn_source).unwrap();
         }
         if self.is_locked_with_conflict {
-            b.push(PESSIMISTIC_LOCK_WITH_CONFLICT_PREFIX);
+            buf.push(PESSIMISTIC_LOCK_WITH_CONFLICT_PREFIX);
         }
         if self.generation > 0 {
-            b.push(GENERATION_PREFIX);
-            b.encode_u64(self.generation).unwrap();
+            buf.push(GENERATION_PREFIX);
+            buf.encode_u64(self.generation).unwrap();
         }
-        b
     }
 
     fn pre_allocate_size(&self) -> usize {
@@ -633,6 +633,24 @@ impl TxnExtra {
         }
         result + std::mem::size_of::<Self>()
     }
+
+    /// Replaces every captured `OldValue::Value` with the much cheaper
+    /// `OldValue::SeekWrite` marker, shrinking `size()` to (approximately)
+    /// the key bytes alone.
+    ///
+    /// Used by the scheduler to shed memory when the aggregate in-flight
+    /// old-value budget across concurrently proposed commands is exceeded:
+    /// CDC/old-value readers fall back to seeking the write CF for these
+    /// keys instead of getting the value inline.
+    pub fn degrade_old_values(&mut self) {
+        for (key, (old_value, _)) in self.old_values.iter_mut() {
+            if matches!(old_value, OldValue::Value { .. }) {
+                // `key` is already `user_key.append_ts(start_ts)`, exactly the
+                // seek position `OldValue::SeekWrite` expects.
+                *old_value = OldValue::SeekWrite(key.clone());
+            }
+        }
+    }
 }
 
 pub trait TxnExtraScheduler: Send + Sync {
@@ -261,9 +261,11 @@ fn fuzz_duration(
 ) -> Result<()> {
     use tidb_query_datatype::codec::{convert::ConvertTo, mysql::decimal::Decimal};
 
+    let mut ctx = EvalContext::default();
+
     let _ = t.fsp();
     let u = t;
-    u.round_frac(cursor.read_as_i8()?)?;
+    u.round_frac(&mut ctx, cursor.read_as_i8()?)?;
     let _ = t.hours();
     let _ = t.minutes();
     let _ = t.secs();
@@ -272,11 +274,10 @@ fn fuzz_duration(
     let _ = t.is_zero();
 
     let u = t;
-    u.round_frac(cursor.read_as_i8()?)?;
+    u.round_frac(&mut ctx, cursor.read_as_i8()?)?;
     let mut v = Vec::new();
     let _ = v.write_datum_duration_int(t);
 
-    let mut ctx = EvalContext::default();
     let _: Decimal = t.convert(&mut ctx)?;
 
     Ok(())
@@ -323,3 +324,47 @@ pub fn fuzz_coprocessor_codec_row_v2_binary_search(data: &[u8]) -> Result<()> {
 
     Ok(())
 }
+
+// Exercises the `collation_ffi` C ABI boundary directly (rather than the
+// safe Rust API it wraps), the way an external caller passing arbitrary
+// collation ids and undersized buffers would.
+pub fn fuzz_collation_ffi(data: &[u8]) -> Result<()> {
+    use collation_ffi::{tikv_collate_compare, tikv_collate_sort_key};
+
+    let mut cursor = Cursor::new(data);
+    let collation_id = cursor.read_as_i32()?;
+    let out_cap = (cursor.read_as_u8()? as usize) % 32;
+    let split_at = cursor.read_as_u8()? as usize;
+
+    let rest = &data[cursor.position() as usize..];
+    let (a, b) = rest.split_at(split_at.min(rest.len()));
+
+    let mut out = vec![0u8; out_cap];
+    let mut written = 0usize;
+    // Must never panic across the FFI boundary, regardless of how
+    // nonsensical `collation_id` or the buffer sizes are.
+    let _ = unsafe {
+        tikv_collate_sort_key(
+            collation_id,
+            a.as_ptr(),
+            a.len(),
+            out.as_mut_ptr(),
+            out.len(),
+            &mut written,
+        )
+    };
+
+    let mut ordering = 0i32;
+    let _ = unsafe {
+        tikv_collate_compare(
+            collation_id,
+            a.as_ptr(),
+            a.len(),
+            b.as_ptr(),
+            b.len(),
+            &mut ordering,
+        )
+    };
+
+    Ok(())
+}
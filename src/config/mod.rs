@@ -5,6 +5,7 @@
 //! TiKV is configured through the `TikvConfig` type, which is in turn
 //! made up of many other configuration types.
 
+pub mod backup;
 mod configurable;
 
 use std::{
@@ -73,7 +74,7 @@ use serde_json::{Map, Value, to_value};
 use tikv_util::{
     config::{
         self, LogFormat, MIB, RaftDataStateMachine, ReadableDuration, ReadableSchedule,
-        ReadableSize, ReadableSizeOrPercent, TomlWriter,
+        ReadableSize, ReadableSizeOrPercent, ReadableSizeOrRatio, ReadableWindows, TomlWriter,
     },
     logger::{get_level_by_string, get_string_by_level, set_log_level},
     sys::SysQuota,
@@ -3064,6 +3065,21 @@ pub struct BackupConfig {
     pub gcp_v2_enable: bool,
     #[online_config(submodule)]
     pub hadoop: HadoopConfig,
+    /// Caps how long a single GCS request may take. `0` (the default)
+    /// leaves requests unbounded, preserving the historical behavior.
+    pub gcs_request_timeout: ReadableDuration,
+    /// Caps how long connecting to GCS may take. `0` (the default) leaves
+    /// hyper's own connect timeout (none) in effect.
+    pub gcs_connect_timeout: ReadableDuration,
+    /// Maximum idle HTTP/1.1 connections kept per GCS host. `0` (the
+    /// default) leaves hyper's own pool size in effect.
+    pub gcs_pool_max_idle_per_host: usize,
+    /// Overrides the OAuth token endpoint GCS credential requests are made
+    /// against. Empty (the default) leaves Google's own endpoint in effect.
+    /// An air-gapped deployment that routes GCS through an internal proxy
+    /// via `storage.gcs.endpoint` usually needs this too, since that setting
+    /// only redirects the storage API, not the OAuth token exchange.
+    pub gcs_oauth_endpoint: String,
 }
 
 impl BackupConfig {
@@ -3113,6 +3129,10 @@ impl Default for BackupConfig {
             s3_multi_part_size: ReadableSize::mb(5),
             gcp_v2_enable: true,
             hadoop: Default::default(),
+            gcs_request_timeout: ReadableDuration::secs(0),
+            gcs_connect_timeout: ReadableDuration::secs(0),
+            gcs_pool_max_idle_per_host: 0,
+            gcs_oauth_endpoint: String::new(),
         }
     }
 }
@@ -3145,7 +3165,7 @@ pub struct BackupStreamConfig {
     pub temp_file_memory_quota: ReadableSize,
 
     #[online_config(skip)]
-    pub initial_scan_pending_memory_quota: ReadableSize,
+    pub initial_scan_pending_memory_quota: ReadableSizeOrRatio,
     #[online_config(skip)]
     pub initial_scan_rate_limit: ReadableSize,
     pub initial_scan_concurrency: usize,
@@ -3217,7 +3237,9 @@ impl Default for BackupStreamConfig {
             // TODO: may be use raft store directory
             temp_path: String::new(),
             file_size_limit,
-            initial_scan_pending_memory_quota: ReadableSize(quota_size as _),
+            initial_scan_pending_memory_quota: ReadableSizeOrRatio::Size(ReadableSize(
+                quota_size as _,
+            )),
             initial_scan_rate_limit: ReadableSize::mb(60),
             initial_scan_concurrency: 6,
             temp_file_memory_quota: cache_size,
@@ -3691,6 +3713,12 @@ pub struct TikvConfig {
     #[online_config(skip)]
     pub abort_on_panic: bool,
 
+    /// Whether an insufficient file-descriptor limit at startup is fatal
+    /// (`true`, the default) or only logged as a warning so the server can
+    /// still start (`false`).
+    #[online_config(skip)]
+    pub enforce_fd_limit: bool,
+
     #[doc(hidden)]
     #[online_config(skip)]
     pub memory_usage_limit: Option<ReadableSizeOrPercent>,
@@ -3799,6 +3827,7 @@ impl Default for TikvConfig {
             panic_when_unexpected_key_or_data: false,
             enable_io_snoop: true,
             abort_on_panic: false,
+            enforce_fd_limit: true,
             memory_usage_limit: None,
             memory_usage_high_water: 0.9,
             log: LogConfig::default(),
@@ -4159,7 +4188,8 @@ impl TikvConfig {
             }
             self.memory_usage_limit = Some(limit);
         }
-        if block_cache_cap.0 + write_buffer_cap.0 > self.memory_usage_limit.unwrap().0 {
+        if block_cache_cap.saturating_add(write_buffer_cap).0 > self.memory_usage_limit.unwrap().0
+        {
             return Err(format!(
                 "The sum of `storage.block-cache.capacity` and `rocksdb.write-buffer-limit` \
                 is greater than memory-usage-limit: {} + {} > {}",
@@ -4839,6 +4869,21 @@ pub fn persist_config(config: &TikvConfig) -> Result<(), String> {
     Ok(())
 }
 
+/// Persists config like [`persist_config`], and additionally snapshots it
+/// into `backup`'s history directory, opportunistically compressing and
+/// pruning older snapshots on `worker` without blocking this call.
+pub fn persist_config_with_backup(
+    config: &TikvConfig,
+    backup: &backup::ConfigBackupManager,
+    worker: &tikv_util::worker::Worker,
+) -> Result<(), String> {
+    persist_config(config)?;
+    backup
+        .backup_and_rotate(worker, toml::to_string(config).unwrap().into_bytes())
+        .map_err(|e| format!("config backup failed: {}", e))?;
+    Ok(())
+}
+
 pub fn write_config<P: AsRef<Path>>(path: P, content: &[u8]) -> CfgResult<()> {
     let tmp_cfg_path = match path.as_ref().parent() {
         Some(p) => p.join(TMP_CONFIG_FILE),
@@ -4965,7 +5010,13 @@ fn to_config_change(change: HashMap<String, String>) -> CfgResult<ConfigChange>
                 }
                 Some(v) => {
                     if fields.is_empty() {
-                        return match to_change_value(&value, v) {
+                        return match to_change_value(&field, &value, v) {
+                            // Structured errors (e.g. a bad `ReadableSize`) already carry
+                            // enough detail to report as-is; anything else falls back to
+                            // the generic message.
+                            Err(e) if e.downcast_ref::<config::ConfigError>().is_some() => {
+                                Err(e)
+                            }
                             Err(_) => Err(format!("failed to parse: {}", value).into()),
                             Ok(v) => {
                                 dst.insert(field, v);
@@ -4994,11 +5045,17 @@ fn to_config_change(change: HashMap<String, String>) -> CfgResult<ConfigChange>
     Ok(res)
 }
 
-fn to_change_value(v: &str, typed: &ConfigValue) -> CfgResult<ConfigValue> {
+fn to_change_value(field: &str, v: &str, typed: &ConfigValue) -> CfgResult<ConfigValue> {
     let v = v.trim_matches('\"');
     let res = match typed {
         ConfigValue::Duration(_) => ConfigValue::from(v.parse::<ReadableDuration>()?),
-        ConfigValue::Size(_) => ConfigValue::from(v.parse::<ReadableSize>()?),
+        ConfigValue::Size(_) => ConfigValue::from(v.parse::<ReadableSize>().map_err(|_| {
+            config::ConfigError::Value(config::InvalidConfigValue::new(
+                field,
+                "a readable byte size (e.g. \"1KiB\")",
+                v,
+            ))
+        })?),
         ConfigValue::U64(_) => ConfigValue::from(v.parse::<u64>()?),
         ConfigValue::F64(_) => ConfigValue::from(v.parse::<f64>()?),
         ConfigValue::U32(_) => ConfigValue::from(v.parse::<u32>()?),
@@ -5010,6 +5067,10 @@ fn to_change_value(v: &str, typed: &ConfigValue) -> CfgResult<ConfigValue> {
             let schedule = v.parse::<ReadableSchedule>()?;
             ConfigValue::from(schedule)
         }
+        ConfigValue::Windows(_) => {
+            let windows = v.parse::<ReadableWindows>()?;
+            ConfigValue::from(windows)
+        }
         ConfigValue::Skip | ConfigValue::None | ConfigValue::Module(_) => unreachable!(),
     };
     Ok(res)
@@ -5913,18 +5974,31 @@ mod tests {
     #[test]
     fn test_to_config_change() {
         assert_eq!(
-            to_change_value("10h", &ConfigValue::Duration(0)).unwrap(),
+            to_change_value("x", "10h", &ConfigValue::Duration(0)).unwrap(),
             ConfigValue::from(ReadableDuration::hours(10))
         );
         assert_eq!(
-            to_change_value("100MB", &ConfigValue::Size(0)).unwrap(),
+            to_change_value("x", "100MB", &ConfigValue::Size(0)).unwrap(),
             ConfigValue::from(ReadableSize::mb(100))
         );
         assert_eq!(
-            to_change_value("10000", &ConfigValue::U64(0)).unwrap(),
+            to_change_value("x", "10000", &ConfigValue::U64(0)).unwrap(),
             ConfigValue::from(10000u64)
         );
 
+        let err = to_change_value("block-cache-size", "not-a-size", &ConfigValue::Size(0))
+            .unwrap_err();
+        let err = err
+            .downcast_ref::<config::ConfigError>()
+            .unwrap();
+        match err {
+            config::ConfigError::Value(v) => {
+                assert_eq!(v.field, "block-cache-size");
+                assert_eq!(v.got, "not-a-size");
+            }
+            _ => panic!("unexpected error: {:?}", err),
+        }
+
         let old = TikvConfig::default();
         let mut incoming = TikvConfig::default();
         incoming.coprocessor.region_split_keys = Some(10000);
@@ -7975,6 +8049,19 @@ mod tests {
                 .unwrap()
                 .contains("rate-limiter-mode = 1")
         );
+
+        // A deprecated alias for a renamed recovery mode still deserializes,
+        // and is written back out using the canonical numeric form.
+        let config_str = r#"
+            wal-recovery-mode = "point-in-time-recovery"
+        "#;
+        let config: DbConfig = toml::from_str(config_str).unwrap();
+        assert_eq!(config.wal_recovery_mode, DBRecoveryMode::PointInTime);
+        assert!(
+            toml::to_string(&config)
+                .unwrap()
+                .contains("wal-recovery-mode = 2")
+        );
     }
 
     #[test]
@@ -397,6 +397,41 @@ impl<E: Engine> Endpoint<E> {
                     .map(|h| h.into_boxed())
                 });
             }
+            REQ_TYPE_COLUMN_TYPE_CHECK => {
+                let column_type_check =
+                    box_try!(column_type_check::ColumnTypeCheckRequest::parse_from_bytes(&data));
+
+                req_tag = ReqTag::column_type_check;
+                req_ctx = ReqContext::new(
+                    context,
+                    ranges,
+                    self.max_handle_duration,
+                    peer,
+                    None,
+                    start_ts.into(),
+                    cache_match_version,
+                    self.perf_level,
+                    false,
+                );
+
+                with_tls_tracker(|tracker| {
+                    tracker.req_info.request_type = RequestType::CoprocessorColumnTypeCheck;
+                    tracker.req_info.start_ts = start_ts;
+                });
+
+                self.check_memory_locks(&req_ctx)?;
+
+                handler_builder = Box::new(move |snap, req_ctx| {
+                    column_type_check::ColumnTypeCheckContext::new(
+                        column_type_check,
+                        req_ctx.ranges.clone(),
+                        start_ts,
+                        snap,
+                        req_ctx,
+                    )
+                    .map(|h| h.into_boxed())
+                });
+            }
             tp => return Err(box_err!("unsupported tp {}", tp)),
         };
 
@@ -521,6 +556,24 @@ impl<E: Engine> Endpoint<E> {
         tracker.buckets = latest_buckets;
         let buckets_version = tracker.buckets.as_ref().map_or(0, |b| b.version);
 
+        // Validate every requested range against the region bounds in one
+        // pass, instead of letting the first out-of-bounds range be
+        // discovered only once an executor seeks an iterator into it.
+        let key_ranges: Vec<kvrpcpb::KeyRange> = tracker
+            .req_ctx
+            .ranges
+            .iter()
+            .map(|r| {
+                let mut key_range = kvrpcpb::KeyRange::default();
+                key_range.set_start_key(txn_types::Key::from_raw(r.get_start()).into_encoded());
+                key_range.set_end_key(txn_types::Key::from_raw(r.get_end()).into_encoded());
+                key_range
+            })
+            .collect();
+        snapshot
+            .ext()
+            .check_key_ranges(&key_ranges, tracker.req_ctx.is_desc_scan.unwrap_or(false))?;
+
         let mut handler = if tracker.req_ctx.cache_match_version.is_some()
             && tracker.req_ctx.cache_match_version == snapshot.ext().get_data_version()
         {
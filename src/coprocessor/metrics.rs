@@ -30,6 +30,7 @@ make_auto_flush_static_metric! {
         analyze_full_sampling,
         checksum_table,
         checksum_index,
+        column_type_check,
         test,
     }
 
@@ -25,6 +25,7 @@
 
 mod cache;
 mod checksum;
+pub mod column_type_check;
 mod config_manager;
 pub mod dag;
 mod endpoint;
@@ -57,6 +58,7 @@ use crate::storage::{Statistics, mvcc::TimeStamp};
 pub const REQ_TYPE_DAG: i64 = 103;
 pub const REQ_TYPE_ANALYZE: i64 = 104;
 pub const REQ_TYPE_CHECKSUM: i64 = 105;
+pub const REQ_TYPE_COLUMN_TYPE_CHECK: i64 = 106;
 
 pub const REQ_FLAG_TIDB_SYSSESSION: u64 = 2048;
 
@@ -435,6 +435,7 @@ impl<E: Engine> Tracker<E> {
             static ANALYZE_FULL_SAMPLING: RefCell<Option<Box<dyn PerfContext>>> = RefCell::new(None);
             static CHECKSUM_TABLE: RefCell<Option<Box<dyn PerfContext>>> = RefCell::new(None);
             static CHECKSUM_INDEX: RefCell<Option<Box<dyn PerfContext>>> = RefCell::new(None);
+            static COLUMN_TYPE_CHECK: RefCell<Option<Box<dyn PerfContext>>> = RefCell::new(None);
             static TEST: RefCell<Option<Box<dyn PerfContext>>> = RefCell::new(None);
         }
         let tls_cell = match self.req_tag {
@@ -447,6 +448,7 @@ impl<E: Engine> Tracker<E> {
             ReqTag::analyze_full_sampling => &ANALYZE_FULL_SAMPLING,
             ReqTag::checksum_table => &CHECKSUM_TABLE,
             ReqTag::checksum_index => &CHECKSUM_INDEX,
+            ReqTag::column_type_check => &COLUMN_TYPE_CHECK,
             ReqTag::test => &TEST,
         };
         tls_cell.with(|c| {
@@ -14,7 +14,6 @@ use lazy_static::lazy_static;
 use online_config::{ConfigChange, ConfigManager, OnlineConfig};
 pub use raftstore::store::Config as RaftStoreConfig;
 use raftstore::store::config::DEFAULT_SNAP_MAX_BYTES_PER_SEC;
-use regex::Regex;
 use tikv_util::{
     config::{self, ReadableDuration, ReadableSize, VersionTrack},
     sys::SysQuota,
@@ -390,6 +389,7 @@ impl Config {
                 self.advertise_addr
             ));
         }
+        self.advertise_addr = box_try!(config::normalize_addr(&self.advertise_addr));
         if self.status_addr.is_empty() && !self.advertise_status_addr.is_empty() {
             return Err(box_err!("status-addr can not be empty"));
         }
@@ -415,6 +415,10 @@ impl Config {
                     "status-addr" => %self.status_addr
                 );
             }
+            if !self.advertise_status_addr.is_empty() {
+                self.advertise_status_addr =
+                    box_try!(config::normalize_addr(&self.advertise_status_addr));
+            }
         }
         if self.advertise_status_addr == self.advertise_addr {
             return Err(box_err!(
@@ -471,9 +475,8 @@ impl Config {
             ));
         }
 
-        for (k, v) in &self.labels {
-            validate_label_key(k)?;
-            validate_label_value(v)?;
+        if let Err(e) = config::validate_store_labels(&self.labels) {
+            return Err(box_err!("{}", e));
         }
 
         if self.forward_max_connections_per_address == 0 {
@@ -608,36 +611,6 @@ impl ConfigManager for ServerConfigManager {
     }
 }
 
-lazy_static! {
-    static ref LABEL_KEY_FORMAT: Regex =
-        Regex::new("^[$]?[A-Za-z0-9]([-A-Za-z0-9_./]*[A-Za-z0-9])?$").unwrap();
-    static ref LABEL_VALUE_FORMAT: Regex = Regex::new("^[-A-Za-z0-9_./]*$").unwrap();
-}
-
-fn validate_label_key(s: &str) -> Result<()> {
-    if LABEL_KEY_FORMAT.is_match(s) {
-        Ok(())
-    } else {
-        Err(box_err!(
-            "store label key: {:?} not match {}",
-            s,
-            *LABEL_KEY_FORMAT
-        ))
-    }
-}
-
-fn validate_label_value(s: &str) -> Result<()> {
-    if LABEL_VALUE_FORMAT.is_match(s) {
-        Ok(())
-    } else {
-        Err(box_err!(
-            "store label value: {:?} not match {}",
-            s,
-            *LABEL_VALUE_FORMAT
-        ))
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use tikv_util::config::ReadableDuration;
@@ -712,33 +685,15 @@ mod tests {
 
     #[test]
     fn test_store_labels() {
-        let cases = vec![
-            ("", false, true),
-            ("123*", false, false),
-            (".123", false, true),
-            ("💖", false, false),
-            ("a", true, true),
-            ("0", true, true),
-            ("a.1-2", true, true),
-            ("Cab", true, true),
-            ("abC", true, true),
-            ("b_1.2", true, true),
-            ("cab-012", true, true),
-            ("3ac.8b2", true, true),
-            ("/abc", false, true),
-            ("abc/", false, true),
-            ("abc/def", true, true),
-            ("-abc", false, true),
-            ("abc-", false, true),
-            ("abc$def", false, false),
-            ("$abc", true, false),
-            ("$a.b-c/d_e", true, false),
-            (".-_/", false, true),
-        ];
+        let mut cfg = Config::default();
+        cfg.labels.insert("zone".to_owned(), "us-west-1".to_owned());
+        cfg.validate().unwrap();
 
-        for (text, can_be_key, can_be_value) in cases {
-            assert_eq!(validate_label_key(text).is_ok(), can_be_key);
-            assert_eq!(validate_label_value(text).is_ok(), can_be_value);
-        }
+        // The label charset/format/collision rules themselves are covered by
+        // `tikv_util::config::validate_store_labels`'s own tests; this just
+        // checks that `Config::validate` actually delegates to it.
+        cfg.labels
+            .insert("Zone".to_owned(), "us-west-1".to_owned());
+        cfg.validate().unwrap_err();
     }
 }
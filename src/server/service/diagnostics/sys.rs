@@ -465,6 +465,73 @@ pub fn system_info(collector: &mut Vec<ServerInfoItem>) {
     if let Some(item) = get_transparent_hugepage() {
         collector.push(item);
     }
+    collector.push(fd_limits_info());
+    collector.extend(data_dir_info());
+}
+
+/// Reports the file-descriptor rlimits discovered at startup by
+/// `tikv_util::config::check_max_open_fds`.
+fn fd_limits_info() -> ServerInfoItem {
+    let limits = tikv_util::config::last_fd_limits();
+    let mut pairs = vec![];
+    let mut pair = ServerInfoPair::default();
+    pair.set_key("soft".to_string());
+    pair.set_value(limits.soft.to_string());
+    pairs.push(pair);
+    let mut pair = ServerInfoPair::default();
+    pair.set_key("hard".to_string());
+    pair.set_value(limits.hard.to_string());
+    pairs.push(pair);
+    if let Some(raised_to) = limits.raised_to {
+        let mut pair = ServerInfoPair::default();
+        pair.set_key("raised_to".to_string());
+        pair.set_value(raised_to.to_string());
+        pairs.push(pair);
+    }
+    let mut item = ServerInfoItem::default();
+    item.set_tp("system".to_string());
+    item.set_name("fd_limits".to_string());
+    item.set_pairs(pairs.into());
+    item
+}
+
+/// Reports the fs type, mount options, device and rotational flag discovered
+/// at startup by `tikv_util::config::collect_data_dir_info`, one item per
+/// checked data directory.
+fn data_dir_info() -> Vec<ServerInfoItem> {
+    let mut infos: Vec<_> = tikv_util::config::last_data_dir_infos().into_iter().collect();
+    // Sort by name to make the result stable.
+    infos.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    infos
+        .into_iter()
+        .map(|(name, info)| {
+            let mut pairs = vec![];
+            let mut pair = ServerInfoPair::default();
+            pair.set_key("fs_type".to_string());
+            pair.set_value(info.fs_type);
+            pairs.push(pair);
+            let mut pair = ServerInfoPair::default();
+            pair.set_key("mount_options".to_string());
+            pair.set_value(info.mount_options);
+            pairs.push(pair);
+            let mut pair = ServerInfoPair::default();
+            pair.set_key("device".to_string());
+            pair.set_value(info.device);
+            pairs.push(pair);
+            if let Some(rotational) = info.rotational {
+                let mut pair = ServerInfoPair::default();
+                pair.set_key("rotational".to_string());
+                pair.set_value(rotational.to_string());
+                pairs.push(pair);
+            }
+            let mut item = ServerInfoItem::default();
+            item.set_tp("system".to_string());
+            item.set_name(format!("data_dir:{}", name));
+            item.set_pairs(pairs.into());
+            item
+        })
+        .collect()
 }
 
 /// Returns system wide configuration
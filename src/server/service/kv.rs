@@ -60,7 +60,8 @@ use crate::{
     storage::{
         self, SecondaryLocksStatus, Storage, TxnStatus,
         errors::{
-            extract_committed, extract_key_error, extract_key_errors, extract_kv_pairs,
+            extract_committed, extract_key_error, extract_key_errors, extract_key_errors_capped,
+            extract_kv_pairs,
             extract_region_error, extract_region_error_from_error, map_kv_pair_entries,
             map_kv_pairs,
         },
@@ -2467,7 +2468,10 @@ txn_command_future!(future_prewrite, PrewriteRequest, PrewriteResponse, (v, resp
         resp.set_min_commit_ts(v.min_commit_ts.into_inner());
         resp.set_one_pc_commit_ts(v.one_pc_commit_ts.into_inner());
     }
-    resp.set_errors(extract_key_errors(v.map(|v| v.locks)).into());
+    resp.set_errors(
+        extract_key_errors_capped(v.map(|v| v.locks), storage.max_key_errors_per_response())
+            .into(),
+    );
 });
 txn_command_future!(future_acquire_pessimistic_lock, PessimisticLockRequest, PessimisticLockResponse,
     (req) {
@@ -2599,7 +2603,12 @@ txn_command_future!(future_mvcc_get_by_start_ts, MvccGetByStartTsRequest, MvccGe
     }
 });
 txn_command_future!(future_flush, FlushRequest, FlushResponse, (v, resp) {
-    resp.set_errors(extract_key_errors(v).into());
+    // `v` pairs each result with the key it came from (see
+    // `ProcessResult::MultiKeyedRes`); the errors reported here don't need
+    // it, since `extract_key_error` already embeds the key for every
+    // variant a prewrite can produce.
+    let v = v.map(|results| results.into_iter().map(|(_, r)| r).collect());
+    resp.set_errors(extract_key_errors_capped(v, storage.max_key_errors_per_response()).into());
 });
 
 pub mod batch_commands_response {
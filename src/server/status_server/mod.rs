@@ -57,6 +57,7 @@ use service::service_manager::GrpcServiceManager;
 use tikv_kv::RaftExtension;
 use tikv_util::{
     GLOBAL_SERVER_READINESS,
+    config::CanonicalizeConfig,
     logger::set_log_level,
     metrics::{dump, dump_to},
     thread_name_prefix::STATUS_SERVER_THREAD,
@@ -190,6 +191,7 @@ where
         cfg_controller: &ConfigController,
     ) -> hyper::Result<Response<Body>> {
         let mut full = false;
+        let mut canonical = false;
         if let Some(query) = req.uri().query() {
             let query_pairs: HashMap<_, _> =
                 url::form_urlencoded::parse(query.as_bytes()).collect();
@@ -200,14 +202,43 @@ where
                 },
                 None => false,
             };
+            canonical = match query_pairs.get("canonical") {
+                Some(val) => match val.parse() {
+                    Ok(val) => val,
+                    Err(err) => return Ok(make_response(StatusCode::BAD_REQUEST, err.to_string())),
+                },
+                None => false,
+            };
         }
-        let encode_res = if full {
-            // Get all config
-            serde_json::to_string(&cfg_controller.get_current())
-        } else {
-            // Filter hidden config
-            serde_json::to_string(&cfg_controller.get_current().get_encoder())
-        };
+        // Emit `ReadableSize` fields as exact byte counts rather than their
+        // human-readable unit form, so that diffing two dumps of this
+        // endpoint doesn't show spurious changes when a value crosses a
+        // power-of-two boundary (e.g. "1073741824" -> "1GiB").
+        let encode_res = tikv_util::config::with_exact_byte_sizes(|| {
+            if full {
+                // Get all config
+                serde_json::to_string(&cfg_controller.get_current())
+            } else {
+                // Filter hidden config
+                serde_json::to_string(&cfg_controller.get_current().get_encoder())
+            }
+        });
+        // With `canonical=true`, run the whole dump through
+        // `CanonicalizeConfig` on top of `with_exact_byte_sizes`, so a value
+        // this endpoint doesn't itself serialize through `ReadableSize`/
+        // `ReadableDuration` (e.g. one embedded in an opaque nested JSON
+        // blob) is still folded to its exact form. This lets a caller pull
+        // dumps from two stores and diff them textually, or feed both into
+        // `tikv_util::config::config_semantic_diff` for a structured diff.
+        let encode_res = encode_res.map(|json| {
+            if !canonical {
+                return json;
+            }
+            match serde_json::from_str::<serde_json::Value>(&json) {
+                Ok(value) => serde_json::to_string(&value.canonicalize()).unwrap_or(json),
+                Err(_) => json,
+            }
+        });
         Ok(match encode_res {
             Ok(json) => Response::builder()
                 .header(header::CONTENT_TYPE, "application/json")
@@ -332,6 +363,8 @@ where
                                 e
                             ),
                         )
+                    } else if let Some(e) = e.downcast_ref::<tikv_util::config::ConfigError>() {
+                        make_config_error_response(e)
                     } else {
                         make_response(
                             StatusCode::INTERNAL_SERVER_ERROR,
@@ -1385,6 +1418,41 @@ where
         .unwrap()
 }
 
+/// JSON body returned for a rejected config update, so callers (e.g. TiDB's
+/// config management) can act on the failure without parsing the `Display`
+/// message meant for logs.
+#[derive(Serialize)]
+struct ConfigErrorResponse<'a> {
+    error_code: &'static str,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    field: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    constraint: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    got: Option<&'a str>,
+}
+
+fn make_config_error_response(e: &tikv_util::config::ConfigError) -> Response<Body> {
+    use tikv_util::config::ConfigError;
+
+    let detail = match e {
+        ConfigError::Limit(v) | ConfigError::Address(v) | ConfigError::Value(v) => Some(v),
+        ConfigError::StoreLabels(_) | ConfigError::FileSystem(_) => None,
+    };
+    let resp = ConfigErrorResponse {
+        error_code: error_code::ErrorCodeExt::error_code(e).code,
+        message: e.to_string(),
+        field: detail.map(|v| v.field.as_str()),
+        constraint: detail.map(|v| v.constraint.as_str()),
+        got: detail.map(|v| v.got.as_str()),
+    };
+    make_response(
+        StatusCode::BAD_REQUEST,
+        serde_json::to_string(&resp).unwrap(),
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use std::{
@@ -1611,6 +1679,47 @@ mod tests {
         test_config(false);
     }
 
+    fn config_error_response_json(e: &tikv_util::config::ConfigError) -> serde_json::Value {
+        let resp = super::make_config_error_response(e);
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let body = block_on(hyper::body::to_bytes(resp.into_body())).unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[test]
+    fn test_config_error_response_value() {
+        let err = tikv_util::config::ConfigError::Value(tikv_util::config::InvalidConfigValue::new(
+            "rocksdb.defaultcf.block-cache-size",
+            "a readable byte size (e.g. \"1KiB\")",
+            "not-a-size",
+        ));
+        let json = config_error_response_json(&err);
+        assert_eq!(json["error_code"], "KV:Config:Value");
+        assert_eq!(json["field"], "rocksdb.defaultcf.block-cache-size");
+        assert_eq!(json["got"], "not-a-size");
+    }
+
+    #[test]
+    fn test_config_error_response_address() {
+        let err = tikv_util::config::ConfigError::Address(
+            tikv_util::config::InvalidConfigValue::new("addr", "a \"Host:Port\" address", "bad"),
+        );
+        let json = config_error_response_json(&err);
+        assert_eq!(json["error_code"], "KV:Config:Address");
+        assert_eq!(json["field"], "addr");
+        assert_eq!(json["got"], "bad");
+    }
+
+    #[test]
+    fn test_config_error_response_store_labels() {
+        let err =
+            tikv_util::config::ConfigError::StoreLabels("duplicate label key: zone".to_owned());
+        let json = config_error_response_json(&err);
+        assert_eq!(json["error_code"], "KV:Config:StoreLabels");
+        assert!(json["message"].as_str().unwrap().contains("zone"));
+        assert!(json.get("field").is_none());
+    }
+
     #[cfg(feature = "failpoints")]
     #[test]
     fn test_status_service_fail_endpoints() {
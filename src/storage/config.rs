@@ -30,6 +30,7 @@ const MAX_SCHED_CONCURRENCY: usize = 2 * 1024 * 1024;
 // on average, in that situation the writing bytes estimated 10MB,
 // here we use 100MB as default value for tolerate 1s latency.
 const DEFAULT_SCHED_PENDING_WRITE_MB: u64 = 100;
+const DEFAULT_SCHED_OLD_VALUES_MEMORY_QUOTA_MB: u64 = 128;
 
 // The default memory quota for pending and running storage commands kv_get,
 // kv_prewrite, kv_commit, etc.
@@ -66,6 +67,21 @@ const FALLBACK_BLOCK_CACHE_CAPACITY: ReadableSizeOrPercent = ReadableSizeOrPerce
 
 const DEFAULT_ACTION_ON_INVALID_MAX_TS_UPDATE: &str = "panic";
 
+// A Flush or Prewrite over a huge, heavily-conflicting batch can produce one
+// `KeyError` per key. Left unbounded, that list alone can blow past the gRPC
+// message size limit and turn a useful lock/conflict report into a transport
+// error. Cap the number of per-key errors carried in a single response;
+// extra ones are dropped and only logged, since kvrpcpb has no field to
+// report a suppressed-error count in-band.
+const DEFAULT_MAX_KEY_ERRORS_PER_RESPONSE: usize = 4096;
+
+// raftstore's default `raft-entry-max-size` is 8MB; keep some headroom below
+// that for the rest of the raft entry (header, other batched requests) so an
+// oversized write command gets auto-split into multiple proposals instead of
+// being rejected outright at propose time. Operators who raise
+// `raftstore.raft-entry-max-size` should raise this in step.
+const DEFAULT_RAFT_WRITE_MAX_SIZE_MB: u64 = 6;
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "kebab-case")]
 pub enum EngineType {
@@ -88,11 +104,31 @@ pub struct Config {
     #[online_config(skip)]
     pub max_key_size: usize,
     #[online_config(skip)]
+    /// Upper bound on the number of per-key `KeyError`s carried in a single
+    /// Flush or Prewrite response. Beyond this, remaining errors are
+    /// dropped and only logged.
+    pub max_key_errors_per_response: usize,
+    #[online_config(skip)]
     pub scheduler_concurrency: usize,
     pub scheduler_worker_pool_size: usize,
     #[online_config(skip)]
     pub scheduler_pending_write_threshold: ReadableSize,
     #[online_config(skip)]
+    /// Ceiling on the serialized size of a single write command's modifies
+    /// proposed as one raft entry. A batch over this is split into several
+    /// consecutive proposals instead of being rejected by raftstore's
+    /// `raft-entry-max-size`; latches are held across the whole split, so
+    /// atomicity wrt other transactions is unaffected. `0` disables
+    /// splitting. Never applies to 1PC batches, since 1PC's atomicity comes
+    /// from being a single raft entry.
+    pub raft_write_max_size: ReadableSize,
+    #[online_config(skip)]
+    /// Ceiling on the total size of old values captured by proposed-but-not-yet-finished
+    /// write commands. Once the in-flight total would exceed this, newly proposed
+    /// commands have their old values degraded to `OldValue::SeekWrite` markers instead
+    /// of being held in memory. `0` disables the ceiling.
+    pub scheduler_old_values_memory_quota: ReadableSize,
+    #[online_config(skip)]
     // Reserve disk space to make tikv would have enough space to compact when disk is full.
     pub reserve_space: ReadableSize,
     #[online_config(skip)]
@@ -128,6 +164,7 @@ impl Default for Config {
             engine: EngineType::RaftKv,
             gc_ratio_threshold: DEFAULT_GC_RATIO_THRESHOLD,
             max_key_size: DEFAULT_MAX_KEY_SIZE,
+            max_key_errors_per_response: DEFAULT_MAX_KEY_ERRORS_PER_RESPONSE,
             scheduler_concurrency: DEFAULT_SCHED_CONCURRENCY,
             scheduler_worker_pool_size: if cpu_num >= 16.0 {
                 8
@@ -135,6 +172,10 @@ impl Default for Config {
                 cpu_num.clamp(1., 4.) as usize
             },
             scheduler_pending_write_threshold: ReadableSize::mb(DEFAULT_SCHED_PENDING_WRITE_MB),
+            raft_write_max_size: ReadableSize::mb(DEFAULT_RAFT_WRITE_MAX_SIZE_MB),
+            scheduler_old_values_memory_quota: ReadableSize::mb(
+                DEFAULT_SCHED_OLD_VALUES_MEMORY_QUOTA_MB,
+            ),
             reserve_space: ReadableSize::gb(DEFAULT_RESERVED_SPACE_GB),
             reserve_raft_space: ReadableSize::gb(DEFAULT_RESERVED_RAFT_SPACE_GB),
             enable_async_apply_prewrite: false,
@@ -198,6 +239,9 @@ impl Config {
             );
             self.scheduler_concurrency = MAX_SCHED_CONCURRENCY;
         }
+        if self.max_key_errors_per_response == 0 {
+            return Err("storage.max-key-errors-per-response should be greater than 0".into());
+        }
         if !matches!(self.api_version, 1 | 2) {
             return Err("storage.api_version can only be set to 1 or 2.".into());
         }
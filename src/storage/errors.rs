@@ -595,6 +595,35 @@ pub fn extract_key_errors(res: Result<Vec<Result<()>>>) -> Vec<kvrpcpb::KeyError
     }
 }
 
+/// Like [`extract_key_errors`], but never returns more than `limit` errors.
+///
+/// A Flush or Prewrite over a large, heavily-conflicting batch can produce
+/// one `KeyError` per key, which can blow past the gRPC message size limit
+/// on its own. This keeps the response bounded by truncating the list and
+/// logging how many errors were dropped; there's no field on
+/// `kvrpcpb::KeyError`/the response messages to report the suppressed count
+/// back to the client in-band, so a log line is the best we can do without a
+/// kvproto change.
+pub fn extract_key_errors_capped(
+    res: Result<Vec<Result<()>>>,
+    limit: usize,
+) -> Vec<kvrpcpb::KeyError> {
+    let errors = extract_key_errors(res);
+    if errors.len() > limit {
+        warn!(
+            "too many key errors in a single response, truncating";
+            "total" => errors.len(),
+            "limit" => limit,
+            "suppressed" => errors.len() - limit,
+        );
+        let mut errors = errors;
+        errors.truncate(limit);
+        errors
+    } else {
+        errors
+    }
+}
+
 /// The shared version of [`Error`]. In some cases, it's necessary to pass a
 /// single error to more than one requests, since the inner error doesn't
 /// support cloning.
@@ -785,4 +814,25 @@ mod test {
         ));
         assert_eq!(mock_commit_ts_expired_err(true), expect);
     }
+
+    #[test]
+    fn test_extract_key_errors_capped() {
+        fn mock_locked(start_ts: u64) -> Result<()> {
+            let mut lock_info = kvrpcpb::LockInfo::default();
+            lock_info.set_key(b"k".to_vec());
+            lock_info.set_lock_version(start_ts);
+            Err(Error::from(TxnError::from(MvccError::from(
+                MvccErrorInner::KeyIsLocked(lock_info),
+            ))))
+        }
+
+        let results: Vec<Result<()>> = (0..10).map(mock_locked).collect();
+        let got = extract_key_errors_capped(Ok(results.clone()), 3);
+        assert_eq!(got.len(), 3);
+        assert_eq!(got, extract_key_errors(Ok(results.clone()))[..3]);
+
+        // Under the limit, nothing is dropped.
+        let got = extract_key_errors_capped(Ok(results.clone()), 20);
+        assert_eq!(got.len(), 10);
+    }
 }
@@ -428,6 +428,18 @@ lazy_static! {
         "Total number of writing kv."
     )
     .unwrap();
+    pub static ref SCHED_OLD_VALUES_INFLIGHT_BYTES_GAUGE: IntGauge = register_int_gauge!(
+        "tikv_scheduler_old_values_inflight_bytes",
+        "Total bytes of old values captured by commands that have been proposed but whose \
+         proposal callback has not fired yet."
+    )
+    .unwrap();
+    pub static ref SCHED_OLD_VALUES_DEGRADE_COUNTER: IntCounter = register_int_counter!(
+        "tikv_scheduler_old_values_degrade_total",
+        "Total number of times captured old values were degraded to the SeekWrite marker \
+         because the in-flight old-value byte budget was exceeded."
+    )
+    .unwrap();
     pub static ref SCHED_CONTEX_GAUGE: IntGauge = register_int_gauge!(
         "tikv_scheduler_contex_total",
         "Total number of pending commands."
@@ -213,6 +213,8 @@ pub struct Storage<E: Engine, L: LockManager, F: KvFormat> {
     // Fields below are storage configurations.
     max_key_size: usize,
 
+    max_key_errors_per_response: usize,
+
     resource_tag_factory: ResourceTagFactory,
 
     api_version: ApiVersion, // TODO: remove this. Use `Api` instead.
@@ -240,6 +242,7 @@ impl<E: Engine, L: LockManager, F: KvFormat> Clone for Storage<E, L, F> {
             read_pool: self.read_pool.clone(),
             refs: self.refs.clone(),
             max_key_size: self.max_key_size,
+            max_key_errors_per_response: self.max_key_errors_per_response,
             concurrency_manager: self.concurrency_manager.clone(),
             api_version: self.api_version,
             causal_ts_provider: self.causal_ts_provider.clone(),
@@ -315,6 +318,7 @@ impl<E: Engine, L: LockManager, F: KvFormat> Storage<E, L, F> {
             concurrency_manager,
             refs: Arc::new(atomic::AtomicUsize::new(1)),
             max_key_size: config.max_key_size,
+            max_key_errors_per_response: config.max_key_errors_per_response,
             api_version: config.api_version(),
             causal_ts_provider,
             resource_tag_factory,
@@ -337,6 +341,12 @@ impl<E: Engine, L: LockManager, F: KvFormat> Storage<E, L, F> {
         self.concurrency_manager.clone()
     }
 
+    /// Upper bound on the number of per-key `KeyError`s a single Flush or
+    /// Prewrite response may carry.
+    pub fn max_key_errors_per_response(&self) -> usize {
+        self.max_key_errors_per_response
+    }
+
     pub fn dump_wait_for_entries(&self, cb: waiter_manager::Callback) {
         self.sched.dump_wait_for_entries(cb);
     }
@@ -3680,6 +3690,14 @@ impl<S: Snapshot> Snapshot for TxnTestSnapshot<S> {
         self.snapshot.get_cf_opt(opts, cf, key)
     }
 
+    fn get_pinned_cf(
+        &self,
+        cf: CfName,
+        key: &Key,
+    ) -> tikv_kv::Result<Option<tikv_kv::PinnedValue>> {
+        self.snapshot.get_pinned_cf(cf, key)
+    }
+
     fn iter(
         &self,
         cf: CfName,
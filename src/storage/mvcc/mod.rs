@@ -116,11 +116,13 @@ pub enum ErrorInner {
     },
 
     #[error(
-        "key {} already exists with existing_start_ts={}", log_wrappers::Value::key(.key),
-        .existing_start_ts)]
+        "key {} already exists with existing_start_ts={}, existing_commit_ts={}",
+        log_wrappers::Value::key(.key), .existing_start_ts, .existing_commit_ts
+    )]
     AlreadyExist {
         key: Vec<u8>,
         existing_start_ts: TimeStamp,
+        existing_commit_ts: TimeStamp,
     },
 
     #[error(
@@ -260,9 +262,11 @@ impl ErrorInner {
             ErrorInner::AlreadyExist {
                 key,
                 existing_start_ts,
+                existing_commit_ts,
             } => Some(ErrorInner::AlreadyExist {
                 key: key.clone(),
                 existing_start_ts: *existing_start_ts,
+                existing_commit_ts: *existing_commit_ts,
             }),
             ErrorInner::DefaultNotFound { key } => Some(ErrorInner::DefaultNotFound {
                 key: key.to_owned(),
@@ -441,11 +441,11 @@ impl<S: Snapshot> PointGetter<S> {
         // TODO: We can avoid this clone.
         let value = self
             .snapshot
-            .get_cf(CF_DEFAULT, &user_key.clone().append_ts(write_start_ts))?;
+            .get_pinned_cf(CF_DEFAULT, &user_key.clone().append_ts(write_start_ts))?;
 
         if let Some(value) = value {
             self.statistics.data.processed_keys += 1;
-            Ok(value)
+            Ok(value.to_vec())
         } else {
             Err(default_not_found_error(
                 user_key.clone().append_ts(write_start_ts).into_encoded(),
@@ -158,6 +158,23 @@ impl<S: Snapshot> ScannerBuilder<S> {
         self
     }
 
+    /// Set whether the write and lock CF cursors should use prefix seek.
+    ///
+    /// This should only be turned on when the scanner is only ever going to
+    /// look at a single user key's version chain (e.g. a get-commit-ts style
+    /// lookup), the same case `PointGetter` and `MvccReader`'s write cursor
+    /// already restrict to a prefix seek. Enabling it for a scan spanning
+    /// multiple user keys would make the cursor unable to move past the
+    /// first key's prefix.
+    ///
+    /// Default is false.
+    #[inline]
+    #[must_use]
+    pub fn prefix_seek(mut self, enabled: bool) -> Self {
+        self.0.prefix_seek = enabled;
+        self
+    }
+
     /// Build `Scanner` from the current configuration.
     pub fn build(mut self) -> Result<Scanner<S>> {
         let lock_cursor = self.build_lock_cursor()?;
@@ -286,6 +303,9 @@ pub struct ScannerConfig<S: Snapshot> {
     access_locks: TsSet,
 
     check_has_newer_ts_data: bool,
+
+    /// See [`ScannerBuilder::prefix_seek`].
+    prefix_seek: bool,
 }
 
 impl<S: Snapshot> ScannerConfig<S> {
@@ -305,6 +325,7 @@ impl<S: Snapshot> ScannerConfig<S> {
             access_locks: Default::default(),
             check_has_newer_ts_data: false,
             load_commit_ts: false,
+            prefix_seek: false,
         }
     }
 
@@ -342,9 +363,15 @@ impl<S: Snapshot> ScannerConfig<S> {
         } else {
             (None, None)
         };
+        // Only the write and lock CFs carry multiple versions of a user key
+        // behind one bloom-filterable prefix; a prefix seek restricted to
+        // the default CF (or a multi-key scan) would wrongly stop the
+        // cursor from moving past the first key.
+        let prefix_seek = self.prefix_seek && (cf == CF_WRITE || cf == CF_LOCK);
         let cursor = CursorBuilder::new(&self.snapshot, cf)
             .range(lower, upper)
             .fill_cache(self.fill_cache)
+            .prefix_seek(prefix_seek)
             .scan_mode(scan_mode)
             .hint_min_ts(hint_min_ts.map(Bound::Included))
             .hint_max_ts(hint_max_ts.map(Bound::Included))
@@ -1288,4 +1315,42 @@ mod tests {
 
         assert!(scanner.next_entry().unwrap().is_none());
     }
+
+    #[test]
+    fn test_scan_with_prefix_seek() {
+        // `prefix_seek` restricts the write/lock cursors to a single user key's
+        // prefix, so it must only be turned on when the scan range is already
+        // bounded to one key. Check that doing so does not change the result
+        // compared to a plain scan over the same multi-version key.
+        let mut engine = TestEngineBuilder::new().build().unwrap();
+        let key = b"foo";
+        let (val1, val2, val3) = (b"bar1", b"bar2", b"bar3");
+
+        must_prewrite_put(&mut engine, key, val1, key, 10);
+        must_commit(&mut engine, key, 10, 20);
+
+        must_prewrite_put(&mut engine, key, val2, key, 30);
+        must_commit(&mut engine, key, 30, 40);
+
+        must_prewrite_put(&mut engine, key, val3, key, 50);
+        must_commit(&mut engine, key, 50, 60);
+
+        let lower = Some(Key::from_raw(key));
+        let upper = Some(Key::from_raw(key).append_ts(TimeStamp::zero()));
+
+        for prefix_seek in [false, true] {
+            let snapshot = engine.snapshot(Default::default()).unwrap();
+            let mut scanner = ScannerBuilder::new(snapshot, 100.into())
+                .fill_cache(false)
+                .range(lower.clone(), upper.clone())
+                .prefix_seek(prefix_seek)
+                .build()
+                .unwrap();
+
+            let (k, v) = scanner.next().unwrap().unwrap();
+            assert_eq!(k, Key::from_raw(key));
+            assert_eq!(v, val3);
+            assert!(scanner.next().unwrap().is_none());
+        }
+    }
 }
@@ -31,18 +31,21 @@ pub(crate) fn check_data_constraint<S: Snapshot>(
     // 1.The current write type is `PUT`
     // 2.The current write type is `Rollback` or `Lock`, and the key have an older
     // version.
-    let existing_start_ts = if write.write_type == WriteType::Put {
-        Some(write.start_ts)
-    } else if let Some(prev_write) = reader.get_write(key, write_commit_ts.prev())? {
-        Some(prev_write.start_ts)
+    let existing = if write.write_type == WriteType::Put {
+        Some((write.start_ts, write_commit_ts))
+    } else if let Some((prev_write, prev_commit_ts)) =
+        reader.get_write_with_commit_ts(key, write_commit_ts.prev())?
+    {
+        Some((prev_write.start_ts, prev_commit_ts))
     } else {
         None
     };
 
-    if let Some(existing_start_ts) = existing_start_ts {
+    if let Some((existing_start_ts, existing_commit_ts)) = existing {
         return Err(ErrorInner::AlreadyExist {
             key: key.to_raw()?,
             existing_start_ts,
+            existing_commit_ts,
         }
         .into());
     }
@@ -108,6 +111,7 @@ mod tests {
                 expected: Err(ErrorInner::AlreadyExist {
                     key: b"a".to_vec(),
                     existing_start_ts: TimeStamp::new(3),
+                    existing_commit_ts: TimeStamp::default(),
                 }
                 .into()),
                 should_not_exist: true,
@@ -120,6 +124,7 @@ mod tests {
                 expected: Err(ErrorInner::AlreadyExist {
                     key: b"a".to_vec(),
                     existing_start_ts: TimeStamp::new(2),
+                    existing_commit_ts: TimeStamp::new(5),
                 }
                 .into()),
                 should_not_exist: true,
@@ -132,6 +137,7 @@ mod tests {
                 expected: Err(ErrorInner::AlreadyExist {
                     key: b"a".to_vec(),
                     existing_start_ts: TimeStamp::new(2),
+                    existing_commit_ts: TimeStamp::new(5),
                 }
                 .into()),
                 should_not_exist: true,
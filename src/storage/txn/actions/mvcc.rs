@@ -48,6 +48,35 @@ pub fn find_mvcc_infos_by_key<S: Snapshot>(
     Ok((lock, writes, values))
 }
 
+/// For each of `keys`, returns the generation (set by `prewrite_with_generation`,
+/// see [`crate::storage::txn::actions::prewrite::prewrite_with_generation`])
+/// recorded in its current lock, or `None` if the key has no lock belonging
+/// to `start_ts` -- either because the transaction already committed/rolled
+/// back that key, or because it was never flushed.
+///
+/// This lets a client doing pipelined DML (buffering mutations locally and
+/// flushing them to TiKV in generations) find out, for a batch of keys, the
+/// newest generation TiKV durably holds, so it can safely evict
+/// already-flushed mutations with smaller generations from its local buffer.
+pub fn find_generations_by_keys<S: Snapshot>(
+    reader: &mut MvccReader<S>,
+    start_ts: TimeStamp,
+    keys: &[Key],
+) -> crate::storage::txn::Result<Vec<(Key, Option<u64>)>> {
+    let mut result = Vec::with_capacity(keys.len());
+    for key in keys {
+        let generation = match reader.load_lock(key)? {
+            Some(Either::Left(lock)) if lock.ts == start_ts => Some(lock.generation),
+            // A lock belonging to a different transaction, a `SharedLocks` entry
+            // (never written by `prewrite_with_generation`), or no lock at all
+            // mean this transaction has no generation recorded for the key.
+            _ => None,
+        };
+        result.push((key.clone(), generation));
+    }
+    Ok(result)
+}
+
 pub fn collect_mvcc_info_for_debug<S: Snapshot>(snapshot: S, key: &Key) -> Option<LockWritesVals> {
     let mut reader = MvccReader::new(snapshot, Some(ScanMode::Forward), false);
     match find_mvcc_infos_by_key(&mut reader, key) {
@@ -76,14 +105,16 @@ pub mod tests {
     #[cfg(test)]
     use txn_types::{Lock, SHORT_VALUE_MAX_LEN, TimeStamp, Value, Write};
 
-    use crate::storage::txn::actions::mvcc::{LockWritesVals, MvccReader, find_mvcc_infos_by_key};
+    use crate::storage::txn::actions::mvcc::{
+        LockWritesVals, MvccReader, find_generations_by_keys, find_mvcc_infos_by_key,
+    };
     #[cfg(test)]
     use crate::storage::{
         TestEngineBuilder,
         mvcc::SnapshotReader,
         txn::{
             actions::mvcc::collect_mvcc_info_for_debug,
-            tests::{must_commit, must_prewrite_put},
+            tests::{must_commit, must_flush_put, must_prewrite_put},
         },
     };
 
@@ -205,4 +236,36 @@ pub mod tests {
             (lock, writes, values),
         )
     }
+
+    #[test]
+    fn test_find_generations_by_keys() {
+        let mut engine = TestEngineBuilder::new().build().unwrap();
+        let start_ts: u64 = 10;
+
+        // k1 is flushed once, then overwritten by a later generation.
+        must_flush_put(&mut engine, b"k1", b"v1", b"k1", start_ts, 1);
+        must_flush_put(&mut engine, b"k1", b"v1-2", b"k1", start_ts, 2);
+        // k2 is flushed once and never overwritten.
+        must_flush_put(&mut engine, b"k2", b"v2", b"k1", start_ts, 1);
+        // k3 was flushed, then committed, so it no longer has a lock.
+        must_flush_put(&mut engine, b"k3", b"v3", b"k3", start_ts, 1);
+        must_commit(&mut engine, b"k3", start_ts, start_ts + 1);
+        // k4 was never flushed or locked at all.
+
+        let snapshot = engine.snapshot(Default::default()).unwrap();
+        let mut reader = MvccReader::new(snapshot, None, true);
+        let keys = [b"k1", b"k2", b"k3", b"k4"].map(|k| Key::from_raw(k));
+        let generations =
+            find_generations_by_keys(&mut reader, start_ts.into(), &keys).unwrap();
+
+        assert_eq!(
+            generations,
+            vec![
+                (keys[0].clone(), Some(2)),
+                (keys[1].clone(), Some(1)),
+                (keys[2].clone(), None),
+                (keys[3].clone(), None),
+            ]
+        );
+    }
 }
@@ -93,7 +93,29 @@ pub fn prewrite_with_generation<S: Snapshot>(
 
     let mut lock_amended = false;
 
-    let (shared_locks, lock_status) = match reader.load_lock(&mutation.key)? {
+    // Check for pending lock modifications first. This is important when a
+    // single `Flush` command batches several generations of the same
+    // transaction for the same key in one raft proposal (`extra_batches`):
+    // an earlier generation's lock is only in `txn`'s pending modifies, not
+    // yet visible through `reader`'s snapshot, so without this a later
+    // generation would mistakenly think the key isn't locked yet and skip
+    // the generation-order check and short_value overwrite below.
+    let lock_state = match txn.get_pending_lock_bytes(&mutation.key) {
+        Some(None) => {
+            // Lock was deleted by a previous operation in this batch.
+            None
+        }
+        Some(Some(bytes)) => {
+            // Use pending lock state.
+            Some(txn_types::parse_lock(bytes)?)
+        }
+        None => {
+            // No pending modification, read from snapshot.
+            reader.load_lock(&mutation.key)?
+        }
+    };
+
+    let (shared_locks, lock_status) = match lock_state {
         Some(lock_or_shared) => match lock_or_shared {
             Either::Left(lock) => {
                 if mutation.is_shared_lock {
@@ -324,6 +346,153 @@ impl TransactionProperties<'_> {
     }
 }
 
+/// Builder for [`TransactionProperties`].
+///
+/// `TransactionProperties` is built by hand at every prewrite-like call
+/// site, and every field added to it (e.g. `assertion_level`, `txn_source`)
+/// has to be threaded through each of those call sites by hand too. Using
+/// this builder instead means a newly added field only needs a default
+/// here; a call site that should have overridden it but didn't will use
+/// that default rather than silently getting whatever `derive(Default)`
+/// would have picked.
+#[must_use = "call `.build()` to construct the `TransactionProperties`"]
+pub struct TransactionPropertiesBuilder<'a> {
+    start_ts: TimeStamp,
+    kind: TransactionKind,
+    primary: &'a [u8],
+    commit_kind: CommitKind,
+    txn_size: u64,
+    lock_ttl: u64,
+    min_commit_ts: TimeStamp,
+    need_old_value: bool,
+    is_retry_request: bool,
+    assertion_level: AssertionLevel,
+    txn_source: u64,
+}
+
+impl<'a> TransactionPropertiesBuilder<'a> {
+    /// Initialize a new `TransactionPropertiesBuilder`.
+    ///
+    /// `start_ts`, `primary` and `kind` are required by every caller, so
+    /// they're taken here rather than defaulted; everything else has a
+    /// setter below.
+    pub fn new(start_ts: TimeStamp, primary: &'a [u8], kind: TransactionKind) -> Self {
+        Self {
+            start_ts,
+            kind,
+            primary,
+            commit_kind: CommitKind::TwoPc,
+            txn_size: 0,
+            lock_ttl: 0,
+            min_commit_ts: TimeStamp::zero(),
+            need_old_value: false,
+            is_retry_request: false,
+            assertion_level: AssertionLevel::Off,
+            txn_source: 0,
+        }
+    }
+
+    /// Set how the transaction will be committed.
+    ///
+    /// Defaults to `CommitKind::TwoPc`.
+    #[inline]
+    #[must_use]
+    pub fn commit_kind(mut self, commit_kind: CommitKind) -> Self {
+        self.commit_kind = commit_kind;
+        self
+    }
+
+    /// Set the number of keys in the transaction, used to decide whether
+    /// `resolve_lock_lite` may skip work.
+    ///
+    /// Defaults to `0`.
+    #[inline]
+    #[must_use]
+    pub fn txn_size(mut self, txn_size: u64) -> Self {
+        self.txn_size = txn_size;
+        self
+    }
+
+    /// Set the TTL, in milliseconds, of locks written by this transaction.
+    ///
+    /// Defaults to `0`.
+    #[inline]
+    #[must_use]
+    pub fn lock_ttl(mut self, lock_ttl: u64) -> Self {
+        self.lock_ttl = lock_ttl;
+        self
+    }
+
+    /// Set the minimum allowed commit ts, used by async commit and 1PC.
+    ///
+    /// Defaults to `TimeStamp::zero()`, i.e. no constraint beyond the usual
+    /// `start_ts < commit_ts` rule.
+    #[inline]
+    #[must_use]
+    pub fn min_commit_ts(mut self, min_commit_ts: TimeStamp) -> Self {
+        self.min_commit_ts = min_commit_ts;
+        self
+    }
+
+    /// Set whether the old value should be collected for CDC/TiCDC.
+    ///
+    /// Defaults to `false`.
+    #[inline]
+    #[must_use]
+    pub fn need_old_value(mut self, need_old_value: bool) -> Self {
+        self.need_old_value = need_old_value;
+        self
+    }
+
+    /// Set whether this is a retry of a request the client already sent.
+    ///
+    /// Defaults to `false`.
+    #[inline]
+    #[must_use]
+    pub fn is_retry_request(mut self, is_retry_request: bool) -> Self {
+        self.is_retry_request = is_retry_request;
+        self
+    }
+
+    /// Set how strictly assertions should be checked.
+    ///
+    /// Defaults to `AssertionLevel::Off`.
+    #[inline]
+    #[must_use]
+    pub fn assertion_level(mut self, assertion_level: AssertionLevel) -> Self {
+        self.assertion_level = assertion_level;
+        self
+    }
+
+    /// Set the source of the transaction, used to distinguish e.g. Lightning
+    /// or CDC-replicated writes from ordinary user writes.
+    ///
+    /// Defaults to `0`.
+    #[inline]
+    #[must_use]
+    pub fn txn_source(mut self, txn_source: u64) -> Self {
+        self.txn_source = txn_source;
+        self
+    }
+
+    /// Build `TransactionProperties` from the current configuration.
+    pub fn build(self) -> TransactionProperties<'a> {
+        TransactionProperties {
+            start_ts: self.start_ts,
+            kind: self.kind,
+            commit_kind: self.commit_kind,
+            primary: self.primary,
+            txn_size: self.txn_size,
+            lock_ttl: self.lock_ttl,
+            min_commit_ts: self.min_commit_ts,
+            need_old_value: self.need_old_value,
+            is_retry_request: self.is_retry_request,
+            assertion_level: self.assertion_level,
+            txn_source: self.txn_source,
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub enum CommitKind {
     TwoPc,
@@ -546,6 +715,8 @@ impl<'a> PrewriteMutation<'a> {
             return Err(ErrorInner::AlreadyExist {
                 key: self.key.to_raw()?,
                 existing_start_ts: lock.ts,
+                // The conflicting write is still only locked, not committed yet.
+                existing_commit_ts: TimeStamp::zero(),
             }
             .into());
         }
@@ -1215,6 +1386,41 @@ pub mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_transaction_properties_builder_defaults() {
+        // Only start_ts/primary/kind are required; every other field must
+        // come out with the documented default so a field added later and
+        // forgotten at some call site fails safe rather than silently
+        // picking up whatever `derive(Default)` would choose.
+        let start_ts = 10.into();
+        let optimistic =
+            TransactionPropertiesBuilder::new(start_ts, b"k1", TransactionKind::Optimistic(false))
+                .build();
+        assert!(matches!(optimistic.commit_kind, CommitKind::TwoPc));
+        assert_eq!(optimistic.txn_size, 0);
+        assert_eq!(optimistic.lock_ttl, 0);
+        assert_eq!(optimistic.min_commit_ts, TimeStamp::zero());
+        assert!(!optimistic.need_old_value);
+        assert!(!optimistic.is_retry_request);
+        assert!(matches!(optimistic.assertion_level, AssertionLevel::Off));
+        assert_eq!(optimistic.txn_source, 0);
+        assert!(!optimistic.is_pessimistic());
+        assert_eq!(optimistic.for_update_ts(), TimeStamp::zero());
+
+        // The pessimistic/optimistic split only affects `kind`-derived
+        // properties, not the builder's independent defaults above.
+        let for_update_ts = 20.into();
+        let pessimistic = TransactionPropertiesBuilder::new(
+            start_ts,
+            b"k1",
+            TransactionKind::Pessimistic(for_update_ts),
+        )
+        .build();
+        assert_eq!(pessimistic.min_commit_ts, TimeStamp::zero());
+        assert!(pessimistic.is_pessimistic());
+        assert_eq!(pessimistic.for_update_ts(), for_update_ts);
+    }
+
     #[test]
     fn test_async_commit_prewrite_check_max_commit_ts() {
         let mut engine = crate::storage::TestEngineBuilder::new().build().unwrap();
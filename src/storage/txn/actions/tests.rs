@@ -20,7 +20,7 @@ use crate::storage::{
     mvcc::{Error, Key, Mutation, MvccTxn, SnapshotReader, TimeStamp, tests::write},
     txn,
     txn::{
-        commands::{Flush, WriteContext, WriteResult},
+        commands::{Flush, ResponsePolicy, WriteContext, WriteResult},
         txn_status_cache::TxnStatusCache,
     },
 };
@@ -253,6 +253,9 @@ pub fn flush_put_impl_with_assertion<E: Engine>(
         generation,
         3000,
         AssertionLevel::Strict,
+        vec![],
+        false,
+        ResponsePolicy::OnApplied,
         Context::new(),
     );
     let mut statistics = Statistics::default();
@@ -288,6 +291,106 @@ pub fn must_flush_put<E: Engine>(
     engine.write(&Context::new(), to_be_write).unwrap();
 }
 
+/// Runs a `Flush` command whose first generation is `mutations` and whose
+/// further generations are `extra_batches`, without applying the result to
+/// `engine`. Shared by [`must_flush_batch_put`] and tests of
+/// `collect_constraint_violations`.
+pub fn flush_batch_impl<E: Engine>(
+    engine: &mut E,
+    mutations: Vec<Mutation>,
+    generation: u64,
+    extra_batches: Vec<crate::storage::txn::commands::FlushBatch>,
+    pk: impl Into<Vec<u8>>,
+    start_ts: impl Into<TimeStamp>,
+    collect_constraint_violations: bool,
+) -> txn::Result<WriteResult> {
+    flush_batch_impl_with_response_policy(
+        engine,
+        mutations,
+        generation,
+        extra_batches,
+        pk,
+        start_ts,
+        collect_constraint_violations,
+        ResponsePolicy::OnApplied,
+    )
+}
+
+/// Like [`flush_batch_impl`], but also lets the caller pick the `Flush`
+/// command's `response_policy`, for tests that exercise early-reply
+/// behavior.
+pub fn flush_batch_impl_with_response_policy<E: Engine>(
+    engine: &mut E,
+    mutations: Vec<Mutation>,
+    generation: u64,
+    extra_batches: Vec<crate::storage::txn::commands::FlushBatch>,
+    pk: impl Into<Vec<u8>>,
+    start_ts: impl Into<TimeStamp>,
+    collect_constraint_violations: bool,
+    response_policy: ResponsePolicy,
+) -> txn::Result<WriteResult> {
+    let start_ts = start_ts.into();
+    let cmd = Flush::new(
+        start_ts,
+        pk.into(),
+        mutations,
+        generation,
+        3000,
+        AssertionLevel::Off,
+        extra_batches,
+        collect_constraint_violations,
+        response_policy,
+        Context::new(),
+    );
+    let mut statistics = Statistics::default();
+    let cm = ConcurrencyManager::new_for_test(start_ts);
+    let context = WriteContext {
+        lock_mgr: &MockLockManager::new(),
+        concurrency_manager: cm.clone(),
+        extra_op: ExtraOp::Noop,
+        statistics: &mut statistics,
+        async_apply_prewrite: false,
+        raw_ext: None,
+        txn_status_cache: Arc::new(TxnStatusCache::new_for_test()),
+    };
+    let snapshot = engine.snapshot(Default::default()).unwrap();
+    cmd.cmd.process_write(snapshot, context)
+}
+
+/// Flushes `(key, value, generation)` for the first mutation plus every
+/// entry in `extra` as `FlushBatch`es carried by the same `Flush` command,
+/// so the whole thing lands as a single raft proposal.
+pub fn must_flush_batch_put<E: Engine>(
+    engine: &mut E,
+    key: &[u8],
+    value: impl Into<Vec<u8>>,
+    generation: u64,
+    extra: Vec<(&[u8], Vec<u8>, u64)>,
+    pk: impl Into<Vec<u8>>,
+    start_ts: impl Into<TimeStamp>,
+) {
+    let extra_batches = extra
+        .into_iter()
+        .map(|(k, v, gen)| crate::storage::txn::commands::FlushBatch {
+            mutations: vec![Mutation::make_put(Key::from_raw(k), v)],
+            generation: gen,
+        })
+        .collect();
+    let res = flush_batch_impl(
+        engine,
+        vec![Mutation::make_put(Key::from_raw(key), value.into())],
+        generation,
+        extra_batches,
+        pk,
+        start_ts,
+        false,
+    )
+    .unwrap();
+    if !res.to_be_write.modifies.is_empty() {
+        engine.write(&Context::new(), res.to_be_write).unwrap();
+    }
+}
+
 pub fn must_prewrite_put_on_region<E: Engine>(
     engine: &mut E,
     region_id: u64,
@@ -6,13 +6,15 @@ use kvproto::kvrpcpb::{AssertionLevel, ExtraOp, PrewriteRequestPessimisticAction
 // #[PerformanceCriticalPath]
 use txn_types::{Mutation, OldValues, TimeStamp, TxnExtra, insert_old_value_if_resolved};
 
+use tikv_util::memory::HeapSize;
+
 use crate::storage::{
     Command, ProcessResult, Result as StorageResult, Snapshot, TypedCommand,
     kv::WriteData,
     lock_manager::LockManager,
     mvcc::{MvccTxn, SnapshotReader},
     txn::{
-        CommitKind, Error, ErrorInner, Result, TransactionKind, TransactionProperties,
+        Error, ErrorInner, Result, TransactionKind, TransactionPropertiesBuilder,
         actions::{common::check_committed_record_on_err, prewrite::prewrite_with_generation},
         commands::{
             CommandExt, ReaderWithStats, ReleasedLocks, ResponsePolicy, WriteCommand, WriteContext,
@@ -21,10 +23,26 @@ use crate::storage::{
     },
 };
 
+/// One additional generation of mutations flushed for the same transaction,
+/// carried alongside a `Flush` command's primary `mutations`/`generation` so
+/// several client `Flush` requests for the same transaction can be committed
+/// as a single raft proposal instead of one proposal per request.
+#[derive(Debug)]
+pub struct FlushBatch {
+    pub mutations: Vec<Mutation>,
+    pub generation: u64,
+}
+
+impl HeapSize for FlushBatch {
+    fn approximate_heap_size(&self) -> usize {
+        self.mutations.approximate_heap_size()
+    }
+}
+
 command! {
     Flush:
-        cmd_ty => Vec<StorageResult<()>>,
-        display => { "kv::command::flush keys({:?}) @ {} | gen={}, {:?}", (mutations, start_ts, generation, ctx), }
+        cmd_ty => Vec<(Vec<u8>, StorageResult<()>)>,
+        display => { "kv::command::flush keys({:?}) @ {} | gen={}, extra_batches={}, {:?}", (mutations, start_ts, generation, extra_batches.len(), ctx), }
         content => {
             start_ts: TimeStamp,
             primary: Vec<u8>,
@@ -32,10 +50,41 @@ command! {
             generation: u64,
             lock_ttl: u64,
             assertion_level: AssertionLevel,
+            // Further generations of the same transaction to apply, in order,
+            // in this same raft proposal. Empty for a plain, unbatched flush.
+            extra_batches: Vec<FlushBatch>,
+            // When set, a `CheckNotExists`/`Insert` mutation whose constraint is
+            // violated is reported as an `AlreadyExist` entry in this command's
+            // `MultiKeyedRes` results instead of aborting the whole command;
+            // other mutations in the same `Flush` (and `extra_batches`) still
+            // take effect. Off by default, so a single violation still fails
+            // the command atomically as before.
+            collect_constraint_violations: bool,
+            // When to reply to the client for this Flush. `OnApplied` (the
+            // default) is the safe, read-your-writes choice: the client won't
+            // see a response until the flushed lock is durable and visible to
+            // readers. `OnCommitted`/`OnProposed` reply earlier, before the
+            // proposal has necessarily applied; a pipelined DML buffer that
+            // doesn't need read-your-writes right after a flush can use one of
+            // these to cut client-observed latency. If the proposal ends up
+            // failing to apply after such an early reply, this command's own
+            // result is lost (the client already moved on), but the missing
+            // lock is discovered as a matter of course by whatever comes next
+            // for this transaction: a later `Flush` generation, `Prewrite`, or
+            // `Commit` on the same key won't find the lock it expects and
+            // fails there instead. See `can_be_pipelined` below for how
+            // `OnProposed` is wired into the scheduler's pipelining decision.
+            //
+            // Partial close of AKJUS/tikv#synth-586: this field and the pipelining
+            // wiring exist, but `From<FlushRequest>` always constructs `Flush` with
+            // `OnApplied` since `FlushRequest` has no field yet for a client to
+            // request an earlier reply; see the TODO there.
+            response_policy: ResponsePolicy,
         }
         in_heap => {
             mutations,
             primary,
+            extra_batches,
         }
 }
 
@@ -47,7 +96,11 @@ impl CommandExt for Flush {
 
     fn write_bytes(&self) -> usize {
         let mut bytes = 0;
-        for m in &self.mutations {
+        for m in self
+            .mutations
+            .iter()
+            .chain(self.extra_batches.iter().flat_map(|b| b.mutations.iter()))
+        {
             match *m {
                 Mutation::Put((ref key, ref value), _)
                 | Mutation::Insert((ref key, ref value), _) => {
@@ -65,27 +118,67 @@ impl CommandExt for Flush {
         bytes
     }
 
-    gen_lock!(mutations: multiple(|x| x.key()));
+    fn gen_lock(&self) -> crate::storage::txn::latch::Lock {
+        let keys = self
+            .mutations
+            .iter()
+            .chain(self.extra_batches.iter().flat_map(|b| b.mutations.iter()))
+            .map(|x| x.key());
+        crate::storage::txn::latch::Lock::new(keys)
+    }
+
+    fn can_be_pipelined(&self) -> bool {
+        self.response_policy == ResponsePolicy::OnProposed
+    }
 }
 
 impl<S: Snapshot, L: LockManager> WriteCommand<S, L> for Flush {
     fn process_write(mut self, snapshot: S, context: WriteContext<'_, L>) -> Result<WriteResult> {
-        if self.generation == 0 {
+        if self.generation == 0 || self.extra_batches.iter().any(|b| b.generation == 0) {
             return Err(ErrorInner::Other(box_err!(
                 "generation should be greater than 0 for Flush requests"
             ))
             .into());
         }
-        let rows = self.mutations.len();
+        // `CheckNotExists` mutations are pure constraint checks: they never
+        // produce a `Modify`, so counting them here would overstate the
+        // scheduler's keywrite throughput metric and the txn write-size
+        // limiter's view of this command.
+        let rows = self
+            .mutations
+            .iter()
+            .chain(self.extra_batches.iter().flat_map(|b| b.mutations.iter()))
+            .filter(|m| !matches!(m, Mutation::CheckNotExists(..)))
+            .count();
         let mut txn = MvccTxn::new(self.start_ts, context.concurrency_manager);
+        txn.reserve(rows);
         let mut reader = ReaderWithStats::new(
             SnapshotReader::new_with_ctx(self.start_ts, snapshot, &self.ctx),
             context.statistics,
         );
         let mut old_values = Default::default();
 
-        let res = self.flush(&mut txn, &mut reader, &mut old_values, context.extra_op);
-        let locks = res?;
+        let mutations = mem::take(&mut self.mutations);
+        let generation = self.generation;
+        let mut locks = self.flush(
+            &mut txn,
+            &mut reader,
+            &mut old_values,
+            context.extra_op,
+            mutations,
+            generation,
+        )?;
+        for batch in mem::take(&mut self.extra_batches) {
+            let mut batch_locks = self.flush(
+                &mut txn,
+                &mut reader,
+                &mut old_values,
+                context.extra_op,
+                batch.mutations,
+                batch.generation,
+            )?;
+            locks.append(&mut batch_locks);
+        }
         let extra = TxnExtra {
             old_values,
             one_pc: false,
@@ -98,12 +191,12 @@ impl<S: Snapshot, L: LockManager> WriteCommand<S, L> for Flush {
             ctx: self.ctx,
             to_be_write: WriteData::new(txn.into_modifies(), extra),
             rows,
-            pr: ProcessResult::MultiRes { results: locks },
+            pr: ProcessResult::MultiKeyedRes { results: locks },
             lock_info: vec![],
             released_locks: ReleasedLocks::new(),
             new_acquired_locks: new_locks,
             lock_guards: guards,
-            response_policy: ResponsePolicy::OnApplied,
+            response_policy: self.response_policy,
             known_txn_status: vec![],
         })
     }
@@ -111,32 +204,34 @@ impl<S: Snapshot, L: LockManager> WriteCommand<S, L> for Flush {
 
 impl Flush {
     fn flush(
-        &mut self,
+        &self,
         txn: &mut MvccTxn,
         reader: &mut SnapshotReader<impl Snapshot>,
         old_values: &mut OldValues,
         extra_op: ExtraOp,
-    ) -> Result<Vec<std::result::Result<(), crate::storage::errors::Error>>> {
-        let props = TransactionProperties {
-            start_ts: self.start_ts,
-            kind: TransactionKind::Optimistic(false),
-            commit_kind: CommitKind::TwoPc,
-            primary: &self.primary,
-            // txn_size is unknown, set it to max to avoid unexpected resolve_lock_lite
-            txn_size: u64::MAX,
-            lock_ttl: self.lock_ttl,
-            // min_commit_ts == 0 will disallow readers pushing it
-            min_commit_ts: self.start_ts.next(),
-            need_old_value: extra_op == ExtraOp::ReadOldValue, // FIXME?
-            is_retry_request: self.ctx.is_retry_request,
-            assertion_level: self.assertion_level,
-            txn_source: self.ctx.get_txn_source(),
-        };
+        mutations: Vec<Mutation>,
+        generation: u64,
+    ) -> Result<Vec<(Vec<u8>, std::result::Result<(), crate::storage::errors::Error>)>> {
+        let props = TransactionPropertiesBuilder::new(
+            self.start_ts,
+            &self.primary,
+            TransactionKind::Optimistic(false),
+        )
+        // txn_size is unknown, set it to max to avoid unexpected resolve_lock_lite
+        .txn_size(u64::MAX)
+        .lock_ttl(self.lock_ttl)
+        // min_commit_ts == 0 will disallow readers pushing it
+        .min_commit_ts(self.start_ts.next())
+        .need_old_value(extra_op == ExtraOp::ReadOldValue) // FIXME?
+        .is_retry_request(self.ctx.is_retry_request)
+        .assertion_level(self.assertion_level)
+        .txn_source(self.ctx.get_txn_source())
+        .build();
         let mut locks = Vec::new();
         // If there are other errors, return other error prior to `AssertionFailed`.
         let mut assertion_failure = None;
 
-        for m in mem::take(&mut self.mutations) {
+        for m in mutations {
             let key = m.key().clone();
             let mutation_type = m.mutation_type();
             let prewrite_result = prewrite_with_generation(
@@ -147,10 +242,11 @@ impl Flush {
                 &None,
                 PrewriteRequestPessimisticAction::SkipPessimisticCheck,
                 None,
-                self.generation,
+                generation,
             );
             match prewrite_result {
                 Ok((_ts, old_value)) => {
+                    let raw_key = key.clone().into_raw().unwrap();
                     insert_old_value_if_resolved(
                         old_values,
                         key,
@@ -158,6 +254,7 @@ impl Flush {
                         old_value,
                         Some(mutation_type),
                     );
+                    locks.push((raw_key, Ok(())));
                 }
                 Err(crate::storage::mvcc::Error(
                     box crate::storage::mvcc::ErrorInner::WriteConflict {
@@ -166,8 +263,12 @@ impl Flush {
                         ..
                     },
                 )) if conflict_commit_ts > start_ts => {
-                    return check_committed_record_on_err(prewrite_result, txn, reader, &key)
-                        .map(|(locks, _)| locks);
+                    // The whole transaction was already committed (this is a
+                    // retry); keep whatever results were already collected
+                    // for earlier keys in this batch instead of discarding
+                    // them.
+                    check_committed_record_on_err(prewrite_result, txn, reader, &key)?;
+                    return Ok(locks);
                 }
                 Err(crate::storage::mvcc::Error(
                     box crate::storage::mvcc::ErrorInner::PessimisticLockNotFound { .. },
@@ -180,8 +281,10 @@ impl Flush {
                 Err(crate::storage::mvcc::Error(
                     box crate::storage::mvcc::ErrorInner::KeyIsLocked { .. },
                 )) => match check_committed_record_on_err(prewrite_result, txn, reader, &key) {
-                    Ok(res) => return Ok(res.0),
-                    Err(e) => locks.push(Err(e.into())),
+                    // Same as above: don't drop results already collected
+                    // for earlier keys.
+                    Ok(_) => return Ok(locks),
+                    Err(e) => locks.push((key.into_raw().unwrap(), Err(e.into()))),
                 },
                 Err(
                     e @ crate::storage::mvcc::Error(
@@ -207,6 +310,13 @@ impl Flush {
                         "lock" => ?lock,
                     );
                 }
+                Err(
+                    e @ crate::storage::mvcc::Error(
+                        box crate::storage::mvcc::ErrorInner::AlreadyExist { .. },
+                    ),
+                ) if self.collect_constraint_violations => {
+                    locks.push((key.into_raw().unwrap(), Err(Error::from(e).into())));
+                }
                 Err(e) => return Err(Error::from(e)),
             }
         }
@@ -222,21 +332,23 @@ mod tests {
 
     use kvproto::kvrpcpb::{Assertion, Context};
     use tikv_kv::Engine;
-    use txn_types::TimeStamp;
+    use txn_types::{Key, LockType, Mutation, TimeStamp, WriteType};
 
     use crate::storage::{
-        ProcessResult, TestEngineBuilder,
+        Error as StorageError, ErrorInner as StorageErrorInner, ProcessResult, TestEngineBuilder,
         mvcc::{
             Error as MvccError, ErrorInner as MvccErrorInner,
-            tests::{must_get, must_locked},
+            tests::{must_get, must_locked, must_unlocked, must_written},
         },
         txn,
         txn::{
             Error, ErrorInner,
+            commands::{CommandExt, ResponsePolicy},
             tests::{
-                flush_put_impl, flush_put_impl_with_assertion, must_acquire_pessimistic_lock,
-                must_acquire_pessimistic_lock_err, must_commit, must_flush_put,
-                must_pessimistic_locked, must_prewrite_put, must_prewrite_put_err,
+                flush_batch_impl, flush_batch_impl_with_response_policy, flush_put_impl,
+                flush_put_impl_with_assertion, must_acquire_pessimistic_lock,
+                must_acquire_pessimistic_lock_err, must_commit, must_flush_batch_put,
+                must_flush_put, must_pessimistic_locked, must_prewrite_put, must_prewrite_put_err,
             },
         },
     };
@@ -271,8 +383,9 @@ mod tests {
         generation: u64,
     ) {
         let res = flush_put_impl(engine, key, value, pk, start_ts, generation, false).unwrap();
-        if let ProcessResult::MultiRes { results } = res.pr {
+        if let ProcessResult::MultiKeyedRes { results } = res.pr {
             assert!(!results.is_empty());
+            assert_eq!(results[0].0, key);
         } else {
             panic!("flush return type error");
         }
@@ -292,6 +405,30 @@ mod tests {
         res.err().unwrap()
     }
 
+    fn must_flush_lock<E: Engine>(
+        engine: &mut E,
+        key: &[u8],
+        pk: impl Into<Vec<u8>>,
+        start_ts: impl Into<TimeStamp>,
+        generation: u64,
+    ) {
+        let res = flush_batch_impl(
+            engine,
+            vec![Mutation::make_lock(Key::from_raw(key))],
+            generation,
+            vec![],
+            pk,
+            start_ts,
+            false,
+        )
+        .unwrap();
+        let to_be_write = res.to_be_write;
+        if to_be_write.modifies.is_empty() {
+            return;
+        }
+        engine.write(&Context::new(), to_be_write).unwrap();
+    }
+
     pub fn must_flush_insert_err<E: Engine>(
         engine: &mut E,
         key: &[u8],
@@ -386,14 +523,18 @@ mod tests {
         must_locked(&mut engine, k, 1);
         assert!(matches!(
             must_flush_insert_err(&mut engine, k, *v, k, 1, 2),
-            Error(box ErrorInner::Mvcc(MvccError(box MvccErrorInner::AlreadyExist { key, existing_start_ts})))
-            if key == k  && existing_start_ts == 1.into()
+            Error(box ErrorInner::Mvcc(MvccError(box MvccErrorInner::AlreadyExist {
+                key, existing_start_ts, existing_commit_ts,
+            })))
+            if key == k && existing_start_ts == 1.into() && existing_commit_ts.is_zero()
         ));
         must_commit(&mut engine, k, 1, 2);
         assert!(matches!(
             must_flush_insert_err(&mut engine, k, *v, k, 3, 1),
-            Error(box ErrorInner::Mvcc(MvccError(box MvccErrorInner::AlreadyExist { key, existing_start_ts})))
-            if key == k  && existing_start_ts == 1.into()
+            Error(box ErrorInner::Mvcc(MvccError(box MvccErrorInner::AlreadyExist {
+                key, existing_start_ts, existing_commit_ts,
+            })))
+            if key == k && existing_start_ts == 1.into() && existing_commit_ts == 2.into()
         ));
     }
 
@@ -409,4 +550,434 @@ mod tests {
         must_commit(&mut engine, k, 1, 2);
         must_get(&mut engine, k, 3, v2);
     }
+
+    #[test]
+    fn test_flush_extra_batches() {
+        // Several generations of the same transaction, batched into a single
+        // `Flush` command, should lock all of their keys in one proposal.
+        let mut engine = TestEngineBuilder::new().build().unwrap();
+        let k1 = b"key1";
+        let v1 = b"value1";
+        let k2 = b"key2";
+        let v2 = b"value2";
+        let k3 = b"key3";
+        let v3 = b"value3";
+        must_flush_batch_put(
+            &mut engine,
+            k1,
+            v1.to_vec(),
+            1,
+            vec![
+                (&k2[..], v2.to_vec(), 1),
+                (&k3[..], v3.to_vec(), 1),
+            ],
+            k1,
+            1,
+        );
+        must_locked(&mut engine, k1, 1);
+        must_locked(&mut engine, k2, 1);
+        must_locked(&mut engine, k3, 1);
+        must_commit(&mut engine, k1, 1, 2);
+        must_commit(&mut engine, k2, 1, 2);
+        must_commit(&mut engine, k3, 1, 2);
+        must_get(&mut engine, k1, 3, v1);
+        must_get(&mut engine, k2, 3, v2);
+        must_get(&mut engine, k3, 3, v3);
+    }
+
+    /// Five `CheckNotExists` mutations, two of which already have a
+    /// committed value. Returns `(engine, mutations)` with `k4`/`k5`
+    /// pre-committed at `start_ts=1, commit_ts=2`.
+    fn setup_flush_with_two_existing_keys()
+    -> (crate::storage::kv::RocksEngine, Vec<Mutation>) {
+        let mut engine = TestEngineBuilder::new().build().unwrap();
+        must_flush_put(&mut engine, b"k4", b"old4".to_vec(), b"k4", 1, 1);
+        must_commit(&mut engine, b"k4", 1, 2);
+        must_flush_put(&mut engine, b"k5", b"old5".to_vec(), b"k5", 1, 1);
+        must_commit(&mut engine, b"k5", 1, 2);
+
+        let mutations = [b"k1".as_ref(), b"k2", b"k3", b"k4", b"k5"]
+            .into_iter()
+            .map(|k| Mutation::make_insert(Key::from_raw(k), b"new".to_vec()))
+            .collect();
+        (engine, mutations)
+    }
+
+    #[test]
+    fn test_flush_collect_constraint_violations() {
+        let (mut engine, mutations) = setup_flush_with_two_existing_keys();
+
+        let mut res = flush_batch_impl(&mut engine, mutations, 1, vec![], b"k1".to_vec(), 3, true)
+            .unwrap();
+        let results = match std::mem::replace(&mut res.pr, ProcessResult::Res) {
+            ProcessResult::MultiKeyedRes { results } => results,
+            _ => panic!("flush return type error"),
+        };
+        assert_eq!(results.len(), 5);
+        let mut results = results.into_iter();
+        for key in [b"k1".as_ref(), b"k2".as_ref(), b"k3".as_ref()] {
+            let (result_key, result) = results.next().unwrap();
+            assert_eq!(result_key, key);
+            assert!(result.is_ok());
+        }
+        for key in [b"k4".as_ref(), b"k5".as_ref()] {
+            let (result_key, result) = results.next().unwrap();
+            assert_eq!(result_key, key);
+            match result {
+                Err(StorageError(box StorageErrorInner::Txn(Error(box ErrorInner::Mvcc(
+                    MvccError(box MvccErrorInner::AlreadyExist {
+                        key: err_key,
+                        existing_start_ts,
+                        existing_commit_ts,
+                    }),
+                ))))) => {
+                    assert_eq!(err_key, key);
+                    assert_eq!(existing_start_ts, 1.into());
+                    assert_eq!(existing_commit_ts, 2.into());
+                }
+                other => panic!("expected AlreadyExist, got {:?}", other),
+            }
+        }
+
+        engine.write(&Context::new(), res.to_be_write).unwrap();
+        must_locked(&mut engine, b"k1", 3);
+        must_locked(&mut engine, b"k2", 3);
+        must_locked(&mut engine, b"k3", 3);
+        must_unlocked(&mut engine, b"k4");
+        must_unlocked(&mut engine, b"k5");
+        must_get(&mut engine, b"k4", 3, b"old4");
+        must_get(&mut engine, b"k5", 3, b"old5");
+    }
+
+    #[test]
+    fn test_flush_check_not_exists_only_generates_no_modify() {
+        // A `Flush` consisting solely of `CheckNotExists` mutations is a pure
+        // constraint check: it must not produce a `Modify` (so the scheduler
+        // skips the raft proposal), and `rows` must not count the checks as
+        // writes.
+        let mut engine = TestEngineBuilder::new().build().unwrap();
+        let mutations = vec![
+            Mutation::CheckNotExists(Key::from_raw(b"k1"), Assertion::None),
+            Mutation::CheckNotExists(Key::from_raw(b"k2"), Assertion::None),
+        ];
+        let res = flush_batch_impl(&mut engine, mutations, 1, vec![], b"k1".to_vec(), 1, false)
+            .unwrap();
+        assert!(res.to_be_write.modifies.is_empty());
+        assert_eq!(res.rows, 0);
+        let results = match res.pr {
+            ProcessResult::MultiKeyedRes { results } => results,
+            _ => panic!("flush return type error"),
+        };
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|(_, r)| r.is_ok()));
+    }
+
+    #[test]
+    fn test_flush_check_not_exists_rows_excludes_checks() {
+        // A `CheckNotExists` mixed in with a real write should not inflate
+        // `rows` beyond the one key that's actually written.
+        let mut engine = TestEngineBuilder::new().build().unwrap();
+        let mutations = vec![
+            Mutation::make_put(Key::from_raw(b"k1"), b"v1".to_vec()),
+            Mutation::CheckNotExists(Key::from_raw(b"k2"), Assertion::None),
+        ];
+        let res = flush_batch_impl(&mut engine, mutations, 1, vec![], b"k1".to_vec(), 1, false)
+            .unwrap();
+        assert!(!res.to_be_write.modifies.is_empty());
+        assert_eq!(res.rows, 1);
+    }
+
+    #[test]
+    fn test_flush_check_not_exists_reports_already_exist() {
+        // `CheckNotExists` against a key that already has a committed value
+        // still reports `AlreadyExist`, even though it produces no `Modify`.
+        let (mut engine, _) = setup_flush_with_two_existing_keys();
+        let mutations = vec![Mutation::CheckNotExists(Key::from_raw(b"k4"), Assertion::None)];
+        let err = flush_batch_impl(&mut engine, mutations, 1, vec![], b"k4".to_vec(), 3, false)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            Error(box ErrorInner::Mvcc(MvccError(box MvccErrorInner::AlreadyExist { .. })))
+        ));
+    }
+
+    #[test]
+    fn test_flush_keyed_results_associate_key_with_lock_error() {
+        // k2 is already locked by a different, unrelated transaction; k1 and
+        // k3 are free. `MultiKeyedRes` should let the caller tell exactly
+        // which mutation failed without relying on its position among the
+        // successes.
+        let mut engine = TestEngineBuilder::new().build().unwrap();
+        must_prewrite_put(&mut engine, b"k2", b"other", b"k2", 5);
+        must_locked(&mut engine, b"k2", 5);
+
+        let mutations = vec![
+            Mutation::make_put(Key::from_raw(b"k1"), b"v1".to_vec()),
+            Mutation::make_put(Key::from_raw(b"k2"), b"v2".to_vec()),
+            Mutation::make_put(Key::from_raw(b"k3"), b"v3".to_vec()),
+        ];
+        let mut res = flush_batch_impl(&mut engine, mutations, 1, vec![], b"k1".to_vec(), 1, false)
+            .unwrap();
+        let results = match std::mem::replace(&mut res.pr, ProcessResult::Res) {
+            ProcessResult::MultiKeyedRes { results } => results,
+            _ => panic!("flush return type error"),
+        };
+        assert_eq!(results.len(), 3);
+
+        let (key, result) = &results[0];
+        assert_eq!(key.as_slice(), b"k1");
+        assert!(result.is_ok());
+
+        let (key, result) = &results[1];
+        assert_eq!(key.as_slice(), b"k2");
+        assert!(matches!(
+            result,
+            Err(StorageError(box StorageErrorInner::Txn(Error(box ErrorInner::Mvcc(
+                MvccError(box MvccErrorInner::KeyIsLocked(_)),
+            )))))
+        ));
+
+        let (key, result) = &results[2];
+        assert_eq!(key.as_slice(), b"k3");
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_flush_check_not_exists_and_put_same_key_in_one_batch() {
+        // Before a `Put` could carry `should_not_exist` semantics itself
+        // (i.e. `Mutation::make_insert`), clients combined a `CheckNotExists`
+        // and a `Put` for the same key in one `Flush` batch to get the same
+        // effect. That combination must keep working: the check produces no
+        // `Modify` and the `Put` right after it still locks and writes the
+        // value.
+        let mut engine = TestEngineBuilder::new().build().unwrap();
+        let mutations = vec![
+            Mutation::CheckNotExists(Key::from_raw(b"k1"), Assertion::None),
+            Mutation::make_put(Key::from_raw(b"k1"), b"v1".to_vec()),
+        ];
+        let res =
+            flush_batch_impl(&mut engine, mutations, 1, vec![], b"k1".to_vec(), 1, false).unwrap();
+        let results = match res.pr {
+            ProcessResult::MultiKeyedRes { results } => results,
+            _ => panic!("flush return type error"),
+        };
+        assert_eq!(results.len(), 2);
+        for (key, r) in &results {
+            assert_eq!(key.as_slice(), b"k1");
+            assert!(r.is_ok());
+        }
+
+        engine.write(&Context::new(), res.to_be_write).unwrap();
+        must_locked(&mut engine, b"k1", 1);
+        must_get(&mut engine, b"k1", 2, b"v1");
+    }
+
+    #[test]
+    fn test_flush_insert_conflicts_with_existing_committed_put() {
+        // An `Insert` mutation — a `Put` with `should_not_exist` semantics —
+        // against a key that another transaction already committed must
+        // report `AlreadyExist` for that key while a sibling key with no
+        // conflict still proceeds.
+        let (mut engine, _) = setup_flush_with_two_existing_keys();
+        let mutations = vec![
+            Mutation::make_insert(Key::from_raw(b"k1"), b"new".to_vec()),
+            Mutation::make_insert(Key::from_raw(b"k4"), b"new".to_vec()),
+        ];
+        let mut res = flush_batch_impl(&mut engine, mutations, 1, vec![], b"k1".to_vec(), 3, true)
+            .unwrap();
+        let results = match std::mem::replace(&mut res.pr, ProcessResult::Res) {
+            ProcessResult::MultiKeyedRes { results } => results,
+            _ => panic!("flush return type error"),
+        };
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].0.as_slice(), b"k1");
+        assert!(results[0].1.is_ok());
+        assert_eq!(results[1].0.as_slice(), b"k4");
+        assert!(matches!(
+            &results[1].1,
+            Err(StorageError(box StorageErrorInner::Txn(Error(box ErrorInner::Mvcc(
+                MvccError(box MvccErrorInner::AlreadyExist { existing_commit_ts, .. })
+            ))))) if *existing_commit_ts == 2.into()
+        ));
+
+        engine.write(&Context::new(), res.to_be_write).unwrap();
+        must_locked(&mut engine, b"k1", 3);
+        must_unlocked(&mut engine, b"k4");
+    }
+
+    #[test]
+    fn test_flush_insert_conflicts_with_lock_from_prior_flush_in_same_txn() {
+        // The transaction's first `Flush` writes a plain `Put` for `k1`; a
+        // later `Flush` of the *same* transaction that tries to insert a
+        // different value under `should_not_exist` semantics must see the
+        // conflict even though nothing has been committed yet, because the
+        // generation-overwrite path re-runs the check against the lock left
+        // by the earlier generation.
+        let mut engine = TestEngineBuilder::new().build().unwrap();
+        must_flush_put(&mut engine, b"k1", b"v1".to_vec(), b"k1", 1, 1);
+        must_locked(&mut engine, b"k1", 1);
+
+        let mutations = vec![Mutation::make_insert(Key::from_raw(b"k1"), b"v2".to_vec())];
+        let err = flush_batch_impl(&mut engine, mutations, 2, vec![], b"k1".to_vec(), 1, false)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            Error(box ErrorInner::Mvcc(MvccError(box MvccErrorInner::AlreadyExist {
+                existing_start_ts,
+                existing_commit_ts,
+                ..
+            })))
+            if existing_start_ts == 1.into() && existing_commit_ts.is_zero()
+        ));
+        // The lock from the first flush is untouched.
+        must_locked(&mut engine, b"k1", 1);
+    }
+
+    #[test]
+    fn test_flush_default_mode_aborts_atomically_on_constraint_violation() {
+        let (mut engine, mutations) = setup_flush_with_two_existing_keys();
+
+        let err = flush_batch_impl(&mut engine, mutations, 1, vec![], b"k1".to_vec(), 3, false)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            Error(box ErrorInner::Mvcc(MvccError(box MvccErrorInner::AlreadyExist { .. })))
+        ));
+        must_unlocked(&mut engine, b"k1");
+        must_unlocked(&mut engine, b"k2");
+        must_unlocked(&mut engine, b"k3");
+    }
+
+    #[test]
+    fn test_flush_lock_then_put_across_generations() {
+        // A Lock-type flush followed, in a later generation, by a Put for the
+        // same key should end up locked as a Put carrying the new value, not
+        // stuck with the Lock generation's (absent) short_value.
+        let mut engine = TestEngineBuilder::new().build().unwrap();
+        let k = b"key";
+        let v = b"value";
+
+        must_flush_lock(&mut engine, k, k, 1, 1);
+        let lock = must_locked(&mut engine, k, 1);
+        assert_eq!(lock.lock_type, LockType::Lock);
+        assert!(lock.short_value.is_none());
+
+        must_flush_put(&mut engine, k, *v, k, 1, 2);
+        let lock = must_locked(&mut engine, k, 1);
+        assert_eq!(lock.lock_type, LockType::Put);
+        assert_eq!(lock.short_value.as_deref(), Some(&v[..]));
+
+        must_commit(&mut engine, k, 1, 2);
+        must_written(&mut engine, k, 1, 2, WriteType::Put);
+        must_get(&mut engine, k, 3, v);
+    }
+
+    #[test]
+    fn test_flush_put_then_lock_across_generations() {
+        // The reverse sequence: a Put followed by a Lock-type flush for the
+        // same key in a later generation should end up locked as a plain
+        // Lock, dropping the earlier generation's value.
+        let mut engine = TestEngineBuilder::new().build().unwrap();
+        let k = b"key";
+        let v = b"value";
+
+        must_flush_put(&mut engine, k, *v, k, 1, 1);
+        let lock = must_locked(&mut engine, k, 1);
+        assert_eq!(lock.lock_type, LockType::Put);
+        assert_eq!(lock.short_value.as_deref(), Some(&v[..]));
+
+        must_flush_lock(&mut engine, k, k, 1, 2);
+        let lock = must_locked(&mut engine, k, 1);
+        assert_eq!(lock.lock_type, LockType::Lock);
+        assert!(lock.short_value.is_none());
+
+        must_commit(&mut engine, k, 1, 2);
+        must_written(&mut engine, k, 1, 2, WriteType::Lock);
+    }
+
+    #[test]
+    fn test_flush_lock_then_put_same_proposal() {
+        // Same sequence as `test_flush_lock_then_put_across_generations`, but
+        // with both generations batched into a single `Flush` command (as
+        // `extra_batches`), so the second generation's lock check must see
+        // the first generation's lock through `MvccTxn`'s pending modifies
+        // rather than the (stale) snapshot.
+        let mut engine = TestEngineBuilder::new().build().unwrap();
+        let k = b"key";
+        let v = b"value";
+
+        let res = flush_batch_impl(
+            &mut engine,
+            vec![Mutation::make_lock(Key::from_raw(k))],
+            1,
+            vec![crate::storage::txn::commands::FlushBatch {
+                mutations: vec![Mutation::make_put(Key::from_raw(k), v.to_vec())],
+                generation: 2,
+            }],
+            k,
+            1,
+            false,
+        )
+        .unwrap();
+        engine.write(&Context::new(), res.to_be_write).unwrap();
+
+        let lock = must_locked(&mut engine, k, 1);
+        assert_eq!(lock.lock_type, LockType::Put);
+        assert_eq!(lock.short_value.as_deref(), Some(&v[..]));
+
+        must_commit(&mut engine, k, 1, 2);
+        must_written(&mut engine, k, 1, 2, WriteType::Put);
+        must_get(&mut engine, k, 3, v);
+    }
+
+    #[test]
+    fn test_flush_response_policy_is_carried_into_write_result() {
+        let mut engine = TestEngineBuilder::new().build().unwrap();
+        for response_policy in [
+            ResponsePolicy::OnApplied,
+            ResponsePolicy::OnCommitted,
+            ResponsePolicy::OnProposed,
+        ] {
+            let mutations = vec![Mutation::make_put(Key::from_raw(b"k1"), b"v1".to_vec())];
+            let res = flush_batch_impl_with_response_policy(
+                &mut engine,
+                mutations,
+                1,
+                vec![],
+                b"k1".to_vec(),
+                1,
+                false,
+                response_policy,
+            )
+            .unwrap();
+            assert_eq!(res.response_policy, response_policy);
+        }
+    }
+
+    #[test]
+    fn test_flush_can_be_pipelined_only_for_on_proposed() {
+        // Only `OnProposed` opts a `Flush` into the scheduler's pipelining
+        // path; the other two policies still wait for their proposal to at
+        // least commit (`OnCommitted`) or fully apply (`OnApplied`) before
+        // the scheduler will treat them as eligible for an early reply.
+        for (response_policy, can_be_pipelined) in [
+            (ResponsePolicy::OnApplied, false),
+            (ResponsePolicy::OnCommitted, false),
+            (ResponsePolicy::OnProposed, true),
+        ] {
+            let cmd = Flush::new(
+                1.into(),
+                b"k1".to_vec(),
+                vec![Mutation::make_put(Key::from_raw(b"k1"), b"v1".to_vec())],
+                1,
+                3000,
+                AssertionLevel::Off,
+                vec![],
+                false,
+                response_policy,
+                Context::new(),
+            );
+            assert_eq!(cmd.cmd.can_be_pipelined(), can_be_pipelined);
+        }
+    }
 }
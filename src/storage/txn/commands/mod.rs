@@ -428,6 +428,17 @@ impl From<FlushRequest> for TypedCommand<Vec<StorageResult<()>>> {
             req.get_generation(),
             req.get_lock_ttl(),
             req.get_assertion_level(),
+            vec![],
+            // TODO: derive this from a request flag once one is added to `FlushRequest`
+            // upstream; for now the atomic, all-or-nothing behavior is preserved.
+            false,
+            // Partial close of AKJUS/tikv#synth-586: `Flush`/`can_be_pipelined` support
+            // `ResponsePolicy::OnProposed` end to end, but `FlushRequest` has no field
+            // for a client to actually ask for it, and adding one requires a kvproto
+            // change this environment has no network access to make. Every Flush still
+            // replies only after applying, same as before `response_policy` existed;
+            // wire this to a request flag once `FlushRequest` upstream has one.
+            ResponsePolicy::OnApplied,
             req.take_context(),
         )
     }
@@ -31,7 +31,7 @@ use crate::storage::{
         Error, ErrorInner, Result,
         actions::{
             common::check_committed_record_on_err,
-            prewrite::{CommitKind, TransactionKind, TransactionProperties, prewrite},
+            prewrite::{CommitKind, TransactionKind, TransactionPropertiesBuilder, prewrite},
         },
         commands::{
             Command, CommandExt, ReleasedLocks, ResponsePolicy, TypedCommand, WriteCommand,
@@ -541,14 +541,14 @@ impl<K: PrewriteKind> Prewriter<K> {
         self.check_max_ts_synced(&snapshot)?;
 
         let mut txn = MvccTxn::new(self.start_ts, context.concurrency_manager);
+        let rows = self.mutations.len();
+        txn.reserve(rows);
         let mut reader = ReaderWithStats::new(
             SnapshotReader::new_with_ctx(self.start_ts, snapshot, &self.ctx),
             context.statistics,
         );
         // Set extra op here for getting the write record when check write conflict in
         // prewrite.
-
-        let rows = self.mutations.len();
         let res = self.prewrite(&mut txn, &mut reader, context.extra_op);
         let (locks, final_min_commit_ts) = res?;
 
@@ -594,19 +594,17 @@ impl<K: PrewriteKind> Prewriter<K> {
             (&None, false) => CommitKind::TwoPc,
         };
 
-        let mut props = TransactionProperties {
-            start_ts: self.start_ts,
-            kind: self.kind.txn_kind(),
-            commit_kind,
-            primary: &self.primary,
-            txn_size: self.txn_size,
-            lock_ttl: self.lock_ttl,
-            min_commit_ts: self.min_commit_ts,
-            need_old_value: extra_op == ExtraOp::ReadOldValue,
-            is_retry_request: self.ctx.is_retry_request,
-            assertion_level: self.assertion_level,
-            txn_source: self.ctx.get_txn_source(),
-        };
+        let mut props =
+            TransactionPropertiesBuilder::new(self.start_ts, &self.primary, self.kind.txn_kind())
+                .commit_kind(commit_kind)
+                .txn_size(self.txn_size)
+                .lock_ttl(self.lock_ttl)
+                .min_commit_ts(self.min_commit_ts)
+                .need_old_value(extra_op == ExtraOp::ReadOldValue)
+                .is_retry_request(self.ctx.is_retry_request)
+                .assertion_level(self.assertion_level)
+                .txn_source(self.ctx.get_txn_source())
+                .build();
 
         let async_commit_pk = self
             .secondary_keys
@@ -31,7 +31,10 @@ pub use self::{
             flashback_to_version_write, rollback_locks,
         },
         gc::gc,
-        prewrite::{CommitKind, TransactionKind, TransactionProperties, prewrite},
+        prewrite::{
+            CommitKind, TransactionKind, TransactionProperties, TransactionPropertiesBuilder,
+            prewrite,
+        },
     },
     commands::{Command, RESOLVE_LOCK_BATCH_SIZE},
     latch::{Latches, Lock},
@@ -55,6 +58,13 @@ pub enum ProcessResult {
     MultiRes {
         results: Vec<StorageResult<()>>,
     },
+    /// Like `MultiRes`, but each entry carries the raw key it corresponds
+    /// to, so callers can associate a failure with its mutation even after
+    /// results have been filtered or a command has stopped short of
+    /// producing one entry per input key.
+    MultiKeyedRes {
+        results: Vec<(Vec<u8>, StorageResult<()>)>,
+    },
     PrewriteResult {
         result: PrewriteResult,
     },
@@ -52,7 +52,7 @@ use resource_metering::{
     record_network_in_bytes,
 };
 use smallvec::{SmallVec, smallvec};
-use tikv_kv::{Modify, Snapshot, SnapshotExt, WriteData, WriteEvent};
+use tikv_kv::{Modify, OnAppliedCb, Snapshot, SnapshotExt, WriteData, WriteEvent};
 use tikv_util::{
     memory::MemoryQuota, quota_limiter::QuotaLimiter, time::Instant, timer::GLOBAL_TIMER_HANDLE,
 };
@@ -248,6 +248,11 @@ struct TxnSchedulerInner<L: LockManager> {
 
     sched_pending_write_threshold: usize,
 
+    // Ceiling on a single raft proposal's serialized size; a write command's
+    // `to_be_write` larger than this is split into several consecutive
+    // proposals by `WriteData::split_by_size`. `0` disables splitting.
+    raft_write_max_size: usize,
+
     // all tasks are executed in this pool
     sched_worker_pool: SchedPool,
 
@@ -257,6 +262,11 @@ struct TxnSchedulerInner<L: LockManager> {
     // used to control write flow
     running_write_bytes: CachePadded<AtomicUsize>,
 
+    // ceiling on the total size of old values captured by commands that have been
+    // proposed but not yet finished; `0` disables the ceiling.
+    sched_old_values_memory_quota: usize,
+    old_values_inflight_bytes: CachePadded<AtomicUsize>,
+
     flow_controller: Arc<FlowController>,
 
     // used for apiv2
@@ -373,6 +383,41 @@ impl<L: LockManager> TxnSchedulerInner<L> {
             || self.flow_controller.should_drop(region_id)
     }
 
+    /// Reserves `size` bytes of the in-flight old-value budget for a command about to
+    /// be proposed. Returns `false`, without reserving anything, if doing so would
+    /// exceed `sched_old_values_memory_quota` (when the quota is enabled).
+    fn try_reserve_old_values(&self, size: usize) -> bool {
+        if self.sched_old_values_memory_quota == 0 {
+            return true;
+        }
+        let reserved = self
+            .old_values_inflight_bytes
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |cur| {
+                if cur + size > self.sched_old_values_memory_quota {
+                    None
+                } else {
+                    Some(cur + size)
+                }
+            })
+            .is_ok();
+        if reserved {
+            SCHED_OLD_VALUES_INFLIGHT_BYTES_GAUGE
+                .set(self.old_values_inflight_bytes.load(Ordering::Acquire) as i64);
+        }
+        reserved
+    }
+
+    /// Releases `size` bytes previously reserved by `try_reserve_old_values`.
+    fn release_old_values(&self, size: usize) {
+        if size == 0 {
+            return;
+        }
+        self.old_values_inflight_bytes
+            .fetch_sub(size, Ordering::AcqRel);
+        SCHED_OLD_VALUES_INFLIGHT_BYTES_GAUGE
+            .set(self.old_values_inflight_bytes.load(Ordering::Acquire) as i64);
+    }
+
     /// Tries to acquire all the required latches for a command when waken up by
     /// another finished command.
     ///
@@ -459,7 +504,10 @@ impl<E: Engine, L: LockManager> TxnScheduler<E, L> {
             id_alloc: AtomicU64::new(0).into(),
             latches: Latches::new(config.scheduler_concurrency),
             running_write_bytes: AtomicUsize::new(0).into(),
+            sched_old_values_memory_quota: config.scheduler_old_values_memory_quota.0 as usize,
+            old_values_inflight_bytes: AtomicUsize::new(0).into(),
             sched_pending_write_threshold: config.scheduler_pending_write_threshold.0 as usize,
+            raft_write_max_size: config.raft_write_max_size.0 as usize,
             sched_worker_pool: SchedPool::new(
                 engine,
                 config.scheduler_worker_pool_size,
@@ -1767,7 +1815,7 @@ impl<E: Engine, L: LockManager> TxnScheduler<E, L> {
         }
         let WriteResult {
             ctx,
-            to_be_write,
+            mut to_be_write,
             rows,
             pr,
             new_acquired_locks,
@@ -1823,35 +1871,146 @@ impl<E: Engine, L: LockManager> TxnScheduler<E, L> {
         let downgraded_guard = pessimistic_locks_guard.and_then(|guard| {
             (!removed_pessimistic_locks.is_empty()).then(|| RwLockWriteGuard::downgrade(guard))
         });
-        let on_applied = Box::new(move |res: &mut kv::Result<()>| {
-            if res.is_ok() && !removed_pessimistic_locks.is_empty() {
-                // Removing pessimistic locks when it succeeds to apply. This should be done in
-                // the apply thread, to make sure it happens before other admin commands are
-                // executed.
-                if let Some(mut pessimistic_locks) = txn_ext
-                    .as_ref()
-                    .map(|txn_ext| txn_ext.pessimistic_locks.write())
-                {
-                    // If epoch version or term does not match, region or leader change has
-                    // happened, so we needn't remove the key.
-                    if pessimistic_locks.term == term && pessimistic_locks.version == version {
-                        for key in removed_pessimistic_locks {
-                            pessimistic_locks.remove(&key);
+        let removed_pessimistic_locks: collections::HashSet<Key> =
+            removed_pessimistic_locks.into_iter().collect();
+        // `to_be_write` may be split into several proposals below, each applied by
+        // raftstore independently. Build an `on_applied` callback scoped to just the
+        // keys whose lock-removing `Modify` is in `modifies`, so a key is only removed
+        // from `PeerPessimisticLocks` once the proposal that actually carries it has
+        // applied, instead of removing every key in the whole (pre-split) batch when
+        // any one proposal applies.
+        let make_on_applied = |modifies: &[Modify]| -> Option<OnAppliedCb> {
+            let keys: Vec<Key> = modifies
+                .iter()
+                .filter_map(|write| match write {
+                    Modify::Put(cf, key, ..) | Modify::Delete(cf, key) if *cf == CF_LOCK => {
+                        removed_pessimistic_locks.contains(key).then(|| key.to_owned())
+                    }
+                    _ => None,
+                })
+                .collect();
+            if keys.is_empty() {
+                return None;
+            }
+            let txn_ext = txn_ext.clone();
+            Some(Box::new(move |res: &mut kv::Result<()>| {
+                if res.is_ok() {
+                    // Removing pessimistic locks when it succeeds to apply. This should be done
+                    // in the apply thread, to make sure it happens before other admin commands
+                    // are executed.
+                    if let Some(mut pessimistic_locks) = txn_ext
+                        .as_ref()
+                        .map(|txn_ext| txn_ext.pessimistic_locks.write())
+                    {
+                        // If epoch version or term does not match, region or leader change has
+                        // happened, so we needn't remove the key.
+                        if pessimistic_locks.term == term && pessimistic_locks.version == version {
+                            for key in &keys {
+                                pessimistic_locks.remove(key);
+                            }
                         }
                     }
                 }
-            }
-        });
+            }) as OnAppliedCb)
+        };
+
+        let old_values_size = to_be_write.extra.size();
+        let old_values_reserved = txn_scheduler.inner.try_reserve_old_values(old_values_size);
+        if !old_values_reserved {
+            to_be_write.extra.degrade_old_values();
+            SCHED_OLD_VALUES_DEGRADE_COUNTER.inc();
+        }
+        let old_values_reserved_bytes = if old_values_reserved { old_values_size } else { 0 };
 
         let async_write_start = Instant::now_coarse();
+        let mut final_pr = Some(pr);
+
+        // A `to_be_write` too large for a single raft entry is proposed as
+        // several consecutive proposals instead of being rejected outright
+        // by raftstore's `raft-entry-max-size`. Latches are already held
+        // for every key in this command, so atomicity wrt other
+        // transactions is unaffected. A crash between proposals leaves the
+        // engine partially applied, which MVCC semantics already tolerate
+        // (the same as a crash mid-prewrite): later reads see a lock or
+        // value for whichever keys applied and nothing for the rest, and
+        // recovery proceeds the same way it does for any other interrupted
+        // write.
+        let mut chunks = to_be_write.split_by_size(txn_scheduler.inner.raft_write_max_size);
+        // `split_by_size` always returns at least one chunk.
+        to_be_write = chunks.pop().unwrap();
+        // Bytes not yet accounted for by a successfully applied chunk. Only this
+        // remainder is refunded on failure, since earlier chunks in the sequence may
+        // already have been applied and consumed real IO; refunding the whole
+        // (pre-split) `write_size` in that case would over-refund the quota.
+        let mut unconsumed_write_size = write_size;
+        for chunk in chunks {
+            let chunk_size = chunk.size();
+            let chunk_on_applied = make_on_applied(&chunk.modifies);
+            let mut prefix_res = unsafe {
+                with_tls_engine(|e: &mut E| {
+                    e.async_write(&ctx, chunk, WriteEvent::BASIC_EVENT, chunk_on_applied)
+                })
+            };
+            let mut prefix_result = None;
+            while let Some(ev) = prefix_res.next().await {
+                if let WriteEvent::Finished(res) = ev {
+                    prefix_result = Some(res);
+                    break;
+                }
+            }
+            let err = match prefix_result {
+                Some(Ok(())) => {
+                    unconsumed_write_size -= chunk_size;
+                    continue;
+                }
+                Some(Err(err)) => err,
+                None => {
+                    if !tikv_util::thread_group::is_shutdown(!cfg!(test)) {
+                        panic!(
+                            "response channel is unexpectedly dropped while proposing a split \
+                             write, tag {:?}, cid {}",
+                            tag, cid
+                        );
+                    }
+                    return;
+                }
+            };
+            txn_scheduler
+                .inner
+                .release_old_values(old_values_reserved_bytes);
+            txn_scheduler.on_write_finished(
+                cid,
+                final_pr.take(),
+                Err(err),
+                lock_guards,
+                pipelined,
+                is_async_apply_prewrite,
+                new_acquired_locks,
+                known_txn_status,
+                tag,
+                &ctx.request_source,
+                task_meta_data,
+                sched_details,
+            );
+            if txn_scheduler.inner.flow_controller.enabled() {
+                txn_scheduler
+                    .inner
+                    .flow_controller
+                    .unconsume(region_id, unconsumed_write_size);
+            }
+            sched_details.async_write_nanos =
+                async_write_start.saturating_elapsed().as_nanos() as u64;
+            return;
+        }
+
+        let final_on_applied = make_on_applied(&to_be_write.modifies);
         let mut res = unsafe {
             with_tls_engine(|e: &mut E| {
-                e.async_write(&ctx, to_be_write, subscribed, Some(on_applied))
+                e.async_write(&ctx, to_be_write, subscribed, final_on_applied)
             })
         };
         drop(downgraded_guard);
 
-        let mut final_pr = Some(pr);
         while let Some(ev) = res.next().await {
             match ev {
                 WriteEvent::Committed => {
@@ -1903,6 +2062,9 @@ impl<E: Engine, L: LockManager> TxnScheduler<E, L> {
                 WriteEvent::Finished(res) => {
                     fail_point!("scheduler_async_write_finish");
                     let ok = res.is_ok();
+                    txn_scheduler
+                        .inner
+                        .release_old_values(old_values_reserved_bytes);
 
                     txn_scheduler.on_write_finished(
                         cid,
@@ -1925,12 +2087,14 @@ impl<E: Engine, L: LockManager> TxnScheduler<E, L> {
                     if !ok {
                         // Only consume the quota when write succeeds, otherwise failed write
                         // requests may exhaust the quota and other write requests would be in long
-                        // delay.
+                        // delay. Only `unconsumed_write_size` is refunded (the whole batch minus
+                        // whatever earlier split chunks already applied), not the full
+                        // (pre-split) `write_size`, since those earlier chunks did real IO.
                         if txn_scheduler.inner.flow_controller.enabled() {
                             txn_scheduler
                                 .inner
                                 .flow_controller
-                                .unconsume(region_id, write_size);
+                                .unconsume(region_id, unconsumed_write_size);
                         }
                     }
                     sched_details.async_write_nanos =
@@ -2451,7 +2615,7 @@ mod tests {
     use futures_executor::block_on;
     use kvproto::kvrpcpb::{
         BatchRollbackRequest, CheckSecondaryLocksRequest, CheckTxnStatusRequest, Context,
-        ResourceControlContext,
+        FlushRequest, Mutation as MutationProto, Op, ResourceControlContext,
     };
     use raftstore::store::{LocksStatus, ReadStats, WriteStats};
     use tikv_util::{
@@ -2464,10 +2628,10 @@ mod tests {
 
     use super::*;
     use crate::storage::{
-        RocksEngine, SecondaryLocksStatus, TestEngineBuilder, TxnStatus,
+        Result as StorageResult, RocksEngine, SecondaryLocksStatus, TestEngineBuilder, TxnStatus,
         kv::{Error as KvError, ErrorInner as KvErrorInner},
         lock_manager::{MockLockManager, WaitTimeout},
-        mvcc::{self, Mutation},
+        mvcc::{self, Mutation, tests::{must_get_none, must_locked}},
         test_util::latest_feature_gate,
         txn::{
             commands,
@@ -2807,6 +2971,105 @@ mod tests {
         block_on(f).unwrap().unwrap();
     }
 
+    #[test]
+    fn test_prewrite_deadline_exceeded_does_not_write() {
+        let (scheduler, mut engine) = new_test_scheduler();
+
+        let mut ctx = Context::default();
+        ctx.max_execution_duration_ms = 100;
+        let cmd = commands::Prewrite::with_context(
+            vec![Mutation::make_put(Key::from_raw(b"k"), b"v".to_vec())],
+            b"k".to_vec(),
+            10.into(),
+            ctx,
+        );
+
+        // Wait enough time to let the cmd's deadline pass before it is even
+        // scheduled, so the check before proposing (not the one at latch
+        // acquisition) is what has to catch it.
+        thread::sleep(Duration::from_millis(120));
+
+        let (cb, f) = paired_future_callback();
+        scheduler.run_cmd(cmd.cmd, StorageCallback::Prewrite(cb));
+        assert!(matches!(
+            block_on(f).unwrap(),
+            Err(StorageError(box StorageErrorInner::DeadlineExceeded))
+        ));
+
+        // The prewrite must never have been proposed: no lock was left behind.
+        must_get_none(&mut engine, b"k", 10);
+    }
+
+    #[test]
+    fn test_flush_deadline_exceeded_does_not_write() {
+        let (scheduler, mut engine) = new_test_scheduler();
+
+        let mut req = FlushRequest::default();
+        req.mut_context().max_execution_duration_ms = 100;
+        req.set_start_ts(10);
+        req.set_primary_key(b"k".to_vec());
+        req.set_generation(1);
+        req.set_mutations(
+            vec![MutationProto {
+                op: Op::Put,
+                key: b"k".to_vec(),
+                value: b"v".to_vec(),
+                ..Default::default()
+            }]
+            .into(),
+        );
+        let cmd: TypedCommand<Vec<StorageResult<()>>> = req.into();
+
+        // Wait enough time to let the cmd's deadline pass before it is even
+        // scheduled, so the check before proposing (not the one at latch
+        // acquisition) is what has to catch it.
+        thread::sleep(Duration::from_millis(120));
+
+        let (cb, f) = paired_future_callback();
+        scheduler.run_cmd(cmd.cmd, StorageCallback::KeyedBooleans(cb));
+        assert!(matches!(
+            block_on(f).unwrap(),
+            Err(StorageError(box StorageErrorInner::DeadlineExceeded))
+        ));
+
+        // The flush must never have been proposed: no lock was left behind.
+        must_get_none(&mut engine, b"k", 10);
+    }
+
+    #[test]
+    fn test_handle_async_write_splits_oversized_batch() {
+        // A `raft_write_max_size` too small to hold even a single key's modify
+        // forces every key of a multi-key prewrite into its own chunk, so
+        // this exercises the split-and-propose-in-sequence path end to end.
+        let config = Config {
+            scheduler_concurrency: 1024,
+            scheduler_worker_pool_size: 1,
+            scheduler_pending_write_threshold: ReadableSize(100 * 1024 * 1024),
+            enable_async_apply_prewrite: false,
+            raft_write_max_size: ReadableSize(1),
+            ..Default::default()
+        };
+        let (scheduler, mut engine) = new_test_scheduler_with_config(config);
+
+        let cmd = commands::Prewrite::with_defaults(
+            vec![
+                Mutation::make_put(Key::from_raw(b"k1"), b"v1".to_vec()),
+                Mutation::make_put(Key::from_raw(b"k2"), b"v2".to_vec()),
+            ],
+            b"k1".to_vec(),
+            10.into(),
+        );
+        let (cb, f) = paired_future_callback();
+        scheduler.run_cmd(cmd.cmd, StorageCallback::Prewrite(cb));
+        block_on(f).unwrap().unwrap();
+
+        // The final engine state must match what an unsplit prewrite would
+        // have produced: both keys locked, despite having been proposed as
+        // two separate raft entries.
+        must_locked(&mut engine, b"k1", 10);
+        must_locked(&mut engine, b"k2", 10);
+    }
+
     #[test]
     fn test_accumulate_many_expired_commands() {
         let (scheduler, _) = new_test_scheduler();
@@ -3079,4 +3342,92 @@ mod tests {
         std::thread::sleep(Duration::from_millis(100));
         assert_eq!(scheduler.inner.memory_quota.in_use(), 0);
     }
+
+    #[test]
+    fn test_run_cmd_memory_quota_flush() {
+        // Large pipelined `Flush` batches should be admission-controlled by
+        // `memory_quota` the same way any other write command is: the quota
+        // accounts the whole command (via `approximate_heap_size`, which for
+        // `Flush` walks `mutations`/`primary`/`extra_batches`), not just
+        // `write_bytes`'s key/value total, so per-lock overhead can't be used
+        // to smuggle unbounded memory past the quota.
+        let key = Key::from_raw(b"flush-quota-key");
+        let mut lock = Lock::new(std::slice::from_ref(&key));
+
+        let build_cmd = || {
+            let mut req = FlushRequest::default();
+            req.set_start_ts(1);
+            req.set_primary_key(b"flush-quota-key".to_vec());
+            req.set_generation(1);
+            req.set_mutations(
+                vec![MutationProto {
+                    op: Op::Put,
+                    key: b"flush-quota-key".to_vec(),
+                    value: vec![0u8; 4096],
+                    ..Default::default()
+                }]
+                .into(),
+            );
+            let cmd: TypedCommand<Vec<StorageResult<()>>> = req.into();
+            cmd.cmd
+        };
+
+        let cmd_bytes = build_cmd().approximate_heap_size();
+        let max_request_count = 10u64;
+        let config = Config {
+            scheduler_concurrency: 1024,
+            scheduler_worker_pool_size: 1,
+            scheduler_pending_write_threshold: ReadableSize(100 * 1024 * 1024),
+            enable_async_apply_prewrite: false,
+            memory_quota: ReadableSize(max_request_count * cmd_bytes as u64),
+            ..Default::default()
+        };
+        let (scheduler, _) = new_test_scheduler_with_config(config);
+
+        let cid = scheduler.inner.gen_id();
+        assert!(scheduler.inner.latches.acquire(&mut lock, cid));
+
+        // Run Flush requests, all blocked behind the latch held above.
+        let mut requests = vec![];
+        for i in 0..max_request_count + 2 {
+            let cmd = build_cmd();
+            let (cb, mut fut) = paired_future_callback();
+            scheduler.run_cmd(cmd, StorageCallback::KeyedBooleans(cb));
+            if i >= max_request_count {
+                // Once the memory quota is exhausted, further Flush commands
+                // are rejected up front instead of buffering unbounded.
+                assert!(matches!(
+                    fut.try_recv(),
+                    Ok(Some(Err(StorageError(box StorageErrorInner::SchedTooBusy))))
+                ));
+            } else {
+                assert!(matches!(fut.try_recv(), Ok(None)));
+                requests.push(fut);
+            }
+        }
+
+        // Release the latch, unblocking the queued Flush commands.
+        scheduler.release_latches(lock, cid, None);
+
+        // The back-pressure recovers once the earlier writes finish.
+        for fut in requests {
+            let _ = block_on_timeout(fut, Duration::from_secs(5))
+                .unwrap()
+                .unwrap()
+                .unwrap();
+        }
+        std::thread::sleep(Duration::from_millis(100));
+        assert_eq!(scheduler.inner.memory_quota.in_use(), 0);
+
+        // New Flush commands are admitted again.
+        let cmd = build_cmd();
+        let (cb, fut) = paired_future_callback();
+        scheduler.run_cmd(cmd, StorageCallback::KeyedBooleans(cb));
+        let _ = block_on_timeout(fut, Duration::from_secs(5))
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        std::thread::sleep(Duration::from_millis(100));
+        assert_eq!(scheduler.inner.memory_quota.in_use(), 0);
+    }
 }
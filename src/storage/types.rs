@@ -478,6 +478,7 @@ macro_rules! storage_callback {
 storage_callback! {
     Boolean(()) ProcessResult::Res => (),
     Booleans(Vec<Result<()>>) ProcessResult::MultiRes { results } => results,
+    KeyedBooleans(Vec<(Vec<u8>, Result<()>)>) ProcessResult::MultiKeyedRes { results } => results,
     MvccInfoByKey(MvccInfo) ProcessResult::MvccKey { mvcc } => mvcc,
     MvccInfoByStartTs(Option<(Key, MvccInfo)>) ProcessResult::MvccStartTs { mvcc } => mvcc,
     Locks(Vec<kvrpcpb::LockInfo>) ProcessResult::Locks { locks } => locks,
@@ -112,6 +112,61 @@ fn txn_prewrite<E: Engine, F: EngineFactory<E>>(b: &mut Bencher<'_>, config: &Be
     )
 }
 
+// Unlike `txn_prewrite`, which starts a fresh `MvccTxn` per mutation, this
+// prewrites a whole batch of mutations into a single `MvccTxn`, mirroring
+// how `Flush`/`Prewrite` commands process many keys in one go and exercising
+// `MvccTxn::reserve` and the `put_lock` scratch buffer.
+fn txn_prewrite_batch<E: Engine, F: EngineFactory<E>>(b: &mut Bencher<'_>, config: &BenchConfig<F>) {
+    let mut engine = config.engine_factory.build();
+    let ctx = Context::default();
+    let cm = ConcurrencyManager::new_for_test(1.into());
+    b.iter_batched(
+        || {
+            let mutations: Vec<(Mutation, Vec<u8>)> =
+                KvGenerator::new(config.key_length, config.value_length)
+                    .generate(DEFAULT_ITERATIONS)
+                    .iter()
+                    .map(|(k, v)| (Mutation::make_put(Key::from_raw(k), v.clone()), k.clone()))
+                    .collect();
+            mutations
+        },
+        |mutations| {
+            let snapshot = engine.snapshot(Default::default()).unwrap();
+            let mut txn = mvcc::MvccTxn::new(1.into(), cm.clone());
+            txn.reserve(mutations.len());
+            let mut reader = SnapshotReader::new(1.into(), snapshot, true);
+            for (mutation, primary) in mutations {
+                let txn_props = TransactionProperties {
+                    start_ts: TimeStamp::default(),
+                    kind: TransactionKind::Optimistic(false),
+                    commit_kind: CommitKind::TwoPc,
+                    primary: &primary,
+                    txn_size: 0,
+                    lock_ttl: 0,
+                    min_commit_ts: TimeStamp::default(),
+                    need_old_value: false,
+                    is_retry_request: false,
+                    assertion_level: AssertionLevel::Off,
+                    txn_source: 0,
+                };
+                prewrite(
+                    &mut txn,
+                    &mut reader,
+                    &txn_props,
+                    mutation,
+                    &None,
+                    SkipPessimisticCheck,
+                    None,
+                )
+                .unwrap();
+            }
+            let write_data = WriteData::from_modifies(txn.into_modifies());
+            black_box(engine.write(&ctx, write_data)).unwrap();
+        },
+        BatchSize::SmallInput,
+    )
+}
+
 fn txn_commit<E: Engine, F: EngineFactory<E>>(b: &mut Bencher<'_>, config: &BenchConfig<F>) {
     let mut engine = config.engine_factory.build();
     let mut engine_clone = engine.clone();
@@ -213,6 +268,11 @@ pub fn bench_txn<E: Engine, F: EngineFactory<E>>(c: &mut Criterion, configs: &[B
     let mut group = c.benchmark_group("txn");
     for config in configs {
         group.bench_with_input(format!("prewrite/{:?}", config), config, txn_prewrite);
+        group.bench_with_input(
+            format!("prewrite_batch/{:?}", config),
+            config,
+            txn_prewrite_batch,
+        );
         group.bench_with_input(format!("commit/{:?}", config), config, txn_commit);
         group.bench_with_input(
             format!("rollback_prewrote/{:?}", config),
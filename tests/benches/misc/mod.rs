@@ -14,5 +14,6 @@ mod writebatch;
 
 #[bench]
 fn _bench_check_requirement(_: &mut test::Bencher) {
-    tikv_util::config::check_max_open_fds(4096).unwrap();
+    tikv_util::config::check_max_open_fds(4096, tikv_util::config::FdLimitMode::Enforce)
+        .unwrap();
 }
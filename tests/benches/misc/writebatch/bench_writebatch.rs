@@ -150,3 +150,61 @@ fn bench_writebatch_with_capacity(b: &mut Bencher) {
         fill_writebatch(&mut wb, 4096);
     });
 }
+
+/// Simulates the raft apply small-batch arrival pattern that motivates
+/// `apply_write_coalesce_max_delay`/`apply_write_coalesce_max_bytes`
+/// (`raftstore::store::Config`): writes arrive in tiny groups (1-3 keys,
+/// mirroring a lightly loaded region), and `arrivals_per_write` of them are
+/// coalesced into a single `RocksWriteBatchVec` before it's written to the
+/// engine. `arrivals_per_write == 1` is the uncoalesced baseline.
+fn bench_small_batch_arrivals(b: &mut Bencher, arrivals_per_write: usize) {
+    let path = Builder::new()
+        .prefix("/tmp/rocksdb_write_batch_bench")
+        .tempdir()
+        .unwrap();
+    let mut opts = RocksDbOptions::default();
+    opts.create_if_missing(true);
+    opts.enable_unordered_write(false);
+    opts.enable_pipelined_write(false);
+    opts.enable_multi_batch_write(true);
+    let engine = engine_rocks::util::new_engine_opt(
+        path.path().to_str().unwrap(),
+        opts,
+        vec![(CF_DEFAULT, RocksCfOptions::default())],
+    )
+    .unwrap();
+    let v = b"operators are syntactic sugar for calls to methods of built-in traits";
+    let arrival_keys = 2;
+    let arrivals = 512;
+    b.iter(|| {
+        let mut batch: Option<RocksWriteBatchVec> = None;
+        for arrival in 0..arrivals {
+            let wb = batch.get_or_insert_with(|| engine.write_batch());
+            for i in 0..arrival_keys {
+                let k = format!("key_arrival{}_key{}", arrival, i);
+                wb.put(k.as_bytes(), v).unwrap();
+            }
+            if (arrival + 1) % arrivals_per_write == 0 {
+                batch.take().unwrap().write().unwrap();
+            }
+        }
+        if let Some(wb) = batch {
+            wb.write().unwrap();
+        }
+    });
+}
+
+#[bench]
+fn bench_small_batch_arrivals_uncoalesced(b: &mut Bencher) {
+    bench_small_batch_arrivals(b, 1);
+}
+
+#[bench]
+fn bench_small_batch_arrivals_coalesced_8(b: &mut Bencher) {
+    bench_small_batch_arrivals(b, 8);
+}
+
+#[bench]
+fn bench_small_batch_arrivals_coalesced_32(b: &mut Bencher) {
+    bench_small_batch_arrivals(b, 32);
+}
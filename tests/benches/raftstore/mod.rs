@@ -171,7 +171,8 @@ impl fmt::Debug for ServerClusterFactory {
 }
 
 fn main() {
-    tikv_util::config::check_max_open_fds(4096).unwrap();
+    tikv_util::config::check_max_open_fds(4096, tikv_util::config::FdLimitMode::Enforce)
+        .unwrap();
 
     let mut criterion = Criterion::default().configure_from_args().sample_size(10);
     bench_raft_cluster(&mut criterion, NodeClusterFactory {}, "raftstore::node");
@@ -44,6 +44,7 @@ use tikv::{
 };
 use tikv_util::config::{
     LogFormat, ReadableDuration, ReadableSchedule, ReadableSize, ReadableSizeOrPercent,
+    ReadableSizeOrRatio,
 };
 
 mod dynamic;
@@ -740,9 +741,12 @@ fn test_serde_custom_tikv_config() {
         engine: EngineType::RaftKv2,
         gc_ratio_threshold: 1.2,
         max_key_size: 4096,
+        max_key_errors_per_response: 100,
         scheduler_concurrency: 123,
         scheduler_worker_pool_size: 1,
         scheduler_pending_write_threshold: ReadableSize::kb(123),
+        raft_write_max_size: ReadableSize::mb(5),
+        scheduler_old_values_memory_quota: ReadableSize::mb(10),
         reserve_space: ReadableSize::gb(10),
         reserve_raft_space: ReadableSize::gb(2),
         enable_async_apply_prewrite: true,
@@ -845,7 +849,7 @@ fn test_serde_custom_tikv_config() {
         enable: true,
         temp_path: "./stream".to_string(),
         file_size_limit: ReadableSize::gb(5),
-        initial_scan_pending_memory_quota: ReadableSize::kb(2),
+        initial_scan_pending_memory_quota: ReadableSizeOrRatio::Size(ReadableSize::kb(2)),
         initial_scan_rate_limit: ReadableSize::mb(3),
         min_ts_interval: ReadableDuration::secs(2),
         ..Default::default()
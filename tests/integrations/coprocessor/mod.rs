@@ -2,4 +2,5 @@
 
 mod test_analyze;
 mod test_checksum;
+mod test_column_type_check;
 mod test_select;
@@ -0,0 +1,119 @@
+// Copyright 2026 TiKV Project Authors. Licensed under Apache-2.0.
+
+use kvproto::{coprocessor::Request, kvrpcpb::Context};
+use test_coprocessor::*;
+use tidb_query_datatype::FieldTypeAccessor;
+use tikv::coprocessor::{
+    column_type_check::{ColumnTypeCheckRequest, ColumnTypeCheckResponse},
+    *,
+};
+use tipb::FieldType;
+
+fn new_column_type_check_request(
+    table: &ProductTable,
+    column_name: &str,
+    new_field_type: FieldType,
+    sample_limit: u32,
+) -> Request {
+    let column = &table[column_name];
+    let req = ColumnTypeCheckRequest {
+        old_column: column.as_column_info(),
+        new_field_type,
+        sample_limit,
+    };
+
+    let mut req_pb = Request::default();
+    req_pb.set_context(Context::default());
+    req_pb.set_start_ts(u64::MAX);
+    req_pb.set_tp(REQ_TYPE_COLUMN_TYPE_CHECK);
+    req_pb.set_data(req.write_to_bytes().unwrap());
+    req_pb.mut_ranges().push(table.get_record_range_all());
+    req_pb
+}
+
+#[test]
+fn test_length_truncation_violations() {
+    let data = vec![
+        (1, Some("ab"), 1),
+        (2, Some("abcdef"), 2),
+        (3, Some("abcdefgh"), 3),
+        (4, Some("cd"), 4),
+    ];
+    let product = ProductTable::new();
+    let (_, endpoint, _) = init_with_data_ext(&product, &data);
+
+    let mut new_ft = FieldType::default();
+    new_ft.set_tp(15 /* FieldTypeTp::VarChar */);
+    new_ft.set_charset("utf8mb4".to_owned());
+    new_ft.as_mut_accessor().set_flen(3);
+
+    let request = new_column_type_check_request(&product, "name", new_ft, 10);
+    let response = handle_request(&endpoint, request);
+    let resp = ColumnTypeCheckResponse::parse_from_bytes(response.get_data()).unwrap();
+
+    // "abcdef" and "abcdefgh" don't fit in VARCHAR(3); "ab" and "cd" do.
+    assert_eq!(resp.violation_count, 2);
+    assert_eq!(resp.sample_handles, vec![2, 3]);
+}
+
+#[test]
+fn test_charset_unmappable_violations() {
+    let data = vec![(1, Some("ok"), 1), (2, Some("中文"), 2)];
+    let product = ProductTable::new();
+    let (_, endpoint, _) = init_with_data_ext(&product, &data);
+
+    let mut new_ft = FieldType::default();
+    new_ft.set_tp(15 /* FieldTypeTp::VarChar */);
+    new_ft.set_charset("ascii".to_owned());
+    new_ft.as_mut_accessor().set_flen(20);
+
+    let request = new_column_type_check_request(&product, "name", new_ft, 10);
+    let response = handle_request(&endpoint, request);
+    let resp = ColumnTypeCheckResponse::parse_from_bytes(response.get_data()).unwrap();
+
+    assert_eq!(resp.violation_count, 1);
+    assert_eq!(resp.sample_handles, vec![2]);
+}
+
+#[test]
+fn test_numeric_range_violations() {
+    let data = vec![(1, Some("a"), 12), (2, Some("b"), 12345)];
+    let product = ProductTable::new();
+    let (_, endpoint, _) = init_with_data_ext(&product, &data);
+
+    let mut new_ft = FieldType::default();
+    new_ft.set_tp(8 /* FieldTypeTp::LongLong */);
+    new_ft.as_mut_accessor().set_flen(3);
+    new_ft.as_mut_accessor().set_decimal(0);
+
+    let request = new_column_type_check_request(&product, "count", new_ft, 10);
+    let response = handle_request(&endpoint, request);
+    let resp = ColumnTypeCheckResponse::parse_from_bytes(response.get_data()).unwrap();
+
+    // 12345 overflows a 3-digit column; 12 doesn't.
+    assert_eq!(resp.violation_count, 1);
+    assert_eq!(resp.sample_handles, vec![2]);
+}
+
+#[test]
+fn test_sample_limit_caps_handles_but_not_count() {
+    let data = vec![
+        (1, Some("abcdef"), 1),
+        (2, Some("abcdef"), 2),
+        (3, Some("abcdef"), 3),
+    ];
+    let product = ProductTable::new();
+    let (_, endpoint, _) = init_with_data_ext(&product, &data);
+
+    let mut new_ft = FieldType::default();
+    new_ft.set_tp(15 /* FieldTypeTp::VarChar */);
+    new_ft.set_charset("utf8mb4".to_owned());
+    new_ft.as_mut_accessor().set_flen(1);
+
+    let request = new_column_type_check_request(&product, "name", new_ft, 1);
+    let response = handle_request(&endpoint, request);
+    let resp = ColumnTypeCheckResponse::parse_from_bytes(response.get_data()).unwrap();
+
+    assert_eq!(resp.violation_count, 3);
+    assert_eq!(resp.sample_handles, vec![1]);
+}